@@ -0,0 +1,95 @@
+//! Measures the cost of polling an idle association for packets to send, as an
+//! event loop waking up 10,000 times per second would.
+//!
+//! Run with `cargo bench --bench idle_poll`.
+
+use sctp_proto::{
+    Association, AssociationHandle, ClientConfig, DatagramEvent, Endpoint, EndpointConfig, Event,
+    Payload, ServerConfig,
+};
+use std::hint::black_box;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 1_000_000;
+const POLL_INTERVAL: Duration = Duration::from_micros(100);
+
+/// Hands the packets `from` has to send to `to`, creating the association on
+/// the receiving side if needed
+fn forward(
+    from: &mut Association,
+    from_addr: SocketAddr,
+    to_endpoint: &mut Endpoint,
+    to: &mut Option<(AssociationHandle, Association)>,
+    now: Instant,
+) {
+    while let Some(transmit) = from.poll_transmit(now) {
+        let Payload::RawEncode(raws) = transmit.payload else {
+            continue;
+        };
+        for raw in raws {
+            match to_endpoint.handle(now, from_addr, None, None, raw) {
+                Some((ch, DatagramEvent::NewAssociation(a))) => *to = Some((ch, a)),
+                Some((_, DatagramEvent::AssociationEvent(event))) => {
+                    if let Some((_, a)) = to.as_mut() {
+                        a.handle_event(event);
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+}
+
+fn established_association(now: Instant) -> Association {
+    let client_addr: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+    let server_addr: SocketAddr = "127.0.0.1:5001".parse().unwrap();
+    let endpoint_config = Arc::new(EndpointConfig::default());
+    let mut client_endpoint = Endpoint::new(endpoint_config.clone(), None);
+    let mut server_endpoint =
+        Endpoint::new(endpoint_config, Some(Arc::new(ServerConfig::default())));
+
+    let (ch, client) = client_endpoint
+        .connect(ClientConfig::default(), server_addr)
+        .unwrap();
+    let mut client = Some((ch, client));
+    let mut server = None;
+    for _ in 0..10 {
+        let (_, c) = client.as_mut().unwrap();
+        forward(c, client_addr, &mut server_endpoint, &mut server, now);
+        if let Some((_, s)) = server.as_mut() {
+            forward(s, server_addr, &mut client_endpoint, &mut client, now);
+        }
+    }
+
+    let (_, mut client) = client.unwrap();
+    assert!(
+        std::iter::from_fn(|| client.poll()).any(|e| matches!(e, Event::Connected)),
+        "handshake did not complete"
+    );
+    client
+}
+
+fn main() {
+    let mut now = Instant::now();
+    let mut association = established_association(now);
+    // Let the timers started along with the association settle
+    while association.poll_transmit(now).is_some() {}
+
+    let start = Instant::now();
+    let mut n_transmits = 0;
+    for _ in 0..ITERATIONS {
+        now += POLL_INTERVAL;
+        if black_box(association.poll_transmit(now)).is_some() {
+            n_transmits += 1;
+        }
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "idle poll_transmit: {:>8.1} ns/poll ({} transmits over {:?} of polling)",
+        elapsed.as_nanos() as f64 / ITERATIONS as f64,
+        n_transmits,
+        POLL_INTERVAL * ITERATIONS
+    );
+}
@@ -0,0 +1,21 @@
+//! Feeds arbitrary datagrams to the packet parsers, which must reject malformed
+//! input with an error instead of panicking.
+//!
+//! cargo +nightly fuzz run packet_unmarshal fuzz/corpus/packet_unmarshal fuzz/seeds/packet_unmarshal
+
+#![no_main]
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use sctp_proto::fuzzing;
+
+fuzz_target!(|data: &[u8]| {
+    // Almost no input carries a valid checksum, so fix it up to reach the
+    // chunk parsers
+    let mut data = data.to_vec();
+    fuzzing::set_packet_checksum(&mut data);
+    let raw = Bytes::copy_from_slice(&data);
+
+    let _ = fuzzing::partial_decode(&raw);
+    let _ = fuzzing::unmarshal_packet(&raw);
+});
@@ -1,4 +1,10 @@
 use super::*;
+use crate::chunk::chunk_header::ChunkHeader;
+use crate::chunk::chunk_payload_data::PAYLOAD_DATA_UNORDERED_BITMASK;
+use crate::chunk::chunk_selective_ack::GapAckBlock;
+use crate::chunk::chunk_type::{ChunkType, CT_PAYLOAD_DATA, CT_RECONFIG};
+use crate::chunk::{INVALID_STREAM_IDENTIFIER, NO_USER_DATA, STALE_COOKIE_ERROR};
+use crate::param::build_param;
 
 const ACCEPT_CH_SIZE: usize = 16;
 
@@ -115,7 +121,7 @@ fn test_create_forward_tsn_forward_two_abandoned_with_the_same_si() -> Result<()
 #[test]
 fn test_handle_forward_tsn_forward_3unreceived_chunks() -> Result<()> {
     let mut a = Association {
-        use_forward_tsn: true,
+        negotiated_extensions: [SctpExtension::ForwardTsn].into_iter().collect(),
         ..Default::default()
     };
 
@@ -151,7 +157,7 @@ fn test_handle_forward_tsn_forward_3unreceived_chunks() -> Result<()> {
 #[test]
 fn test_handle_forward_tsn_forward_1for1_missing() -> Result<()> {
     let mut a = Association {
-        use_forward_tsn: true,
+        negotiated_extensions: [SctpExtension::ForwardTsn].into_iter().collect(),
         ..Default::default()
     };
 
@@ -201,11 +207,10 @@ fn test_handle_forward_tsn_forward_1for1_missing() -> Result<()> {
 #[test]
 fn test_handle_forward_tsn_forward_1for2_missing() -> Result<()> {
     let mut a = Association {
-        use_forward_tsn: true,
+        negotiated_extensions: [SctpExtension::ForwardTsn].into_iter().collect(),
         ..Default::default()
     };
 
-    a.use_forward_tsn = true;
     let prev_tsn = a.peer_last_tsn;
 
     // this chunk is blocked by the missing chunk at tsn=1
@@ -250,7 +255,7 @@ fn test_handle_forward_tsn_forward_1for2_missing() -> Result<()> {
 #[test]
 fn test_handle_forward_tsn_dup_forward_tsn_chunk_should_generate_sack() -> Result<()> {
     let mut a = Association {
-        use_forward_tsn: true,
+        negotiated_extensions: [SctpExtension::ForwardTsn].into_iter().collect(),
         ..Default::default()
     };
 
@@ -279,12 +284,15 @@ fn test_assoc_create_new_stream() -> Result<()> {
     let mut a = Association::default();
 
     for i in 0..ACCEPT_CH_SIZE {
-        let stream_identifier =
-            if let Some(s) = a.create_stream(i as u16, true, PayloadProtocolIdentifier::Unknown) {
-                s.stream_identifier
-            } else {
-                panic!("{} should success", i);
-            };
+        let stream_identifier = if let Some(s) = a.create_stream(
+            i as u16,
+            Some(PayloadProtocolIdentifier::Unknown),
+            PayloadProtocolIdentifier::Unknown,
+        ) {
+            s.stream_identifier
+        } else {
+            panic!("{} should success", i);
+        };
         let result = a.streams.get(&stream_identifier);
         assert!(result.is_some(), "should be in a.streams map");
     }
@@ -329,7 +337,7 @@ fn handle_init_test(name: &str, initial_state: AssociationState, expect_err: boo
     };
     init.set_supported_extensions();
 
-    let result = a.handle_init(&pkt, &init);
+    let result = a.handle_init(&pkt, &init, Instant::now());
     if expect_err {
         assert!(result.is_err(), "{} should fail", name);
         return;
@@ -346,8 +354,8 @@ fn handle_init_test(name: &str, initial_state: AssociationState, expect_err: boo
         "{} should match",
         name
     );
-    assert_eq!(1001, a.my_max_num_outbound_streams, "{} should match", name);
-    assert_eq!(1002, a.my_max_num_inbound_streams, "{} should match", name);
+    assert_eq!(1002, a.my_max_num_outbound_streams, "{} should match", name);
+    assert_eq!(1001, a.my_max_num_inbound_streams, "{} should match", name);
     assert_eq!(5678, a.peer_verification_tag, "{} should match", name);
     assert_eq!(
         pkt.common_header.source_port, a.destination_port,
@@ -359,7 +367,11 @@ fn handle_init_test(name: &str, initial_state: AssociationState, expect_err: boo
         "{} should match",
         name
     );
-    assert!(a.use_forward_tsn, "{} should be set to true", name);
+    assert!(
+        a.negotiated_extensions().has(SctpExtension::ForwardTsn),
+        "{} should be set to true",
+        name
+    );
 }
 
 #[test]
@@ -405,7 +417,7 @@ fn test_assoc_max_message_size_default() -> Result<()> {
     assert_eq!(65536, a.max_message_size, "should match");
 
     let ppi = PayloadProtocolIdentifier::Unknown;
-    let stream = a.create_stream(1, false, ppi);
+    let stream = a.create_stream(1, None, ppi);
     assert!(stream.is_some(), "should succeed");
 
     if let Some(mut s) = stream {
@@ -435,6 +447,30 @@ fn test_assoc_max_message_size_default() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_assoc_set_max_message_size() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+
+    a.set_max_message_size(1000);
+    assert_eq!(1000, a.max_message_size());
+
+    a.send_with_options(1, Bytes::from(vec![0u8; 1000]), SendOptions::default())?;
+    assert_eq!(1000, a.bytes_pending());
+    assert_eq!(Some(1), a.get_stream_send_sequence_number(1));
+
+    assert_eq!(
+        Err(Error::ErrOutboundPacketTooLarge),
+        a.send_with_options(1, Bytes::from(vec![0u8; 1001]), SendOptions::default())
+    );
+    assert_eq!(1000, a.bytes_pending(), "nothing should be queued");
+    assert_eq!(Some(1), a.get_stream_send_sequence_number(1));
+    assert_eq!(1000, a.stream(1)?.buffered_amount()?);
+
+    Ok(())
+}
+
 #[test]
 fn test_assoc_max_message_size_explicit() -> Result<()> {
     let mut a = create_association(TransportConfig::default().with_max_message_size(30000));
@@ -442,7 +478,7 @@ fn test_assoc_max_message_size_explicit() -> Result<()> {
     assert_eq!(30000, a.max_message_size, "should match");
 
     let ppi = PayloadProtocolIdentifier::Unknown;
-    let stream = a.create_stream(1, false, ppi);
+    let stream = a.create_stream(1, None, ppi);
     assert!(stream.is_some(), "should succeed");
 
     if let Some(mut s) = stream {
@@ -471,3 +507,3410 @@ fn test_assoc_max_message_size_explicit() -> Result<()> {
 
     Ok(())
 }
+
+fn create_established_association_with_inflight(tsns: &[u32]) -> Association {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.cumulative_tsn_ack_point = tsns[0] - 1;
+    a.my_next_tsn = tsns[tsns.len() - 1] + 1;
+
+    let now = Instant::now();
+    for tsn in tsns {
+        a.inflight_queue.push_no_check(ChunkPayloadData {
+            beginning_fragment: true,
+            ending_fragment: true,
+            tsn: *tsn,
+            stream_identifier: 1,
+            user_data: Bytes::from_static(b"ABC"),
+            nsent: 1,
+            since: Some(now),
+            ..Default::default()
+        });
+    }
+
+    a
+}
+
+#[test]
+fn test_assoc_handle_sack_replayed_after_inflight_advanced() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    let now = Instant::now();
+
+    // TSN 10 acked, 12 gap-acked
+    let sack1 = ChunkSelectiveAck {
+        cumulative_tsn_ack: 10,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![GapAckBlock { start: 2, end: 2 }],
+        duplicate_tsn: vec![],
+    };
+    // Everything acked
+    let sack2 = ChunkSelectiveAck {
+        cumulative_tsn_ack: 13,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+
+    a.handle_sack(&sack1, now)?;
+    a.handle_sack(&sack2, now)?;
+    assert_eq!(13, a.cumulative_tsn_ack_point);
+    assert!(a.inflight_queue.is_empty());
+
+    // Replaying the first SACK must be harmless
+    a.handle_sack(&sack1, now)?;
+    assert_eq!(AssociationState::Established, a.state());
+    assert_eq!(13, a.cumulative_tsn_ack_point);
+    assert_eq!(0, a.stats.get_num_unknown_tsn_acks());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_sack_unknown_gap_tsns() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10, 11, 12]);
+    let now = Instant::now();
+
+    // Gap block covers 12 (known) and 13..14 (never sent)
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 10,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![GapAckBlock { start: 2, end: 4 }],
+        duplicate_tsn: vec![],
+    };
+    a.handle_sack(&sack, now)?;
+
+    assert_eq!(AssociationState::Established, a.state());
+    assert_eq!(10, a.cumulative_tsn_ack_point);
+    assert_eq!(2, a.inflight_queue.len());
+    assert!(
+        a.inflight_queue.get(12).unwrap().acked,
+        "tsn 12 should be acked"
+    );
+    assert_eq!(2, a.stats.get_num_unknown_tsn_acks());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_sack_cumulative_ack_beyond_sent() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10, 11, 12]);
+    let now = Instant::now();
+
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 20,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+    a.consecutive_retransmissions = 2;
+    let rwnd = a.congestion.rwnd;
+    a.handle_sack(&sack, now)?;
+
+    assert_eq!(AssociationState::Established, a.state());
+    assert_eq!(
+        9, a.cumulative_tsn_ack_point,
+        "a SACK acking unsent TSNs should be dropped"
+    );
+    assert_eq!(3, a.inflight_queue.len());
+    assert_eq!(9, a.inflight_queue.get_num_bytes());
+    assert_eq!(2, a.consecutive_retransmissions);
+    assert_eq!(rwnd, a.congestion.rwnd);
+    assert_eq!(1, a.stats.get_num_unknown_tsn_acks());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_sack_reneged_gap_ack() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    let now = Instant::now();
+
+    // SACK n gap-acks TSN 12
+    let sack1 = ChunkSelectiveAck {
+        cumulative_tsn_ack: 10,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![GapAckBlock { start: 2, end: 2 }],
+        duplicate_tsn: vec![],
+    };
+    // SACK n+1 gap-acks TSN 13, but no longer covers TSN 12
+    let sack2 = ChunkSelectiveAck {
+        cumulative_tsn_ack: 10,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![GapAckBlock { start: 3, end: 3 }],
+        duplicate_tsn: vec![],
+    };
+
+    a.handle_sack(&sack1, now)?;
+    assert!(
+        a.inflight_queue.get(12).unwrap().acked,
+        "tsn 12 should be acked"
+    );
+    assert_eq!(6, a.inflight_queue.get_num_bytes());
+
+    a.handle_sack(&sack2, now)?;
+    assert_eq!(AssociationState::Established, a.state());
+    let c = a.inflight_queue.get(12).unwrap();
+    assert!(!c.acked, "tsn 12 should no longer be acked");
+    assert_eq!(3, c.user_data.len(), "reneged data should be kept");
+    assert_eq!(6, a.inflight_queue.get_num_bytes());
+    assert_eq!(1, a.stats.get_num_reneged_tsns());
+    assert!(a.timers.get(Timer::T3RTX).is_some(), "T3-rtx should run");
+
+    // On T3-rtx expiry the reneged chunk must be retransmitted
+    a.inflight_queue.mark_all_to_retrasmit();
+    let mut tsns = vec![];
+    for p in a.get_data_packets_to_retransmit(now) {
+        for c in &p.chunks {
+            if let Some(c) = c.as_any().downcast_ref::<ChunkPayloadData>() {
+                assert_eq!(3, c.user_data.len());
+                tsns.push(c.tsn);
+            }
+        }
+    }
+    assert_eq!(vec![11, 12], tsns);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_sack_reordered_is_not_reneging() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    let now = Instant::now();
+    let sack = |gap_ack_blocks| ChunkSelectiveAck {
+        cumulative_tsn_ack: 10,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks,
+        duplicate_tsn: vec![],
+    };
+
+    // The newer SACK overtakes the older one, which lacks TSN 13
+    a.handle_sack(&sack(vec![GapAckBlock { start: 2, end: 3 }]), now)?;
+    a.handle_sack(&sack(vec![GapAckBlock { start: 2, end: 2 }]), now)?;
+    a.handle_sack(&sack(vec![GapAckBlock { start: 2, end: 3 }]), now)?;
+
+    assert!(a.inflight_queue.get(13).unwrap().acked);
+    assert_eq!(0, a.stats.get_num_reneged_tsns());
+    assert_eq!(3, a.inflight_queue.get_num_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_send_with_options_unordered() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.create_stream(1, None, PayloadProtocolIdentifier::Binary);
+
+    a.send_with_options(
+        1,
+        Bytes::from_static(b"ordered"),
+        SendOptions {
+            ppi: PayloadProtocolIdentifier::String,
+            ..Default::default()
+        },
+    )?;
+    a.send_with_options(
+        1,
+        Bytes::from_static(b"unordered"),
+        SendOptions {
+            unordered: true,
+            ppi: PayloadProtocolIdentifier::Binary,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        1,
+        a.streams.get(&1).unwrap().sequence_number,
+        "unordered send should not consume a stream sequence number"
+    );
+
+    // The unordered chunk does not wait behind the ordered one
+    let c = a.pending_queue.peek().unwrap();
+    assert!(c.unordered, "unordered chunk should be sent first");
+    assert_eq!(PayloadProtocolIdentifier::Binary, c.payload_type);
+    let raw = c.marshal()?;
+    assert_ne!(
+        0,
+        raw[1] & PAYLOAD_DATA_UNORDERED_BITMASK,
+        "U-bit should be set"
+    );
+
+    let result = a.send_with_options(2, Bytes::from_static(b"x"), SendOptions::default());
+    assert_eq!(Err(Error::ErrStreamNotExisted), result);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_estimate_fragment_count() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.create_stream(1, None, PayloadProtocolIdentifier::Binary);
+
+    let max_payload_size = a.max_single_packet_payload() as usize;
+    assert_eq!(1400, max_payload_size);
+
+    for size in [
+        1,
+        max_payload_size - 1,
+        max_payload_size,
+        max_payload_size + 1,
+        3 * max_payload_size,
+        3 * max_payload_size + 7,
+        a.max_send_message_size() as usize,
+    ] {
+        let estimate = a.estimate_fragment_count(size);
+
+        a.send_with_options(1, Bytes::from(vec![0u8; size]), SendOptions::default())?;
+        assert_eq!(1, a.pending_queue.len(), "messages are queued whole");
+
+        let mut n_fragments = 0;
+        while let Some(c) = a.pending_queue.peek() {
+            let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
+            a.pending_queue
+                .pop(beginning_fragment, unordered, max_payload_size)
+                .unwrap();
+            n_fragments += 1;
+        }
+        assert_eq!(
+            estimate, n_fragments,
+            "estimate should match fragments for size {}",
+            size
+        );
+    }
+    assert_eq!(0, a.estimate_fragment_count(0));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_max_send_message_size() -> Result<()> {
+    let a = create_association(TransportConfig::default());
+    assert_eq!(
+        std::cmp::min(65536, 64 * a.max_single_packet_payload()),
+        a.max_send_message_size()
+    );
+
+    let a = create_association(TransportConfig::default().with_max_message_size(30000));
+    assert_eq!(30000, a.max_send_message_size());
+
+    let a = create_association(TransportConfig::default().with_max_message_size(u32::MAX));
+    assert_eq!(
+        64 * a.max_single_packet_payload(),
+        a.max_send_message_size(),
+        "should be clamped to the fragment limit"
+    );
+
+    Ok(())
+}
+
+fn first_fragment(tsn: u32, si: u16, ssn: u16, len: usize) -> ChunkPayloadData {
+    ChunkPayloadData {
+        beginning_fragment: true,
+        tsn,
+        stream_identifier: si,
+        stream_sequence_number: ssn,
+        user_data: Bytes::from(vec![0u8; len]),
+        ..Default::default()
+    }
+}
+
+fn dropped_messages(a: &mut Association) -> Vec<(StreamId, usize)> {
+    let mut dropped = vec![];
+    while let Some(e) = a.poll() {
+        if let Event::Stream(StreamEvent::MessageDropped { id, bytes }) = e {
+            dropped.push((id, bytes));
+        }
+    }
+    dropped
+}
+
+#[test]
+fn test_assoc_reassembly_stream_limit() -> Result<()> {
+    let mut a = create_association(
+        TransportConfig::default()
+            .with_max_stream_reassembly_size(250)
+            .with_max_reassembly_size(1000),
+    );
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 0;
+
+    // Never-completed messages on stream 1
+    for i in 0..3 {
+        a.handle_data(&first_fragment(1 + i, 1, i as u16, 100))?;
+    }
+
+    assert_eq!(vec![(1, 100)], dropped_messages(&mut a));
+    assert_eq!(1, a.stats.get_num_reassembly_drops());
+    let q = &a.streams.get(&1).unwrap().reassembly_queue;
+    assert_eq!(200, q.get_num_incomplete_bytes());
+    assert_eq!(Some(2), q.oldest_incomplete_tsn(), "oldest should go first");
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_reassembly_association_limit() -> Result<()> {
+    let mut a = create_association(
+        TransportConfig::default()
+            .with_max_stream_reassembly_size(1000)
+            .with_max_reassembly_size(250),
+    );
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 0;
+
+    a.handle_data(&first_fragment(1, 1, 0, 100))?;
+    a.handle_data(&first_fragment(2, 2, 0, 100))?;
+    assert!(dropped_messages(&mut a).is_empty());
+
+    // Exceeding the association limit drops the oldest message on any stream
+    a.handle_data(&first_fragment(3, 3, 0, 100))?;
+    assert_eq!(vec![(1, 100)], dropped_messages(&mut a));
+
+    let total: usize = a
+        .streams
+        .values()
+        .map(|s| s.reassembly_queue.get_num_bytes())
+        .sum();
+    assert_eq!(200, total);
+    assert_eq!(
+        a.max_receive_buffer_size - 200,
+        a.get_my_receiver_window_credit(),
+        "receive window should account for the dropped bytes"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_forward_tsn_reclaims_ordered_on_unordered_stream() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.negotiated_extensions.insert(SctpExtension::ForwardTsn);
+    a.peer_last_tsn = 0;
+
+    a.handle_data(&first_fragment(1, 1, 0, 100))?;
+    a.stream(1)?
+        .set_reliability_params(true, ReliabilityType::Reliable, 0)?;
+
+    let fwdtsn = ChunkForwardTsn {
+        new_cumulative_tsn: 2,
+        streams: vec![ChunkForwardTsnStream {
+            identifier: 1,
+            sequence: 0,
+        }],
+    };
+    a.handle_forward_tsn(&fwdtsn)?;
+
+    let q = &a.streams.get(&1).unwrap().reassembly_queue;
+    assert_eq!(
+        0,
+        q.get_num_bytes(),
+        "incomplete message should be reclaimed"
+    );
+    assert_eq!(1, q.next_ssn);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_forward_tsn_across_tsn_wraparound() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.negotiated_extensions.insert(SctpExtension::ForwardTsn);
+    a.peer_last_tsn = u32::MAX - 2;
+
+    a.handle_data(&first_fragment(u32::MAX, 1, 0, 100))?;
+    assert!(a.payload_queue.get(u32::MAX).is_some());
+
+    let fwdtsn = ChunkForwardTsn {
+        new_cumulative_tsn: 1,
+        streams: vec![ChunkForwardTsnStream {
+            identifier: 1,
+            sequence: 0,
+        }],
+    };
+    a.handle_forward_tsn(&fwdtsn)?;
+
+    assert_eq!(1, a.peer_last_tsn);
+    assert!(
+        a.payload_queue.is_empty(),
+        "skipped chunks should be removed from the payload queue"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_peer_last_tsn_across_tsn_wraparound() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = u32::MAX - 3;
+
+    let tsns = [u32::MAX - 2, u32::MAX - 1, u32::MAX, 0, 1, 2];
+    for (i, tsn) in tsns.into_iter().enumerate() {
+        let c = first_fragment(tsn, 1, i as u16, 100);
+        assert!(a.payload_queue.push(c, a.peer_last_tsn));
+    }
+
+    a.handle_peer_last_tsn_and_acknowledgement(false)?;
+
+    assert_eq!(2, a.peer_last_tsn);
+    assert!(a.payload_queue.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_forward_tsn_skipping_too_many_tsns() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.negotiated_extensions.insert(SctpExtension::ForwardTsn);
+    a.peer_last_tsn = u32::MAX - 10;
+
+    let fwdtsn = ChunkForwardTsn {
+        new_cumulative_tsn: 0x7000_0000,
+        streams: vec![],
+    };
+    a.handle_forward_tsn(&fwdtsn)?;
+
+    assert_eq!(
+        (u32::MAX - 10).wrapping_add(u16::MAX as u32 + 1),
+        a.peer_last_tsn
+    );
+
+    Ok(())
+}
+
+fn data_datagram(a: &Association, tsn: u32, now: Instant) -> Transmit {
+    let raw = Packet {
+        common_header: CommonHeader {
+            source_port: a.destination_port,
+            destination_port: a.source_port,
+            verification_tag: a.my_verification_tag,
+        },
+        chunks: vec![Box::new(ChunkPayloadData {
+            beginning_fragment: true,
+            ending_fragment: true,
+            tsn,
+            stream_identifier: 1,
+            stream_sequence_number: (tsn - 1) as u16,
+            user_data: Bytes::from(vec![tsn as u8; 100]),
+            ..Default::default()
+        })],
+    }
+    .marshal()
+    .unwrap();
+
+    Transmit {
+        now,
+        remote: a.remote_addr(),
+        ecn: None,
+        local_ip: None,
+        payload: Payload::PartialDecode(crate::packet::PartialDecode::unmarshal(&raw).unwrap()),
+    }
+}
+
+fn chunk_datagram(
+    a: &Association,
+    chunk: Box<dyn Chunk + Send + Sync>,
+    now: Instant,
+) -> AssociationEvent {
+    let raw = Packet {
+        common_header: CommonHeader {
+            source_port: a.destination_port,
+            destination_port: a.source_port,
+            verification_tag: a.my_verification_tag,
+        },
+        chunks: vec![chunk],
+    }
+    .marshal()
+    .unwrap();
+
+    AssociationEvent(AssociationEventInner::Datagram(Transmit {
+        now,
+        remote: a.remote_addr(),
+        ecn: None,
+        local_ip: None,
+        payload: Payload::PartialDecode(crate::packet::PartialDecode::unmarshal(&raw).unwrap()),
+    }))
+}
+
+fn packets_on_wire(a: &mut Association, now: Instant) -> Vec<Packet> {
+    let mut packets = vec![];
+    while let Some(t) = a.poll_transmit(now) {
+        if let Payload::RawEncode(raws) = t.payload {
+            packets.extend(raws.iter().map(|raw| Packet::unmarshal(raw).unwrap()));
+        }
+    }
+    packets
+}
+
+#[test]
+fn test_assoc_poll_transmit_skips_gather_when_idle() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    let now = Instant::now();
+
+    assert!(a.poll_transmit(now).is_none());
+    assert!(!a.needs_gather, "an empty gather leaves nothing to do");
+
+    // Not gathered as nothing asked for a write
+    let packet = a.create_packet(vec![Box::<ChunkShutdownComplete>::default()]);
+    a.control_queue.push_back(packet);
+    assert!(a.poll_transmit(now).is_none());
+    assert_eq!(1, a.control_queue.len());
+
+    a.awake_write_loop();
+    assert!(a.poll_transmit(now).is_some());
+    assert!(a.control_queue.is_empty());
+    // Gathered once more in case something was held back
+    assert!(a.needs_gather);
+    assert!(a.poll_transmit(now).is_none());
+    assert!(!a.needs_gather);
+
+    // Expiring timers wake it up as well
+    a.timers.start(Timer::Ack, now, ACK_INTERVAL);
+    a.ack_state = AckState::Delay;
+    a.handle_timeout(now + Duration::from_millis(ACK_INTERVAL));
+    assert!(a.poll_transmit(now).is_some());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_closed_association_lingers() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    let now = Instant::now();
+
+    a.close()?;
+    assert!(packets_on_wire(&mut a, now).is_empty());
+    let linger = Duration::from_millis(2 * a.rto_mgr.get_rto());
+    assert_eq!(Some(linger), a.timer_remaining(Timer::Close, now));
+    assert!(!a.is_drained());
+
+    // Late DATA is answered with an ABORT, without reviving the association
+    a.handle_event(AssociationEvent(AssociationEventInner::Datagram(
+        data_datagram(&a, 1, now),
+    )));
+    let packets = packets_on_wire(&mut a, now);
+    assert_eq!(1, packets.len());
+    assert_eq!(
+        a.my_verification_tag,
+        packets[0].common_header.verification_tag
+    );
+    let abort = packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkAbort>()
+        .expect("ABORT");
+    assert!(abort.verification_tag_reflected);
+    assert_eq!(AssociationState::Closed, a.state());
+    assert!(a.payload_queue.is_empty());
+    assert!(a.poll().is_none());
+
+    // A retransmitted SHUTDOWN ACK still gets its SHUTDOWN COMPLETE
+    a.handle_event(chunk_datagram(&a, Box::new(ChunkShutdownAck {}), now));
+    let packets = packets_on_wire(&mut a, now);
+    assert_eq!(1, packets.len());
+    let complete = packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkShutdownComplete>()
+        .expect("SHUTDOWN COMPLETE");
+    assert!(complete.verification_tag_reflected);
+
+    // ABORTs are never answered
+    a.handle_event(chunk_datagram(&a, Box::<ChunkAbort>::default(), now));
+    assert!(packets_on_wire(&mut a, now).is_empty());
+    assert!(a.poll_endpoint_event().is_none());
+
+    // The endpoint forgets the association once it is done lingering
+    a.handle_timeout(now + linger);
+    assert!(a.is_drained());
+    assert!(a.poll_endpoint_event().is_some_and(|e| e.is_drained()));
+    assert!(a.poll_timeout().is_none());
+    a.handle_event(AssociationEvent(AssociationEventInner::Datagram(
+        data_datagram(&a, 2, now + linger),
+    )));
+    assert!(packets_on_wire(&mut a, now + linger).is_empty());
+
+    Ok(())
+}
+
+fn sacks_on_wire(a: &mut Association, now: Instant) -> Vec<u32> {
+    let mut sacks = vec![];
+    while let Some(t) = a.poll_transmit(now) {
+        if let Payload::RawEncode(raws) = t.payload {
+            for raw in &raws {
+                let p = Packet::unmarshal(raw).unwrap();
+                sacks.extend(p.chunks.iter().filter_map(|c| {
+                    c.as_any()
+                        .downcast_ref::<ChunkSelectiveAck>()
+                        .map(|sack| sack.cumulative_tsn_ack)
+                }));
+            }
+        }
+    }
+    sacks
+}
+
+#[test]
+fn test_assoc_handle_datagrams_matches_handle_event() -> Result<()> {
+    let now = Instant::now();
+    let mut receivers = vec![];
+    for _ in 0..2 {
+        let mut a = create_association(TransportConfig::default());
+        a.set_state(AssociationState::Established);
+        a.timers.stop(Timer::T1Init);
+        a.control_queue.clear();
+        a.peer_last_tsn = 0;
+        receivers.push(a);
+    }
+    let (single, batched) = receivers.split_at_mut(1);
+    let (single, batched) = (&mut single[0], &mut batched[0]);
+
+    for tsn in 1..=8 {
+        let t = data_datagram(single, tsn, now);
+        single.handle_event(AssociationEvent(AssociationEventInner::Datagram(t)));
+    }
+    let datagrams: Vec<Transmit> = (1..=8)
+        .map(|tsn| data_datagram(batched, tsn, now))
+        .collect();
+    batched.handle_datagrams(datagrams, now);
+
+    for a in [&mut *single, &mut *batched] {
+        assert_eq!(8, a.peer_last_tsn);
+        assert_eq!(AckState::Immediate, a.ack_state);
+        let messages: Vec<Bytes> = std::iter::from_fn(|| a.read_message())
+            .map(|m| m.data)
+            .collect();
+        assert_eq!(
+            (1..=8u8)
+                .map(|i| Bytes::from(vec![i; 100]))
+                .collect::<Vec<_>>(),
+            messages
+        );
+        assert_eq!(vec![8], sacks_on_wire(a, now));
+    }
+
+    // A lone packet still gets a delayed SACK
+    let t = data_datagram(batched, 9, now);
+    batched.handle_datagrams(vec![t], now);
+    assert_eq!(AckState::Delay, batched.ack_state);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_set_max_receive_buffer_size() -> Result<()> {
+    let mut a = create_association(TransportConfig::default().with_max_receive_buffer_size(1000));
+    a.set_state(AssociationState::Established);
+    a.handshake_completed = true;
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.peer_last_tsn = 0;
+    a.set_ack_mode(AckMode::NoDelay);
+    let now = Instant::now();
+
+    let a_rwnds = |a: &mut Association| -> Vec<u32> {
+        packets_on_wire(a, now)
+            .iter()
+            .flat_map(|p| &p.chunks)
+            .filter_map(|c| c.as_any().downcast_ref::<ChunkSelectiveAck>())
+            .map(|sack| sack.advertised_receiver_window_credit)
+            .collect()
+    };
+
+    let datagrams: Vec<Transmit> = (1..=3).map(|tsn| data_datagram(&a, tsn, now)).collect();
+    a.handle_datagrams(datagrams, now);
+    assert_eq!(vec![700], a_rwnds(&mut a));
+
+    // A larger window is announced right away
+    a.set_max_receive_buffer_size(2000);
+    assert_eq!(2000, a.max_receive_buffer_size());
+    assert_eq!(vec![1700], a_rwnds(&mut a));
+
+    // A buffer smaller than the data it holds closes the window, keeping the data
+    a.set_max_receive_buffer_size(200);
+    assert!(
+        a_rwnds(&mut a).is_empty(),
+        "a shrinking window is not announced"
+    );
+    assert_eq!(0, a.get_my_receiver_window_credit());
+    let t = data_datagram(&a, 4, now);
+    a.handle_event(AssociationEvent(AssociationEventInner::Datagram(t)));
+    assert_eq!(vec![0], a_rwnds(&mut a));
+    assert_eq!(3, a.peer_last_tsn, "DATA beyond the window is dropped");
+    assert_eq!(3, std::iter::from_fn(|| a.read_message()).count());
+
+    a.set_max_receive_buffer_size(1000);
+    assert_eq!(vec![1000], a_rwnds(&mut a));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_pending_events() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    assert!(!a.has_events());
+    assert_eq!(0, a.event_count());
+
+    a.events.push_back(Event::Connected);
+    a.events.push_back(Event::DatagramReceived);
+    a.error = Some(AssociationError::TimedOut);
+    assert!(a.has_events());
+    assert_eq!(3, a.event_count());
+
+    assert!(matches!(a.poll(), Some(Event::Connected)));
+    assert_eq!(2, a.event_count());
+
+    let events = a.clear_events();
+    assert_eq!(2, events.len(), "{:?}", events);
+    assert!(matches!(events[0], Event::DatagramReceived));
+    assert!(matches!(
+        events[1],
+        Event::AssociationLost {
+            reason: AssociationError::TimedOut
+        }
+    ));
+    assert!(!a.has_events());
+    assert_eq!(0, a.event_count());
+    assert!(a.poll().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_coalesces_receive_events() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.peer_last_tsn = 0;
+    let now = Instant::now();
+
+    let n_fragments = 50u32;
+    let fragment = |a: &Association, tsn: u32| {
+        let raw = Packet {
+            common_header: CommonHeader {
+                source_port: a.destination_port,
+                destination_port: a.source_port,
+                verification_tag: a.my_verification_tag,
+            },
+            chunks: vec![Box::new(ChunkPayloadData {
+                beginning_fragment: tsn == 1,
+                ending_fragment: tsn == n_fragments,
+                tsn,
+                stream_identifier: 1,
+                user_data: Bytes::from(vec![0; 1000]),
+                ..Default::default()
+            })],
+        }
+        .marshal()
+        .unwrap();
+        Transmit {
+            now,
+            remote: a.remote_addr(),
+            ecn: None,
+            local_ip: None,
+            payload: Payload::PartialDecode(crate::packet::PartialDecode::unmarshal(&raw).unwrap()),
+        }
+    };
+    let datagrams: Vec<Transmit> = (1..=n_fragments).map(|tsn| fragment(&a, tsn)).collect();
+    a.handle_datagrams(datagrams, now);
+
+    let events: Vec<Event> = std::iter::from_fn(|| a.poll()).collect();
+    assert_eq!(4, events.len(), "{:?}", events);
+    assert!(matches!(
+        events[0],
+        Event::Stream(StreamEvent::OpenedWithPpi { id: 1, .. })
+    ));
+    #[allow(deprecated)]
+    {
+        assert!(matches!(events[1], Event::Stream(StreamEvent::Opened)));
+    }
+    assert!(matches!(events[2], Event::DatagramReceived));
+    assert!(matches!(
+        events[3],
+        Event::Stream(StreamEvent::Readable { id: 1 })
+    ));
+
+    // Packets handled one by one get a DatagramReceived each, but the stream
+    // is announced readable only once until that event is polled
+    for tsn in n_fragments + 1..=n_fragments + 3 {
+        let t = data_datagram(&a, tsn, now);
+        a.handle_event(AssociationEvent(AssociationEventInner::Datagram(t)));
+    }
+    let events: Vec<Event> = std::iter::from_fn(|| a.poll()).collect();
+    let n_received = events
+        .iter()
+        .filter(|e| matches!(e, Event::DatagramReceived))
+        .count();
+    let n_readable = events
+        .iter()
+        .filter(|e| matches!(e, Event::Stream(StreamEvent::Readable { .. })))
+        .count();
+    assert_eq!((3, 1), (n_received, n_readable));
+
+    let t = data_datagram(&a, n_fragments + 4, now);
+    a.handle_event(AssociationEvent(AssociationEventInner::Datagram(t)));
+    let events: Vec<Event> = std::iter::from_fn(|| a.poll()).collect();
+    assert!(matches!(
+        events[..],
+        [
+            Event::DatagramReceived,
+            Event::Stream(StreamEvent::Readable { id: 1 })
+        ]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_inbound_timestamps_going_backwards() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.peer_last_tsn = 0;
+
+    let now = Instant::now();
+    let later = now + Duration::from_secs(2);
+    let t = data_datagram(&a, 1, later);
+    a.handle_event(AssociationEvent(AssociationEventInner::Datagram(t)));
+    assert_eq!(Some(later), a.last_received_time);
+    let ack_timer = a.timers.get(Timer::Ack);
+    assert_eq!(Some(later + Duration::from_millis(ACK_INTERVAL)), ack_timer);
+
+    for (tsn, at) in [(2, now), (3, now + Duration::from_secs(1))] {
+        let t = data_datagram(&a, tsn, at);
+        a.handle_event(AssociationEvent(AssociationEventInner::Datagram(t)));
+        assert_eq!(Some(later), a.last_received_time);
+    }
+    assert_eq!(3, a.peer_last_tsn);
+
+    // A SACK for data sent after the packet's timestamp does not make for a
+    // negative RTT
+    a.inflight_queue.push_no_check(ChunkPayloadData {
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn: a.my_next_tsn,
+        stream_identifier: 1,
+        user_data: Bytes::from_static(b"ABC"),
+        nsent: 1,
+        since: Some(later),
+        ..Default::default()
+    });
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: a.my_next_tsn,
+        advertised_receiver_window_credit: 1024 * 1024,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+    a.my_next_tsn += 1;
+    let raw = Packet {
+        common_header: CommonHeader {
+            source_port: a.destination_port,
+            destination_port: a.source_port,
+            verification_tag: a.my_verification_tag,
+        },
+        chunks: vec![Box::new(sack)],
+    }
+    .marshal()?;
+    a.handle_event(AssociationEvent(AssociationEventInner::Datagram(
+        Transmit {
+            now,
+            remote: a.remote_addr(),
+            ecn: None,
+            local_ip: None,
+            payload: Payload::PartialDecode(crate::packet::PartialDecode::unmarshal(&raw)?),
+        },
+    )));
+    assert!(a.inflight_queue.is_empty());
+
+    Ok(())
+}
+
+fn poll_heartbeats(a: &mut Association, now: Instant) -> usize {
+    let mut n = 0;
+    while let Some(t) = a.poll_transmit(now) {
+        if let Payload::RawEncode(raws) = t.payload {
+            for raw in &raws {
+                let p = Packet::unmarshal(raw).unwrap();
+                n += p
+                    .chunks
+                    .iter()
+                    .filter(|c| c.as_any().downcast_ref::<ChunkHeartbeat>().is_some())
+                    .count();
+            }
+        }
+    }
+    n
+}
+
+#[test]
+fn test_assoc_set_peer_addr_params_heartbeat_interval() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+
+    let params = PeerAddrParams {
+        heartbeat_interval: Some(Duration::from_secs(2)),
+        rto_initial: Duration::from_millis(500),
+        rto_min: Duration::from_millis(100),
+        rto_max: Duration::from_secs(10),
+        ..Default::default()
+    };
+    let remote = a.remote_addr();
+    a.set_peer_addr_params(remote, params)?;
+    assert_eq!(Some(params), a.peer_addr_params());
+    assert_eq!(Duration::from_millis(500), a.rto());
+
+    let mut now = Instant::now();
+    assert_eq!(0, poll_heartbeats(&mut a, now));
+
+    // HB.interval + RTO between heartbeats
+    for _ in 0..3 {
+        let next = a.poll_timeout().expect("heartbeat timer should be running");
+        assert_eq!(Duration::from_millis(2500), next - now);
+        a.handle_timeout(next);
+        now = next;
+        assert_eq!(1, poll_heartbeats(&mut a, now));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_heartbeat_ack_measures_rtt() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    let remote = a.remote_addr();
+    a.set_peer_addr_params(
+        remote,
+        PeerAddrParams {
+            heartbeat_interval: Some(Duration::from_secs(1)),
+            ..Default::default()
+        },
+    )?;
+    let _ = a.poll_transmit(Instant::now());
+
+    let sent_at = a.poll_timeout().expect("heartbeat timer should be running");
+    a.handle_timeout(sent_at);
+    let mut infos = vec![];
+    while let Some(t) = a.poll_transmit(sent_at) {
+        if let Payload::RawEncode(raws) = t.payload {
+            for raw in &raws {
+                for c in Packet::unmarshal(raw)?.chunks {
+                    if let Some(hb) = c.as_any().downcast_ref::<ChunkHeartbeat>() {
+                        infos.push(hb.params[0].clone());
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(1, infos.len());
+
+    let (source_port, destination_port) = (a.destination_port, a.source_port);
+    let verification_tag = a.my_verification_tag;
+    let heartbeat_ack = |info| Packet {
+        common_header: CommonHeader {
+            source_port,
+            destination_port,
+            verification_tag,
+        },
+        chunks: vec![Box::new(ChunkHeartbeatAck { params: vec![info] })],
+    };
+
+    // An unknown Heartbeat Information yields no sample
+    let forged = heartbeat_ack(Box::new(ParamHeartbeatInfo {
+        heartbeat_information: Bytes::from_static(b"12345678"),
+    }));
+    a.handle_inbound(forged, sent_at + Duration::from_millis(10))?;
+    assert_eq!(Duration::ZERO, a.rtt());
+
+    let ack = heartbeat_ack(infos.remove(0));
+    a.handle_inbound(ack, sent_at + Duration::from_millis(80))?;
+    assert_eq!(Duration::from_millis(80), a.rtt());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_set_peer_addr_params_heartbeat_failure() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+
+    let remote = a.remote_addr();
+    a.set_peer_addr_params(
+        remote,
+        PeerAddrParams {
+            heartbeat_interval: Some(Duration::from_secs(1)),
+            max_retrans: 2,
+            ..Default::default()
+        },
+    )?;
+
+    let mut now = Instant::now();
+    poll_heartbeats(&mut a, now);
+
+    // A HEARTBEAT ACK clears the error counter
+    for _ in 0..2 {
+        now = a.poll_timeout().unwrap();
+        a.handle_timeout(now);
+        assert_eq!(1, poll_heartbeats(&mut a, now));
+    }
+    a.handle_heartbeat_ack(&ChunkHeartbeatAck::default(), now);
+    for _ in 0..2 {
+        now = a.poll_timeout().unwrap();
+        a.handle_timeout(now);
+        assert_eq!(1, poll_heartbeats(&mut a, now));
+    }
+    assert!(!a.is_closed());
+
+    now = a.poll_timeout().unwrap();
+    a.handle_timeout(now);
+    assert!(a.is_closed());
+    assert!(matches!(
+        a.poll(),
+        Some(Event::AssociationLost {
+            reason: AssociationError::TimedOut
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_set_peer_addr_params_invalid() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+
+    let other = SocketAddr::from_str("10.0.0.1:5000").unwrap();
+    assert_eq!(
+        Err(Error::ErrPeerAddrUnknown),
+        a.set_peer_addr_params(other, PeerAddrParams::default())
+    );
+
+    let remote = a.remote_addr();
+    assert_eq!(
+        Err(Error::ErrPeerAddrParamsInvalid),
+        a.set_peer_addr_params(
+            remote,
+            PeerAddrParams {
+                rto_min: Duration::from_secs(5),
+                ..Default::default()
+            }
+        )
+    );
+    assert_eq!(None, a.peer_addr_params());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_reassembly_queue_full_drops_data() -> Result<()> {
+    let mut a = create_association(TransportConfig::default().with_max_receive_buffer_size(10_000));
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 0;
+    // Only the reassembly queue limit is under test here
+    a.payload_queue = PayloadQueue::new();
+
+    // TSN 1 never arrives, so every later fragment is out of order and is accepted
+    // as a missing chunk even after the receive window has closed.
+    for tsn in (2..1002).rev() {
+        a.immediate_ack_triggered = false;
+        a.handle_data(&first_fragment(tsn, 1, tsn as u16, 100))?;
+    }
+    assert!(
+        a.immediate_ack_triggered,
+        "dropped DATA should trigger an immediate SACK"
+    );
+
+    let q = &a.streams.get(&1).unwrap().reassembly_queue;
+    assert_eq!(10_000, q.get_num_bytes());
+    assert_eq!(100, a.payload_queue.len(), "dropped TSNs must not be acked");
+
+    let sack = a.create_selective_ack_chunk();
+    assert_eq!(0, sack.advertised_receiver_window_credit);
+    assert_eq!(0, sack.cumulative_tsn_ack);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_reassembly_queue_full_accepts_missing_chunk() -> Result<()> {
+    let mut a = create_association(TransportConfig::default().with_max_receive_buffer_size(10_000));
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 0;
+    a.payload_queue = PayloadQueue::new();
+
+    // Messages 1.. fill the queue while message 0 is missing
+    for tsn in 2..102 {
+        a.handle_data(&first_fragment(tsn, 1, tsn as u16 - 1, 100))?;
+    }
+    assert!(a.streams.get(&1).unwrap().reassembly_queue.is_full());
+
+    a.handle_data(&ChunkPayloadData {
+        ending_fragment: true,
+        ..first_fragment(1, 1, 0, 100)
+    })?;
+    assert_eq!(101, a.create_selective_ack_chunk().cumulative_tsn_ack);
+
+    let chunks = a.stream(1)?.read_sctp()?.unwrap();
+    assert_eq!(0, chunks.ssn);
+    assert_eq!(100, chunks.len());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_send_empty_message_is_ignored() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.create_stream(1, None, PayloadProtocolIdentifier::Binary);
+
+    a.send_with_options(1, Bytes::new(), SendOptions::default())?;
+    assert!(a.pending_queue.is_empty(), "nothing should be queued");
+
+    a.send_with_options(1, Bytes::from_static(b"ABC"), SendOptions::default())?;
+    let (chunks, sis_to_reset) = a.pop_pending_data_chunks_to_send(Instant::now());
+    assert!(sis_to_reset.is_empty(), "stream must not be reset");
+    assert_eq!(1, chunks.len());
+    assert_eq!(0, chunks[0].stream_sequence_number);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_marshal_packet_after_error() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+
+    let bad = a.create_packet(vec![Box::new(ChunkHeartbeatAck { params: vec![] })]);
+    assert!(a.marshal_packet(&bad).is_err());
+
+    let p = a.create_packet(vec![Box::new(ChunkCookieAck {})]);
+    assert_eq!(p.marshal()?, a.marshal_packet(&p)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_writable_after_sack_opens_window() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10]);
+    a.create_stream(1, None, PayloadProtocolIdentifier::Binary);
+    a.congestion.rwnd = 0;
+    let now = Instant::now();
+
+    a.send_with_options(1, Bytes::from_static(b"DEF"), SendOptions::default())?;
+    while a.poll_transmit(now).is_some() {}
+    assert_eq!(3, a.bytes_pending(), "blocked by rwnd");
+    assert_eq!(0, a.send_capacity());
+    while a.poll().is_some() {}
+
+    // A SACK that does not open the window is not worth a wakeup
+    a.handle_sack(
+        &ChunkSelectiveAck {
+            cumulative_tsn_ack: 9,
+            advertised_receiver_window_credit: 3,
+            gap_ack_blocks: vec![],
+            duplicate_tsn: vec![],
+        },
+        now,
+    )?;
+    assert_eq!(0, a.send_capacity());
+    assert!(!std::iter::from_fn(|| a.poll()).any(|e| matches!(e, Event::Writable)));
+
+    a.handle_sack(
+        &ChunkSelectiveAck {
+            cumulative_tsn_ack: 10,
+            advertised_receiver_window_credit: 65535,
+            gap_ack_blocks: vec![],
+            duplicate_tsn: vec![],
+        },
+        now,
+    )?;
+    assert_eq!(
+        std::cmp::min(a.congestion.cwnd as usize, 65535),
+        a.send_capacity()
+    );
+    let mut n_writable = 0;
+    while let Some(e) = a.poll() {
+        if matches!(e, Event::Writable) {
+            n_writable += 1;
+        }
+    }
+    assert_eq!(1, n_writable, "writable should be reported once");
+
+    while a.poll_transmit(now).is_some() {}
+    assert_eq!(0, a.bytes_pending());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_payload_queue_full_closes_window() -> Result<()> {
+    // 10 chunks fit the payload queue
+    let mut a = create_association(TransportConfig::default().with_max_receive_buffer_size(14_280));
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 0;
+
+    // TSN 1 is missing, so everything else waits in the payload queue
+    for tsn in 2..20 {
+        a.immediate_ack_triggered = false;
+        a.handle_data(&first_fragment(tsn, 1, tsn as u16, 10))?;
+    }
+    assert!(a.immediate_ack_triggered);
+    assert_eq!(10, a.payload_queue.len());
+    assert_eq!(1.0, a.receive_memory_pressure());
+
+    let sack = a.create_selective_ack_chunk();
+    assert_eq!(0, sack.advertised_receiver_window_credit);
+    assert_eq!(0, sack.cumulative_tsn_ack);
+
+    // The missing TSN is still accepted and drains the queue
+    a.handle_data(&first_fragment(1, 1, 1, 10))?;
+    assert_eq!(11, a.peer_last_tsn);
+    assert!(a.payload_queue.is_empty());
+    assert_ne!(
+        0,
+        a.create_selective_ack_chunk()
+            .advertised_receiver_window_credit
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_sack_malformed_gap_blocks() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    let now = Instant::now();
+
+    let malformed = [
+        vec![GapAckBlock { start: 3, end: 1 }],
+        vec![GapAckBlock { start: 0, end: 1 }],
+        vec![
+            GapAckBlock { start: 3, end: 3 },
+            GapAckBlock { start: 1, end: 1 },
+        ],
+        vec![
+            GapAckBlock { start: 1, end: 2 },
+            GapAckBlock { start: 2, end: 3 },
+        ],
+    ];
+    for gap_ack_blocks in malformed {
+        a.handle_sack(
+            &ChunkSelectiveAck {
+                cumulative_tsn_ack: 10,
+                advertised_receiver_window_credit: 65535,
+                gap_ack_blocks,
+                duplicate_tsn: vec![],
+            },
+            now,
+        )?;
+    }
+
+    // Dropped without touching the inflight queue
+    assert_eq!(AssociationState::Established, a.state());
+    assert_eq!(9, a.cumulative_tsn_ack_point);
+    assert_eq!(4, a.inflight_queue.len());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_sack_too_many_gap_blocks() -> Result<()> {
+    let tsns: Vec<u32> = (10..=2010).collect();
+    let mut a = create_established_association_with_inflight(&tsns);
+    let now = Instant::now();
+
+    // 1000 gap blocks acknowledging every other TSN from 11 on
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 9,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: (1..=1000)
+            .map(|i| GapAckBlock {
+                start: 2 * i,
+                end: 2 * i,
+            })
+            .collect(),
+        duplicate_tsn: vec![],
+    };
+    a.handle_event(chunk_datagram(&a, Box::new(sack), now));
+
+    // Only the first 256 blocks were processed
+    assert_eq!(AssociationState::Established, a.state());
+    assert!(a.inflight_queue.get(11).unwrap().acked);
+    assert!(a.inflight_queue.get(9 + 2 * 256).unwrap().acked);
+    assert!(!a.inflight_queue.get(9 + 2 * 257).unwrap().acked);
+    assert_eq!(1, a.inflight_queue.get(10).unwrap().miss_indicator);
+    assert_eq!(0, a.inflight_queue.get(9 + 2 * 300).unwrap().miss_indicator);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_max_gap_ack_blocks_config() -> Result<()> {
+    let mut a = create_association(TransportConfig::default().with_max_gap_ack_blocks(1));
+    a.set_state(AssociationState::Established);
+    a.cumulative_tsn_ack_point = 9;
+    a.my_next_tsn = 14;
+    for tsn in 10..14 {
+        a.inflight_queue.push_no_check(data_chunk(tsn));
+    }
+
+    let mut sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 9,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![
+            GapAckBlock { start: 2, end: 2 },
+            GapAckBlock { start: 4, end: 4 },
+        ],
+        duplicate_tsn: vec![],
+    };
+    a.handle_sack(&sack, Instant::now())?;
+
+    assert!(a.inflight_queue.get(11).unwrap().acked);
+    assert!(!a.inflight_queue.get(13).unwrap().acked);
+
+    // A block cut off from a later SACK does not renege on its TSNs
+    sack.gap_ack_blocks = vec![GapAckBlock { start: 2, end: 4 }];
+    a.handle_sack(&sack, Instant::now())?;
+    sack.gap_ack_blocks = vec![
+        GapAckBlock { start: 2, end: 2 },
+        GapAckBlock { start: 4, end: 4 },
+    ];
+    a.handle_sack(&sack, Instant::now())?;
+
+    assert!(a.inflight_queue.get(13).unwrap().acked);
+    assert_eq!(0, a.stats.get_num_reneged_tsns());
+
+    Ok(())
+}
+
+fn data_chunk(tsn: u32) -> ChunkPayloadData {
+    ChunkPayloadData {
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn,
+        stream_identifier: 1,
+        user_data: Bytes::from_static(b"ABC"),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_assoc_ack_mode_always_delay() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.set_ack_mode(AckMode::AlwaysDelay);
+    a.peer_last_tsn = 0;
+
+    a.handle_data(&data_chunk(1))?;
+    assert_eq!(1, a.peer_last_tsn);
+    assert!(a.delayed_ack_triggered, "delayed sack should be triggered");
+    assert!(
+        !a.immediate_ack_triggered,
+        "immediate sack should NOT be triggered"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_ack_mode_immediate_on_loss() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.set_ack_mode(AckMode::ImmediateOnLoss);
+    assert_eq!(AckMode::ImmediateOnLoss, a.ack_mode());
+    a.peer_last_tsn = 0;
+
+    // In-order delivery is not acknowledged right away, even on every second packet
+    a.handle_data(&data_chunk(1))?;
+    assert!(a.delayed_ack_triggered, "delayed sack should be triggered");
+    a.ack_state = AckState::Delay;
+    a.delayed_ack_triggered = false;
+    a.handle_data(&data_chunk(2))?;
+    assert!(
+        !a.immediate_ack_triggered,
+        "immediate sack should NOT be triggered"
+    );
+
+    // A gap is reported immediately
+    a.handle_data(&data_chunk(4))?;
+    assert_eq!(2, a.peer_last_tsn);
+    assert!(
+        a.immediate_ack_triggered,
+        "immediate sack should be triggered"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_enforce_negotiated_stream_limits() -> Result<()> {
+    let mut a = create_association(
+        TransportConfig::default()
+            .with_max_num_outbound_streams(10)
+            .with_max_num_inbound_streams(4),
+    );
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 0;
+    assert_eq!(10, a.max_outbound_streams());
+    assert_eq!(4, a.max_inbound_streams());
+
+    assert!(a.open_stream(9, PayloadProtocolIdentifier::Binary).is_ok());
+    assert_eq!(
+        Err(Error::ErrStreamIdExceedsLimit),
+        a.open_stream(10, PayloadProtocolIdentifier::Binary)
+            .map(|_| ())
+    );
+
+    let mut d = data_chunk(1);
+    d.stream_identifier = 4;
+    let reply = a.handle_data(&d)?;
+    assert_eq!(1, reply.len());
+    let cerr = reply[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkError>()
+        .expect("should reply with an ERROR chunk");
+    assert_eq!(1, cerr.error_causes.len());
+    assert_eq!(INVALID_STREAM_IDENTIFIER, cerr.error_causes[0].code);
+    assert_eq!(&[0, 4, 0, 0], &cerr.error_causes[0].raw[..]);
+
+    assert!(!a.streams.contains_key(&4), "stream should not be created");
+    assert_eq!(1, a.peer_last_tsn, "TSN should still be acknowledged");
+    assert!(
+        a.immediate_ack_triggered,
+        "immediate sack should be triggered"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_abort_on_data_without_user_data() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.peer_last_tsn = 0;
+
+    // An empty WebRTC message is padded with a single byte and accepted
+    let mut d = data_chunk(1);
+    d.payload_type = PayloadProtocolIdentifier::BinaryEmpty;
+    d.user_data = Bytes::from_static(&[0]);
+    a.handle_data(&d)?;
+    assert_eq!(1, a.peer_last_tsn);
+    assert_eq!(AssociationState::Established, a.state());
+
+    let mut d = data_chunk(2);
+    d.user_data = Bytes::new();
+    a.handle_data(&d)?;
+    assert_eq!(AssociationState::Closed, a.state());
+    assert_eq!(1, a.peer_last_tsn, "empty DATA should not be acknowledged");
+
+    let abort = a
+        .control_queue
+        .iter()
+        .find_map(|p| p.chunks[0].as_any().downcast_ref::<ChunkAbort>())
+        .expect("should queue an ABORT");
+    assert_eq!(1, abort.error_causes.len());
+    assert_eq!(NO_USER_DATA, abort.error_causes[0].code);
+    assert_eq!(&2u32.to_be_bytes(), &abort.error_causes[0].raw[..]);
+
+    let lost = std::iter::from_fn(|| a.poll()).any(|e| {
+        matches!(
+            e,
+            Event::AssociationLost {
+                reason: AssociationError::TransportError
+            }
+        )
+    });
+    assert!(lost, "should report the association as lost");
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_unregister_stream_drains_pending() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    a.open_stream(2, PayloadProtocolIdentifier::Binary)?;
+    for _ in 0..100 {
+        a.send_with_options(1, Bytes::from_static(b"ABC"), SendOptions::default())?;
+    }
+    a.send_with_options(2, Bytes::from_static(b"DEFG"), SendOptions::default())?;
+    assert_eq!(304, a.pending_queue.get_num_bytes());
+
+    a.unregister_stream(1);
+    assert_eq!(4, a.pending_queue.get_num_bytes());
+    assert_eq!(1, a.pending_queue.len());
+
+    // Only the data of the remaining stream is sent
+    let (chunks, sis_to_reset) = a.pop_pending_data_chunks_to_send(Instant::now());
+    assert!(sis_to_reset.is_empty());
+    assert_eq!(1, chunks.len());
+    assert_eq!(2, chunks[0].stream_identifier);
+    assert_eq!(0, a.pending_queue.get_num_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_stream_write_unordered() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.congestion.rwnd = 65535;
+
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    let mut s = a.stream(1)?;
+    assert_eq!(3000, s.write_unordered(&[0; 3000])?);
+    s.write(b"A")?;
+    s.set_unordered(true)?;
+    s.write(b"B")?;
+    s.write_ordered(b"C")?;
+
+    let (chunks, _) = a.pop_pending_data_chunks_to_send(Instant::now());
+    let fragments: Vec<_> = chunks.iter().filter(|c| c.user_data.len() > 1).collect();
+    assert!(fragments.len() > 1, "message should be fragmented");
+    assert!(fragments.iter().all(|c| c.unordered));
+
+    let find = |data: &[u8]| chunks.iter().find(|c| c.user_data[..] == *data).unwrap();
+    assert!(!find(b"A").unordered);
+    assert_eq!(0, find(b"A").stream_sequence_number);
+    assert!(find(b"B").unordered);
+    assert!(!find(b"C").unordered);
+    assert_eq!(
+        1,
+        find(b"C").stream_sequence_number,
+        "unordered messages should not consume a sequence number"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_drain_pending_for_stream_releases_buffer() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    for _ in 0..100 {
+        a.send_with_options(1, Bytes::from_static(b"ABC"), SendOptions::default())?;
+    }
+    assert_eq!(300, a.stream(1)?.buffered_amount()?);
+
+    assert_eq!(300, a.drain_pending_for_stream(1));
+    assert_eq!(0, a.pending_queue.get_num_bytes());
+    assert_eq!(0, a.stream(1)?.buffered_amount()?);
+    assert_eq!(0, a.drain_pending_for_stream(1));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_init_reports_unrecognized_params() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    let pkt = Packet {
+        common_header: CommonHeader {
+            source_port: 5001,
+            destination_port: 5002,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // 0xC00F must be reported, 0x800E is skipped silently
+    let reported = Bytes::from_static(&[0xC0, 0x0F, 0x00, 0x06, 0xAB, 0xCD]);
+    let skipped = Bytes::from_static(&[0x80, 0x0E, 0x00, 0x04]);
+    let mut init = ChunkInit {
+        initial_tsn: 1234,
+        num_outbound_streams: 1001,
+        num_inbound_streams: 1002,
+        initiate_tag: 5678,
+        advertised_receiver_window_credit: 512 * 1024,
+        params: vec![build_param(&reported)?, build_param(&skipped)?],
+        ..Default::default()
+    };
+    init.set_supported_extensions();
+
+    let packets = a.handle_init(&pkt, &init, Instant::now())?;
+    assert_eq!(1, packets.len());
+
+    // Round trip through the wire format to check the peer can parse it
+    let raw = packets[0].chunks[0].marshal()?;
+    let init_ack = ChunkInit::unmarshal(&raw)?;
+    assert!(init_ack.is_ack);
+
+    let unrecognized: Vec<&ParamUnrecognized> = init_ack
+        .params
+        .iter()
+        .filter_map(|p| p.as_any().downcast_ref::<ParamUnrecognized>())
+        .collect();
+    assert_eq!(1, unrecognized.len());
+    assert_eq!(reported, unrecognized[0].raw_param);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_init_stops_at_unrecognized_param() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    let pkt = Packet {
+        common_header: CommonHeader {
+            source_port: 5001,
+            destination_port: 5002,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    // 0x400D must be reported and ends processing, 0xC00F after it is never seen
+    let init = ChunkInit {
+        initial_tsn: 1234,
+        num_outbound_streams: 1001,
+        num_inbound_streams: 1002,
+        initiate_tag: 5678,
+        advertised_receiver_window_credit: 512 * 1024,
+        params: vec![
+            build_param(&Bytes::from_static(&[0x40, 0x0D, 0x00, 0x05, 0x01]))?,
+            build_param(&Bytes::from_static(&[0xC0, 0x0F, 0x00, 0x04]))?,
+        ],
+        ..Default::default()
+    };
+    let init = ChunkInit::unmarshal(&init.marshal()?)?;
+    assert_eq!(1, init.params.len());
+
+    let packets = a.handle_init(&pkt, &init, Instant::now())?;
+    let init_ack = packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkInit>()
+        .unwrap();
+    let unrecognized: Vec<&ParamUnrecognized> = init_ack
+        .params
+        .iter()
+        .filter_map(|p| p.as_any().downcast_ref::<ParamUnrecognized>())
+        .collect();
+    assert_eq!(1, unrecognized.len());
+    assert_eq!(
+        Bytes::from_static(&[0x40, 0x0D, 0x00, 0x05, 0x01]),
+        unrecognized[0].raw_param
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_init_ack_reports_unrecognized_params() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.control_queue.clear();
+    let pkt = Packet {
+        common_header: CommonHeader {
+            source_port: a.destination_port,
+            destination_port: a.source_port,
+            verification_tag: a.my_verification_tag,
+        },
+        ..Default::default()
+    };
+
+    let reported = Bytes::from_static(&[0xC0, 0x0F, 0x00, 0x05, 0xAB]);
+    let init_ack = ChunkInit {
+        is_ack: true,
+        initial_tsn: 1234,
+        num_outbound_streams: 1001,
+        num_inbound_streams: 1002,
+        initiate_tag: 5678,
+        advertised_receiver_window_credit: 512 * 1024,
+        params: vec![
+            Box::new(ParamStateCookie::new()),
+            build_param(&reported)?,
+            build_param(&Bytes::from_static(&[0x80, 0x0E, 0x00, 0x04]))?,
+        ],
+    };
+
+    let packets = a.handle_init_ack(&pkt, &init_ack, Instant::now())?;
+    assert_eq!(AssociationState::CookieEchoed, a.state());
+
+    // The COOKIE ECHO is queued ahead of the ERROR
+    assert_eq!(1, a.control_queue.len());
+    assert!(a.control_queue[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkCookieEcho>()
+        .is_some());
+
+    assert_eq!(1, packets.len());
+    let cerr = packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkError>()
+        .expect("should be an ERROR chunk");
+    assert_eq!(
+        vec![TypedErrorCause::UnrecognizedParameters {
+            params: Bytes::from_static(&[0xC0, 0x0F, 0x00, 0x05, 0xAB, 0x00, 0x00, 0x00]),
+        }],
+        cerr.typed_error_causes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_congestion_avoidance_requires_full_flight() -> Result<()> {
+    let now = Instant::now();
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 10,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+
+    // 12 bytes in flight, below cwnd, with more data waiting to be sent
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    a.congestion.cwnd = 20;
+    a.congestion.ssthresh = 10;
+    a.congestion.partial_bytes_acked = 17;
+    a.pending_queue.push(data_chunk(0));
+    a.handle_sack(&sack, now)?;
+    assert_eq!(
+        20, a.congestion.cwnd,
+        "cwnd should not grow when not fully utilized"
+    );
+    assert_eq!(20, a.congestion.partial_bytes_acked);
+
+    // 12 bytes in flight filling cwnd, nothing pending
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    a.congestion.cwnd = 12;
+    a.congestion.ssthresh = 10;
+    a.congestion.partial_bytes_acked = 9;
+    a.handle_sack(&sack, now)?;
+    assert_eq!(12 + a.mtu, a.congestion.cwnd, "cwnd should grow by one MTU");
+    assert_eq!(0, a.congestion.partial_bytes_acked);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_slow_start_requires_full_flight() -> Result<()> {
+    let now = Instant::now();
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 10,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+
+    // 12 bytes in flight, no room left for a full-sized chunk, nothing pending
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    let cwnd = 12 + a.max_payload_size - 1;
+    a.congestion.cwnd = cwnd;
+    a.congestion.ssthresh = u32::MAX;
+    a.handle_sack(&sack, now)?;
+    assert_eq!(
+        cwnd + 3,
+        a.congestion.cwnd,
+        "cwnd should grow by the bytes acked"
+    );
+
+    // 12 bytes in flight, far below cwnd, with more data waiting to be sent
+    let mut a = create_established_association_with_inflight(&[10, 11, 12, 13]);
+    a.congestion.cwnd = 100_000;
+    a.congestion.ssthresh = u32::MAX;
+    a.pending_queue.push(data_chunk(0));
+    a.handle_sack(&sack, now)?;
+    assert_eq!(
+        100_000, a.congestion.cwnd,
+        "cwnd should not grow when not fully utilized"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_unrecognized_chunk_type_actions() -> Result<()> {
+    // (chunk type, SACK processed, reported)
+    let tests = [
+        (0x3f, false, false),
+        (0x7f, false, true),
+        (0xbf, true, false),
+        (0xff, true, true),
+    ];
+
+    for (typ, processed, reported) in tests {
+        let mut a = create_established_association_with_inflight(&[10, 11]);
+        a.control_queue.clear();
+
+        let unknown = ChunkUnknown {
+            typ: ChunkType(typ),
+            flags: 0,
+            value: Bytes::from_static(&[0x1, 0x2, 0x3]),
+        };
+        let sack = ChunkSelectiveAck {
+            cumulative_tsn_ack: 10,
+            advertised_receiver_window_credit: 65535,
+            gap_ack_blocks: vec![],
+            duplicate_tsn: vec![],
+        };
+        let raw = Packet {
+            common_header: CommonHeader {
+                source_port: 5000,
+                destination_port: 5000,
+                verification_tag: 0,
+            },
+            chunks: vec![Box::new(unknown.clone()), Box::new(sack)],
+        }
+        .marshal()?;
+
+        let p = Packet::unmarshal(&raw)?;
+        assert_eq!(
+            if processed { 2 } else { 1 },
+            p.chunks.len(),
+            "type {:#x}",
+            typ
+        );
+        a.handle_inbound(p, Instant::now())?;
+
+        assert_eq!(AssociationState::Established, a.state());
+        assert_eq!(
+            if processed { 10 } else { 9 },
+            a.cumulative_tsn_ack_point,
+            "type {:#x}",
+            typ
+        );
+
+        let errors: Vec<Vec<TypedErrorCause>> = a
+            .control_queue
+            .iter()
+            .filter_map(|p| p.chunks[0].as_any().downcast_ref::<ChunkError>())
+            .map(|c| c.typed_error_causes())
+            .collect();
+        if reported {
+            assert_eq!(
+                vec![vec![TypedErrorCause::UnrecognizedChunkType {
+                    chunk: unknown.marshal()?
+                }]],
+                errors,
+                "type {:#x}",
+                typ
+            );
+        } else {
+            assert!(errors.is_empty(), "type {:#x}", typ);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_chunk_unhandled_type() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[10]);
+
+    let p = Packet {
+        common_header: CommonHeader {
+            source_port: a.destination_port,
+            destination_port: a.source_port,
+            verification_tag: a.my_verification_tag,
+        },
+        chunks: vec![Box::new(ChunkHeader {
+            typ: ChunkType(0x42),
+            flags: 0,
+            value_length: 0,
+        })],
+    };
+    let result = a.handle_inbound(p, Instant::now());
+    assert!(
+        matches!(result, Err(Error::ErrChunkTypeUnhandled(0x42))),
+        "error should carry the chunk type, got {:?}",
+        result
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_active_timers() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    let now = Instant::now();
+
+    a.timers.stop(Timer::T1Init);
+    assert!(a.active_timers().is_empty());
+
+    a.timers.start(Timer::T1Init, now, 1000);
+    a.timers.start(Timer::T3RTX, now, 3000);
+    a.timers.start(Timer::Ack, now, 200);
+
+    let active = a.active_timers();
+    assert_eq!(
+        active,
+        vec![
+            (Timer::T1Init, now + Duration::from_millis(1000)),
+            (Timer::T3RTX, now + Duration::from_millis(3000)),
+            (Timer::Ack, now + Duration::from_millis(200)),
+        ]
+    );
+
+    let later = now + Duration::from_millis(500);
+    assert_eq!(
+        a.timer_remaining(Timer::T3RTX, later),
+        Some(Duration::from_millis(2500))
+    );
+    assert_eq!(a.timer_remaining(Timer::Ack, later), Some(Duration::ZERO));
+    assert_eq!(a.timer_remaining(Timer::Heartbeat, later), None);
+
+    a.timers.stop(Timer::T3RTX);
+    assert!(!a.active_timers().iter().any(|(t, _)| *t == Timer::T3RTX));
+    assert_eq!(a.timer_remaining(Timer::T3RTX, later), None);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_abort_on_protocol_violation() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+
+    // An INIT ACK without a State Cookie cannot be answered
+    let p = Packet {
+        common_header: a.create_packet(vec![]).common_header,
+        chunks: vec![Box::new(ChunkInit {
+            is_ack: true,
+            initiate_tag: 1,
+            num_inbound_streams: 1,
+            num_outbound_streams: 1,
+            advertised_receiver_window_credit: 1500,
+            ..Default::default()
+        })],
+    };
+    let err = a.handle_inbound(p, Instant::now()).unwrap_err();
+    a.handle_inbound_error(err);
+
+    assert_eq!(AssociationState::Closed, a.state());
+    let abort = a
+        .control_queue
+        .back()
+        .and_then(|p| p.chunks[0].as_any().downcast_ref::<ChunkAbort>())
+        .expect("should send an ABORT");
+    assert_eq!(
+        vec![TypedErrorCause::ProtocolViolation {
+            information: Error::ErrInitAckNoCookie.to_string(),
+        }],
+        abort.typed_error_causes()
+    );
+    assert!(matches!(a.poll(), Some(Event::AssociationLost { .. })));
+
+    Ok(())
+}
+
+#[cfg(feature = "hmac-cookie")]
+#[test]
+fn test_assoc_handle_cookie_echo_signed() -> Result<()> {
+    let server_config = Arc::new(
+        ServerConfig::default()
+            .with_stateless_cookies(true)
+            .with_cookie_secret([7u8; 32]),
+    );
+    let new_server = || -> Result<(Association, ChunkCookieEcho)> {
+        let mut a = Association::new(
+            Some(server_config.clone()),
+            server_config.transport.clone(),
+            1200,
+            1,
+            SocketAddr::from_str("0.0.0.0:0").unwrap(),
+            None,
+            Instant::now(),
+        );
+        let pkt = Packet {
+            common_header: CommonHeader {
+                source_port: 5001,
+                destination_port: 5002,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let init = ChunkInit {
+            initial_tsn: 1234,
+            num_outbound_streams: 1,
+            num_inbound_streams: 1,
+            initiate_tag: 5678,
+            advertised_receiver_window_credit: 512 * 1024,
+            ..Default::default()
+        };
+        let packets = a.handle_init(&pkt, &init, Instant::now())?;
+        let init_ack = packets[0].chunks[0]
+            .as_any()
+            .downcast_ref::<ChunkInit>()
+            .unwrap();
+        let cookie = init_ack
+            .params
+            .iter()
+            .find_map(|p| p.as_any().downcast_ref::<ParamStateCookie>())
+            .unwrap()
+            .cookie
+            .clone();
+        Ok((a, ChunkCookieEcho { cookie }))
+    };
+
+    let (mut a, echo) = new_server()?;
+    a.handle_cookie_echo(&echo)?;
+    assert_eq!(AssociationState::Established, a.state());
+    // A retransmitted cookie still authenticates
+    assert_eq!(1, a.handle_cookie_echo(&echo)?.len());
+
+    let (mut a, echo) = new_server()?;
+    let mut tampered = echo.cookie.to_vec();
+    tampered[0] ^= 0x01;
+    let packets = a.handle_cookie_echo(&ChunkCookieEcho {
+        cookie: Bytes::from(tampered),
+    })?;
+    assert!(packets.is_empty());
+    assert_eq!(AssociationState::Closed, a.state());
+    let abort = a
+        .control_queue
+        .back()
+        .and_then(|p| p.chunks[0].as_any().downcast_ref::<ChunkAbort>())
+        .expect("should send an ABORT");
+    assert_eq!(
+        vec![TypedErrorCause::InvalidMandatoryParameter],
+        abort.typed_error_causes()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_all_data_acknowledged_event() -> Result<()> {
+    let now = Instant::now();
+    let sack = |cumulative_tsn_ack| ChunkSelectiveAck {
+        cumulative_tsn_ack,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+    let count_events = |a: &mut Association| {
+        let mut n = 0;
+        while let Some(e) = a.poll() {
+            if matches!(e, Event::AllDataAcknowledged) {
+                n += 1;
+            }
+        }
+        n
+    };
+
+    let mut a = create_established_association_with_inflight(&[10, 11, 12]);
+
+    a.handle_sack(&sack(10), now)?;
+    assert_eq!(0, count_events(&mut a), "data is still in flight");
+
+    a.handle_sack(&sack(12), now)?;
+    assert!(a.inflight_queue.is_empty());
+    assert_eq!(1, count_events(&mut a));
+
+    // Nothing outstanding anymore
+    a.handle_sack(&sack(12), now)?;
+    assert_eq!(0, count_events(&mut a));
+
+    // Data marked for retransmission by T3-rtx is outstanding as well
+    let mut a = create_established_association_with_inflight(&[10, 11]);
+    a.on_retransmission_timeout(Timer::T3RTX, 1);
+    assert_eq!(0, a.flight_size());
+    a.handle_sack(&sack(11), now)?;
+    assert_eq!(1, count_events(&mut a));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_shutdown_ack_before_cookie_ack() -> Result<()> {
+    for state in [
+        AssociationState::CookieWait,
+        AssociationState::CookieEchoed,
+        AssociationState::Closed,
+    ] {
+        let mut a = create_association(TransportConfig::default());
+        a.set_state(state);
+
+        // The peer completed the handshake and shut down, but our COOKIE ACK got lost
+        let pkt = Packet {
+            common_header: CommonHeader {
+                source_port: a.destination_port,
+                destination_port: a.source_port,
+                verification_tag: 0x1234_5678,
+            },
+            chunks: vec![],
+        };
+        let packets = a.handle_shutdown_ack(&pkt, &ChunkShutdownAck {})?;
+
+        assert_eq!(1, packets.len(), "{}", state);
+        assert_eq!(0x1234_5678, packets[0].common_header.verification_tag);
+        let c = packets[0].chunks[0]
+            .as_any()
+            .downcast_ref::<ChunkShutdownComplete>()
+            .expect("should reply with SHUTDOWN COMPLETE");
+        assert!(c.verification_tag_reflected);
+        assert_eq!(state, a.state());
+    }
+
+    // In the regular flow SHUTDOWN COMPLETE is sent with our own tag
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::ShutdownSent);
+    let packets = a.handle_shutdown_ack(&Packet::default(), &ChunkShutdownAck {})?;
+    assert!(packets.is_empty());
+    assert!(a.will_send_shutdown_complete);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_duplicate_cookie_echo() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    let my_cookie = ParamStateCookie::new();
+    a.my_cookie = Some(my_cookie.clone());
+    let echo = ChunkCookieEcho {
+        cookie: my_cookie.cookie.clone(),
+    };
+
+    a.set_state(AssociationState::CookieEchoed);
+    a.handle_cookie_echo(&echo)?;
+    assert_eq!(AssociationState::Established, a.state());
+
+    // Retransmitted COOKIE ECHO after our COOKIE ACK got lost
+    let packets = a.handle_cookie_echo(&echo)?;
+    assert_eq!(1, packets.len());
+    assert!(packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkCookieAck>()
+        .is_some());
+
+    // A cookie we did not hand out is dropped
+    let packets = a.handle_cookie_echo(&ChunkCookieEcho {
+        cookie: ParamStateCookie::new().cookie,
+    })?;
+    assert!(packets.is_empty());
+
+    let cookie_while_shutting_down = |packet: &Packet, idx: usize| {
+        packet.chunks[idx]
+            .as_any()
+            .downcast_ref::<ChunkError>()
+            .map(|c| c.typed_error_causes())
+            == Some(vec![TypedErrorCause::CookieReceivedWhileShuttingDown])
+    };
+
+    a.set_state(AssociationState::ShutdownSent);
+    let packets = a.handle_cookie_echo(&echo)?;
+    assert_eq!(1, packets.len());
+    assert_eq!(1, packets[0].chunks.len());
+    assert!(cookie_while_shutting_down(&packets[0], 0));
+
+    a.set_state(AssociationState::ShutdownAckSent);
+    let packets = a.handle_cookie_echo(&echo)?;
+    assert_eq!(1, packets.len());
+    assert!(packets[0].chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkShutdownAck>()
+        .is_some());
+    assert!(cookie_while_shutting_down(&packets[0], 1));
+    assert_eq!(AssociationState::ShutdownAckSent, a.state());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_abort_verification_tag_rules() -> Result<()> {
+    const MY_TAG: u32 = 0x1111_1111;
+    const PEER_TAG: u32 = 0x2222_2222;
+
+    let tests = vec![
+        ("own tag, T bit clear", MY_TAG, false, true),
+        ("peer tag, T bit set", PEER_TAG, true, true),
+        ("peer tag, T bit clear", PEER_TAG, false, false),
+        ("own tag, T bit set", MY_TAG, true, false),
+    ];
+
+    for (name, verification_tag, verification_tag_reflected, accepted) in tests {
+        let mut a = create_association(TransportConfig::default());
+        a.set_state(AssociationState::Established);
+        a.my_verification_tag = MY_TAG;
+        a.peer_verification_tag = PEER_TAG;
+
+        let p = Packet {
+            common_header: CommonHeader {
+                source_port: a.destination_port,
+                destination_port: a.source_port,
+                verification_tag,
+            },
+            chunks: vec![Box::new(ChunkAbort {
+                verification_tag_reflected,
+                error_causes: vec![TypedErrorCause::OutOfResource.into()],
+            })],
+        };
+        let result = a.handle_inbound(p, Instant::now());
+
+        if accepted {
+            assert!(
+                matches!(result, Err(Error::ErrAbortChunk(_))),
+                "{}: ABORT should be processed",
+                name
+            );
+        } else {
+            assert!(result.is_ok(), "{}: ABORT should be discarded", name);
+            assert_eq!(AssociationState::Established, a.state(), "{}", name);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_abort_causes() -> Result<()> {
+    let tests = vec![
+        (
+            vec![TypedErrorCause::InvalidMandatoryParameter],
+            vec![AbortCause::InvalidMandatoryParameter],
+        ),
+        (
+            vec![TypedErrorCause::UnrecognizedChunkType {
+                chunk: Bytes::from_static(&[0xc1, 0x00, 0x00, 0x04]),
+            }],
+            vec![AbortCause::UnrecognizedChunkType(0xc1)],
+        ),
+        (
+            vec![
+                TypedErrorCause::UnresolvableAddress {
+                    address: Bytes::from_static(b"host"),
+                },
+                TypedErrorCause::OutOfResource,
+            ],
+            vec![AbortCause::UnresolvableAddress, AbortCause::OutOfResource],
+        ),
+        (
+            vec![TypedErrorCause::CookieReceivedWhileShuttingDown],
+            vec![AbortCause::CookieReceiveDuringShutdown],
+        ),
+        (
+            vec![TypedErrorCause::RestartOfAnAssociationWithNewAddresses {
+                addresses: Bytes::new(),
+            }],
+            vec![AbortCause::RestartWithNewAddresses],
+        ),
+        (
+            vec![TypedErrorCause::UserInitiatedAbort {
+                reason: Bytes::from_static(b"bye"),
+            }],
+            vec![AbortCause::UserInitiated("bye".to_owned())],
+        ),
+        (
+            vec![TypedErrorCause::ProtocolViolation {
+                information: "bad".to_owned(),
+            }],
+            vec![AbortCause::ProtocolViolation("bad".to_owned())],
+        ),
+        (
+            vec![TypedErrorCause::StaleCookie { staleness_us: 1 }],
+            vec![AbortCause::Other(STALE_COOKIE_ERROR)],
+        ),
+        (vec![], vec![]),
+    ];
+
+    for (error_causes, expected) in tests {
+        let mut a = create_association(TransportConfig::default());
+        a.set_state(AssociationState::Established);
+        let now = Instant::now();
+
+        let abort = ChunkAbort {
+            verification_tag_reflected: false,
+            error_causes: error_causes.into_iter().map(Into::into).collect(),
+        };
+        a.handle_event(chunk_datagram(&a, Box::new(abort), now));
+
+        assert!(a.is_closed());
+        match a.poll() {
+            Some(Event::AssociationLost {
+                reason: AssociationError::PeerAborted { causes },
+            }) => assert_eq!(expected, causes),
+            other => panic!("expected PeerAborted, got {:?}", other),
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs the timers of `a` until it gives up, returning the reason
+fn run_until_lost(a: &mut Association) -> AssociationError {
+    while !a.is_closed() {
+        let now = a.poll_timeout().expect("a timer should be running");
+        a.handle_timeout(now);
+        while a.poll_transmit(now).is_some() {}
+    }
+    match a.poll() {
+        Some(Event::AssociationLost { reason }) => reason,
+        other => panic!("expected AssociationLost, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_assoc_handshake_failure_phases() -> Result<()> {
+    let now = Instant::now();
+
+    // Unanswered INIT
+    let mut a = create_association(TransportConfig::default());
+    a.timers.set_max_retrans(Timer::T1Init, 2);
+    assert_eq!(
+        AssociationError::HandshakeFailed {
+            phase: HandshakePhase::InitTimeout,
+            attempts: 2,
+        },
+        run_until_lost(&mut a)
+    );
+
+    // Unanswered COOKIE ECHO
+    let mut a = create_association(TransportConfig::default());
+    a.timers.stop(Timer::T1Init);
+    a.set_state(AssociationState::CookieEchoed);
+    a.timers.set_max_retrans(Timer::T1Cookie, 3);
+    a.timers.start(Timer::T1Cookie, now, a.rto_mgr.get_rto());
+    assert_eq!(
+        AssociationError::HandshakeFailed {
+            phase: HandshakePhase::CookieEchoTimeout,
+            attempts: 3,
+        },
+        run_until_lost(&mut a)
+    );
+
+    // INIT answered with an ABORT after one retransmission
+    let mut a = create_association(TransportConfig::default());
+    let now = a.poll_timeout().unwrap();
+    a.handle_timeout(now);
+    let abort = ChunkAbort {
+        verification_tag_reflected: false,
+        error_causes: vec![TypedErrorCause::OutOfResource.into()],
+    };
+    a.handle_event(chunk_datagram(&a, Box::new(abort), now));
+    assert!(a.is_closed());
+    assert_eq!(
+        Some(AssociationError::HandshakeFailed {
+            phase: HandshakePhase::AbortReceivedDuringHandshake {
+                causes: vec![AbortCause::OutOfResource],
+            },
+            attempts: 1,
+        }),
+        a.error
+    );
+
+    // COOKIE ECHO answered with a Stale Cookie error
+    let mut a = create_association(TransportConfig::default());
+    a.timers.stop(Timer::T1Init);
+    a.set_state(AssociationState::CookieEchoed);
+    a.timers.start(Timer::T1Cookie, now, a.rto_mgr.get_rto());
+    let error = ChunkError {
+        error_causes: vec![TypedErrorCause::StaleCookie { staleness_us: 1 }.into()],
+    };
+    a.handle_event(chunk_datagram(&a, Box::new(error), now));
+    assert!(a.is_closed());
+    assert_eq!(
+        Some(AssociationError::HandshakeFailed {
+            phase: HandshakePhase::StaleCookie,
+            attempts: 0,
+        }),
+        a.error
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_set_local_ip() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    assert_eq!(None, a.local_ip());
+
+    let ip = IpAddr::from_str("192.168.0.1").unwrap();
+    a.set_local_ip(Some(ip))?;
+    assert_eq!(Some(ip), a.local_ip());
+
+    let t = a
+        .poll_transmit(Instant::now())
+        .expect("INIT should be sent");
+    assert_eq!(
+        Some(ip),
+        t.local_ip,
+        "Transmit should carry the new local ip"
+    );
+
+    let result = a.set_local_ip(Some(IpAddr::from_str("::1").unwrap()));
+    assert!(
+        matches!(result, Err(Error::ErrLocalIpFamilyMismatch)),
+        "IPv6 local ip should be rejected for an IPv4 peer"
+    );
+    assert_eq!(Some(ip), a.local_ip());
+
+    a.set_local_ip(None)?;
+    assert_eq!(None, a.local_ip());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_cwnd_restart_after_idle() -> Result<()> {
+    let now = Instant::now();
+
+    // (idle restart enabled, time since DATA was last sent, cwnd restarted)
+    let tests = [
+        (true, Duration::from_secs(30), true),
+        (true, Duration::from_millis(10), false),
+        (false, Duration::from_secs(30), false),
+    ];
+
+    for (enabled, idle, restarted) in tests {
+        let mut a = create_association(TransportConfig::default().with_cwnd_idle_restart(enabled));
+        a.set_state(AssociationState::Established);
+        a.congestion.rwnd = 1_000_000;
+        a.congestion.cwnd = 100_000;
+        a.last_data_sent_at = Some(now);
+        a.pending_queue.push(data_chunk(0));
+
+        let (chunks, _) = a.pop_pending_data_chunks_to_send(now + idle);
+        assert_eq!(1, chunks.len());
+
+        let expected = if restarted {
+            std::cmp::min(4 * a.mtu, std::cmp::max(2 * a.mtu, 4380))
+        } else {
+            100_000
+        };
+        assert_eq!(
+            expected, a.congestion.cwnd,
+            "enabled={} idle={:?}",
+            enabled, idle
+        );
+        assert_eq!(Some(now + idle), a.last_data_sent_at);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_one_cwnd_reduction_per_window() -> Result<()> {
+    let now = Instant::now();
+    let sack = |cumulative_tsn_ack, blocks: &[(u16, u16)]| ChunkSelectiveAck {
+        cumulative_tsn_ack,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: blocks
+            .iter()
+            .map(|&(start, end)| GapAckBlock { start, end })
+            .collect(),
+        duplicate_tsn: vec![],
+    };
+
+    let tsns: Vec<u32> = (10..30).collect();
+    let mut a = create_established_association_with_inflight(&tsns);
+    let cwnd = 40 * a.mtu;
+    a.congestion.cwnd = cwnd;
+    a.congestion.ssthresh = cwnd;
+
+    // TSN 10 arrives late, the third SACK reporting it missing enters fast recovery
+    a.handle_sack(&sack(9, &[(2, 2)]), now)?;
+    a.handle_sack(&sack(9, &[(2, 3)]), now)?;
+    a.handle_sack(&sack(9, &[(2, 4)]), now)?;
+    assert!(a.congestion.in_fast_recovery);
+    assert_eq!(cwnd / 2, a.congestion.cwnd, "first loss should halve cwnd");
+
+    // TSN 15 is reordered as well, and reported missing three more times
+    a.handle_sack(&sack(9, &[(2, 5), (7, 7)]), now)?;
+    a.handle_sack(&sack(14, &[(2, 2)]), now)?;
+    a.handle_sack(&sack(14, &[(2, 3)]), now)?;
+    a.handle_sack(&sack(14, &[(2, 4)]), now)?;
+    assert!(
+        a.congestion.in_fast_recovery,
+        "recovery lasts until TSN 29 is acked"
+    );
+    assert_eq!(
+        cwnd / 2,
+        a.congestion.cwnd,
+        "same window should not reduce cwnd again"
+    );
+
+    a.handle_sack(&sack(29, &[]), now)?;
+    assert!(!a.congestion.in_fast_recovery);
+    assert_eq!(cwnd / 2, a.congestion.cwnd);
+
+    // A T3-rtx timeout for data covered by an earlier reduction keeps ssthresh
+    let mut a = create_established_association_with_inflight(&tsns);
+    a.congestion.cwnd = cwnd;
+    a.congestion.ssthresh = cwnd;
+    a.handle_sack(&sack(9, &[(2, 2)]), now)?;
+    a.handle_sack(&sack(9, &[(2, 3)]), now)?;
+    a.handle_sack(&sack(9, &[(2, 4)]), now)?;
+    a.on_retransmission_timeout(Timer::T3RTX, 1);
+    assert_eq!(cwnd / 2, a.congestion.ssthresh, "ssthresh should be held");
+    assert_eq!(a.mtu, a.congestion.cwnd);
+
+    // Without an earlier reduction the timeout halves it
+    let mut a = create_established_association_with_inflight(&tsns);
+    a.congestion.cwnd = cwnd;
+    a.congestion.ssthresh = cwnd;
+    a.on_retransmission_timeout(Timer::T3RTX, 1);
+    assert_eq!(cwnd / 2, a.congestion.ssthresh);
+    assert_eq!(a.mtu, a.congestion.cwnd);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_spurious_timeout_undo() -> Result<()> {
+    let now = Instant::now();
+    let sack = |cumulative_tsn_ack| ChunkSelectiveAck {
+        cumulative_tsn_ack,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+
+    // (cumulative TSN ack of the SACK following the timeout, msec since the
+    // chunk was resent, undone)
+    let tests = [(15, 10, true), (15, 150, false), (10, 150, false)];
+
+    for (cumulative_tsn_ack, delay, undone) in tests {
+        let tsns: Vec<u32> = (10..20).collect();
+        let mut a = create_established_association_with_inflight(&tsns);
+        let cwnd = 20 * a.mtu;
+        a.congestion.cwnd = cwnd;
+        a.congestion.ssthresh = cwnd;
+        a.rto_mgr.min_rtt = 100;
+
+        a.on_retransmission_timeout(Timer::T3RTX, 1);
+        assert_eq!(a.mtu, a.congestion.cwnd);
+
+        // Only the first chunk fits in cwnd and gets resent
+        {
+            let mut c = a.inflight_queue.get_mut(10).unwrap();
+            c.retransmit = false;
+            c.nsent += 1;
+            c.since = Some(now);
+        }
+
+        a.handle_sack(
+            &sack(cumulative_tsn_ack),
+            now + Duration::from_millis(delay),
+        )?;
+
+        if undone {
+            assert_eq!(cwnd, a.congestion.cwnd, "cwnd should be restored");
+            assert_eq!(cwnd, a.congestion.ssthresh, "ssthresh should be restored");
+            assert!(
+                (16..20).all(|tsn| !a.inflight_queue.get(tsn).unwrap().retransmit),
+                "pending retransmissions should be cancelled"
+            );
+            assert_eq!(1, a.stats.get_num_spurious_timeouts());
+        } else {
+            // The resent chunk could have been acked, the loss was real
+            assert_eq!(cwnd / 2, a.congestion.ssthresh);
+            assert!(a.inflight_queue.get(16).unwrap().retransmit);
+            assert_eq!(0, a.stats.get_num_spurious_timeouts());
+        }
+    }
+
+    Ok(())
+}
+
+fn count_data_tsns_on_wire(a: &mut Association, now: Instant, counts: &mut HashMap<u32, usize>) {
+    while let Some(t) = a.poll_transmit(now) {
+        if let Payload::RawEncode(raws) = t.payload {
+            for raw in &raws {
+                let p = Packet::unmarshal(raw).unwrap();
+                for c in &p.chunks {
+                    if let Some(c) = c.as_any().downcast_ref::<ChunkPayloadData>() {
+                        *counts.entry(c.tsn).or_default() += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_assoc_single_retransmission_per_loss() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[11, 12, 13, 14, 15]);
+    a.congestion.rwnd = 65535;
+    let now = Instant::now();
+
+    // TSN 11 is lost; three SACKs reporting it missing trigger fast retransmit
+    for end in 2..=4 {
+        let sack = ChunkSelectiveAck {
+            cumulative_tsn_ack: 10,
+            advertised_receiver_window_credit: 65535,
+            gap_ack_blocks: vec![GapAckBlock { start: 2, end }],
+            duplicate_tsn: vec![],
+        };
+        a.handle_sack(&sack, now)?;
+    }
+    assert!(a.congestion.in_fast_recovery, "should be in fast recovery");
+
+    let mut counts = HashMap::new();
+    count_data_tsns_on_wire(&mut a, now, &mut counts);
+    assert_eq!(
+        Some(&1),
+        counts.get(&11),
+        "tsn 11 should be fast retransmitted"
+    );
+    assert!(!a.inflight_queue.get(11).unwrap().retransmit);
+
+    // T3-rtx expires before the fast retransmission could be acked
+    a.on_retransmission_timeout(Timer::T3RTX, 1);
+    count_data_tsns_on_wire(&mut a, now, &mut counts);
+
+    assert_eq!(Some(&1), counts.get(&11), "tsn 11 should not be sent twice");
+    assert_eq!(Some(&1), counts.get(&15), "tsn 15 should be retransmitted");
+    assert_eq!(2, counts.len(), "acked chunks should not be retransmitted");
+    assert!(!a.inflight_queue.get(11).unwrap().retransmit);
+
+    // Once an RTO has elapsed since, a further timeout retransmits it again
+    let later = now + Duration::from_millis(a.rto_mgr.get_rto());
+    a.on_retransmission_timeout(Timer::T3RTX, 2);
+    count_data_tsns_on_wire(&mut a, later, &mut counts);
+    assert_eq!(Some(&2), counts.get(&11));
+    assert_eq!(Some(&2), counts.get(&15));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_max_assoc_retransmissions() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[1, 2]);
+    a.max_assoc_retransmissions = 3;
+    a.congestion.rwnd = 65535;
+    let mut now = Instant::now();
+    a.timers.start(Timer::T3RTX, now, a.rto_mgr.get_rto());
+
+    // A SACK clears the counter
+    for _ in 0..2 {
+        now = a.poll_timeout().unwrap();
+        a.handle_timeout(now);
+    }
+    assert_eq!(2, a.consecutive_retransmissions);
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 1,
+        advertised_receiver_window_credit: 65535,
+        gap_ack_blocks: vec![],
+        duplicate_tsn: vec![],
+    };
+    a.handle_sack(&sack, now)?;
+    assert_eq!(0, a.consecutive_retransmissions);
+
+    for _ in 0..2 {
+        now = a.poll_timeout().unwrap();
+        a.handle_timeout(now);
+    }
+    assert!(!a.is_closed());
+
+    now = a.poll_timeout().unwrap();
+    a.handle_timeout(now);
+    assert!(a.is_closed());
+    assert!(a.poll_timeout().is_none(), "no timer should be restarted");
+    assert!(matches!(
+        a.poll(),
+        Some(Event::AssociationLost {
+            reason: AssociationError::TimedOut
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_shutdown_drained_when_peer_stops_answering() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.max_assoc_retransmissions = 3;
+    let mut now = Instant::now();
+
+    a.shutdown()?;
+    assert_eq!(AssociationState::ShutdownSent, a.state());
+    assert!(a.poll_transmit(now).is_some(), "SHUTDOWN");
+    assert!(a.poll_endpoint_event().is_none());
+
+    while let Some(timeout) = a.poll_timeout() {
+        now = timeout;
+        a.handle_timeout(now);
+        while a.poll_transmit(now).is_some() {}
+    }
+    assert!(a.is_closed());
+    assert!(a
+        .poll_endpoint_event()
+        .is_some_and(|event| event.is_drained()));
+    assert!(matches!(
+        a.poll(),
+        Some(Event::AssociationLost {
+            reason: AssociationError::TimedOut
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_no_new_data_in_shutdown_received() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[1, 2]);
+    a.congestion.cwnd = 65535;
+    a.congestion.rwnd = 65535;
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    a.send_with_options(1, Bytes::from_static(b"queued"), SendOptions::default())?;
+    let now = Instant::now();
+
+    a.handle_shutdown(&ChunkShutdown {
+        cumulative_tsn_ack: 0,
+    })?;
+    assert_eq!(AssociationState::ShutdownReceived, a.state());
+
+    // Neither gap reports nor a T3-rtx timeout let the queued message out
+    a.inflight_queue.get_mut(1).unwrap().miss_indicator = 3;
+    a.will_retransmit_fast = true;
+    let mut counts = HashMap::new();
+    count_data_tsns_on_wire(&mut a, now, &mut counts);
+    assert!(counts.is_empty(), "no fast retransmission: {:?}", counts);
+
+    a.on_retransmission_timeout(Timer::T3RTX, 1);
+    count_data_tsns_on_wire(&mut a, now, &mut counts);
+    assert_eq!(Some(&1), counts.get(&1));
+    assert_eq!(Some(&1), counts.get(&2));
+    assert_eq!(2, counts.len(), "only in-flight data is resent");
+    assert_eq!(1, a.pending_queue.len());
+    assert_eq!(3, a.my_next_tsn);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_flight_size_with_gap_acked_and_marked_chunks() -> Result<()> {
+    let tsns: Vec<u32> = (1..=10).collect();
+    let mut a = create_established_association_with_inflight(&tsns);
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    let now = Instant::now();
+    assert_eq!(30, a.flight_size());
+
+    // TSN 2 is missing, 3-10 are gap acked
+    let sack = ChunkSelectiveAck {
+        cumulative_tsn_ack: 1,
+        advertised_receiver_window_credit: 15,
+        gap_ack_blocks: vec![GapAckBlock { start: 2, end: 9 }],
+        duplicate_tsn: vec![],
+    };
+    a.handle_sack(&sack, now)?;
+    assert_eq!(3, a.flight_size());
+    assert_eq!(3, a.bytes_in_flight());
+    assert_eq!(
+        12, a.congestion.rwnd,
+        "rwnd should exclude the bytes outstanding"
+    );
+
+    // After T3-rtx, TSN 2 is outstanding but no longer in flight
+    a.on_retransmission_timeout(Timer::T3RTX, 1);
+    assert_eq!(0, a.flight_size());
+    assert_eq!(3, a.bytes_in_flight());
+
+    a.congestion.cwnd = 9;
+    for _ in 0..10 {
+        a.send_with_options(1, Bytes::from_static(b"DEF"), SendOptions::default())?;
+    }
+    let retransmitted = a.get_data_packets_to_retransmit(now);
+    assert_eq!(1, retransmitted.len());
+    assert_eq!(3, a.flight_size());
+
+    let (chunks, _) = a.pop_pending_data_chunks_to_send(now);
+    assert_eq!(
+        2,
+        chunks.len(),
+        "cwnd should leave room for two more chunks"
+    );
+    assert!(a.flight_size() <= a.congestion.cwnd as usize);
+
+    // The peer's window limits the data once cwnd opens up
+    a.congestion.cwnd = 1000;
+    let (chunks, _) = a.pop_pending_data_chunks_to_send(now);
+    let sent: usize = chunks.iter().map(|c| c.user_data.len()).sum();
+    assert_eq!(6, sent);
+    assert_eq!(15, a.bytes_in_flight(), "a_rwnd should not be exceeded");
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_bundle_control_packets_on_stream_reset() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    a.peer_last_tsn = 100;
+
+    let p = Packet {
+        common_header: CommonHeader {
+            source_port: a.destination_port,
+            destination_port: a.source_port,
+            verification_tag: a.my_verification_tag,
+        },
+        chunks: vec![
+            Box::new(ChunkReconfig {
+                param_a: Some(Box::new(ParamOutgoingResetRequest {
+                    reconfig_request_sequence_number: 5,
+                    sender_last_tsn: 100,
+                    stream_identifiers: vec![1],
+                    ..Default::default()
+                })),
+                param_b: None,
+            }),
+            Box::new(ChunkHeartbeat {
+                params: vec![Box::new(ParamHeartbeatInfo {
+                    heartbeat_information: Bytes::from_static(b"1234"),
+                })],
+            }),
+        ],
+    };
+    a.handle_inbound(p, Instant::now())?;
+    assert_eq!(3, a.control_queue.len());
+
+    // The reset request, the reset response and the HEARTBEAT ACK share a datagram
+    let mut datagrams = vec![];
+    while let Some(t) = a.poll_transmit(Instant::now()) {
+        if let Payload::RawEncode(raws) = t.payload {
+            datagrams.extend(raws);
+        }
+    }
+    assert_eq!(1, datagrams.len());
+    let p = Packet::unmarshal(&datagrams[0])?;
+    assert_eq!(3, p.chunks.len());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_bundle_reconfig_with_data() -> Result<()> {
+    // (bundling enabled, expected packets as (DATA, RECONFIG) chunk counts)
+    let tests = [(true, vec![(1, 1)]), (false, vec![(1, 0), (0, 1)])];
+    for (enabled, expected) in tests {
+        let mut a =
+            create_association(TransportConfig::default().with_reconfig_bundling_enabled(enabled));
+        a.set_state(AssociationState::Established);
+        a.timers.stop(Timer::T1Init);
+        a.control_queue.clear();
+        a.congestion.cwnd = 65535;
+        a.congestion.rwnd = 65535;
+        a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+
+        let mut s = a.stream(1)?;
+        s.write(&[0; 100])?;
+        s.stop()?;
+
+        let mut packets = vec![];
+        while let Some(t) = a.poll_transmit(Instant::now()) {
+            if let Payload::RawEncode(raws) = t.payload {
+                for raw in &raws {
+                    let p = Packet::unmarshal(raw)?;
+                    let count = |typ| p.chunks.iter().filter(|c| c.header().typ == typ).count();
+                    packets.push((count(CT_PAYLOAD_DATA), count(CT_RECONFIG)));
+                }
+            }
+        }
+        assert_eq!(expected, packets, "bundling enabled: {enabled}");
+        assert_eq!(1, a.reconfigs.len());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_write_after_reset() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+
+    let mut s = a.stream(1)?;
+    s.write(&[0; 100])?;
+    s.stop()?;
+    assert!(!s.is_writable());
+    assert_eq!(Some(Error::ErrStreamClosed), s.write(&[0; 100]).err());
+    assert_eq!(
+        Some(Error::ErrStreamClosed),
+        s.write_unordered(&[0; 100]).err()
+    );
+    assert_eq!(
+        Some(Error::ErrStreamClosed),
+        a.send_with_options(1, Bytes::from_static(b"ABC"), SendOptions::default())
+            .err()
+    );
+
+    // Only the data written before the reset and the reset marker are queued
+    assert_eq!(2, a.pending_queue.len());
+    assert_eq!(100, a.pending_queue.get_num_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_bundle_data_chunks_at_mtu_boundary() -> Result<()> {
+    let a = create_association(TransportConfig::default());
+    let mtu = a.mtu as usize;
+    let chunk = |len: usize| ChunkPayloadData {
+        user_data: Bytes::from(vec![0; len]),
+        ..Default::default()
+    };
+
+    // (sizes, expected number of packets)
+    let tests = [
+        // Fill the MTU exactly: 12 + (16 + 700) + (16 + 684)
+        (vec![700, 684], 1),
+        (vec![700, 685], 2),
+        // The padding of the first chunk counts: 12 + (16 + 701 + 3) + (16 + 680)
+        (vec![701, 680], 1),
+        (vec![701, 681], 2),
+        (vec![mtu - 28], 1),
+        (vec![mtu - 28, 1], 2),
+    ];
+    for (sizes, n_packets) in tests {
+        let chunks = sizes.iter().map(|n| chunk(*n)).collect();
+        let packets = a.bundle_data_chunks_into_packets(chunks);
+        assert_eq!(n_packets, packets.len(), "sizes {:?}", sizes);
+        for p in &packets {
+            assert!(p.marshal()?.len() <= mtu, "sizes {:?}", sizes);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_poll_transmit_n() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    a.congestion.cwnd = 65535;
+    a.congestion.rwnd = 65535;
+    let now = Instant::now();
+
+    // One DATA chunk per packet
+    for _ in 0..10 {
+        a.send_with_options(1, Bytes::from(vec![0u8; 1000]), SendOptions::default())?;
+    }
+
+    let transmits = a.poll_transmit_n(now, 3);
+    assert_eq!(3, transmits.len());
+    for t in &transmits {
+        assert!(matches!(&t.payload, Payload::RawEncode(raws) if raws.len() == 1));
+    }
+
+    // The rest is returned by later calls
+    assert_eq!(4, a.poll_transmit_n(now, 4).len());
+    match a.poll_transmit(now).map(|t| t.payload) {
+        Some(Payload::RawEncode(raws)) => assert_eq!(3, raws.len()),
+        _ => panic!("expected the remaining packets"),
+    }
+    assert!(a.poll_transmit_n(now, 3).is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_marshal_reuses_buffer() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    a.congestion.cwnd = 65535;
+    a.congestion.rwnd = 65535;
+
+    a.send_with_options(1, Bytes::from(vec![7u8; 8 * 1400]), SendOptions::default())?;
+    let (raws, _) = a.gather_outbound(Instant::now());
+    assert_eq!(8, raws.len());
+
+    for (i, raw) in raws.iter().enumerate() {
+        // The wire output matches marshalling the packet on its own
+        assert_eq!(raw, &Packet::unmarshal(raw)?.marshal()?);
+
+        // Consecutive packets are carved out of the same buffer
+        if i > 0 {
+            let prev = &raws[i - 1];
+            assert_eq!(prev.as_ptr().wrapping_add(prev.len()), raw.as_ptr());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_set_mtu_applies_to_pending_data() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    a.congestion.cwnd = 65535;
+    a.congestion.rwnd = 65535;
+    let now = Instant::now();
+
+    assert_eq!(Err(Error::ErrMtuTooSmall), a.set_mtu(28));
+    assert_eq!(Err(Error::ErrMtuTooSmall), a.set_mtu(575));
+
+    // The first message leaves before the MTU shrinks
+    a.send_with_options(1, Bytes::from(vec![0u8; 1300]), SendOptions::default())?;
+    assert!(a.poll_transmit(now).is_some());
+    for _ in 0..4 {
+        a.send_with_options(1, Bytes::from(vec![0u8; 1300]), SendOptions::default())?;
+    }
+
+    a.set_mtu(600)?;
+    assert_eq!(600 - 28, a.max_single_packet_payload());
+    assert_eq!(600, a.congestion.cwnd);
+    assert!(a.congestion.ssthresh >= 4 * 600);
+
+    // Let the pending messages out at once
+    a.congestion.cwnd = 65535;
+
+    let mut n_bytes = 0;
+    let mut n_messages = 0;
+    while let Some(t) = a.poll_transmit(now) {
+        if let Payload::RawEncode(raws) = t.payload {
+            for raw in &raws {
+                assert!(raw.len() <= 600, "packet of {} bytes", raw.len());
+                let p = Packet::unmarshal(raw)?;
+                for c in &p.chunks {
+                    if let Some(c) = c.as_any().downcast_ref::<ChunkPayloadData>() {
+                        n_bytes += c.user_data.len();
+                        if c.ending_fragment {
+                            n_messages += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    assert_eq!(4, n_messages);
+    assert_eq!(4 * 1300, n_bytes);
+
+    // A large message is queued whole and only fragmented as it is sent
+    a.send_with_options(1, Bytes::from(vec![0u8; 64 * 1024]), SendOptions::default())?;
+    assert_eq!(1, a.pending_queue.len());
+    assert_eq!(64 * 1024, a.pending_queue.get_num_bytes());
+
+    a.congestion.cwnd = (a.inflight_queue.get_num_bytes_in_flight() + 4 * (600 - 28)) as u32;
+    let (chunks, _) = a.pop_pending_data_chunks_to_send(now);
+    assert_eq!(4, chunks.len());
+    assert!(chunks[0].beginning_fragment);
+    assert!(chunks.iter().all(|c| c.user_data.len() == 600 - 28));
+    assert!(chunks.iter().all(|c| !c.ending_fragment));
+    assert_eq!(
+        1,
+        a.pending_queue.len(),
+        "the rest of the message stays queued"
+    );
+    assert_eq!(64 * 1024 - 4 * (600 - 28), a.pending_queue.get_num_bytes());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_abandon_inflight_data_of_reset_stream() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[11, 12, 13]);
+    a.negotiated_extensions.insert(SctpExtension::ForwardTsn);
+    a.advanced_peer_tsn_ack_point = a.cumulative_tsn_ack_point;
+    a.congestion.rwnd = 65535;
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    for tsn in 11..=13 {
+        a.inflight_queue.get_mut(tsn).unwrap().set_all_inflight();
+    }
+    let now = Instant::now();
+
+    // The stream goes away while its data is still in flight
+    a.unregister_stream(1);
+    a.on_retransmission_timeout(Timer::T3RTX, 1);
+
+    let mut fwd_tsns = vec![];
+    while let Some(t) = a.poll_transmit(now) {
+        if let Payload::RawEncode(raws) = t.payload {
+            for raw in &raws {
+                let p = Packet::unmarshal(raw)?;
+                for c in &p.chunks {
+                    if let Some(c) = c.as_any().downcast_ref::<ChunkForwardTsn>() {
+                        fwd_tsns.push(c.new_cumulative_tsn);
+                    }
+                }
+            }
+        }
+    }
+
+    for tsn in 11..=13 {
+        assert!(a.inflight_queue.get(tsn).unwrap().abandoned());
+    }
+    assert_eq!(vec![13], fwd_tsns, "a FORWARD TSN should skip the chunks");
+    assert_eq!(13, a.advanced_peer_tsn_ack_point);
+
+    // Abandoned chunks are not retransmitted again
+    a.on_retransmission_timeout(Timer::T3RTX, 2);
+    let mut counts = HashMap::new();
+    count_data_tsns_on_wire(&mut a, now, &mut counts);
+    assert!(counts.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_reliable_stream_never_abandons() -> Result<()> {
+    let mut a = create_established_association_with_inflight(&[11, 12, 13]);
+    a.negotiated_extensions.insert(SctpExtension::ForwardTsn);
+    a.advanced_peer_tsn_ack_point = a.cumulative_tsn_ack_point;
+    a.congestion.rwnd = 65535;
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(
+        Some((ReliabilityType::Reliable, 0)),
+        a.get_stream_reliability(1)
+    );
+    for tsn in 11..=13 {
+        a.inflight_queue.get_mut(tsn).unwrap().set_all_inflight();
+    }
+
+    let mut now = Instant::now();
+    for n_rtos in 1..=5 {
+        now += Duration::from_secs(60);
+        a.on_retransmission_timeout(Timer::T3RTX, n_rtos);
+        while let Some(t) = a.poll_transmit(now) {
+            if let Payload::RawEncode(raws) = t.payload {
+                for raw in &raws {
+                    let p = Packet::unmarshal(raw)?;
+                    assert!(
+                        p.chunks
+                            .iter()
+                            .all(|c| c.as_any().downcast_ref::<ChunkForwardTsn>().is_none()),
+                        "no FORWARD TSN for a reliable stream"
+                    );
+                }
+            }
+        }
+    }
+
+    for tsn in 11..=13 {
+        let c = a.inflight_queue.get(tsn).unwrap();
+        assert!(!c.abandoned());
+        assert!(c.nsent > 1, "tsn {} should be retransmitted", tsn);
+    }
+    assert_eq!(a.cumulative_tsn_ack_point, a.advanced_peer_tsn_ack_point);
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_set_reliability_params_rejects_zero_value() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.negotiated_extensions = [SctpExtension::ForwardTsn].into_iter().collect();
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    let mut s = a.stream(1)?;
+
+    for rel_type in [ReliabilityType::Rexmit, ReliabilityType::Timed] {
+        assert_eq!(
+            Err(Error::ErrZeroReliabilityValue),
+            s.set_reliability_params(false, rel_type, 0)
+        );
+        s.set_reliability_params(false, rel_type, 1)?;
+    }
+    s.set_reliability_params(false, ReliabilityType::Reliable, 0)?;
+    assert_eq!(
+        Some((ReliabilityType::Reliable, 0)),
+        a.get_stream_reliability(1)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_set_reliability_params_requires_forward_tsn() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    let mut s = a.stream(1)?;
+
+    for rel_type in [ReliabilityType::Rexmit, ReliabilityType::Timed] {
+        assert_eq!(
+            Err(Error::ErrPartialReliabilityUnsupported),
+            s.set_reliability_params(false, rel_type, 1)
+        );
+    }
+    s.set_reliability_params(true, ReliabilityType::Reliable, 0)?;
+    assert_eq!(
+        Some((ReliabilityType::Reliable, 0)),
+        a.get_stream_reliability(1)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_stream_set_reliability_params_mid_message() -> Result<()> {
+    let mut a = create_association(TransportConfig::default());
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+    a.negotiated_extensions = [SctpExtension::ForwardTsn].into_iter().collect();
+    a.congestion.rwnd = 65535;
+    a.congestion.cwnd = a.mtu;
+    a.open_stream(1, PayloadProtocolIdentifier::Binary)?;
+    let now = Instant::now();
+
+    // Only the first fragment of the message fits in cwnd
+    a.stream(1)?.write(&[0; 3000])?;
+    assert_eq!(1, packets_on_wire(&mut a, now).len());
+    assert!(!a.pending_queue.is_empty());
+    assert_eq!(
+        Err(Error::ErrReliabilityChangeMidMessage),
+        a.stream(1)?
+            .set_reliability_params(false, ReliabilityType::Rexmit, 1)
+    );
+
+    // Once the whole message is in flight, the new policy applies to it: the
+    // chunks already sent once are abandoned when retransmitted
+    a.congestion.cwnd = 65535;
+    a.awake_write_loop();
+    assert!(!packets_on_wire(&mut a, now).is_empty());
+    assert!(a.pending_queue.is_empty());
+    a.stream(1)?
+        .set_reliability_params(true, ReliabilityType::Rexmit, 1)?;
+    a.inflight_queue.mark_all_to_retrasmit();
+    a.get_data_packets_to_retransmit(now);
+    let first = a.cumulative_tsn_ack_point.wrapping_add(1);
+    for tsn in first..first + a.inflight_queue.len() as u32 {
+        assert!(a.inflight_queue.get(tsn).unwrap().abandoned, "tsn {}", tsn);
+    }
+    let last = a.inflight_queue.get(a.my_next_tsn.wrapping_sub(1)).unwrap();
+    assert_eq!(1, last.nsent, "an abandoned message is not retransmitted");
+    assert!(a.will_send_forward_tsn);
+
+    // The unordered flag only applies to messages written afterwards
+    a.stream(1)?.write(&[0; 10])?;
+    assert!(a.pending_queue.peek().is_some_and(|c| c.unordered));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_pmtu_discovery_converges_to_bottleneck() -> Result<()> {
+    const BOTTLENECK: usize = 1400;
+    let config = TransportConfig::default()
+        .with_pmtu_discovery(true)
+        .with_max_probe_size(1500);
+    let mut a = Association::new(
+        None,
+        Arc::new(config),
+        1200 - COMMON_HEADER_SIZE - DATA_CHUNK_HEADER_SIZE,
+        0,
+        SocketAddr::from_str("0.0.0.0:0").unwrap(),
+        None,
+        Instant::now(),
+    );
+    a.set_state(AssociationState::Established);
+    a.timers.stop(Timer::T1Init);
+    a.control_queue.clear();
+
+    // Packets larger than the bottleneck are dropped on the path, the others
+    // are answered like the peer would
+    let mut now = Instant::now();
+    let mut n_probes = 0;
+    for _ in 0..100 {
+        while let Some(t) = a.poll_transmit(now) {
+            let Payload::RawEncode(raws) = t.payload else {
+                continue;
+            };
+            for raw in &raws {
+                n_probes += 1;
+                if raw.len() > BOTTLENECK {
+                    continue;
+                }
+                let p = Packet::unmarshal(raw)?;
+                for c in &p.chunks {
+                    if let Some(hb) = c.as_any().downcast_ref::<ChunkHeartbeat>() {
+                        let ack = ChunkHeartbeatAck {
+                            params: vec![hb.params[0].clone()],
+                        };
+                        a.handle_heartbeat_ack(&ack, now);
+                    }
+                }
+            }
+        }
+        if a.pmtu_prober.as_ref().unwrap().state == PmtuSearchState::SearchComplete {
+            break;
+        }
+        now = a.poll_timeout().unwrap();
+        a.handle_timeout(now);
+    }
+
+    let prober = a.pmtu_prober.as_ref().unwrap();
+    assert_eq!(PmtuSearchState::SearchComplete, prober.state);
+    assert_eq!(BOTTLENECK as u32, prober.confirmed_size());
+    assert_eq!(BOTTLENECK as u32, a.mtu);
+    assert_eq!(BOTTLENECK as u32 - 28, a.max_payload_size);
+    assert!(n_probes < 30, "took {} probes", n_probes);
+
+    // The search restarts later on, looking for a larger PMTU
+    let raise_at = a.timers.get(Timer::Pmtu).unwrap();
+    assert!(raise_at >= now + Duration::from_millis(PMTU_RAISE_INTERVAL));
+
+    // Repeated T3-rtx timeouts make it fall back to the base size
+    a.on_retransmission_timeout(Timer::T3RTX, 2);
+    assert_eq!(1200, a.mtu);
+    assert!(a.timers.get(Timer::Pmtu).is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_handle_pmtu_signal() -> Result<()> {
+    let mut a = create_association(TransportConfig::default().with_min_pmtu(600));
+    a.set_state(AssociationState::Established);
+    a.cumulative_tsn_ack_point = 9;
+    a.my_next_tsn = 13;
+    a.congestion.cwnd = 65535;
+    a.congestion.rwnd = 65535;
+    let now = Instant::now();
+    for (tsn, len, sent) in [(10, 1000, now), (11, 100, now), (12, 1000, now)] {
+        a.inflight_queue.push_no_check(ChunkPayloadData {
+            beginning_fragment: true,
+            ending_fragment: true,
+            tsn,
+            stream_identifier: 1,
+            user_data: Bytes::from(vec![0; len]),
+            nsent: 1,
+            since: Some(sent),
+            last_sent: Some(sent),
+            ..Default::default()
+        });
+    }
+    // TSN 12 was sent long enough ago to have been acked if it got through
+    a.inflight_queue.get_mut(12).unwrap().last_sent =
+        Some(now - Duration::from_millis(a.rto_mgr.get_rto()));
+
+    // Hints that do not lower the MTU are ignored
+    a.handle_pmtu_signal(1500, now);
+    assert_eq!(1428, a.mtu);
+    assert!(!a.inflight_queue.get(10).unwrap().retransmit);
+
+    a.handle_pmtu_signal(800, now);
+    assert_eq!(800, a.mtu);
+    assert_eq!(800 - 28, a.max_payload_size);
+    assert!(a.inflight_queue.get(10).unwrap().retransmit);
+    assert!(!a.inflight_queue.get(11).unwrap().retransmit);
+    assert!(!a.inflight_queue.get(12).unwrap().retransmit);
+
+    let mut counts = HashMap::new();
+    count_data_tsns_on_wire(&mut a, now, &mut counts);
+    assert_eq!(Some(&1), counts.get(&10), "tsn 10 should be resent at once");
+    assert_eq!(1, counts.len());
+
+    // The configured floor wins over smaller hints
+    a.handle_pmtu_signal(300, now);
+    assert_eq!(600, a.mtu);
+
+    Ok(())
+}
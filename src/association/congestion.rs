@@ -0,0 +1,371 @@
+use crate::config::{COMMON_HEADER_SIZE, DATA_CHUNK_HEADER_SIZE};
+use crate::util::sna32gte;
+
+/// RFC 4960 Sec 7.2.1
+/// The initial cwnd before DATA transmission or after a sufficiently
+/// long idle period MUST be set to min(4*MTU, max (2*MTU, 4380bytes)).
+pub(crate) fn initial_cwnd(mtu: u32) -> u32 {
+    (2 * mtu).clamp(4380, 4 * mtu)
+}
+
+/// Congestion control state saved when T3-rtx expires, restored if the
+/// timeout turns out to be spurious
+#[derive(Debug, Clone, Copy)]
+struct CwndUndo {
+    cwnd: u32,
+    ssthresh: u32,
+    partial_bytes_acked: u32,
+    congestion_recovery_point: Option<u32>,
+}
+
+/// CongestionController holds the congestion control state of an association
+/// (RFC 4960 Sec 7.2): slow-start, congestion avoidance, fast recovery and the
+/// reaction to T3-rtx expiry.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CongestionController {
+    // my congestion window size
+    pub(crate) cwnd: u32,
+    // calculated peer's receiver windows size
+    pub(crate) rwnd: u32,
+    // slow start threshold
+    pub(crate) ssthresh: u32,
+    pub(crate) partial_bytes_acked: u32,
+    pub(crate) in_fast_recovery: bool,
+    pub(crate) fast_recover_exit_point: u32,
+    // highest TSN outstanding when cwnd was last reduced, cwnd is not reduced
+    // again for losses in the same window
+    pub(crate) congestion_recovery_point: Option<u32>,
+    // congestion control state before the latest T3-rtx expiry
+    t3_undo: Option<CwndUndo>,
+}
+
+impl CongestionController {
+    pub(crate) fn new(mtu: u32) -> Self {
+        CongestionController {
+            cwnd: initial_cwnd(mtu),
+            ..Default::default()
+        }
+    }
+
+    /// Sets rwnd to the a_rwnd the peer advertised in its INIT or INIT ACK
+    pub(crate) fn set_initial_rwnd(&mut self, rwnd: u32) {
+        self.rwnd = rwnd;
+
+        // RFC 4690 Sec 7.2.1
+        //  o  The initial value of ssthresh MAY be arbitrarily high (for
+        //     example, implementations MAY use the size of the receiver
+        //     advertised window).
+        self.ssthresh = rwnd;
+    }
+
+    /// RFC 4960 sec 6.2.1 D) ii)
+    /// Set rwnd equal to the newly received a_rwnd minus the number of bytes
+    /// still outstanding after processing the Cumulative TSN Ack and the Gap
+    /// Ack Blocks.
+    pub(crate) fn update_rwnd(&mut self, a_rwnd: u32, bytes_outstanding: u32) {
+        self.rwnd = a_rwnd.saturating_sub(bytes_outstanding);
+    }
+
+    /// Exits fast recovery once the Cumulative TSN Ack Point reaches the exit
+    /// point (RFC 4960 sec 7.2.4). Returns whether fast recovery was exited.
+    pub(crate) fn exit_fast_recovery(&mut self, cumulative_tsn_ack_point: u32) -> bool {
+        if self.in_fast_recovery && sna32gte(cumulative_tsn_ack_point, self.fast_recover_exit_point)
+        {
+            self.in_fast_recovery = false;
+            return true;
+        }
+        false
+    }
+
+    /// Grows cwnd after a SACK advanced the Cumulative TSN Ack Point to
+    /// `cumulative_tsn_ack_point`, newly acknowledging `bytes_acked` bytes out
+    /// of the `flight_size` bytes outstanding before it arrived.
+    pub(crate) fn on_ack(
+        &mut self,
+        cumulative_tsn_ack_point: u32,
+        bytes_acked: u32,
+        flight_size: u32,
+        mtu: u32,
+    ) {
+        if self
+            .congestion_recovery_point
+            .is_some_and(|tsn| sna32gte(cumulative_tsn_ack_point, tsn))
+        {
+            self.congestion_recovery_point = None;
+            self.t3_undo = None;
+        }
+
+        if self.cwnd <= self.ssthresh {
+            // RFC 4096, sec 7.2.1.  Slow-Start
+            //   o  When cwnd is less than or equal to ssthresh, an SCTP endpoint MUST
+            //		use the slow-start algorithm to increase cwnd only if the current
+            //      congestion window is being fully utilized, an incoming SACK
+            //      advances the Cumulative TSN Ack Point, and the data sender is not
+            //      in Fast Recovery.  Only when these three conditions are met can
+            //      the cwnd be increased; otherwise, the cwnd MUST not be increased.
+            //		If these conditions are met, then cwnd MUST be increased by, at
+            //      most, the lesser of 1) the total size of the previously
+            //      outstanding DATA chunk(s) acknowledged, and 2) the destination's
+            //      path MTU.
+            if !self.in_fast_recovery && self.is_cwnd_fully_utilized(flight_size, mtu) {
+                self.cwnd += std::cmp::min(bytes_acked, self.cwnd); // TCP way
+                                                                    // self.cwnd += min32(uint32(total_bytes_acked), self.mtu) // SCTP way (slow)
+            }
+        } else {
+            // RFC 4096, sec 7.2.2.  Congestion Avoidance
+            //   o  Whenever cwnd is greater than ssthresh, upon each SACK arrival
+            //      that advances the Cumulative TSN Ack Point, increase
+            //      partial_bytes_acked by the total number of bytes of all new chunks
+            //      acknowledged in that SACK including chunks acknowledged by the new
+            //      Cumulative TSN Ack and by Gap Ack Blocks.
+            self.partial_bytes_acked += bytes_acked;
+
+            //   o  When partial_bytes_acked is equal to or greater than cwnd and
+            //      before the arrival of the SACK the sender had cwnd or more bytes
+            //      of data outstanding (i.e., before arrival of the SACK, flight size
+            //      was greater than or equal to cwnd), increase cwnd by MTU, and
+            //      reset partial_bytes_acked to (partial_bytes_acked - cwnd).
+            if self.partial_bytes_acked >= self.cwnd && flight_size >= self.cwnd {
+                self.partial_bytes_acked -= self.cwnd;
+                self.cwnd += mtu;
+            }
+        }
+    }
+
+    /// Whether a flight of `flight_size` bytes left no room in cwnd for another
+    /// full-sized DATA chunk
+    fn is_cwnd_fully_utilized(&self, flight_size: u32, mtu: u32) -> bool {
+        let max_payload_size = mtu - COMMON_HEADER_SIZE - DATA_CHUNK_HEADER_SIZE;
+        flight_size + max_payload_size > self.cwnd
+    }
+
+    /// RFC 4960 sec 7.2.3
+    /// Upon detection of packet losses from SACK, ssthresh = max(cwnd/2, 4*MTU)
+    /// and cwnd = ssthresh.
+    pub(crate) fn on_loss(&mut self, mtu: u32) {
+        self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * mtu);
+        self.cwnd = self.ssthresh;
+        self.partial_bytes_acked = 0;
+    }
+
+    /// Enters fast recovery with `htna`, the highest TSN outstanding, as exit
+    /// point (RFC 4960 sec 7.2.4). cwnd is only reduced if this loss is not
+    /// part of a congestion event cwnd was already reduced for.
+    pub(crate) fn enter_fast_recovery(&mut self, htna: u32, mtu: u32) {
+        self.in_fast_recovery = true;
+        self.fast_recover_exit_point = htna;
+
+        if self.congestion_recovery_point.is_none() {
+            self.on_loss(mtu);
+            self.congestion_recovery_point = Some(htna);
+        }
+    }
+
+    /// Falls back to slow start after T3-rtx expired with `htna` as the highest
+    /// TSN outstanding.
+    ///
+    /// RFC 4960 sec 7.2.3
+    ///   When the T3-rtx timer expires on an address, SCTP should perform slow
+    ///   start by:
+    ///      ssthresh = max(cwnd/2, 4*MTU)
+    ///      cwnd = 1*MTU
+    ///
+    /// ssthresh is held when cwnd was already reduced for the data still
+    /// outstanding, as it already reflects this loss.
+    pub(crate) fn on_retransmit_timeout(&mut self, htna: u32, mtu: u32) {
+        if self.t3_undo.is_none() {
+            self.t3_undo = Some(CwndUndo {
+                cwnd: self.cwnd,
+                ssthresh: self.ssthresh,
+                partial_bytes_acked: self.partial_bytes_acked,
+                congestion_recovery_point: self.congestion_recovery_point,
+            });
+        }
+        if self.congestion_recovery_point.is_none() {
+            self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * mtu);
+        }
+        self.cwnd = mtu;
+        self.congestion_recovery_point = Some(htna);
+    }
+
+    /// Whether the changes of a T3-rtx expiry can still be reverted
+    pub(crate) fn can_undo_timeout(&self) -> bool {
+        self.t3_undo.is_some()
+    }
+
+    /// Reverts the changes of the latest T3-rtx expiry, after it turned out
+    /// to be spurious
+    pub(crate) fn undo_timeout(&mut self) {
+        if let Some(undo) = self.t3_undo.take() {
+            self.cwnd = undo.cwnd;
+            self.ssthresh = undo.ssthresh;
+            self.partial_bytes_acked = undo.partial_bytes_acked;
+            self.congestion_recovery_point = undo.congestion_recovery_point;
+        }
+    }
+
+    /// Falls back to the initial cwnd after an idle period. Returns whether
+    /// cwnd changed.
+    pub(crate) fn restart_after_idle(&mut self, mtu: u32) -> bool {
+        let cwnd = initial_cwnd(mtu);
+        if self.cwnd == cwnd {
+            return false;
+        }
+        self.cwnd = cwnd;
+        self.partial_bytes_acked = 0;
+        true
+    }
+
+    /// cwnd must leave room for at least one full-sized packet
+    pub(crate) fn on_mtu_changed(&mut self, mtu: u32) {
+        self.cwnd = std::cmp::max(self.cwnd, mtu);
+    }
+
+    /// Restarts from a single packet on a path that was replaced by one with a
+    /// smaller MTU, keeping ssthresh high enough for slow start to regain the
+    /// previous rate
+    pub(crate) fn on_mtu_decreased(&mut self, mtu: u32) {
+        self.ssthresh = std::cmp::max(self.ssthresh, 4 * mtu);
+        self.cwnd = mtu;
+        self.partial_bytes_acked = 0;
+    }
+
+    /// Starts over after the peer moved to a new path whose capacity is
+    /// unknown: slow start from the initial cwnd
+    pub(crate) fn on_path_changed(&mut self, mtu: u32) {
+        self.cwnd = initial_cwnd(mtu);
+        self.ssthresh = std::cmp::max(self.rwnd, self.cwnd);
+        self.partial_bytes_acked = 0;
+        self.in_fast_recovery = false;
+        self.congestion_recovery_point = None;
+        self.t3_undo = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MTU: u32 = 1228;
+
+    #[test]
+    fn test_congestion_slow_start_threshold_crossover() {
+        let mut cc = CongestionController::new(MTU);
+        assert_eq!(4380, cc.cwnd);
+        cc.set_initial_rwnd(6000);
+        assert_eq!(6000, cc.ssthresh);
+
+        // cwnd does not grow while it is not fully utilized
+        cc.on_ack(1, 1000, 1000, MTU);
+        assert_eq!(4380, cc.cwnd);
+
+        // Slow start doubles cwnd at most
+        cc.on_ack(2, 6000, 4380, MTU);
+        assert_eq!(8760, cc.cwnd);
+        assert_eq!(0, cc.partial_bytes_acked);
+
+        // Above ssthresh, cwnd grows by one MTU per cwnd of acknowledged bytes
+        cc.on_ack(3, 5000, 8760, MTU);
+        assert_eq!(8760, cc.cwnd);
+        assert_eq!(5000, cc.partial_bytes_acked);
+        cc.on_ack(4, 5000, 8760, MTU);
+        assert_eq!(8760 + MTU, cc.cwnd);
+        assert_eq!(10000 - 8760, cc.partial_bytes_acked);
+    }
+
+    #[test]
+    fn test_congestion_fast_recovery() {
+        let mut cc = CongestionController::new(MTU);
+        cc.set_initial_rwnd(1_000_000);
+        cc.cwnd = 20000;
+
+        cc.enter_fast_recovery(10, MTU);
+        assert!(cc.in_fast_recovery);
+        assert_eq!(10, cc.fast_recover_exit_point);
+        assert_eq!(10000, cc.ssthresh);
+        assert_eq!(10000, cc.cwnd);
+
+        // Losses in the same window do not reduce cwnd again
+        cc.exit_fast_recovery(5);
+        cc.enter_fast_recovery(12, MTU);
+        assert_eq!(10000, cc.cwnd);
+
+        // cwnd does not grow in fast recovery
+        cc.cwnd = 5000;
+        cc.on_ack(6, 4000, 5000, MTU);
+        assert_eq!(5000, cc.cwnd);
+
+        assert!(!cc.exit_fast_recovery(11));
+        assert!(cc.exit_fast_recovery(12));
+        assert!(!cc.in_fast_recovery);
+        assert!(!cc.exit_fast_recovery(13));
+
+        // A new loss after the recovery point reduces cwnd
+        cc.on_ack(13, 4000, 5000, MTU);
+        assert_eq!(9000, cc.cwnd);
+        cc.enter_fast_recovery(20, MTU);
+        assert_eq!(4 * MTU, cc.ssthresh);
+        assert_eq!(4 * MTU, cc.cwnd);
+    }
+
+    #[test]
+    fn test_congestion_retransmit_timeout() {
+        let mut cc = CongestionController::new(MTU);
+        cc.set_initial_rwnd(1_000_000);
+        cc.cwnd = 20000;
+
+        cc.on_retransmit_timeout(10, MTU);
+        assert_eq!(MTU, cc.cwnd);
+        assert_eq!(10000, cc.ssthresh);
+        assert!(cc.can_undo_timeout());
+
+        // A repeated timeout for the same data holds ssthresh
+        cc.on_retransmit_timeout(10, MTU);
+        assert_eq!(MTU, cc.cwnd);
+        assert_eq!(10000, cc.ssthresh);
+
+        // A spurious timeout is reverted to the state before the first one
+        cc.undo_timeout();
+        assert_eq!(20000, cc.cwnd);
+        assert_eq!(1_000_000, cc.ssthresh);
+        assert!(!cc.can_undo_timeout());
+
+        // Acknowledging the outstanding data ends the congestion event
+        cc.on_retransmit_timeout(10, MTU);
+        cc.on_ack(10, MTU, MTU, MTU);
+        assert!(!cc.can_undo_timeout());
+        assert_eq!(None, cc.congestion_recovery_point);
+    }
+
+    #[test]
+    fn test_congestion_restart_after_idle() {
+        let mut cc = CongestionController::new(MTU);
+        assert!(!cc.restart_after_idle(MTU));
+
+        cc.cwnd = 20000;
+        cc.partial_bytes_acked = 100;
+        assert!(cc.restart_after_idle(MTU));
+        assert_eq!(initial_cwnd(MTU), cc.cwnd);
+        assert_eq!(0, cc.partial_bytes_acked);
+
+        cc.on_mtu_changed(9000);
+        assert_eq!(9000, cc.cwnd);
+    }
+
+    #[test]
+    fn test_congestion_mtu_decreased() {
+        let mut cc = CongestionController::new(MTU);
+        cc.set_initial_rwnd(2000);
+        cc.cwnd = 20000;
+
+        cc.on_mtu_decreased(600);
+        assert_eq!(600, cc.cwnd);
+        assert_eq!(2400, cc.ssthresh);
+
+        // ssthresh is never lowered
+        cc.ssthresh = 50000;
+        cc.on_mtu_decreased(580);
+        assert_eq!(580, cc.cwnd);
+        assert_eq!(50000, cc.ssthresh);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::chunk::chunk_type::{ChunkType, CT_FORWARD_TSN, CT_PAD, CT_RECONFIG};
+
+const CT_I_DATA: ChunkType = ChunkType(64);
+const CT_AUTH: ChunkType = ChunkType(15);
+const CT_ASCONF: ChunkType = ChunkType(193);
+const CT_ASCONF_ACK: ChunkType = ChunkType(128);
+
+/// SCTP extensions that are negotiated through the Supported Extensions
+/// parameter of INIT and INIT ACK (RFC 5061 Sec 4.2.7)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SctpExtension {
+    /// Partial reliability with the FORWARD TSN chunk (RFC 3758)
+    ForwardTsn,
+    /// Interleaved user messages with the I-DATA chunk (RFC 8260)
+    IData,
+    /// Authenticated chunks (RFC 4895)
+    Authentication,
+    /// Dynamic address reconfiguration (RFC 5061)
+    AddIp,
+    /// Stream reconfiguration with the RE-CONFIG chunk (RFC 6525)
+    ReConfig,
+    /// The PAD chunk (RFC 4820)
+    Padding,
+}
+
+impl SctpExtension {
+    const ALL: [SctpExtension; 6] = [
+        SctpExtension::ForwardTsn,
+        SctpExtension::IData,
+        SctpExtension::Authentication,
+        SctpExtension::AddIp,
+        SctpExtension::ReConfig,
+        SctpExtension::Padding,
+    ];
+
+    /// The extension a chunk type listed in the Supported Extensions parameter
+    /// stands for
+    pub(crate) fn from_chunk_type(ct: ChunkType) -> Option<Self> {
+        match ct {
+            CT_FORWARD_TSN => Some(SctpExtension::ForwardTsn),
+            CT_I_DATA => Some(SctpExtension::IData),
+            CT_AUTH => Some(SctpExtension::Authentication),
+            CT_ASCONF | CT_ASCONF_ACK => Some(SctpExtension::AddIp),
+            CT_RECONFIG => Some(SctpExtension::ReConfig),
+            CT_PAD => Some(SctpExtension::Padding),
+            _ => None,
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The set of extensions both endpoints of an association support
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NegotiatedExtensions(u8);
+
+impl NegotiatedExtensions {
+    /// Extensions this implementation advertises in its INIT and INIT ACK
+    pub(crate) fn local() -> Self {
+        [SctpExtension::ReConfig, SctpExtension::ForwardTsn]
+            .into_iter()
+            .collect()
+    }
+
+    /// Whether the extension was negotiated
+    pub fn has(&self, ext: SctpExtension) -> bool {
+        self.0 & ext.bit() != 0
+    }
+
+    /// Iterates over the negotiated extensions
+    pub fn iter(&self) -> impl Iterator<Item = SctpExtension> + '_ {
+        SctpExtension::ALL.into_iter().filter(|ext| self.has(*ext))
+    }
+
+    pub(crate) fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub(crate) fn from_bits(bits: u8) -> Self {
+        NegotiatedExtensions(bits)
+    }
+
+    pub(crate) fn insert(&mut self, ext: SctpExtension) {
+        self.0 |= ext.bit();
+    }
+
+    /// The extensions in both `self` and `other`
+    pub(crate) fn intersection(&self, other: &NegotiatedExtensions) -> Self {
+        NegotiatedExtensions(self.0 & other.0)
+    }
+
+    /// The extensions listed in a Supported Extensions parameter
+    pub(crate) fn from_chunk_types(chunk_types: &[ChunkType]) -> Self {
+        chunk_types
+            .iter()
+            .filter_map(|ct| SctpExtension::from_chunk_type(*ct))
+            .collect()
+    }
+}
+
+impl FromIterator<SctpExtension> for NegotiatedExtensions {
+    fn from_iter<I: IntoIterator<Item = SctpExtension>>(iter: I) -> Self {
+        let mut extensions = NegotiatedExtensions::default();
+        for ext in iter {
+            extensions.insert(ext);
+        }
+        extensions
+    }
+}
@@ -1,41 +1,63 @@
 use crate::association::{
-    state::{AckMode, AckState, AssociationState},
-    stats::AssociationStats,
+    congestion::CongestionController,
+    extensions::{NegotiatedExtensions, SctpExtension},
+    pmtud::{
+        PmtuProber, PmtuSearchState, PMTU_BLACK_HOLE_RTOS, PMTU_PROBE_INFO_LENGTH,
+        PMTU_RAISE_INTERVAL,
+    },
+    state::{AckMode, AckState, AssociationState, AssociationStatus, HandshakeStep},
+    stats::{AssociationStats, PathRtt},
 };
 use crate::chunk::{
     chunk_abort::ChunkAbort, chunk_cookie_ack::ChunkCookieAck, chunk_cookie_echo::ChunkCookieEcho,
     chunk_error::ChunkError, chunk_forward_tsn::ChunkForwardTsn,
-    chunk_forward_tsn::ChunkForwardTsnStream, chunk_heartbeat::ChunkHeartbeat,
-    chunk_heartbeat_ack::ChunkHeartbeatAck, chunk_init::ChunkInit, chunk_init::ChunkInitAck,
-    chunk_payload_data::ChunkPayloadData, chunk_payload_data::PayloadProtocolIdentifier,
-    chunk_reconfig::ChunkReconfig, chunk_selective_ack::ChunkSelectiveAck,
-    chunk_shutdown::ChunkShutdown, chunk_shutdown_ack::ChunkShutdownAck,
-    chunk_shutdown_complete::ChunkShutdownComplete, chunk_type::CT_FORWARD_TSN, Chunk,
-    ErrorCauseUnrecognizedChunkType, USER_INITIATED_ABORT,
+    chunk_forward_tsn::ChunkForwardTsnStream, chunk_header::CHUNK_HEADER_SIZE,
+    chunk_heartbeat::ChunkHeartbeat, chunk_heartbeat_ack::ChunkHeartbeatAck, chunk_init::ChunkInit,
+    chunk_init::ChunkInitAck, chunk_padding::ChunkPadding, chunk_payload_data::ChunkPayloadData,
+    chunk_payload_data::PayloadProtocolIdentifier, chunk_reconfig::ChunkReconfig,
+    chunk_selective_ack::ChunkSelectiveAck, chunk_shutdown::ChunkShutdown,
+    chunk_shutdown_ack::ChunkShutdownAck, chunk_shutdown_complete::ChunkShutdownComplete,
+    chunk_unknown::ChunkUnknown, error_cause::TypedErrorCause, Chunk, ErrorCauseCode,
+    ErrorCauseUnrecognizedChunkType,
+};
+use crate::config::{
+    PeerAddrParams, ServerConfig, TransportConfig, COMMON_HEADER_SIZE, DATA_CHUNK_HEADER_SIZE,
+    DEFAULT_MAX_ASSOC_RETRANSMISSIONS, DEFAULT_MAX_GAP_ACK_BLOCKS,
+    DEFAULT_MAX_TOTAL_SEND_BUFFER_SIZE, INITIAL_RECV_BUF_SIZE, MIN_MTU,
 };
-use crate::config::{ServerConfig, TransportConfig, COMMON_HEADER_SIZE, DATA_CHUNK_HEADER_SIZE};
 use crate::error::{Error, Result};
 use crate::packet::{CommonHeader, Packet};
+#[cfg(feature = "hmac-cookie")]
+use crate::param::param_state_cookie::{CookieClock, CookieParams};
 use crate::param::{
+    param_add_outgoing_streams_request::ParamAddOutgoingStreamsRequest,
+    param_header::PARAM_HEADER_LENGTH,
     param_heartbeat_info::ParamHeartbeatInfo,
     param_outgoing_reset_request::ParamOutgoingResetRequest,
+    param_padding::ParamPadding,
     param_reconfig_response::{ParamReconfigResponse, ReconfigResult},
     param_state_cookie::ParamStateCookie,
     param_supported_extensions::ParamSupportedExtensions,
+    param_uknown::ParamUnknown,
+    param_unrecognized::ParamUnrecognized,
+    param_zero_checksum::{ParamZeroChecksumAcceptable, ZERO_CHECKSUM_EDMID_DTLS},
     Param,
 };
 use crate::queue::{payload_queue::PayloadQueue, pending_queue::PendingQueue};
 use crate::shared::{AssociationEventInner, AssociationId, EndpointEvent, EndpointEventInner};
-use crate::util::{sna16lt, sna32gt, sna32gte, sna32lt, sna32lte};
+use crate::util::{get_padding_size, sna16lt, sna32gt, sna32gte, sna32lt, sna32lte};
 use crate::{AssociationEvent, Payload, Side, Transmit};
-use stream::{ReliabilityType, Stream, StreamEvent, StreamId, StreamState};
+use stream::{
+    IncomingMessage, ReliabilityType, SendOptions, Stream, StreamEvent, StreamHandle, StreamId,
+    StreamState,
+};
 use timer::{RtoManager, Timer, TimerTable, ACK_INTERVAL};
 
 use crate::association::stream::RecvSendState;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use fxhash::FxHashMap;
 use log::{debug, error, trace, warn};
-use rand::random;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::collections::{HashMap, VecDeque};
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
@@ -43,20 +65,35 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 use thiserror::Error;
 
+pub(crate) mod congestion;
+pub(crate) mod extensions;
+pub(crate) mod pmtud;
 pub(crate) mod state;
 pub(crate) mod stats;
 pub(crate) mod stream;
-mod timer;
+pub(crate) mod timer;
 
 #[cfg(test)]
 mod association_test;
 
+/// Practical upper bound on the number of fragments a single user message is split into
+const MAX_SEND_FRAGMENTS: u32 = 64;
+
+/// Size of the buffers outgoing packets are marshalled into, enough for a burst
+/// of full-sized packets
+const MARSHAL_BUFFER_SIZE: usize = 64 * 1024;
+
 /// Reasons why an association might be lost
 #[derive(Debug, Error, Eq, Clone, PartialEq)]
 pub enum AssociationError {
     /// Handshake failed
-    #[error("{0}")]
-    HandshakeFailed(#[from] Error),
+    #[error("handshake failed: {phase:?} after {attempts} retransmissions")]
+    HandshakeFailed {
+        /// How far the handshake went and why it failed
+        phase: HandshakePhase,
+        /// Retransmissions of the INIT or COOKIE ECHO made before failing
+        attempts: u32,
+    },
     /// The peer violated the QUIC specification as understood by this implementation
     #[error("transport error")]
     TransportError,
@@ -78,6 +115,80 @@ pub enum AssociationError {
     /// The local application closed the association
     #[error("closed")]
     LocallyClosed,
+    /// The peer sent an ABORT chunk
+    #[error("peer aborted the association: {causes:?}")]
+    PeerAborted {
+        /// The error causes the ABORT carried, in order
+        causes: Vec<AbortCause>,
+    },
+}
+
+/// The point at which a handshake failed, as reported by
+/// `AssociationError::HandshakeFailed`
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum HandshakePhase {
+    /// The INIT was never answered with an INIT ACK
+    InitTimeout,
+    /// The COOKIE ECHO was never answered with a COOKIE ACK
+    CookieEchoTimeout,
+    /// The peer answered the INIT or the COOKIE ECHO with an ABORT
+    AbortReceivedDuringHandshake {
+        /// The error causes the ABORT carried, in order
+        causes: Vec<AbortCause>,
+    },
+    /// The peer found the cookie we echoed stale
+    StaleCookie,
+}
+
+/// Why the peer aborted an association, as given by an error cause of its
+/// ABORT chunk (RFC 4960 Sec 3.3.10)
+#[derive(Debug, Eq, Clone, PartialEq)]
+pub enum AbortCause {
+    /// A mandatory parameter of an INIT or INIT ACK was set to an invalid value
+    InvalidMandatoryParameter,
+    /// The peer did not understand a chunk of the given type
+    UnrecognizedChunkType(u8),
+    /// The peer could not resolve one of our addresses
+    UnresolvableAddress,
+    /// A COOKIE ECHO reached the peer while it was shutting down
+    CookieReceiveDuringShutdown,
+    /// The peer ran out of resources
+    OutOfResource,
+    /// A restart attempted to add new addresses to the association
+    RestartWithNewAddresses,
+    /// The application of the peer aborted the association, for the given reason
+    UserInitiated(String),
+    /// The peer detected a protocol violation, described by the given text
+    ProtocolViolation(String),
+    /// Any other cause, by its code
+    Other(ErrorCauseCode),
+}
+
+impl From<&TypedErrorCause> for AbortCause {
+    fn from(cause: &TypedErrorCause) -> Self {
+        match cause {
+            TypedErrorCause::InvalidMandatoryParameter => AbortCause::InvalidMandatoryParameter,
+            // The cause holds the unrecognized chunk, starting with its type
+            TypedErrorCause::UnrecognizedChunkType { chunk } => {
+                AbortCause::UnrecognizedChunkType(chunk.first().copied().unwrap_or_default())
+            }
+            TypedErrorCause::UnresolvableAddress { .. } => AbortCause::UnresolvableAddress,
+            TypedErrorCause::CookieReceivedWhileShuttingDown => {
+                AbortCause::CookieReceiveDuringShutdown
+            }
+            TypedErrorCause::OutOfResource => AbortCause::OutOfResource,
+            TypedErrorCause::RestartOfAnAssociationWithNewAddresses { .. } => {
+                AbortCause::RestartWithNewAddresses
+            }
+            TypedErrorCause::UserInitiatedAbort { reason } => {
+                AbortCause::UserInitiated(String::from_utf8_lossy(reason).into_owned())
+            }
+            TypedErrorCause::ProtocolViolation { information } => {
+                AbortCause::ProtocolViolation(information.clone())
+            }
+            other => AbortCause::Other(other.code()),
+        }
+    }
 }
 
 /// Events of interest to the application
@@ -95,7 +206,26 @@ pub enum Event {
     /// Stream events
     Stream(StreamEvent),
     /// One or more application datagrams have been received
+    ///
+    /// Emitted at most once per call to `handle_event` or `handle_datagrams`.
     DatagramReceived,
+    /// A SACK opened up cwnd or rwnd enough for data that was blocked on them to be sent
+    Writable,
+    /// All data sent so far has been acknowledged by the peer
+    ///
+    /// Emitted once each time the last outstanding DATA chunk gets acknowledged.
+    AllDataAcknowledged,
+    /// The SHUTDOWN sequence started by either side finished after all data
+    /// sent before it was acknowledged. The association is closed.
+    ShutdownComplete,
+    /// Packets are now sent to a new address of the peer, which was validated
+    /// with a HEARTBEAT (see `ServerConfig::with_migration`)
+    PeerAddressChanged {
+        /// Address packets were sent to before
+        old: SocketAddr,
+        /// Address packets are sent to from now on
+        new: SocketAddr,
+    },
 }
 
 ///Association represents an SCTP association
@@ -128,58 +258,111 @@ pub struct Association {
 
     peer_verification_tag: u32,
     my_verification_tag: u32,
+    // Source of the initial TSN, State Cookies and nonces
+    rng: StdRng,
     my_next_tsn: u32,
     peer_last_tsn: u32,
     // for RTT measurement
     min_tsn2measure_rtt: u32,
+    // nonce and send time of the latest HEARTBEAT, for an RTT sample from its ACK
+    heartbeat_sent: Option<(u64, Instant)>,
+    // first outstanding TSN when T3-rtx last expired
+    t3_rtx_tsn: u32,
     will_send_forward_tsn: bool,
     will_retransmit_fast: bool,
     will_retransmit_reconfig: bool,
 
     will_send_shutdown_ack: bool,
     will_send_shutdown_complete: bool,
+    // set while a closed association lingers to absorb late packets
+    lingering: bool,
+    drained: bool,
 
     // Reconfig
     my_next_rsn: u32,
     reconfigs: FxHashMap<u32, ChunkReconfig>,
     reconfig_requests: FxHashMap<u32, ParamOutgoingResetRequest>,
+    /// Request sequence number of the last Add Outgoing Streams request applied,
+    /// so that a retransmitted one is not applied twice
+    peer_add_streams_rsn: Option<u32>,
 
     // Non-RFC internal data
     remote_addr: SocketAddr,
     local_ip: Option<IpAddr>,
-    source_port: u16,
-    destination_port: u16,
+    // whether remote_addr may follow the peer to a new address
+    migration: bool,
+    // new address of the peer, with the nonce and send time of the HEARTBEAT
+    // checking that it is reachable
+    path_challenge: Option<(SocketAddr, u64, Instant)>,
+    // that HEARTBEAT, waiting to be sent to the new address
+    path_challenge_packet: Option<Bytes>,
+    pub(crate) source_port: u16,
+    pub(crate) destination_port: u16,
     my_max_num_inbound_streams: u16,
     my_max_num_outbound_streams: u16,
     my_cookie: Option<ParamStateCookie>,
+    // key and clock of the signed cookies, with stateless cookies enabled
+    #[cfg(feature = "hmac-cookie")]
+    cookie_secret: Option<([u8; 32], CookieClock)>,
 
     payload_queue: PayloadQueue,
     inflight_queue: PayloadQueue,
     pending_queue: PendingQueue,
     control_queue: VecDeque<Packet>,
+    // serialized packets gathered but not handed out by poll_transmit_n yet
+    gathered_packets: VecDeque<Bytes>,
+    // scratch buffer outgoing packets are marshalled into
+    marshal_buf: BytesMut,
     stream_queue: VecDeque<u16>,
+    // streams that had a complete message when they were last looked at
+    readable_streams: VecDeque<StreamId>,
 
     pub(crate) mtu: u32,
     // max DATA chunk payload size
     max_payload_size: u32,
     cumulative_tsn_ack_point: u32,
+    // highest TSN reported by the newest SACK
+    highest_sacked_tsn: Option<u32>,
     advanced_peer_tsn_ack_point: u32,
-    use_forward_tsn: bool,
+    negotiated_extensions: NegotiatedExtensions,
 
     pub(crate) rto_mgr: RtoManager,
     timers: TimerTable,
+    peer_addr_params: Option<PeerAddrParams>,
 
     // Congestion control parameters
     max_receive_buffer_size: u32,
-    // my congestion window size
-    pub(crate) cwnd: u32,
-    // calculated peer's receiver windows size
-    rwnd: u32,
-    // slow start threshold
-    pub(crate) ssthresh: u32,
-    partial_bytes_acked: u32,
-    pub(crate) in_fast_recovery: bool,
-    fast_recover_exit_point: u32,
+    // limits on incomplete incoming messages
+    max_stream_reassembly_size: u32,
+    max_reassembly_size: u32,
+    // limit of a stream's buffered_amount
+    max_send_buffer_size: u32,
+    pub(crate) max_total_send_buffer_size: u32,
+    // streams whose writes failed because the association send buffer was full
+    write_blocked_streams: Vec<StreamId>,
+    pub(crate) congestion: CongestionController,
+    // restart cwnd after an idle period, and when DATA was last sent
+    cwnd_idle_restart: bool,
+    last_data_sent_at: Option<Instant>,
+    // retransmission timeouts since the peer last acknowledged anything
+    max_assoc_retransmissions: u32,
+    pub(crate) consecutive_retransmissions: u32,
+    // path MTU discovery, if enabled
+    pmtu_prober: Option<PmtuProber>,
+    // floor for MTUs reported by ICMP
+    min_pmtu: u32,
+    // latest timestamp of a handled inbound packet
+    last_received_time: Option<Instant>,
+    // zero checksums are advertised as acceptable in INIT and INIT ACK
+    zero_checksum_acceptable: bool,
+    // both sides accept zero checksums (RFC 9653)
+    pub(crate) zero_checksum: bool,
+    // pending data could not be sent because of cwnd or rwnd
+    send_blocked: bool,
+    // new RECONFIG chunks may share a packet with DATA chunks
+    reconfig_bundling_enabled: bool,
+    // Gap Ack Blocks of a received SACK beyond this many are ignored
+    max_gap_ack_blocks: usize,
 
     // Chunks stored for retransmission
     stored_init: Option<ChunkInit>,
@@ -193,6 +376,10 @@ pub struct Association {
     // per inbound packet context
     delayed_ack_triggered: bool,
     immediate_ack_triggered: bool,
+    datagram_received_queued: bool,
+
+    // Whether something may have changed what gather_outbound would send
+    needs_gather: bool,
 
     pub(crate) stats: AssociationStats,
     ack_state: AckState,
@@ -203,9 +390,19 @@ pub struct Association {
 
 impl Default for Association {
     fn default() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+}
+
+impl Association {
+    /// An association in the default state that draws its random numbers from
+    /// `rng`, so that a configured seed is used without consulting entropy.
+    fn with_rng(rng: StdRng) -> Self {
         Association {
             side: Side::default(),
             state: AssociationState::default(),
+            #[cfg(feature = "hmac-cookie")]
+            cookie_secret: None,
             handshake_completed: false,
             max_message_size: 0,
             inflight_queue_length: 0,
@@ -215,58 +412,81 @@ impl Default for Association {
 
             peer_verification_tag: 0,
             my_verification_tag: 0,
+            rng,
             my_next_tsn: 0,
             peer_last_tsn: 0,
             // for RTT measurement
             min_tsn2measure_rtt: 0,
+            heartbeat_sent: None,
+            t3_rtx_tsn: 0,
             will_send_forward_tsn: false,
             will_retransmit_fast: false,
             will_retransmit_reconfig: false,
 
             will_send_shutdown_ack: false,
             will_send_shutdown_complete: false,
+            lingering: false,
+            drained: false,
 
             // Reconfig
             my_next_rsn: 0,
             reconfigs: FxHashMap::default(),
             reconfig_requests: FxHashMap::default(),
+            peer_add_streams_rsn: None,
 
             // Non-RFC internal data
             remote_addr: SocketAddr::from_str("0.0.0.0:0").unwrap(),
+            migration: false,
+            path_challenge: None,
+            path_challenge_packet: None,
             local_ip: None,
             source_port: 0,
             destination_port: 0,
-            my_max_num_inbound_streams: 0,
-            my_max_num_outbound_streams: 0,
+            my_max_num_inbound_streams: u16::MAX,
+            my_max_num_outbound_streams: u16::MAX,
             my_cookie: None,
 
             payload_queue: PayloadQueue::default(),
             inflight_queue: PayloadQueue::default(),
             pending_queue: PendingQueue::default(),
             control_queue: VecDeque::default(),
+            gathered_packets: VecDeque::default(),
+            marshal_buf: BytesMut::new(),
             stream_queue: VecDeque::default(),
+            readable_streams: VecDeque::default(),
 
             mtu: 0,
             // max DATA chunk payload size
             max_payload_size: 0,
             cumulative_tsn_ack_point: 0,
+            highest_sacked_tsn: None,
             advanced_peer_tsn_ack_point: 0,
-            use_forward_tsn: false,
+            negotiated_extensions: NegotiatedExtensions::default(),
 
             rto_mgr: RtoManager::default(),
             timers: TimerTable::default(),
+            peer_addr_params: None,
 
             // Congestion control parameters
             max_receive_buffer_size: 0,
-            // my congestion window size
-            cwnd: 0,
-            // calculated peer's receiver windows size
-            rwnd: 0,
-            // slow start threshold
-            ssthresh: 0,
-            partial_bytes_acked: 0,
-            in_fast_recovery: false,
-            fast_recover_exit_point: 0,
+            max_stream_reassembly_size: INITIAL_RECV_BUF_SIZE,
+            max_reassembly_size: INITIAL_RECV_BUF_SIZE,
+            max_send_buffer_size: u32::MAX,
+            max_total_send_buffer_size: DEFAULT_MAX_TOTAL_SEND_BUFFER_SIZE,
+            write_blocked_streams: vec![],
+            congestion: CongestionController::default(),
+            cwnd_idle_restart: true,
+            last_data_sent_at: None,
+            max_assoc_retransmissions: DEFAULT_MAX_ASSOC_RETRANSMISSIONS,
+            consecutive_retransmissions: 0,
+            pmtu_prober: None,
+            min_pmtu: MIN_MTU,
+            last_received_time: None,
+            zero_checksum_acceptable: false,
+            zero_checksum: false,
+            send_blocked: false,
+            reconfig_bundling_enabled: true,
+            max_gap_ack_blocks: DEFAULT_MAX_GAP_ACK_BLOCKS,
 
             // Chunks stored for retransmission
             stored_init: None,
@@ -280,6 +500,9 @@ impl Default for Association {
             // per inbound packet context
             delayed_ack_triggered: false,
             immediate_ack_triggered: false,
+            datagram_received_queued: false,
+
+            needs_gather: true,
 
             stats: AssociationStats::default(),
             ack_state: AckState::default(),
@@ -290,6 +513,48 @@ impl Default for Association {
     }
 }
 
+/// Size of a DATA chunk on the wire, including its padding
+fn data_chunk_size(c: &ChunkPayloadData) -> u32 {
+    let size = DATA_CHUNK_HEADER_SIZE as usize + c.user_data.len();
+    (size + get_padding_size(size)) as u32
+}
+
+/// Adds the parameters following the state cookie to an INIT ACK answering `i`
+fn add_init_ack_params(
+    init_ack: &mut ChunkInit,
+    i: &ChunkInit,
+    zero_checksum_acceptable: bool,
+) -> Result<()> {
+    for param in &i.params {
+        if let Some(v) = param.as_any().downcast_ref::<ParamUnknown>() {
+            if v.should_report() {
+                debug!("reporting unrecognized INIT param: {}", v);
+                init_ack.params.push(Box::new(ParamUnrecognized {
+                    raw_param: v.marshal()?,
+                }));
+            }
+        }
+    }
+
+    init_ack.set_supported_extensions();
+    if zero_checksum_acceptable {
+        init_ack
+            .params
+            .push(Box::new(ParamZeroChecksumAcceptable::default()));
+    }
+    Ok(())
+}
+
+/// Whether an INIT or INIT ACK accepts zero checksums for DTLS (RFC 9653 Sec 5.2)
+fn accepts_zero_checksum(i: &ChunkInit) -> bool {
+    i.params.iter().any(|param| {
+        param
+            .as_any()
+            .downcast_ref::<ParamZeroChecksumAcceptable>()
+            .is_some_and(|p| p.edmid == ZERO_CHECKSUM_EDMID_DTLS)
+    })
+}
+
 impl Association {
     pub(crate) fn new(
         server_config: Option<Arc<ServerConfig>>,
@@ -310,31 +575,57 @@ impl Association {
         // config.rs to get max_payload_size from INITIAL_MTU.
         let mtu = max_payload_size + COMMON_HEADER_SIZE + DATA_CHUNK_HEADER_SIZE;
 
-        // RFC 4690 Sec 7.2.1
-        // The initial cwnd before DATA transmission or after a sufficiently
-        // long idle period MUST be set to min(4*MTU, max (2*MTU, 4380bytes)).
-        let cwnd = (2 * mtu).clamp(4380, 4 * mtu);
-        let mut tsn = random::<u32>();
+        let mut rng = config.rng();
+        let mut tsn = rng.gen::<u32>();
         if tsn == 0 {
             tsn += 1;
         }
 
+        // Out-of-order chunks are bounded by what a receive buffer full of
+        // MTU-sized chunks would hold.
+        let max_out_of_order_chunks =
+            std::cmp::max(config.max_receive_buffer_size() / mtu, 1) as usize;
+
         let mut this = Association {
             side,
             handshake_completed: false,
             max_receive_buffer_size: config.max_receive_buffer_size(),
+            max_stream_reassembly_size: config.max_stream_reassembly_size(),
+            max_reassembly_size: config.max_reassembly_size(),
+            max_send_buffer_size: config.max_send_buffer_size(),
+            max_total_send_buffer_size: config.max_total_send_buffer_size(),
+            cwnd_idle_restart: config.cwnd_idle_restart(),
+            max_assoc_retransmissions: config.max_assoc_retransmissions(),
+            pmtu_prober: config.pmtu_discovery().then(|| {
+                PmtuProber::new(
+                    mtu,
+                    config.min_probe_size(),
+                    config.max_probe_size(),
+                    rng.gen(),
+                )
+            }),
+            min_pmtu: config.min_pmtu(),
+            zero_checksum_acceptable: config.zero_checksum(),
+            reconfig_bundling_enabled: config.reconfig_bundling_enabled(),
+            max_gap_ack_blocks: config.max_gap_ack_blocks(),
             max_message_size: config.max_message_size(),
             my_max_num_outbound_streams: config.max_num_outbound_streams(),
             my_max_num_inbound_streams: config.max_num_inbound_streams(),
             max_payload_size,
+            // The server side learns the actual ports from the peer's INIT
+            source_port: config.source_port(),
+            destination_port: config.destination_port(),
 
+            payload_queue: PayloadQueue::new_with_capacity(max_out_of_order_chunks),
             rto_mgr: RtoManager::new(),
             timers: TimerTable::new(),
+            peer_addr_params: None,
 
             mtu,
-            cwnd,
+            congestion: CongestionController::new(mtu),
             remote_addr,
             local_ip,
+            migration: server_config.as_ref().is_some_and(|c| c.migration),
 
             my_verification_tag: local_aid,
             my_next_tsn: tsn,
@@ -343,8 +634,13 @@ impl Association {
             cumulative_tsn_ack_point: tsn - 1,
             advanced_peer_tsn_ack_point: tsn - 1,
             error: None,
+            #[cfg(feature = "hmac-cookie")]
+            cookie_secret: server_config
+                .as_ref()
+                .filter(|c| c.stateless_cookies)
+                .map(|c| (c.cookie_secret, c.cookie_clock)),
 
-            ..Default::default()
+            ..Self::with_rng(rng)
         };
 
         if side.is_client() {
@@ -357,6 +653,10 @@ impl Association {
                 ..Default::default()
             };
             init.set_supported_extensions();
+            if this.zero_checksum_acceptable {
+                init.params
+                    .push(Box::new(ParamZeroChecksumAcceptable::default()));
+            }
 
             this.set_state(AssociationState::CookieWait);
             this.stored_init = Some(init);
@@ -368,6 +668,107 @@ impl Association {
         this
     }
 
+    /// Answers the INIT of `p` without setting up an association (RFC 4960
+    /// Sec 5.1.3). Everything the association needs is carried by the signed
+    /// cookie of the INIT ACK, and it is only created once the peer echoes the
+    /// cookie back.
+    #[cfg(feature = "hmac-cookie")]
+    pub(crate) fn stateless_init_ack(
+        server_config: &ServerConfig,
+        p: &Packet,
+        local_aid: AssociationId,
+        now: Instant,
+    ) -> Result<Packet> {
+        p.check_packet()?;
+        let i = p
+            .chunks
+            .first()
+            .and_then(|c| c.as_any().downcast_ref::<ChunkInit>())
+            .filter(|i| !i.is_ack)
+            .ok_or(Error::ErrChunkTypeNotTypeInit)?;
+        i.check()?;
+
+        let config = &server_config.transport;
+        let extensions = i
+            .params
+            .iter()
+            .find_map(|param| param.as_any().downcast_ref::<ParamSupportedExtensions>())
+            .map(|v| {
+                NegotiatedExtensions::from_chunk_types(&v.chunk_types)
+                    .intersection(&NegotiatedExtensions::local())
+            })
+            .unwrap_or_default();
+        let mut my_initial_tsn = config.rng().gen::<u32>();
+        if my_initial_tsn == 0 {
+            my_initial_tsn += 1;
+        }
+        let params = CookieParams {
+            peer_verification_tag: i.initiate_tag,
+            source_port: p.common_header.destination_port,
+            destination_port: p.common_header.source_port,
+            my_verification_tag: local_aid,
+            my_initial_tsn,
+            peer_initial_tsn: i.initial_tsn,
+            num_outbound_streams: std::cmp::min(
+                i.num_inbound_streams,
+                config.max_num_outbound_streams(),
+            ),
+            num_inbound_streams: std::cmp::min(
+                i.num_outbound_streams,
+                config.max_num_inbound_streams(),
+            ),
+            extensions,
+            zero_checksum: config.zero_checksum() && accepts_zero_checksum(i),
+        };
+
+        let mut init_ack = ChunkInit {
+            is_ack: true,
+            initial_tsn: params.my_initial_tsn,
+            num_outbound_streams: params.num_outbound_streams,
+            num_inbound_streams: params.num_inbound_streams,
+            initiate_tag: params.my_verification_tag,
+            advertised_receiver_window_credit: config.max_receive_buffer_size(),
+            params: vec![Box::new(ParamStateCookie::new_signed(
+                &server_config.cookie_secret,
+                &params,
+                server_config.cookie_clock.unix_time_ms(now),
+            ))],
+        };
+        add_init_ack_params(&mut init_ack, i, config.zero_checksum())?;
+
+        Ok(Packet {
+            common_header: CommonHeader {
+                verification_tag: params.peer_verification_tag,
+                source_port: params.source_port,
+                destination_port: params.destination_port,
+            },
+            chunks: vec![Box::new(init_ack)],
+        })
+    }
+
+    /// Sets up a server association from the parameters of a cookie handed
+    /// out by `stateless_init_ack`. The COOKIE ECHO is then handled as usual.
+    #[cfg(feature = "hmac-cookie")]
+    pub(crate) fn restore_from_cookie(&mut self, params: &CookieParams, cookie: Bytes) {
+        self.peer_verification_tag = params.peer_verification_tag;
+        self.source_port = params.source_port;
+        self.destination_port = params.destination_port;
+
+        let tsn = params.my_initial_tsn;
+        self.my_next_tsn = tsn;
+        self.my_next_rsn = tsn;
+        self.min_tsn2measure_rtt = tsn;
+        self.cumulative_tsn_ack_point = tsn.wrapping_sub(1);
+        self.advanced_peer_tsn_ack_point = tsn.wrapping_sub(1);
+        self.peer_last_tsn = params.peer_initial_tsn.wrapping_sub(1);
+
+        self.my_max_num_outbound_streams = params.num_outbound_streams;
+        self.my_max_num_inbound_streams = params.num_inbound_streams;
+        self.negotiated_extensions = params.extensions;
+        self.zero_checksum = params.zero_checksum;
+        self.my_cookie = Some(ParamStateCookie { cookie });
+    }
+
     /// Returns application-facing event
     ///
     /// Associations should be polled for events after:
@@ -376,6 +777,11 @@ impl Association {
     #[must_use]
     pub fn poll(&mut self) -> Option<Event> {
         if let Some(x) = self.events.pop_front() {
+            if let Event::Stream(StreamEvent::Readable { id }) = &x {
+                if let Some(s) = self.streams.get_mut(id) {
+                    s.readable_event_queued = false;
+                }
+            }
             return Some(x);
         }
 
@@ -390,6 +796,22 @@ impl Association {
         None
     }
 
+    /// Whether `poll` would return an event
+    pub fn has_events(&self) -> bool {
+        !self.events.is_empty() || self.error.is_some()
+    }
+
+    /// Number of events `poll` would return before returning `None`
+    pub fn event_count(&self) -> usize {
+        self.events.len() + usize::from(self.error.is_some())
+    }
+
+    /// Drains all pending application-facing events, in the order `poll` would
+    /// have returned them
+    pub fn clear_events(&mut self) -> Vec<Event> {
+        std::iter::from_fn(|| self.poll()).collect()
+    }
+
     /// Return endpoint-facing event
     #[must_use]
     pub fn poll_endpoint_event(&mut self) -> Option<EndpointEvent> {
@@ -416,7 +838,11 @@ impl Association {
     /// - a call was made to `handle_timeout`
     #[must_use]
     pub fn poll_transmit(&mut self, now: Instant) -> Option<Transmit> {
-        let (contents, _) = self.gather_outbound(now);
+        if let Some(transmit) = self.poll_path_challenge(now) {
+            return Some(transmit);
+        }
+
+        let contents = self.gather_outbound_batch(now, usize::MAX);
         if contents.is_empty() {
             None
         } else {
@@ -436,6 +862,46 @@ impl Association {
         }
     }
 
+    /// Returns at most `max_packets` packets to transmit, each in its own `Transmit`
+    ///
+    /// Packets gathered beyond `max_packets` are kept for the next call to this
+    /// method or `poll_transmit`, which lets the caller interleave sending with
+    /// other work.
+    #[must_use]
+    pub fn poll_transmit_n(&mut self, now: Instant, max_packets: usize) -> Vec<Transmit> {
+        let mut transmits: Vec<Transmit> = self
+            .poll_path_challenge(now)
+            .into_iter()
+            .take(max_packets)
+            .collect();
+        let max_packets = max_packets - transmits.len();
+        transmits.extend(
+            self.gather_outbound_batch(now, max_packets)
+                .into_iter()
+                .map(|content| Transmit {
+                    now,
+                    remote: self.remote_addr,
+                    payload: Payload::RawEncode(vec![content]),
+                    ecn: None,
+                    local_ip: self.local_ip,
+                }),
+        );
+        transmits
+    }
+
+    /// The HEARTBEAT checking a new peer address, which is not sent to `remote_addr`
+    fn poll_path_challenge(&mut self, now: Instant) -> Option<Transmit> {
+        let raw = self.path_challenge_packet.take()?;
+        let (remote, _, _) = self.path_challenge?;
+        Some(Transmit {
+            now,
+            remote,
+            payload: Payload::RawEncode(vec![raw]),
+            ecn: None,
+            local_ip: self.local_ip,
+        })
+    }
+
     /// Process timer expirations
     ///
     /// Executes protocol logic, potentially preparing signals (including application `Event`s,
@@ -452,15 +918,30 @@ impl Association {
                 continue;
             }
             self.timers.set(timer, None);
+            self.awake_write_loop();
             //trace!("{:?} timeout", timer);
 
             if timer == Timer::Ack {
                 self.on_ack_timeout();
+            } else if timer == Timer::Close {
+                self.on_close_timeout();
             } else if failure {
                 self.on_retransmission_failure(timer);
             } else {
-                self.on_retransmission_timeout(timer, n_rtos);
-                self.timers.start(timer, now, self.rto_mgr.get_rto());
+                if timer == Timer::Heartbeat {
+                    self.send_heartbeat(n_rtos, now);
+                } else {
+                    self.on_retransmission_timeout(timer, n_rtos);
+                }
+                if self.state() == AssociationState::Closed {
+                    continue;
+                }
+                let interval = match timer {
+                    Timer::Heartbeat => self.heartbeat_interval(),
+                    Timer::Pmtu => self.pmtu_timer_interval(),
+                    _ => self.rto_mgr.get_rto(),
+                };
+                self.timers.start(timer, now, interval);
             }
         }
     }
@@ -473,39 +954,168 @@ impl Association {
     pub fn handle_event(&mut self, event: AssociationEvent) {
         match event.0 {
             AssociationEventInner::Datagram(transmit) => {
-                // If this packet could initiate a migration and we're a client or a server that
-                // forbids migration, drop the datagram. This could be relaxed to heuristically
-                // permit NAT-rebinding-like migration.
-                /*TODO:if remote != self.remote && self.server_config.as_ref().map_or(true, |x| !x.migration)
-                {
-                    trace!("discarding packet from unrecognized peer {}", remote);
-                    return;
-                }*/
+                let now = transmit.now;
+                self.handle_datagrams(std::iter::once(transmit), now);
+            } //TODO:
+        }
+    }
 
-                if let Payload::PartialDecode(partial_decode) = transmit.payload {
-                    trace!(
-                        "[{}] receiving {} bytes",
-                        self.side,
-                        COMMON_HEADER_SIZE as usize + partial_decode.remaining.len()
-                    );
+    /// Processes a batch of datagrams received for this association, as read by a
+    /// single `recvmmsg` call for instance
+    ///
+    /// Equivalent to passing each of them to `handle_event`, except that whether and
+    /// when to acknowledge the DATA they carry is decided once for the whole batch.
+    pub fn handle_datagrams(
+        &mut self,
+        datagrams: impl IntoIterator<Item = Transmit>,
+        now: Instant,
+    ) {
+        let now = self.clamp_receive_time(now);
+        self.handle_chunk_start();
+        // Inbound chunks may call for a reply of their own
+        self.awake_write_loop();
+        let mut n_delayed_ack_packets = 0;
+        for transmit in datagrams {
+            let Payload::PartialDecode(partial_decode) = transmit.payload else {
+                trace!("discarding invalid partial_decode");
+                continue;
+            };
+            trace!(
+                "[{}] receiving {} bytes",
+                self.side,
+                COMMON_HEADER_SIZE as usize + partial_decode.remaining.len()
+            );
 
-                    let pkt = match partial_decode.finish() {
-                        Ok(p) => p,
-                        Err(err) => {
-                            warn!("[{}] unable to parse SCTP packet {}", self.side, err);
-                            return;
-                        }
-                    };
+            let zero_checksum_acceptable =
+                self.zero_checksum && partial_decode.is_zero_checksum_acceptable();
+            if !zero_checksum_acceptable && !partial_decode.verify_checksum() {
+                warn!("[{}] discarding packet with zero checksum", self.side);
+                continue;
+            }
+
+            let pkt = match partial_decode.finish() {
+                Ok(p) => p,
+                Err(err) => {
+                    warn!("[{}] unable to parse SCTP packet {}", self.side, err);
+                    continue;
+                }
+            };
 
-                    if let Err(err) = self.handle_inbound(pkt, transmit.now) {
-                        error!("handle_inbound got err: {}", err);
-                        let _ = self.close();
+            if self.lingering {
+                self.handle_out_of_the_blue(&pkt);
+                continue;
+            }
+            if self.drained {
+                trace!("[{}] discarding packet for drained association", self.side);
+                continue;
+            }
+
+            let received_at = self.clamp_receive_time(transmit.now);
+            let verification_tag = pkt.common_header.verification_tag;
+            match self.process_inbound(pkt, received_at) {
+                Ok(()) => {
+                    self.last_received_time = Some(received_at);
+                    if transmit.remote != self.remote_addr
+                        && verification_tag == self.my_verification_tag
+                    {
+                        self.challenge_path(transmit.remote, received_at);
                     }
-                } else {
-                    trace!("discarding invalid partial_decode");
                 }
-            } //TODO:
+                Err(err) => self.handle_inbound_error(err),
+            }
+            if self.delayed_ack_triggered {
+                self.delayed_ack_triggered = false;
+                n_delayed_ack_packets += 1;
+            }
         }
+
+        // A SACK may be delayed for a single packet only (RFC 4960 Sec 6.2), as if
+        // the packets had been handled one by one
+        if n_delayed_ack_packets > 1 && self.ack_mode != AckMode::ImmediateOnLoss {
+            self.immediate_ack_triggered = true;
+        } else if n_delayed_ack_packets > 0 {
+            self.delayed_ack_triggered = true;
+        }
+        self.handle_chunk_end(now);
+    }
+
+    /// Sends a HEARTBEAT to `remote`, which authenticated traffic arrived from, so
+    /// that the association moves there once it is acknowledged. A challenge
+    /// still unanswered after an RTO is sent again.
+    fn challenge_path(&mut self, remote: SocketAddr, now: Instant) {
+        if !self.migration || self.state() != AssociationState::Established {
+            return;
+        }
+        if let Some((addr, _, sent_at)) = self.path_challenge {
+            let rto = Duration::from_millis(self.rto_mgr.get_rto());
+            if addr == remote && now < sent_at + rto {
+                return;
+            }
+        }
+
+        debug!("[{}] validating new peer address {}", self.side, remote);
+        let nonce = self.rng.gen::<u64>();
+        let heartbeat = ChunkHeartbeat {
+            params: vec![Box::new(ParamHeartbeatInfo {
+                heartbeat_information: Bytes::copy_from_slice(&nonce.to_be_bytes()),
+            })],
+        };
+        let p = self.create_packet(vec![Box::new(heartbeat)]);
+        match self.marshal_packet(&p) {
+            Ok(raw) => {
+                self.path_challenge = Some((remote, nonce, now));
+                self.path_challenge_packet = Some(raw);
+            }
+            Err(err) => warn!("[{}] failed to serialize a HEARTBEAT: {}", self.side, err),
+        }
+    }
+
+    /// Moves the association to the address of the path challenge that `info`
+    /// acknowledges, if any
+    fn on_path_challenge_acked(&mut self, info: &Bytes) {
+        let Some((new, nonce, _)) = self.path_challenge else {
+            return;
+        };
+        if info.as_ref() != nonce.to_be_bytes() {
+            return;
+        }
+
+        self.path_challenge = None;
+        self.path_challenge_packet = None;
+        let old = std::mem::replace(&mut self.remote_addr, new);
+        debug!(
+            "[{}] peer address changed from {} to {}",
+            self.side, old, new
+        );
+        self.congestion.on_path_changed(self.mtu);
+        self.events
+            .push_back(Event::PeerAddressChanged { old, new });
+    }
+
+    /// Timestamps of inbound packets must not go back in time, or RTT and timer
+    /// computations would be off. Earlier ones are raised to the latest seen.
+    fn clamp_receive_time(&self, now: Instant) -> Instant {
+        match self.last_received_time {
+            Some(last) if now < last => {
+                warn!(
+                    "[{}] inbound packet timestamp {:?} before the previous one",
+                    self.side,
+                    last - now
+                );
+                last
+            }
+            _ => now,
+        }
+    }
+
+    /// Running timers along with the instant each of them fires
+    pub fn active_timers(&self) -> Vec<(Timer, Instant)> {
+        self.timers.active_timers()
+    }
+
+    /// Time left until the timer fires, or `None` if it is not running
+    pub fn timer_remaining(&self, timer: Timer, now: Instant) -> Option<Duration> {
+        self.timers.timer_remaining(timer, now)
     }
 
     /// Returns Association statistics
@@ -513,6 +1123,118 @@ impl Association {
         self.stats
     }
 
+    /// Bytes of user data sent to the peer but not yet acknowledged
+    pub fn bytes_in_flight(&self) -> usize {
+        self.inflight_queue.get_num_bytes()
+    }
+
+    /// Flight size of RFC 4960 Sec 6.1: the part of `bytes_in_flight` that is
+    /// not waiting to be retransmitted after a timeout
+    pub fn flight_size(&self) -> usize {
+        self.inflight_queue.get_num_bytes_in_flight()
+    }
+
+    /// Fill level of the queue holding DATA received out of order, from 0.0 to
+    /// 1.0. Once it is full, the receive window is advertised as closed.
+    pub fn receive_memory_pressure(&self) -> f32 {
+        self.payload_queue.memory_pressure()
+    }
+
+    /// Bytes of user data queued for sending but not yet sent
+    pub fn bytes_pending(&self) -> usize {
+        self.pending_queue.get_num_bytes()
+    }
+
+    /// SSN that will be assigned to the next ordered message sent on the stream
+    pub fn get_stream_send_sequence_number(&self, id: StreamId) -> Option<u16> {
+        self.streams.get(&id).map(|s| s.sequence_number)
+    }
+
+    /// SSN of the next ordered message expected from the peer on the stream
+    pub fn get_stream_recv_sequence_number(&self, id: StreamId) -> Option<u16> {
+        self.streams.get(&id).map(|s| s.reassembly_queue.next_ssn)
+    }
+
+    /// Reliability type and value configured for the stream
+    pub fn get_stream_reliability(&self, id: StreamId) -> Option<(ReliabilityType, u32)> {
+        self.streams
+            .get(&id)
+            .map(|s| (s.reliability_type, s.reliability_value))
+    }
+
+    /// Bytes of new data that cwnd and the peer's rwnd currently allow to be sent
+    ///
+    /// The rwnd tracked by the sender already excludes the bytes in flight.
+    pub fn send_capacity(&self) -> usize {
+        let cwnd_room = (self.congestion.cwnd as usize).saturating_sub(self.flight_size());
+        std::cmp::min(cwnd_room, self.congestion.rwnd as usize)
+    }
+
+    /// Current SACK strategy for received DATA
+    pub fn ack_mode(&self) -> AckMode {
+        self.ack_mode
+    }
+
+    /// Change when received DATA is acknowledged
+    pub fn set_ack_mode(&mut self, mode: AckMode) {
+        self.ack_mode = mode;
+    }
+
+    /// Number of outbound streams negotiated with the peer
+    pub fn max_outbound_streams(&self) -> u16 {
+        self.my_max_num_outbound_streams
+    }
+
+    /// Number of inbound streams negotiated with the peer
+    pub fn max_inbound_streams(&self) -> u16 {
+        self.my_max_num_inbound_streams
+    }
+
+    /// Asks the peer to add `count` outgoing streams (RFC 6525 sec 5.1.5)
+    ///
+    /// The new streams can be opened once the peer accepted the request, which is
+    /// signaled by `StreamEvent::OutgoingStreamsAdded`.
+    pub fn request_add_outgoing_streams(&mut self, count: u16) -> Result<()> {
+        if self.state() != AssociationState::Established {
+            return Err(Error::ErrAddStreamsNonEstablished);
+        }
+        if self
+            .my_max_num_outbound_streams
+            .checked_add(count)
+            .is_none()
+        {
+            return Err(Error::ErrStreamIdExceedsLimit);
+        }
+
+        let rsn = self.generate_next_rsn();
+        debug!(
+            "[{}] requesting {} additional outgoing streams: rsn={}",
+            self.side, count, rsn
+        );
+        self.reconfigs.insert(
+            rsn,
+            ChunkReconfig {
+                param_a: Some(Box::new(ParamAddOutgoingStreamsRequest {
+                    reconfig_request_sequence_number: rsn,
+                    number_of_new_streams: count,
+                })),
+                param_b: None,
+            },
+        );
+
+        // Stored RECONFIG chunks are sent on the next transmission, which also
+        // arms the Reconfig timer for them.
+        self.will_retransmit_reconfig = true;
+        self.awake_write_loop();
+
+        Ok(())
+    }
+
+    /// Sum of `bytes_in_flight` and `bytes_pending`
+    pub fn total_queued_bytes(&self) -> usize {
+        self.bytes_in_flight() + self.bytes_pending()
+    }
+
     /// Whether the Association is in the process of being established
     ///
     /// If this returns `false`, the Association may be either established or closed, signaled by the
@@ -532,12 +1254,34 @@ impl Association {
         self.state == AssociationState::Closed
     }
 
+    /// Coarse state of the association
+    pub fn status(&self) -> AssociationStatus {
+        self.state.into()
+    }
+
+    /// Whether data can be exchanged with the peer
+    pub fn is_established(&self) -> bool {
+        self.state == AssociationState::Established
+    }
+
+    /// The handshake message still waiting for an answer from the peer, if
+    /// the association is connecting
+    ///
+    /// Useful to tell where a connection attempt is stuck.
+    pub fn handshake_step(&self) -> Option<HandshakeStep> {
+        match self.state {
+            AssociationState::CookieWait => Some(HandshakeStep::InitSent),
+            AssociationState::CookieEchoed => Some(HandshakeStep::CookieEchoed),
+            _ => None,
+        }
+    }
+
     /// Whether there is no longer any need to keep the association around
     ///
     /// Closed associations become drained after a brief timeout to absorb any remaining in-flight
     /// packets from the peer. All drained associations have been closed.
     pub fn is_drained(&self) -> bool {
-        self.state.is_drained()
+        self.drained
     }
 
     /// Look up whether we're the client or server of this Association
@@ -545,16 +1289,82 @@ impl Association {
         self.side
     }
 
+    /// Verification tag the peer puts in the packets it sends to us, which is
+    /// also the `AssociationId` the endpoint routes them by
+    pub fn my_verification_tag(&self) -> AssociationId {
+        self.my_verification_tag
+    }
+
+    /// Verification tag we put in the packets we send to the peer, as taken
+    /// from the Initiate Tag of its INIT or INIT ACK. Zero until then.
+    pub fn peer_verification_tag(&self) -> u32 {
+        self.peer_verification_tag
+    }
+
     /// The latest socket address for this Association's peer
     pub fn remote_addr(&self) -> SocketAddr {
         self.remote_addr
     }
 
-    /// Current best estimate of this Association's latency (round-trip-time)
+    /// Current best estimate of this Association's latency (smoothed round-trip-time)
+    ///
+    /// Returns `Duration::ZERO` until the first RTT sample is taken.
     pub fn rtt(&self) -> Duration {
+        Duration::from_millis(self.rto_mgr.get_srtt())
+    }
+
+    /// Current retransmission timeout of this Association
+    pub fn rto(&self) -> Duration {
         Duration::from_millis(self.rto_mgr.get_rto())
     }
 
+    /// RTT estimates of the path to the peer, along with how many measurements
+    /// they are based on
+    pub fn get_path_rtt(&self) -> PathRtt {
+        PathRtt {
+            srtt: Duration::from_millis(self.rto_mgr.get_srtt()),
+            rttvar: Duration::from_secs_f64(self.rto_mgr.get_rttvar() / 1000.0),
+            rto: Duration::from_millis(self.rto_mgr.get_rto()),
+            last_measured: self.rto_mgr.last_rtt_measurement,
+            samples_taken: self.rto_mgr.samples_taken,
+        }
+    }
+
+    /// Overrides the heartbeat and retransmission parameters of the path to `addr`
+    ///
+    /// `addr` must be the current peer address. Heartbeats (re)start with the new interval
+    /// on the next call to `poll_transmit` while the association is established.
+    pub fn set_peer_addr_params(&mut self, addr: SocketAddr, params: PeerAddrParams) -> Result<()> {
+        if addr != self.remote_addr {
+            return Err(Error::ErrPeerAddrUnknown);
+        }
+
+        let rto_initial = params.rto_initial.as_millis() as u64;
+        let rto_min = params.rto_min.as_millis() as u64;
+        let rto_max = params.rto_max.as_millis() as u64;
+        if rto_min == 0 || rto_min > rto_initial || rto_initial > rto_max {
+            return Err(Error::ErrPeerAddrParamsInvalid);
+        }
+        if params.heartbeat_interval.is_some_and(|d| d.is_zero()) {
+            return Err(Error::ErrPeerAddrParamsInvalid);
+        }
+
+        self.rto_mgr.set_bounds(rto_initial, rto_min, rto_max);
+        self.timers.set_rto_max(rto_max);
+        self.timers
+            .set_max_retrans(Timer::Heartbeat, params.max_retrans);
+        self.timers.stop(Timer::Heartbeat);
+        self.peer_addr_params = Some(params);
+        self.awake_write_loop();
+
+        Ok(())
+    }
+
+    /// Returns the parameters set by `set_peer_addr_params`, if any
+    pub fn peer_addr_params(&self) -> Option<PeerAddrParams> {
+        self.peer_addr_params
+    }
+
     /// The local IP address which was used when the peer established
     /// the association
     ///
@@ -573,9 +1383,121 @@ impl Association {
         self.local_ip
     }
 
-    /// Shutdown initiates the shutdown sequence. The method blocks until the
-    /// shutdown sequence is completed and the association is closed, or until the
-    /// passed context is done, in which case the context's error is returned.
+    /// Changes the local IP address passed along with outgoing `Transmit`s
+    ///
+    /// The address must be of the same family as the peer address.
+    pub fn set_local_ip(&mut self, ip: Option<IpAddr>) -> Result<()> {
+        if let Some(ip) = ip {
+            if ip.is_ipv4() != self.remote_addr.is_ipv4() {
+                return Err(Error::ErrLocalIpFamilyMismatch);
+            }
+        }
+
+        if ip != self.local_ip {
+            debug!(
+                "[{}] local ip changed from {:?} to {:?}",
+                self.side, self.local_ip, ip
+            );
+            self.local_ip = ip;
+        }
+
+        Ok(())
+    }
+
+    /// Changes the path MTU, e.g. after ICE switched to another candidate pair
+    /// or a VPN came up. The MTU must be at least 576 bytes.
+    ///
+    /// Queued messages are fragmented to fit the new MTU as they are sent, while
+    /// DATA chunks already in flight keep their size. As a smaller MTU means a
+    /// new path, cwnd then starts over from a single packet.
+    pub fn set_mtu(&mut self, mtu: u32) -> Result<()> {
+        if mtu < MIN_MTU {
+            return Err(Error::ErrMtuTooSmall);
+        }
+
+        let old_mtu = self.mtu;
+        self.apply_mtu(mtu);
+        if mtu < old_mtu {
+            self.congestion.on_mtu_decreased(mtu);
+            debug!(
+                "[{}] cwnd={} ssthresh={} after mtu decrease",
+                self.side, self.congestion.cwnd, self.congestion.ssthresh
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Switches to `mtu` found by PMTU discovery on the current path, whose
+    /// congestion state remains valid
+    fn apply_mtu(&mut self, mtu: u32) {
+        if mtu == self.mtu {
+            return;
+        }
+
+        debug!("[{}] mtu changed from {} to {}", self.side, self.mtu, mtu);
+        self.mtu = mtu;
+        self.max_payload_size = mtu - COMMON_HEADER_SIZE - DATA_CHUNK_HEADER_SIZE;
+
+        self.congestion.on_mtu_changed(mtu);
+        self.awake_write_loop();
+    }
+
+    /// Lowers the MTU after the I/O layer received an ICMP "Fragmentation Needed"
+    /// or "Packet Too Big" message reporting `mtu_hint` for the path.
+    ///
+    /// Hints that do not lower the MTU are ignored, and the MTU never drops
+    /// below `TransportConfig::with_min_pmtu`. DATA chunks sent within the last
+    /// RTO that do not fit the new MTU were most likely dropped, so they are
+    /// retransmitted right away.
+    pub fn handle_pmtu_signal(&mut self, mtu_hint: u32, now: Instant) {
+        let mtu = std::cmp::max(mtu_hint, self.min_pmtu);
+        if mtu >= self.mtu {
+            trace!(
+                "[{}] ignoring PMTU signal of {} (mtu={})",
+                self.side,
+                mtu_hint,
+                self.mtu
+            );
+            return;
+        }
+
+        debug!("[{}] PMTU signal of {} received", self.side, mtu_hint);
+        self.apply_mtu(mtu);
+        if let Some(prober) = self.pmtu_prober.as_mut() {
+            prober.on_packet_too_big(mtu);
+        }
+
+        let rto = Duration::from_millis(self.rto_mgr.get_rto());
+        let mut n_marked = 0;
+        let mut tsn = self.cumulative_tsn_ack_point.wrapping_add(1);
+        while sna32lt(tsn, self.my_next_tsn) {
+            if let Some(mut c) = self.inflight_queue.get_mut(tsn) {
+                let oversized = COMMON_HEADER_SIZE + data_chunk_size(&c) > mtu;
+                let recent = c.last_sent.is_some_and(|t| now < t + rto);
+                if oversized && recent && !c.acked && !c.retransmit && !c.abandoned() {
+                    c.retransmit = true;
+                    // The chunk never made it, so it is not retransmitted twice
+                    // within an RTO either
+                    c.last_sent = None;
+                    n_marked += 1;
+                }
+            }
+            tsn = tsn.wrapping_add(1);
+        }
+        if n_marked > 0 {
+            debug!(
+                "[{}] retransmitting {} chunks too large for the new mtu",
+                self.side, n_marked
+            );
+            self.awake_write_loop();
+        }
+    }
+
+    /// Shutdown initiates the shutdown sequence. New messages are rejected from
+    /// now on, while those already queued are still delivered before SHUTDOWN is
+    /// sent. `Event::ShutdownComplete` is emitted once the peer has
+    /// acknowledged the shutdown and the association is closed.
     pub fn shutdown(&mut self) -> Result<()> {
         debug!("[{}] closing association..", self.side);
 
@@ -587,14 +1509,12 @@ impl Association {
         // Attempt a graceful shutdown.
         self.set_state(AssociationState::ShutdownPending);
 
-        if self.inflight_queue_length == 0 {
+        if self.inflight_queue.is_empty() && self.pending_queue.is_empty() {
             // No more outstanding, send shutdown.
             self.will_send_shutdown = true;
-            self.awake_write_loop();
             self.set_state(AssociationState::ShutdownSent);
         }
-
-        self.endpoint_events.push_back(EndpointEventInner::Drained);
+        self.awake_write_loop();
 
         Ok(())
     }
@@ -603,6 +1523,8 @@ impl Association {
     pub fn close(&mut self) -> Result<()> {
         if self.state() != AssociationState::Closed {
             self.set_state(AssociationState::Closed);
+            self.lingering = true;
+            self.awake_write_loop();
 
             debug!("[{}] closing association..", self.side);
 
@@ -643,24 +1565,34 @@ impl Association {
         Ok(())
     }
 
-    /// open_stream opens a stream
+    /// open_stream opens a stream and returns a handle to it that does not borrow
+    /// the association
     pub fn open_stream(
         &mut self,
         stream_identifier: StreamId,
         default_payload_type: PayloadProtocolIdentifier,
-    ) -> Result<Stream<'_>> {
+    ) -> Result<StreamHandle> {
         if self.streams.contains_key(&stream_identifier) {
             return Err(Error::ErrStreamAlreadyExist);
         }
+        if stream_identifier >= self.my_max_num_outbound_streams {
+            return Err(Error::ErrStreamIdExceedsLimit);
+        }
 
-        if let Some(s) = self.create_stream(stream_identifier, false, default_payload_type) {
-            Ok(s)
+        if self
+            .create_stream(stream_identifier, None, default_payload_type)
+            .is_some()
+        {
+            Ok(StreamHandle { stream_identifier })
         } else {
             Err(Error::ErrStreamCreateFailed)
         }
     }
 
-    /// accept_stream accepts a stream
+    /// accept_stream accepts a stream opened by the peer
+    ///
+    /// Streams are returned in the order they were opened. Each one is announced by a
+    /// `StreamEvent::OpenedWithPpi`, but they can also be accepted without waiting for the event.
     pub fn accept_stream(&mut self) -> Option<Stream<'_>> {
         self.stream_queue
             .pop_front()
@@ -670,6 +1602,53 @@ impl Association {
             })
     }
 
+    /// Like `accept_stream`, but only returns the id of the accepted stream
+    pub fn accept_stream_id(&mut self) -> Option<StreamId> {
+        self.stream_queue.pop_front()
+    }
+
+    /// The ids of the streams currently registered with the association, in no
+    /// particular order
+    pub fn streams_iter(&self) -> impl Iterator<Item = StreamId> + '_ {
+        self.streams.keys().copied()
+    }
+
+    /// Number of streams currently registered with the association
+    pub fn stream_count(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// Reads the next complete message from whichever stream has one, taking
+    /// turns between the readable streams.
+    ///
+    /// Messages of a single stream are returned in the same order as
+    /// `Stream::read_message` would return them.
+    pub fn read_message(&mut self) -> Option<IncomingMessage> {
+        while let Some(stream_id) = self.readable_streams.pop_front() {
+            let Some(s) = self.streams.get_mut(&stream_id) else {
+                continue;
+            };
+            if s.state != RecvSendState::ReadWritable && s.state != RecvSendState::Readable {
+                continue;
+            }
+            let Some(chunks) = s.reassembly_queue.read() else {
+                continue;
+            };
+            if s.reassembly_queue.is_readable() {
+                self.readable_streams.push_back(stream_id);
+            }
+
+            return Some(IncomingMessage {
+                stream_id,
+                ppi: chunks.ppi,
+                unordered: chunks.chunks.first().is_some_and(|c| c.unordered),
+                data: chunks.to_bytes(),
+            });
+        }
+
+        None
+    }
+
     /// stream returns a stream
     pub fn stream(&mut self, stream_identifier: StreamId) -> Result<Stream<'_>> {
         if !self.streams.contains_key(&stream_identifier) {
@@ -682,6 +1661,77 @@ impl Association {
         }
     }
 
+    /// send_with_options fragments `data` into DATA chunks for the given stream
+    /// according to `opts` and queues them for transmission.
+    pub fn send_with_options(
+        &mut self,
+        stream_identifier: StreamId,
+        data: Bytes,
+        opts: SendOptions,
+    ) -> Result<()> {
+        if data.len() > self.max_message_size() as usize {
+            return Err(Error::ErrOutboundPacketTooLarge);
+        }
+
+        match self.state() {
+            AssociationState::ShutdownSent
+            | AssociationState::ShutdownAckSent
+            | AssociationState::ShutdownPending
+            | AssociationState::ShutdownReceived => return Err(Error::ErrAssociationShuttingDown),
+            _ => {}
+        };
+
+        let chunk = match self.streams.get_mut(&stream_identifier) {
+            Some(s)
+                if s.state == RecvSendState::Writable || s.state == RecvSendState::ReadWritable =>
+            {
+                // An empty DATA chunk is not allowed on the wire, and in the
+                // pending queue it marks a stream reset
+                if data.is_empty() {
+                    return Ok(());
+                }
+                if s.buffered_amount + data.len() > self.max_send_buffer_size as usize {
+                    return Err(Error::ErrSendBufferFull);
+                }
+                if self.pending_queue.get_num_bytes() + data.len()
+                    > self.max_total_send_buffer_size as usize
+                {
+                    if !self.write_blocked_streams.contains(&stream_identifier) {
+                        self.write_blocked_streams.push(stream_identifier);
+                    }
+                    return Err(Error::ErrSendBufferFull);
+                }
+                s.stats.inc_messages_sent(data.len());
+                s.packetize(&data, &opts)
+            }
+            Some(_) => return Err(Error::ErrStreamClosed),
+            None => return Err(Error::ErrStreamNotExisted),
+        };
+
+        self.send_payload_data(chunk)
+    }
+
+    /// Bytes that can still be queued across all streams before writes fail with
+    /// `Error::ErrSendBufferFull`
+    pub fn send_buffer_available(&self) -> usize {
+        self.max_total_send_buffer_size
+            .saturating_sub(self.pending_queue.get_num_bytes() as u32) as usize
+    }
+
+    /// Emits `StreamEvent::Writable` for the streams whose writes failed on a full
+    /// send buffer, once queued data has left it
+    fn notify_write_blocked_streams(&mut self) {
+        if self.write_blocked_streams.is_empty() || self.send_buffer_available() == 0 {
+            return;
+        }
+        for id in self.write_blocked_streams.drain(..) {
+            if self.streams.contains_key(&id) {
+                self.events
+                    .push_back(Event::Stream(StreamEvent::Writable { id }));
+            }
+        }
+    }
+
     /// bytes_sent returns the number of bytes sent
     pub(crate) fn bytes_sent(&self) -> usize {
         self.bytes_sent
@@ -693,24 +1743,104 @@ impl Association {
     }
 
     /// max_message_size returns the maximum message size you can send.
-    pub(crate) fn max_message_size(&self) -> u32 {
+    /// Larger messages are rejected with `Error::ErrOutboundPacketTooLarge`.
+    pub fn max_message_size(&self) -> u32 {
         self.max_message_size
     }
 
-    /// set_max_message_size sets the maximum message size you can send.
-    pub(crate) fn set_max_message_size(&mut self, max_message_size: u32) {
+    /// set_max_message_size sets the maximum message size you can send, e.g. to
+    /// the value negotiated for WebRTC data channels in SDP (RFC 8841).
+    pub fn set_max_message_size(&mut self, max_message_size: u32) {
         self.max_message_size = max_message_size;
     }
 
+    /// Receive buffer size the advertised receiver window is derived from
+    pub fn max_receive_buffer_size(&self) -> u32 {
+        self.max_receive_buffer_size
+    }
+
+    /// Changes the receive buffer size, e.g. to shrink the buffers of idle
+    /// associations in favor of busy ones.
+    ///
+    /// Data already buffered is kept even if it exceeds the new size; the
+    /// window stays closed until the application reads enough of it. If the
+    /// window grows, a SACK announcing it is sent right away.
+    pub fn set_max_receive_buffer_size(&mut self, value: u32) {
+        let old_rwnd = self.get_my_receiver_window_credit();
+
+        self.max_receive_buffer_size = value;
+        for s in self.streams.values_mut() {
+            s.reassembly_queue.max_bytes = value as usize;
+        }
+        self.payload_queue
+            .set_capacity(std::cmp::max(value / self.mtu, 1) as usize);
+
+        let rwnd = self.get_my_receiver_window_credit();
+        debug!(
+            "[{}] receive buffer size set to {}, a_rwnd {} -> {}",
+            self.side, value, old_rwnd, rwnd
+        );
+        if rwnd > old_rwnd && self.handshake_completed && !self.is_closed() {
+            self.ack_state = AckState::Immediate;
+            self.timers.stop(Timer::Ack);
+            self.awake_write_loop();
+        }
+    }
+
+    /// max_single_packet_payload returns the largest user message that fits in a single
+    /// DATA chunk. Larger messages will be fragmented.
+    pub fn max_single_packet_payload(&self) -> u32 {
+        self.max_payload_size
+    }
+
+    /// max_send_message_size returns the largest user message that can be sent, which
+    /// is the configured max_message_size clamped to a practical number of fragments.
+    pub fn max_send_message_size(&self) -> u32 {
+        std::cmp::min(
+            self.max_message_size,
+            self.max_payload_size.saturating_mul(MAX_SEND_FRAGMENTS),
+        )
+    }
+
+    /// estimate_fragment_count returns the number of DATA chunks a user message of
+    /// `size` bytes will be split into.
+    pub fn estimate_fragment_count(&self, size: usize) -> usize {
+        size.div_ceil(std::cmp::max(self.max_payload_size, 1) as usize)
+    }
+
     /// unregister_stream un-registers a stream from the association
     /// The caller should hold the association write lock.
     fn unregister_stream(&mut self, stream_identifier: StreamId) {
+        self.drain_pending_for_stream(stream_identifier);
         if let Some(mut s) = self.streams.remove(&stream_identifier) {
             debug!("[{}] unregister_stream {}", self.side, stream_identifier);
             s.state = RecvSendState::Closed;
         }
     }
 
+    /// Discards the data of the stream that is queued but not yet sent, without
+    /// waiting for the data in flight to be acknowledged. Returns the number of
+    /// bytes discarded.
+    pub fn drain_pending_for_stream(&mut self, stream_identifier: StreamId) -> usize {
+        let n_bytes = self.pending_queue.drain_stream(stream_identifier);
+        if n_bytes > 0 {
+            debug!(
+                "[{}] drained {} pending bytes of stream {}",
+                self.side, n_bytes, stream_identifier
+            );
+            if let Some(s) = self.streams.get_mut(&stream_identifier) {
+                if s.on_buffer_released(n_bytes as i64) {
+                    self.events
+                        .push_back(Event::Stream(StreamEvent::BufferedAmountLow {
+                            id: stream_identifier,
+                        }))
+                }
+            }
+            self.notify_write_blocked_streams();
+        }
+        n_bytes
+    }
+
     /// set_state atomically sets the state of the Association.
     fn set_state(&mut self, new_state: AssociationState) {
         if new_state != self.state {
@@ -732,9 +1862,6 @@ impl Association {
         if let Some(stored_init) = &self.stored_init {
             debug!("[{}] sending INIT", self.side);
 
-            self.source_port = 5000; // Spec??
-            self.destination_port = 5000; // Spec??
-
             let outbound = Packet {
                 common_header: CommonHeader {
                     source_port: self.source_port,
@@ -778,25 +1905,202 @@ impl Association {
 
     /// handle_inbound parses incoming raw packets
     fn handle_inbound(&mut self, p: Packet, now: Instant) -> Result<()> {
+        self.handle_chunk_start();
+        let result = self.process_inbound(p, now);
+        self.handle_chunk_end(now);
+        result
+    }
+
+    /// Handles the chunks of an incoming packet, leaving the acknowledgement
+    /// decision to the caller
+    fn process_inbound(&mut self, p: Packet, now: Instant) -> Result<()> {
         if let Err(err) = p.check_packet() {
             warn!("[{}] failed validating packet {}", self.side, err);
             return Ok(());
         }
 
-        self.handle_chunk_start();
+        // The ports of an INIT are the ones the association takes on
+        let is_init = p.chunks.first().is_some_and(|c| {
+            c.as_any()
+                .downcast_ref::<ChunkInit>()
+                .is_some_and(|i| !i.is_ack)
+        });
+        if !is_init
+            && (p.common_header.destination_port != self.source_port
+                || p.common_header.source_port != self.destination_port)
+        {
+            debug!(
+                "[{}] packet from port {} to port {} does not belong to the association",
+                self.side, p.common_header.source_port, p.common_header.destination_port
+            );
+            self.handle_out_of_the_blue(&p);
+            return Ok(());
+        }
+
+        if let Some(abort) = p
+            .chunks
+            .iter()
+            .find_map(|c| c.as_any().downcast_ref::<ChunkAbort>())
+        {
+            if !self.is_abort_verification_tag_valid(p.common_header.verification_tag, abort) {
+                debug!(
+                    "[{}] discarding ABORT with unexpected verification tag {}",
+                    self.side, p.common_header.verification_tag
+                );
+                return Ok(());
+            }
+        }
+
+        // Chunks handled before a failing one keep their effects, so their
+        // acknowledgement is still scheduled.
+        p.chunks
+            .iter()
+            .try_for_each(|c| self.handle_chunk(&p, c, now))
+    }
 
+    /// Answers a packet that does not belong to the association, because it
+    /// lingers after being closed or the ports do not match, the way an
+    /// endpoint without a matching association would (RFC 9260 Sec 8.4). The
+    /// association is left as it is.
+    fn handle_out_of_the_blue(&mut self, p: &Packet) {
+        let mut shutdown_ack = false;
         for c in &p.chunks {
-            self.handle_chunk(&p, c, now)?;
+            let c = c.as_any();
+            if c.is::<ChunkAbort>() || c.is::<ChunkShutdownComplete>() {
+                return;
+            }
+            if let Some(e) = c.downcast_ref::<ChunkError>() {
+                let stale_cookie = e
+                    .typed_error_causes()
+                    .iter()
+                    .any(|cause| matches!(cause, TypedErrorCause::StaleCookie { .. }));
+                if stale_cookie {
+                    return;
+                }
+            }
+            shutdown_ack |= c.is::<ChunkShutdownAck>();
         }
 
-        self.handle_chunk_end(now);
+        let init = p
+            .chunks
+            .first()
+            .and_then(|c| c.as_any().downcast_ref::<ChunkInit>())
+            .filter(|c| !c.is_ack);
+        let (verification_tag, chunk): (u32, Box<dyn Chunk + Send + Sync>) =
+            if let Some(init) = init {
+                (init.initiate_tag, Box::<ChunkAbort>::default())
+            } else if shutdown_ack {
+                (
+                    p.common_header.verification_tag,
+                    Box::new(ChunkShutdownComplete {
+                        verification_tag_reflected: true,
+                    }),
+                )
+            } else {
+                (
+                    p.common_header.verification_tag,
+                    Box::new(ChunkAbort {
+                        verification_tag_reflected: true,
+                        ..Default::default()
+                    }),
+                )
+            };
+        debug!(
+            "[{}] answering out of the blue packet with {}",
+            self.side, chunk
+        );
+
+        self.control_queue.push_back(Packet {
+            common_header: CommonHeader {
+                source_port: p.common_header.destination_port,
+                destination_port: p.common_header.source_port,
+                verification_tag,
+            },
+            chunks: vec![chunk],
+        });
+        self.awake_write_loop();
+    }
+
+    /// RFC 4960 sec 8.5.1 (B)
+    ///   The receiver of an ABORT MUST accept the packet if the Verification
+    ///   Tag field of the packet matches its own tag and the T bit is not set
+    ///   OR if it is set to its peer's tag and the T bit is set in the Chunk
+    ///   Flags.  Otherwise, the receiver MUST silently discard the packet and
+    ///   take no further action.
+    fn is_abort_verification_tag_valid(&self, verification_tag: u32, abort: &ChunkAbort) -> bool {
+        if abort.verification_tag_reflected {
+            verification_tag == self.peer_verification_tag
+        } else {
+            verification_tag == self.my_verification_tag
+        }
+    }
+
+    /// Decides the fate of the association after an inbound packet failed to
+    /// be processed. Most failures only concern the packet itself, which is
+    /// dropped; the association is closed only when the peer has aborted, the
+    /// peer violated the protocol in a way that prevents going on, or our own
+    /// state is inconsistent.
+    fn handle_inbound_error(&mut self, err: Error) {
+        match err {
+            Error::ErrAbortChunk(causes) => {
+                warn!("[{}] association aborted by peer", self.side);
+                let causes = causes.iter().map(AbortCause::from).collect();
+                if let Some(timer) = self.handshake_timer() {
+                    let attempts = self.timers.retransmissions(timer);
+                    self.fail_handshake(
+                        HandshakePhase::AbortReceivedDuringHandshake { causes },
+                        attempts,
+                    );
+                } else {
+                    self.error = Some(AssociationError::PeerAborted { causes });
+                    let _ = self.close();
+                }
+            }
+            Error::ErrInitAckNoCookie
+            | Error::ErrChunkTypeInitInitiateTagZero
+            | Error::ErrInitInboundStreamRequestZero
+            | Error::ErrInitOutboundStreamRequestZero
+            | Error::ErrInitAdvertisedReceiver1500 => {
+                error!("[{}] protocol violation by peer: {}", self.side, err);
+                self.abort_with_cause(TypedErrorCause::ProtocolViolation {
+                    information: err.to_string(),
+                });
+            }
+            Error::ErrInitNotStoredToSend
+            | Error::ErrCookieEchoNotStoredToSend
+            | Error::ErrUnexpectedQState
+            | Error::ErrUnexpectedChuckPoppedOrdered
+            | Error::ErrUnexpectedChuckPoppedUnordered
+            | Error::ErrInflightQueueTsnPop
+            | Error::ErrTsnRequestNotExist
+            | Error::Other(_) => {
+                error!("[{}] handle_inbound got err: {}", self.side, err);
+                let _ = self.close();
+            }
+            _ => {
+                warn!("[{}] discarding inbound packet: {}", self.side, err);
+                self.stats.inc_discarded_packets();
+            }
+        }
+    }
 
-        Ok(())
+    /// Sends an ABORT carrying `cause` to the peer and closes the association
+    fn abort_with_cause(&mut self, cause: TypedErrorCause) {
+        let abort = ChunkAbort {
+            error_causes: vec![cause.into()],
+            ..Default::default()
+        };
+        self.control_queue
+            .push_back(self.create_packet(vec![Box::new(abort)]));
+        self.awake_write_loop();
+        self.error = Some(AssociationError::TransportError);
+        let _ = self.close();
     }
 
     fn handle_chunk_start(&mut self) {
         self.delayed_ack_triggered = false;
         self.immediate_ack_triggered = false;
+        self.datagram_received_queued = false;
     }
 
     fn handle_chunk_end(&mut self, now: Instant) {
@@ -824,27 +2128,33 @@ impl Association {
             if c.is_ack {
                 self.handle_init_ack(p, c, now)?
             } else {
-                self.handle_init(p, c)?
+                self.handle_init(p, c, now)?
             }
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkAbort>() {
-            let mut err_str = String::new();
-            for e in &c.error_causes {
-                if matches!(e.code, USER_INITIATED_ABORT) {
-                    debug!("User initiated abort received");
-                    let _ = self.close();
-                    return Ok(());
-                }
-                err_str += &format!("({})", e);
+            let causes = c.typed_error_causes();
+            for cause in &causes {
+                warn!("[{}] ABORT received: {}", self.side, cause);
             }
-            return Err(Error::ErrAbortChunk(err_str));
+            return Err(Error::ErrAbortChunk(causes));
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkError>() {
-            let mut err_str = String::new();
-            for e in &c.error_causes {
-                err_str += &format!("({})", e);
+            // ERROR chunks report conditions the peer was able to recover
+            // from, so they do not affect the association, except for a stale
+            // cookie which leaves the handshake nothing to wait for.
+            for cause in c.typed_error_causes() {
+                warn!("[{}] ERROR received: {}", self.side, cause);
+                if matches!(cause, TypedErrorCause::StaleCookie { .. })
+                    && self.state() == AssociationState::CookieEchoed
+                {
+                    let attempts = self.timers.retransmissions(Timer::T1Cookie);
+                    self.fail_handshake(HandshakePhase::StaleCookie, attempts);
+                    return Ok(());
+                }
             }
-            return Err(Error::ErrAbortChunk(err_str));
+            vec![]
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkHeartbeat>() {
             self.handle_heartbeat(c)?
+        } else if let Some(c) = chunk_any.downcast_ref::<ChunkHeartbeatAck>() {
+            self.handle_heartbeat_ack(c, now)
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkCookieEcho>() {
             self.handle_cookie_echo(c)?
         } else if chunk_any.downcast_ref::<ChunkCookieAck>().is_some() {
@@ -860,11 +2170,16 @@ impl Association {
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkShutdown>() {
             self.handle_shutdown(c)?
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkShutdownAck>() {
-            self.handle_shutdown_ack(c)?
+            self.handle_shutdown_ack(p, c)?
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkShutdownComplete>() {
             self.handle_shutdown_complete(c)?
+        } else if chunk_any.downcast_ref::<ChunkPadding>().is_some() {
+            // RFC 4820 Sec 3: the receiver of the PAD chunk MUST discard it
+            vec![]
+        } else if let Some(c) = chunk_any.downcast_ref::<ChunkUnknown>() {
+            self.handle_unknown_chunk(c)?
         } else {
-            return Err(Error::ErrChunkTypeUnhandled);
+            return Err(Error::ErrChunkTypeUnhandled(chunk.header().typ.0));
         };
 
         if !packets.is_empty() {
@@ -876,7 +2191,7 @@ impl Association {
         Ok(())
     }
 
-    fn handle_init(&mut self, p: &Packet, i: &ChunkInit) -> Result<Vec<Packet>> {
+    fn handle_init(&mut self, p: &Packet, i: &ChunkInit, now: Instant) -> Result<Vec<Packet>> {
         let state = self.state();
         debug!("[{}] chunkInit received in state '{}'", self.side, state);
 
@@ -897,10 +2212,11 @@ impl Association {
         }
 
         // Should we be setting any of these permanently until we've ACKed further?
+        // The peer's MIS bounds our outbound streams and its OS our inbound ones.
         self.my_max_num_inbound_streams =
-            std::cmp::min(i.num_inbound_streams, self.my_max_num_inbound_streams);
+            std::cmp::min(i.num_outbound_streams, self.my_max_num_inbound_streams);
         self.my_max_num_outbound_streams =
-            std::cmp::min(i.num_outbound_streams, self.my_max_num_outbound_streams);
+            std::cmp::min(i.num_inbound_streams, self.my_max_num_outbound_streams);
         self.peer_verification_tag = i.initiate_tag;
         self.source_port = p.common_header.destination_port;
         self.destination_port = p.common_header.source_port;
@@ -917,17 +2233,13 @@ impl Association {
 
         for param in &i.params {
             if let Some(v) = param.as_any().downcast_ref::<ParamSupportedExtensions>() {
-                for t in &v.chunk_types {
-                    if *t == CT_FORWARD_TSN {
-                        debug!("[{}] use ForwardTSN (on init)", self.side);
-                        self.use_forward_tsn = true;
-                    }
-                }
+                self.negotiate_extensions(v);
             }
         }
-        if !self.use_forward_tsn {
+        if !self.use_forward_tsn() {
             warn!("[{}] not using ForwardTSN (on init)", self.side);
         }
+        self.negotiate_zero_checksum(i);
 
         let mut outbound = Packet {
             common_header: CommonHeader {
@@ -949,14 +2261,13 @@ impl Association {
         };
 
         if self.my_cookie.is_none() {
-            self.my_cookie = Some(ParamStateCookie::new());
+            self.my_cookie = Some(self.new_cookie(now));
         }
 
         if let Some(my_cookie) = &self.my_cookie {
             init_ack.params = vec![Box::new(my_cookie.clone())];
         }
-
-        init_ack.set_supported_extensions();
+        add_init_ack_params(&mut init_ack, i, self.zero_checksum_acceptable)?;
 
         outbound.chunks = vec![Box::new(init_ack)];
 
@@ -982,9 +2293,9 @@ impl Association {
         }
 
         self.my_max_num_inbound_streams =
-            std::cmp::min(i.num_inbound_streams, self.my_max_num_inbound_streams);
+            std::cmp::min(i.num_outbound_streams, self.my_max_num_inbound_streams);
         self.my_max_num_outbound_streams =
-            std::cmp::min(i.num_outbound_streams, self.my_max_num_outbound_streams);
+            std::cmp::min(i.num_inbound_streams, self.my_max_num_outbound_streams);
         self.peer_verification_tag = i.initiate_tag;
         self.peer_last_tsn = if i.initial_tsn == 0 {
             u32::MAX
@@ -998,41 +2309,44 @@ impl Association {
             return Ok(vec![]);
         }
 
-        self.rwnd = i.advertised_receiver_window_credit;
-        debug!("[{}] initial rwnd={}", self.side, self.rwnd);
-
-        // RFC 4690 Sec 7.2.1
-        //  o  The initial value of ssthresh MAY be arbitrarily high (for
-        //     example, implementations MAY use the size of the receiver
-        //     advertised window).
-        self.ssthresh = self.rwnd;
+        self.congestion
+            .set_initial_rwnd(i.advertised_receiver_window_credit);
+        debug!("[{}] initial rwnd={}", self.side, self.congestion.rwnd);
         trace!(
             "[{}] updated cwnd={} ssthresh={} inflight={} (INI)",
             self.side,
-            self.cwnd,
-            self.ssthresh,
-            self.inflight_queue.get_num_bytes()
+            self.congestion.cwnd,
+            self.congestion.ssthresh,
+            self.inflight_queue.get_num_bytes_in_flight()
         );
 
         self.timers.stop(Timer::T1Init);
         self.stored_init = None;
 
         let mut cookie_param = None;
+        let mut unrecognized = BytesMut::new();
         for param in &i.params {
             if let Some(v) = param.as_any().downcast_ref::<ParamStateCookie>() {
                 cookie_param = Some(v);
             } else if let Some(v) = param.as_any().downcast_ref::<ParamSupportedExtensions>() {
-                for t in &v.chunk_types {
-                    if *t == CT_FORWARD_TSN {
-                        debug!("[{}] use ForwardTSN (on initAck)", self.side);
-                        self.use_forward_tsn = true;
-                    }
+                self.negotiate_extensions(v);
+            } else if let Some(v) = param.as_any().downcast_ref::<ParamUnknown>() {
+                if v.should_report() {
+                    debug!(
+                        "[{}] reporting unrecognized INIT ACK param: {}",
+                        self.side, v
+                    );
+                    let raw = v.marshal()?;
+                    let padding = get_padding_size(raw.len());
+                    unrecognized.extend(raw);
+                    unrecognized.extend(vec![0u8; padding]);
                 }
             }
         }
-        if !self.use_forward_tsn {
+        if !self.use_forward_tsn() {
             warn!("[{}] not using ForwardTSN (on initAck)", self.side);
         }
+        self.negotiate_zero_checksum(i);
 
         if let Some(v) = cookie_param {
             self.stored_cookie_echo = Some(ChunkCookieEcho {
@@ -1046,7 +2360,19 @@ impl Association {
 
             self.set_state(AssociationState::CookieEchoed);
 
-            Ok(vec![])
+            if unrecognized.is_empty() {
+                return Ok(vec![]);
+            }
+
+            // RFC 4960 sec 5.1: unrecognized INIT ACK parameters are reported
+            // in an ERROR chunk, which must follow the COOKIE ECHO.
+            let cerr = ChunkError {
+                error_causes: vec![TypedErrorCause::UnrecognizedParameters {
+                    params: unrecognized.freeze(),
+                }
+                .into()],
+            };
+            Ok(vec![self.create_packet(vec![Box::new(cerr)])])
         } else {
             Err(Error::ErrInitAckNoCookie)
         }
@@ -1079,41 +2405,180 @@ impl Association {
         Ok(vec![])
     }
 
-    fn handle_cookie_echo(&mut self, c: &ChunkCookieEcho) -> Result<Vec<Packet>> {
-        let state = self.state();
-        debug!("[{}] COOKIE-ECHO received in state '{}'", self.side, state);
+    fn handle_heartbeat_ack(&mut self, c: &ChunkHeartbeatAck, now: Instant) -> Vec<Packet> {
+        trace!("[{}] chunkHeartbeatAck", self.side);
 
-        if let Some(my_cookie) = &self.my_cookie {
-            match state {
-                AssociationState::Established => {
-                    if my_cookie.cookie != c.cookie {
-                        return Ok(vec![]);
-                    }
-                }
-                AssociationState::Closed
-                | AssociationState::CookieWait
-                | AssociationState::CookieEchoed => {
-                    if my_cookie.cookie != c.cookie {
-                        return Ok(vec![]);
-                    }
+        if let Some(hbi) = c
+            .params
+            .first()
+            .and_then(|p| p.as_any().downcast_ref::<ParamHeartbeatInfo>())
+        {
+            self.on_pmtu_probe_acked(&hbi.heartbeat_information, now);
+            self.on_heartbeat_acked(&hbi.heartbeat_information, now);
+            self.on_path_challenge_acked(&hbi.heartbeat_information);
+        }
 
-                    self.timers.stop(Timer::T1Init);
-                    self.stored_init = None;
+        // RFC 4960 sec 8.3
+        // Upon the receipt of the HEARTBEAT ACK, the sender of the HEARTBEAT
+        // should clear the error counter of the destination transport
+        // address to which the HEARTBEAT was sent.
+        if self.timers.get(Timer::Heartbeat).is_some() {
+            self.timers.stop(Timer::Heartbeat);
+            self.timers
+                .start(Timer::Heartbeat, now, self.heartbeat_interval());
+        }
 
-                    self.timers.stop(Timer::T1Cookie);
-                    self.stored_cookie_echo = None;
+        vec![]
+    }
 
-                    self.events.push_back(Event::Connected);
-                    self.set_state(AssociationState::Established);
-                    self.handshake_completed = true;
-                }
-                _ => return Ok(vec![]),
+    /// RFC 4960 sec 8.3
+    ///   Upon the receipt of the HEARTBEAT ACK, the sender of the HEARTBEAT
+    ///   should also perform an RTT measurement for that destination transport
+    ///   address using the time value carried in the HEARTBEAT ACK chunk.
+    fn on_heartbeat_acked(&mut self, info: &Bytes, now: Instant) {
+        let Some((nonce, sent_at)) = self.heartbeat_sent else {
+            return;
+        };
+        if info.as_ref() != nonce.to_be_bytes() {
+            return;
+        }
+
+        self.heartbeat_sent = None;
+        let rtt = now.duration_since(sent_at);
+        let srtt = self.rto_mgr.set_new_rtt(rtt.as_millis() as u64, now);
+        trace!(
+            "[{}] HEARTBEAT ACK: measured-rtt={} srtt={} new-rto={}",
+            self.side,
+            rtt.as_millis(),
+            srtt,
+            self.rto_mgr.get_rto()
+        );
+    }
+
+    /// Records the extensions of a Supported Extensions parameter received in
+    /// an INIT or INIT ACK that this implementation supports as well
+    fn negotiate_extensions(&mut self, p: &ParamSupportedExtensions) {
+        self.negotiated_extensions = NegotiatedExtensions::from_chunk_types(&p.chunk_types)
+            .intersection(&NegotiatedExtensions::local());
+        debug!(
+            "[{}] negotiated extensions: {:?}",
+            self.side,
+            self.negotiated_extensions.iter().collect::<Vec<_>>()
+        );
+    }
+
+    /// Zero checksums are used once the peer's INIT or INIT ACK accepts them for
+    /// DTLS, and this endpoint does as well (RFC 9653 Sec 5.2)
+    fn negotiate_zero_checksum(&mut self, i: &ChunkInit) {
+        self.zero_checksum = self.zero_checksum_acceptable && accepts_zero_checksum(i);
+        debug!("[{}] zero checksum: {}", self.side, self.zero_checksum);
+    }
+
+    /// Extensions supported by both this endpoint and the peer, as advertised
+    /// during the handshake
+    pub fn negotiated_extensions(&self) -> &NegotiatedExtensions {
+        &self.negotiated_extensions
+    }
+
+    fn use_forward_tsn(&self) -> bool {
+        self.negotiated_extensions.has(SctpExtension::ForwardTsn)
+    }
+
+    fn new_cookie(&mut self, _now: Instant) -> ParamStateCookie {
+        #[cfg(feature = "hmac-cookie")]
+        if let Some((secret, clock)) = &self.cookie_secret {
+            let params = CookieParams {
+                peer_verification_tag: self.peer_verification_tag,
+                source_port: self.source_port,
+                destination_port: self.destination_port,
+                my_verification_tag: self.my_verification_tag,
+                my_initial_tsn: self.my_next_tsn,
+                peer_initial_tsn: self.peer_last_tsn.wrapping_add(1),
+                num_outbound_streams: self.my_max_num_outbound_streams,
+                num_inbound_streams: self.my_max_num_inbound_streams,
+                extensions: self.negotiated_extensions,
+                zero_checksum: self.zero_checksum,
             };
-        } else {
+            return ParamStateCookie::new_signed(secret, &params, clock.unix_time_ms(_now));
+        }
+        ParamStateCookie::generate(&mut self.rng)
+    }
+
+    /// Whether `cookie` is one this association handed out in its INIT ACK
+    fn is_own_cookie(&self, cookie: &Bytes) -> bool {
+        #[cfg(feature = "hmac-cookie")]
+        if let Some((secret, _)) = &self.cookie_secret {
+            return ParamStateCookie::verify_signed(
+                cookie,
+                secret,
+                self.peer_verification_tag,
+                self.source_port,
+                self.destination_port,
+            );
+        }
+        self.my_cookie
+            .as_ref()
+            .is_some_and(|my_cookie| my_cookie.cookie == *cookie)
+    }
+
+    fn handle_cookie_echo(&mut self, c: &ChunkCookieEcho) -> Result<Vec<Packet>> {
+        let state = self.state();
+        debug!("[{}] COOKIE-ECHO received in state '{}'", self.side, state);
+
+        if self.my_cookie.is_none() {
             debug!("[{}] COOKIE-ECHO received before initialization", self.side);
             return Ok(vec![]);
         }
 
+        if !self.is_own_cookie(&c.cookie) {
+            #[cfg(feature = "hmac-cookie")]
+            if self.cookie_secret.is_some() {
+                warn!("[{}] COOKIE-ECHO with an invalid signature", self.side);
+                self.abort_with_cause(TypedErrorCause::InvalidMandatoryParameter);
+                return Ok(vec![]);
+            }
+            debug!("[{}] COOKIE-ECHO with an unknown cookie", self.side);
+            return Ok(vec![]);
+        }
+
+        match state {
+            AssociationState::Closed
+            | AssociationState::CookieWait
+            | AssociationState::CookieEchoed => {
+                self.timers.stop(Timer::T1Init);
+                self.stored_init = None;
+
+                self.timers.stop(Timer::T1Cookie);
+                self.stored_cookie_echo = None;
+
+                self.events.push_back(Event::Connected);
+                self.set_state(AssociationState::Established);
+                self.handshake_completed = true;
+                self.endpoint_events
+                    .push_back(EndpointEventInner::HandshakeCompleted);
+            }
+            // A duplicate: our COOKIE ACK was lost, so send it again
+            AssociationState::Established => {}
+            // RFC 4960 sec 3.3.10.10
+            //   This error is usually returned in an ERROR chunk bundled with the
+            //   retransmitted SHUTDOWN ACK.
+            AssociationState::ShutdownAckSent => {
+                return Ok(vec![self.create_packet(vec![
+                    Box::new(ChunkShutdownAck {}),
+                    Box::new(ChunkError {
+                        error_causes: vec![TypedErrorCause::CookieReceivedWhileShuttingDown.into()],
+                    }),
+                ])]);
+            }
+            AssociationState::ShutdownPending
+            | AssociationState::ShutdownReceived
+            | AssociationState::ShutdownSent => {
+                return Ok(vec![self.create_packet(vec![Box::new(ChunkError {
+                    error_causes: vec![TypedErrorCause::CookieReceivedWhileShuttingDown.into()],
+                })])]);
+            }
+        };
+
         Ok(vec![Packet {
             common_header: CommonHeader {
                 verification_tag: self.peer_verification_tag,
@@ -1137,6 +2602,7 @@ impl Association {
 
         self.timers.stop(Timer::T1Cookie);
         self.stored_cookie_echo = None;
+        self.consecutive_retransmissions = 0;
 
         self.events.push_back(Event::Connected);
         self.set_state(AssociationState::Established);
@@ -1155,21 +2621,78 @@ impl Association {
         );
         self.stats.inc_datas();
 
+        // RFC 4960 sec 6.2: a DATA chunk without user data MUST be answered with an
+        // ABORT. Empty WebRTC messages carry a single padding byte and never get here.
+        if d.user_data.is_empty() {
+            warn!(
+                "[{}] DATA without user data: tsn={}, aborting",
+                self.side, d.tsn
+            );
+            self.abort_with_cause(TypedErrorCause::NoUserData { tsn: d.tsn });
+            return Ok(vec![]);
+        }
+
         let can_push = self.payload_queue.can_push(d, self.peer_last_tsn);
+
+        // RFC 4960 sec 6.5: report the invalid stream identifier and discard the
+        // user data, but still acknowledge the TSN.
+        if d.stream_identifier >= self.my_max_num_inbound_streams {
+            warn!(
+                "[{}] DATA on invalid stream {} (max inbound streams: {})",
+                self.side, d.stream_identifier, self.my_max_num_inbound_streams
+            );
+            if can_push {
+                let discarded = ChunkPayloadData {
+                    user_data: Bytes::new(),
+                    ..d.clone()
+                };
+                self.payload_queue.push(discarded, self.peer_last_tsn);
+            }
+
+            let cerr = ChunkError {
+                error_causes: vec![TypedErrorCause::InvalidStreamIdentifier {
+                    stream_identifier: d.stream_identifier,
+                }
+                .into()],
+            };
+            let mut reply = vec![self.create_packet(vec![Box::new(cerr)])];
+            reply.extend(self.handle_peer_last_tsn_and_acknowledgement(true)?);
+            return Ok(reply);
+        }
         let mut stream_handle_data = false;
         if can_push {
-            if self.get_or_create_stream(d.stream_identifier).is_some() {
+            if self
+                .get_or_create_stream(d.stream_identifier, d.payload_type)
+                .is_some()
+            {
+                // The chunk the stream waits for is always taken, otherwise a
+                // queue filled with messages queued behind it would never drain.
+                let next_expected = d.tsn == self.peer_last_tsn.wrapping_add(1);
+                let is_full = self.streams.get(&d.stream_identifier).is_some_and(|s| {
+                    s.reassembly_queue.is_full()
+                        && !next_expected
+                        && !s.reassembly_queue.is_next_ssn(d)
+                });
+                if is_full {
+                    // Leave the TSN unacknowledged so that the peer retransmits it
+                    // once the window reported by the SACK opens up again.
+                    warn!(
+                        "[{}] reassembly queue of stream {} is full, dropping DATA with tsn={}",
+                        self.side, d.stream_identifier, d.tsn
+                    );
+                    return self.handle_peer_last_tsn_and_acknowledgement(true);
+                }
+
                 if self.get_my_receiver_window_credit() > 0 {
                     // Pass the new chunk to stream level as soon as it arrives
-                    self.payload_queue.push(d.clone(), self.peer_last_tsn);
-                    stream_handle_data = true;
+                    stream_handle_data = self.payload_queue.push(d.clone(), self.peer_last_tsn);
                 } else {
                     // Receive buffer is full
                     if let Some(last_tsn) = self.payload_queue.get_last_tsn_received() {
                         if sna32lt(d.tsn, *last_tsn) {
                             debug!("[{}] receive buffer full, but accepted as this is a missing chunk with tsn={} ssn={}", self.side, d.tsn, d.stream_sequence_number);
-                            self.payload_queue.push(d.clone(), self.peer_last_tsn);
-                            stream_handle_data = true; //s.handle_data(d.clone());
+                            stream_handle_data =
+                                self.payload_queue.push(d.clone(), self.peer_last_tsn);
                         }
                     } else {
                         debug!(
@@ -1186,16 +2709,39 @@ impl Association {
             }
         }
 
-        let immediate_sack = d.immediate_sack;
+        // can_push ruled out duplicates, so the chunk was dropped for lack of space.
+        // Tell the peer about the closed window right away.
+        let dropped = can_push && !stream_handle_data && self.payload_queue.is_full();
+        if dropped {
+            debug!(
+                "[{}] payload queue full. dropping DATA with tsn={}",
+                self.side, d.tsn
+            );
+        }
+        let immediate_sack = d.immediate_sack || dropped;
 
         if stream_handle_data {
             if let Some(s) = self.streams.get_mut(&d.stream_identifier) {
-                self.events.push_back(Event::DatagramReceived);
+                if !self.datagram_received_queued {
+                    self.datagram_received_queued = true;
+                    self.events.push_back(Event::DatagramReceived);
+                }
                 s.handle_data(d);
+            }
+            self.enforce_reassembly_limits(d.stream_identifier);
+
+            if let Some(s) = self.streams.get_mut(&d.stream_identifier) {
                 if s.reassembly_queue.is_readable() {
-                    self.events.push_back(Event::Stream(StreamEvent::Readable {
-                        id: d.stream_identifier,
-                    }))
+                    if !self.readable_streams.contains(&d.stream_identifier) {
+                        self.readable_streams.push_back(d.stream_identifier);
+                    }
+                    // One Readable per stream is enough until the application has seen it
+                    if !s.readable_event_queued {
+                        s.readable_event_queued = true;
+                        self.events.push_back(Event::Stream(StreamEvent::Readable {
+                            id: d.stream_identifier,
+                        }))
+                    }
                 }
             }
         }
@@ -1203,6 +2749,64 @@ impl Association {
         self.handle_peer_last_tsn_and_acknowledgement(immediate_sack)
     }
 
+    /// Drops the oldest incomplete messages while the stream that just received data,
+    /// or the association as a whole, buffers more incomplete bytes than allowed.
+    fn enforce_reassembly_limits(&mut self, stream_identifier: StreamId) {
+        let mut dropped = vec![];
+
+        if let Some(s) = self.streams.get_mut(&stream_identifier) {
+            while s.reassembly_queue.get_num_incomplete_bytes()
+                > self.max_stream_reassembly_size as usize
+            {
+                let n = s.reassembly_queue.drop_oldest_incomplete();
+                if n == 0 {
+                    break;
+                }
+                dropped.push((stream_identifier, n));
+            }
+        }
+
+        let mut num_incomplete_bytes: usize = self
+            .streams
+            .values()
+            .map(|s| s.reassembly_queue.get_num_incomplete_bytes())
+            .sum();
+        while num_incomplete_bytes > self.max_reassembly_size as usize {
+            // Drop the oldest incomplete message across all streams
+            let oldest = self
+                .streams
+                .iter_mut()
+                .filter_map(|(si, s)| {
+                    s.reassembly_queue
+                        .oldest_incomplete_tsn()
+                        .map(|tsn| (tsn, *si, s))
+                })
+                .reduce(|a, b| if sna32lt(b.0, a.0) { b } else { a });
+            let n = match oldest {
+                Some((_, si, s)) => {
+                    let n = s.reassembly_queue.drop_oldest_incomplete();
+                    dropped.push((si, n));
+                    n
+                }
+                None => 0,
+            };
+            if n == 0 {
+                break;
+            }
+            num_incomplete_bytes -= n;
+        }
+
+        for (si, bytes) in dropped {
+            warn!(
+                "[{}] reassembly limit exceeded: dropped {} bytes of an incomplete message on stream {}",
+                self.side, bytes, si
+            );
+            self.stats.inc_reassembly_drops();
+            self.events
+                .push_back(Event::Stream(StreamEvent::MessageDropped { id: si, bytes }));
+        }
+    }
+
     fn handle_sack(&mut self, d: &ChunkSelectiveAck, now: Instant) -> Result<Vec<Packet>> {
         trace!(
             "[{}] {}, SACK: cumTSN={} a_rwnd={}",
@@ -1221,6 +2825,48 @@ impl Association {
 
         self.stats.inc_sacks();
 
+        if let Err(err) = d.validate_gap_blocks() {
+            warn!("[{}] dropping malformed SACK: {}", self.side, err);
+            return Ok(vec![]);
+        }
+        // The blocks are ordered, so the ones kept cover the lowest TSNs. The
+        // TSNs of the others lie above the highest TSN newly acknowledged and
+        // are therefore not counted as missing for fast retransmission.
+        let is_truncated = d.gap_ack_blocks.len() > self.max_gap_ack_blocks;
+        let truncated;
+        let d = if is_truncated {
+            warn!(
+                "[{}] SACK has {} gap ack blocks, processing the first {}",
+                self.side,
+                d.gap_ack_blocks.len(),
+                self.max_gap_ack_blocks
+            );
+            truncated = ChunkSelectiveAck {
+                cumulative_tsn_ack: d.cumulative_tsn_ack,
+                advertised_receiver_window_credit: d.advertised_receiver_window_credit,
+                gap_ack_blocks: d.gap_ack_blocks[..self.max_gap_ack_blocks].to_vec(),
+                duplicate_tsn: d.duplicate_tsn.clone(),
+            };
+            &truncated
+        } else {
+            d
+        };
+
+        // A peer must never acknowledge a TSN that has not been sent yet. Such a
+        // SACK cannot be trusted, so drop it before it changes any state.
+        let highest_sent_tsn = self.my_next_tsn.wrapping_sub(1);
+        if sna32gt(d.cumulative_tsn_ack, highest_sent_tsn) {
+            warn!(
+                "[{}] SACK Cumulative ACK {} is beyond highest sent TSN {}",
+                self.side, d.cumulative_tsn_ack, highest_sent_tsn
+            );
+            self.stats.inc_unknown_tsn_acks();
+
+            return Ok(vec![]);
+        }
+
+        self.consecutive_retransmissions = 0;
+
         if sna32gt(self.cumulative_tsn_ack_point, d.cumulative_tsn_ack) {
             // RFC 4960 sec 6.2.1.  Processing a Received SACK
             // D)
@@ -1238,8 +2884,33 @@ impl Association {
             return Ok(vec![]);
         }
 
+        self.undo_spurious_timeout(d.cumulative_tsn_ack, now);
+
+        // Flight size before this SACK, for the congestion avoidance rule
+        let pre_sack_inflight = self.inflight_queue.get_num_bytes_in_flight() as u32;
+        // Data marked for retransmission after a T3-rtx is still outstanding
+        let was_empty = self.inflight_queue.get_num_bytes() == 0;
+
+        // A SACK reordered in the network may lack the blocks of a newer one.
+        // Only a SACK known to be the newest, as it acknowledges more than any
+        // before, can show that the peer reneged.
+        let highest_sacked_tsn = d.gap_ack_blocks.last().map_or(d.cumulative_tsn_ack, |g| {
+            d.cumulative_tsn_ack.wrapping_add(g.end as u32)
+        });
+        let is_newest = sna32gt(d.cumulative_tsn_ack, self.cumulative_tsn_ack_point)
+            || !matches!(self.highest_sacked_tsn, Some(tsn) if sna32lte(highest_sacked_tsn, tsn));
+        if is_newest {
+            self.highest_sacked_tsn = Some(highest_sacked_tsn);
+        }
+
         // Process selective ack
-        let (bytes_acked_per_stream, htna) = self.process_selective_ack(d, now)?;
+        let (bytes_acked_per_stream, htna) = self.process_selective_ack(d, now);
+
+        // The blocks cut off still acknowledge their TSNs, so their absence
+        // from a truncated SACK is no sign of reneging
+        if is_newest && !is_truncated {
+            self.process_reneged_chunks(d, now);
+        }
 
         let mut total_bytes_acked = 0;
         for n_bytes_acked in bytes_acked_per_stream.values() {
@@ -1257,7 +2928,12 @@ impl Association {
 
             self.cumulative_tsn_ack_point = d.cumulative_tsn_ack;
             cum_tsn_ack_point_advanced = true;
-            self.on_cumulative_tsn_ack_point_advanced(total_bytes_acked, now);
+            self.on_cumulative_tsn_ack_point_advanced(
+                total_bytes_acked,
+                pre_sack_inflight,
+                was_empty,
+                now,
+            );
         }
 
         for (si, n_bytes_acked) in &bytes_acked_per_stream {
@@ -1270,23 +2946,14 @@ impl Association {
         }
 
         // New rwnd value
-        // RFC 4960 sec 6.2.1.  Processing a Received SACK
-        // D)
-        //   ii) Set rwnd equal to the newly received a_rwnd minus the number
-        //       of bytes still outstanding after processing the Cumulative
-        //       TSN Ack and the Gap Ack Blocks.
-
         // bytes acked were already subtracted by markAsAcked() method
         let bytes_outstanding = self.inflight_queue.get_num_bytes() as u32;
-        if bytes_outstanding >= d.advertised_receiver_window_credit {
-            self.rwnd = 0;
-        } else {
-            self.rwnd = d.advertised_receiver_window_credit - bytes_outstanding;
-        }
+        self.congestion
+            .update_rwnd(d.advertised_receiver_window_credit, bytes_outstanding);
 
-        self.process_fast_retransmission(d.cumulative_tsn_ack, htna, cum_tsn_ack_point_advanced)?;
+        self.process_fast_retransmission(d.cumulative_tsn_ack, htna, cum_tsn_ack_point_advanced);
 
-        if self.use_forward_tsn {
+        if self.use_forward_tsn() {
             // RFC 3758 Sec 3.5 C1
             if sna32lt(
                 self.advanced_peer_tsn_ack_point,
@@ -1324,6 +2991,17 @@ impl Association {
 
         self.postprocess_sack(state, cum_tsn_ack_point_advanced, now);
 
+        if self.send_blocked {
+            let fits = self
+                .pending_queue
+                .peek()
+                .is_some_and(|c| self.fragment_len(c) <= self.send_capacity());
+            if fits {
+                self.send_blocked = false;
+                self.events.push_back(Event::Writable);
+            }
+        }
+
         Ok(vec![])
     }
 
@@ -1343,10 +3021,26 @@ impl Association {
         Ok(pp)
     }
 
+    fn handle_unknown_chunk(&mut self, c: &ChunkUnknown) -> Result<Vec<Packet>> {
+        if !c.should_report() {
+            debug!("[{}] skipping unrecognized chunk: {}", self.side, c);
+            return Ok(vec![]);
+        }
+
+        warn!("[{}] reporting unrecognized chunk: {}", self.side, c);
+        let cerr = ChunkError {
+            error_causes: vec![TypedErrorCause::UnrecognizedChunkType {
+                chunk: c.marshal()?,
+            }
+            .into()],
+        };
+        Ok(vec![self.create_packet(vec![Box::new(cerr)])])
+    }
+
     fn handle_forward_tsn(&mut self, c: &ChunkForwardTsn) -> Result<Vec<Packet>> {
-        trace!("[{}] FwdTSN: {}", self.side, c.to_string());
+        trace!("[{}] FwdTSN: {}", self.side, c);
 
-        if !self.use_forward_tsn {
+        if !self.use_forward_tsn() {
             warn!("[{}] received FwdTSN but not enabled", self.side);
             // Return an error chunk
             let cerr = ChunkError {
@@ -1397,9 +3091,19 @@ impl Association {
         //   chunk,
 
         // Advance peer_last_tsn
+        let mut n_skipped = 0u32;
         while sna32lt(self.peer_last_tsn, c.new_cumulative_tsn) {
-            self.payload_queue.pop(self.peer_last_tsn + 1); // may not exist
-            self.peer_last_tsn += 1;
+            // A FORWARD TSN skipping this many TSNs cannot be valid
+            if n_skipped > u16::MAX as u32 {
+                error!(
+                    "[{}] FwdTSN skips too many TSNs: newCumTSN={} peer_last_tsn={}",
+                    self.side, c.new_cumulative_tsn, self.peer_last_tsn
+                );
+                break;
+            }
+            self.payload_queue.pop(self.peer_last_tsn.wrapping_add(1)); // may not exist
+            self.peer_last_tsn = self.peer_last_tsn.wrapping_add(1);
+            n_skipped += 1;
         }
 
         // Report new peer_last_tsn value and abandoned largest SSN value to
@@ -1448,13 +3152,44 @@ impl Association {
         Ok(vec![])
     }
 
-    fn handle_shutdown_ack(&mut self, _: &ChunkShutdownAck) -> Result<Vec<Packet>> {
+    fn handle_shutdown_ack(&mut self, p: &Packet, _: &ChunkShutdownAck) -> Result<Vec<Packet>> {
         let state = self.state();
-        if state == AssociationState::ShutdownSent || state == AssociationState::ShutdownAckSent {
-            self.timers.stop(Timer::T2Shutdown);
-            self.will_send_shutdown_complete = true;
+        match state {
+            AssociationState::ShutdownSent | AssociationState::ShutdownAckSent => {
+                self.timers.stop(Timer::T2Shutdown);
+                self.consecutive_retransmissions = 0;
+                self.will_send_shutdown_complete = true;
 
-            self.awake_write_loop();
+                self.awake_write_loop();
+            }
+            // RFC 4960 sec 9.2
+            //   If a SHUTDOWN ACK is received by an endpoint in the COOKIE-WAIT or
+            //   COOKIE-ECHOED state, the SHUTDOWN ACK chunk SHOULD be treated as an
+            //   OOTB packet.
+            // RFC 4960 sec 8.4.5
+            //   the receiver should respond to the sender of the OOTB packet with
+            //   a SHUTDOWN COMPLETE. [...] the receiver of the OOTB packet must fill
+            //   in the Verification Tag field of the outbound packet with the
+            //   Verification Tag received in the SHUTDOWN ACK and set the T bit
+            AssociationState::Closed
+            | AssociationState::CookieWait
+            | AssociationState::CookieEchoed => {
+                debug!(
+                    "[{}] SHUTDOWN-ACK received in state '{}', sending SHUTDOWN-COMPLETE",
+                    self.side, state
+                );
+                return Ok(vec![Packet {
+                    common_header: CommonHeader {
+                        verification_tag: p.common_header.verification_tag,
+                        source_port: self.source_port,
+                        destination_port: self.destination_port,
+                    },
+                    chunks: vec![Box::new(ChunkShutdownComplete {
+                        verification_tag_reflected: true,
+                    })],
+                }]);
+            }
+            _ => {}
         }
 
         Ok(vec![])
@@ -1464,12 +3199,22 @@ impl Association {
         let state = self.state();
         if state == AssociationState::ShutdownAckSent {
             self.timers.stop(Timer::T2Shutdown);
-            self.close()?;
+            self.complete_shutdown();
         }
 
         Ok(vec![])
     }
 
+    /// Closes the association at the end of the SHUTDOWN sequence
+    fn complete_shutdown(&mut self) {
+        if self.state() == AssociationState::Closed {
+            return;
+        }
+
+        let _ = self.close();
+        self.events.push_back(Event::ShutdownComplete);
+    }
+
     /// A common routine for handle_data and handle_forward_tsn routines
     fn handle_peer_last_tsn_and_acknowledgement(
         &mut self,
@@ -1485,8 +3230,12 @@ impl Association {
         // Meaning, if peer_last_tsn+1 points to a chunk that is received,
         // advance peer_last_tsn until peer_last_tsn+1 points to unreceived chunk.
         //debug!("[{}] peer_last_tsn = {}", self.side, self.peer_last_tsn);
-        while self.payload_queue.pop(self.peer_last_tsn + 1).is_some() {
-            self.peer_last_tsn += 1;
+        while self
+            .payload_queue
+            .pop(self.peer_last_tsn.wrapping_add(1))
+            .is_some()
+        {
+            self.peer_last_tsn = self.peer_last_tsn.wrapping_add(1);
             //debug!("[{}] peer_last_tsn = {}", self.side, self.peer_last_tsn);
 
             let rst_reqs: Vec<ParamOutgoingResetRequest> =
@@ -1506,12 +3255,21 @@ impl Association {
             );
         }
 
-        if (self.ack_state != AckState::Immediate
-            && !sack_immediately
-            && !has_packet_loss
-            && self.ack_mode == AckMode::Normal)
-            || self.ack_mode == AckMode::AlwaysDelay
-        {
+        let delay_ack = match self.ack_mode {
+            AckMode::Normal => {
+                self.ack_state != AckState::Immediate && !sack_immediately && !has_packet_loss
+            }
+            AckMode::NoDelay => false,
+            AckMode::AlwaysDelay => true,
+            AckMode::ImmediateOnLoss => !sack_immediately && !has_packet_loss,
+        };
+
+        if delay_ack && self.ack_mode == AckMode::ImmediateOnLoss {
+            // Leave a running ack timer or an already scheduled SACK alone
+            if self.ack_state == AckState::Idle {
+                self.delayed_ack_triggered = true;
+            }
+        } else if delay_ack {
             if self.ack_state == AckState::Idle {
                 self.delayed_ack_triggered = true;
             } else {
@@ -1536,21 +3294,67 @@ impl Association {
             self.reset_streams_if_any(p, true, reply)?;
             Ok(())
         } else if let Some(p) = raw.as_any().downcast_ref::<ParamReconfigResponse>() {
-            self.reconfigs.remove(&p.reconfig_response_sequence_number);
+            let request = self.reconfigs.remove(&p.reconfig_response_sequence_number);
             if self.reconfigs.is_empty() {
                 self.timers.stop(Timer::Reconfig);
             }
+
+            if let Some(add) = request
+                .as_ref()
+                .and_then(|c| c.param_a.as_ref())
+                .and_then(|a| a.as_any().downcast_ref::<ParamAddOutgoingStreamsRequest>())
+            {
+                if p.result == ReconfigResult::SuccessPerformed {
+                    self.my_max_num_outbound_streams = self
+                        .my_max_num_outbound_streams
+                        .saturating_add(add.number_of_new_streams);
+                    self.events
+                        .push_back(Event::Stream(StreamEvent::OutgoingStreamsAdded {
+                            new_max: self.my_max_num_outbound_streams,
+                        }));
+                } else {
+                    warn!(
+                        "[{}] adding {} outgoing streams failed: {}",
+                        self.side, add.number_of_new_streams, p.result
+                    );
+                }
+            }
+            Ok(())
+        } else if let Some(p) = raw
+            .as_any()
+            .downcast_ref::<ParamAddOutgoingStreamsRequest>()
+        {
+            // The peer's new outgoing streams are our new incoming streams
+            if self.peer_add_streams_rsn != Some(p.reconfig_request_sequence_number) {
+                self.peer_add_streams_rsn = Some(p.reconfig_request_sequence_number);
+                self.my_max_num_inbound_streams = self
+                    .my_max_num_inbound_streams
+                    .saturating_add(p.number_of_new_streams);
+                debug!(
+                    "[{}] peer added {} streams, max inbound streams: {}",
+                    self.side, p.number_of_new_streams, self.my_max_num_inbound_streams
+                );
+            }
+            reply.push(self.create_packet(vec![Box::new(ChunkReconfig {
+                param_a: Some(Box::new(ParamReconfigResponse {
+                    reconfig_response_sequence_number: p.reconfig_request_sequence_number,
+                    result: ReconfigResult::SuccessPerformed,
+                })),
+                param_b: None,
+            })]));
             Ok(())
         } else {
             Err(Error::ErrParameterType)
         }
     }
 
+    /// Processes the acknowledgements carried by `d`. TSNs that are not in the inflight
+    /// queue are counted in the stats and otherwise ignored.
     fn process_selective_ack(
         &mut self,
         d: &ChunkSelectiveAck,
         now: Instant,
-    ) -> Result<(HashMap<u16, i64>, u32)> {
+    ) -> (HashMap<u16, i64>, u32) {
         let mut bytes_acked_per_stream = HashMap::new();
 
         // New ack point, so pop all ACKed packets from inflight_queue
@@ -1593,7 +3397,7 @@ impl Association {
                         self.min_tsn2measure_rtt = self.my_next_tsn;
                         if let Some(since) = &c.since {
                             let rtt = now.duration_since(*since);
-                            let srtt = self.rto_mgr.set_new_rtt(rtt.as_millis() as u64);
+                            let srtt = self.rto_mgr.set_new_rtt(rtt.as_millis() as u64, now);
                             trace!(
                                 "[{}] SACK: measured-rtt={} srtt={} new-rto={}",
                                 self.side,
@@ -1606,13 +3410,9 @@ impl Association {
                         }
                     }
                 }
-
-                if self.in_fast_recovery && c.tsn == self.fast_recover_exit_point {
-                    debug!("[{}] exit fast-recovery", self.side);
-                    self.in_fast_recovery = false;
-                }
             } else {
-                return Err(Error::ErrInflightQueueTsnPop);
+                debug!("[{}] SACK: tsn={} is not in inflight queue", self.side, i);
+                self.stats.inc_unknown_tsn_acks();
             }
 
             i += 1;
@@ -1651,7 +3451,7 @@ impl Association {
                             self.min_tsn2measure_rtt = self.my_next_tsn;
                             if let Some(since) = &c.since {
                                 let rtt = now.duration_since(*since);
-                                let srtt = self.rto_mgr.set_new_rtt(rtt.as_millis() as u64);
+                                let srtt = self.rto_mgr.set_new_rtt(rtt.as_millis() as u64, now);
                                 trace!(
                                     "[{}] SACK: measured-rtt={} srtt={} new-rto={}",
                                     self.side,
@@ -1669,15 +3469,68 @@ impl Association {
                         }
                     }
                 } else {
-                    return Err(Error::ErrTsnRequestNotExist);
+                    debug!("[{}] SACK: gap-acked tsn={} is unknown", self.side, tsn);
+                    self.stats.inc_unknown_tsn_acks();
                 }
             }
         }
 
-        Ok((bytes_acked_per_stream, htna))
+        (bytes_acked_per_stream, htna)
+    }
+
+    /// Detects chunks that were gap-acked by an earlier SACK but are no longer
+    /// covered by `d`, and makes them outstanding again.
+    fn process_reneged_chunks(&mut self, d: &ChunkSelectiveAck, now: Instant) {
+        // RFC 4960 sec 6.2
+        //   The data receiver MAY take the drastic measure of reneging on data
+        //   that it has gap-acked. The data sender MUST NOT consider such data
+        //   delivered until it is acknowledged by the Cumulative TSN Ack, and
+        //   has to be ready to retransmit it.
+        let reneged: Vec<u32> = self
+            .inflight_queue
+            .sorted
+            .iter()
+            .copied()
+            .filter(|tsn| {
+                sna32gt(*tsn, d.cumulative_tsn_ack)
+                    && self.inflight_queue.get(*tsn).is_some_and(|c| c.acked)
+                    && !d.gap_ack_blocks.iter().any(|g| {
+                        let offset = tsn.wrapping_sub(d.cumulative_tsn_ack);
+                        g.start as u32 <= offset && offset <= g.end as u32
+                    })
+            })
+            .collect();
+
+        for tsn in reneged {
+            let n_bytes = self.inflight_queue.mark_as_reneged(tsn);
+            debug!("[{}] SACK: peer reneged on tsn={}", self.side, tsn);
+            self.stats.inc_reneged_tsns();
+
+            if let Some(c) = self.inflight_queue.get(tsn) {
+                if let Some(s) = self.streams.get_mut(&c.stream_identifier) {
+                    s.buffered_amount += n_bytes;
+                }
+            }
+
+            self.timers
+                .restart_if_stale(Timer::T3RTX, now, self.rto_mgr.get_rto());
+        }
     }
 
-    fn on_cumulative_tsn_ack_point_advanced(&mut self, total_bytes_acked: i64, now: Instant) {
+    fn on_cumulative_tsn_ack_point_advanced(
+        &mut self,
+        total_bytes_acked: i64,
+        pre_sack_inflight: u32,
+        was_empty: bool,
+        now: Instant,
+    ) {
+        if self
+            .congestion
+            .exit_fast_recovery(self.cumulative_tsn_ack_point)
+        {
+            debug!("[{}] exit fast-recovery", self.side);
+        }
+
         // RFC 4096, sec 6.3.2.  Retransmission Timer Rules
         //   R2)  Whenever all outstanding data sent to an address have been
         //        acknowledged, turn off the T3-rtx timer of that address.
@@ -1688,6 +3541,9 @@ impl Association {
                 self.pending_queue.len()
             );
             self.timers.stop(Timer::T3RTX);
+            if !was_empty {
+                self.events.push_back(Event::AllDataAcknowledged);
+            }
         } else {
             trace!("[{}] T3-rtx timer start (pt2)", self.side);
             self.timers
@@ -1695,65 +3551,57 @@ impl Association {
         }
 
         // Update congestion control parameters
-        if self.cwnd <= self.ssthresh {
-            // RFC 4096, sec 7.2.1.  Slow-Start
-            //   o  When cwnd is less than or equal to ssthresh, an SCTP endpoint MUST
-            //		use the slow-start algorithm to increase cwnd only if the current
-            //      congestion window is being fully utilized, an incoming SACK
-            //      advances the Cumulative TSN Ack Point, and the data sender is not
-            //      in Fast Recovery.  Only when these three conditions are met can
-            //      the cwnd be increased; otherwise, the cwnd MUST not be increased.
-            //		If these conditions are met, then cwnd MUST be increased by, at
-            //      most, the lesser of 1) the total size of the previously
-            //      outstanding DATA chunk(s) acknowledged, and 2) the destination's
-            //      path MTU.
-            if !self.in_fast_recovery && !self.pending_queue.is_empty() {
-                self.cwnd += std::cmp::min(total_bytes_acked as u32, self.cwnd); // TCP way
-                                                                                 // self.cwnd += min32(uint32(total_bytes_acked), self.mtu) // SCTP way (slow)
-                trace!(
-                    "[{}] updated cwnd={} ssthresh={} acked={} (SS)",
-                    self.side,
-                    self.cwnd,
-                    self.ssthresh,
-                    total_bytes_acked
-                );
-            } else {
-                trace!(
-                    "[{}] cwnd did not grow: cwnd={} ssthresh={} acked={} FR={} pending={}",
-                    self.side,
-                    self.cwnd,
-                    self.ssthresh,
-                    total_bytes_acked,
-                    self.in_fast_recovery,
-                    self.pending_queue.len()
-                );
-            }
-        } else {
-            // RFC 4096, sec 7.2.2.  Congestion Avoidance
-            //   o  Whenever cwnd is greater than ssthresh, upon each SACK arrival
-            //      that advances the Cumulative TSN Ack Point, increase
-            //      partial_bytes_acked by the total number of bytes of all new chunks
-            //      acknowledged in that SACK including chunks acknowledged by the new
-            //      Cumulative TSN Ack and by Gap Ack Blocks.
-            self.partial_bytes_acked += total_bytes_acked as u32;
-
-            //   o  When partial_bytes_acked is equal to or greater than cwnd and
-            //      before the arrival of the SACK the sender had cwnd or more bytes
-            //      of data outstanding (i.e., before arrival of the SACK, flight size
-            //      was greater than or equal to cwnd), increase cwnd by MTU, and
-            //      reset partial_bytes_acked to (partial_bytes_acked - cwnd).
-            if self.partial_bytes_acked >= self.cwnd && !self.pending_queue.is_empty() {
-                self.partial_bytes_acked -= self.cwnd;
-                self.cwnd += self.mtu;
-                trace!(
-                    "[{}] updated cwnd={} ssthresh={} acked={} (CA)",
-                    self.side,
-                    self.cwnd,
-                    self.ssthresh,
-                    total_bytes_acked
-                );
-            }
+        let cwnd = self.congestion.cwnd;
+        self.congestion.on_ack(
+            self.cumulative_tsn_ack_point,
+            total_bytes_acked as u32,
+            pre_sack_inflight,
+            self.mtu,
+        );
+        trace!(
+            "[{}] updated cwnd={} -> {} ssthresh={} acked={} FR={} inflight={}",
+            self.side,
+            cwnd,
+            self.congestion.cwnd,
+            self.congestion.ssthresh,
+            total_bytes_acked,
+            self.congestion.in_fast_recovery,
+            pre_sack_inflight
+        );
+    }
+
+    /// Detects a T3-rtx expiry that was spurious, and reverts its congestion
+    /// control changes
+    ///
+    /// The Cumulative TSN Ack covering the chunk that timed out proves that
+    /// its original transmission arrived if the chunk was not resent yet, or
+    /// if it was resent less than the minimum RTT ago.
+    fn undo_spurious_timeout(&mut self, cumulative_tsn_ack: u32, now: Instant) {
+        if !self.congestion.can_undo_timeout()
+            || sna32lte(self.t3_rtx_tsn, self.cumulative_tsn_ack_point)
+            || sna32gt(self.t3_rtx_tsn, cumulative_tsn_ack)
+        {
+            return;
         }
+
+        let min_rtt = Duration::from_millis(self.rto_mgr.get_min_rtt());
+        let spurious = self.inflight_queue.get(self.t3_rtx_tsn).is_some_and(|c| {
+            c.retransmit
+                || c.since
+                    .is_some_and(|since| now.saturating_duration_since(since) < min_rtt)
+        });
+        if !spurious {
+            return;
+        }
+
+        let (cwnd, ssthresh) = (self.congestion.cwnd, self.congestion.ssthresh);
+        self.congestion.undo_timeout();
+        debug!(
+            "[{}] spurious T3-rtx timeout: cwnd={} -> {} ssthresh={} -> {}",
+            self.side, cwnd, self.congestion.cwnd, ssthresh, self.congestion.ssthresh
+        );
+        self.inflight_queue.unmark_all_to_retransmit();
+        self.stats.inc_spurious_timeouts();
     }
 
     fn process_fast_retransmission(
@@ -1761,7 +3609,7 @@ impl Association {
         cum_tsn_ack_point: u32,
         htna: u32,
         cum_tsn_ack_point_advanced: bool,
-    ) -> Result<()> {
+    ) {
         // HTNA algorithm - RFC 4960 Sec 7.2.4
         // Increment missIndicator of each chunks that the SACK reported missing
         // when either of the following is met:
@@ -1771,8 +3619,8 @@ impl Association {
         // b)  In fast-recovery AND the Cumulative TSN Ack Point advanced
         //     the miss indications are incremented for all TSNs reported missing
         //     in the SACK.
-        if !self.in_fast_recovery || cum_tsn_ack_point_advanced {
-            let max_tsn = if !self.in_fast_recovery {
+        if !self.congestion.in_fast_recovery || cum_tsn_ack_point_advanced {
+            let max_tsn = if !self.congestion.in_fast_recovery {
                 // a) increment only for missing TSNs prior to the HTNA
                 htna
             } else {
@@ -1782,42 +3630,39 @@ impl Association {
 
             let mut tsn = cum_tsn_ack_point + 1;
             while sna32lt(tsn, max_tsn) {
-                if let Some(c) = self.inflight_queue.get_mut(tsn) {
-                    if !c.acked && !c.abandoned() && c.miss_indicator < 3 {
+                let reached_three = match self.inflight_queue.get_mut(tsn) {
+                    Some(mut c) if !c.acked && !c.abandoned() && c.miss_indicator < 3 => {
                         c.miss_indicator += 1;
-                        if c.miss_indicator == 3 && !self.in_fast_recovery {
-                            // 2)  If not in Fast Recovery, adjust the ssthresh and cwnd of the
-                            //     destination address(es) to which the missing DATA chunks were
-                            //     last sent, according to the formula described in Section 7.2.3.
-                            self.in_fast_recovery = true;
-                            self.fast_recover_exit_point = htna;
-                            self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * self.mtu);
-                            self.cwnd = self.ssthresh;
-                            self.partial_bytes_acked = 0;
-                            self.will_retransmit_fast = true;
-
-                            trace!(
-                                "[{}] updated cwnd={} ssthresh={} inflight={} (FR)",
-                                self.side,
-                                self.cwnd,
-                                self.ssthresh,
-                                self.inflight_queue.get_num_bytes()
-                            );
-                        }
+                        c.miss_indicator == 3
                     }
-                } else {
-                    return Err(Error::ErrTsnRequestNotExist);
+                    _ => false,
+                };
+                if reached_three && !self.congestion.in_fast_recovery {
+                    // 2)  If not in Fast Recovery, adjust the ssthresh and cwnd of the
+                    //     destination address(es) to which the missing DATA chunks were
+                    //     last sent, according to the formula described in Section 7.2.3.
+                    //     ... mark the highest outstanding TSN as the Fast Recovery
+                    //     exit point.
+                    self.congestion
+                        .enter_fast_recovery(self.my_next_tsn.wrapping_sub(1), self.mtu);
+                    self.will_retransmit_fast = true;
+
+                    trace!(
+                        "[{}] updated cwnd={} ssthresh={} inflight={} (FR)",
+                        self.side,
+                        self.congestion.cwnd,
+                        self.congestion.ssthresh,
+                        self.inflight_queue.get_num_bytes_in_flight()
+                    );
                 }
 
                 tsn += 1;
             }
         }
 
-        if self.in_fast_recovery && cum_tsn_ack_point_advanced {
+        if self.congestion.in_fast_recovery && cum_tsn_ack_point_advanced {
             self.will_retransmit_fast = true;
         }
-
-        Ok(())
     }
 
     /// The caller must hold the lock. This method was only added because the
@@ -1833,7 +3678,7 @@ impl Association {
             trace!("[{}] T3-rtx timer start (pt3)", self.side);
             self.timers
                 .restart_if_stale(Timer::T3RTX, now, self.rto_mgr.get_rto());
-        } else if state == AssociationState::ShutdownPending {
+        } else if state == AssociationState::ShutdownPending && self.pending_queue.is_empty() {
             // No more outstanding, send shutdown.
             should_awake_write_loop = true;
             self.will_send_shutdown = true;
@@ -1932,22 +3777,43 @@ impl Association {
         }
     }
 
+    /// marshal_packet serializes an outgoing packet. Packets are written one
+    /// after the other into a shared buffer, so that a burst of packets costs
+    /// a single allocation.
+    fn marshal_packet(&mut self, p: &Packet) -> Result<Bytes> {
+        let size = p.marshal_size();
+        if self.marshal_buf.capacity() < size {
+            self.marshal_buf
+                .reserve(std::cmp::max(size, MARSHAL_BUFFER_SIZE));
+        }
+        if let Err(err) = p.marshal_to(&mut self.marshal_buf, self.zero_checksum) {
+            // Drop what was written so far, it would end up in the next packet
+            self.marshal_buf.clear();
+            return Err(err);
+        }
+        Ok(self.marshal_buf.split().freeze())
+    }
+
     /// create_stream creates a stream. The caller should hold the lock and check no stream exists for this id.
+    ///
+    /// `accept` carries the PPID of the first DATA chunk of a stream opened by the peer.
     fn create_stream(
         &mut self,
         stream_identifier: StreamId,
-        accept: bool,
+        accept: Option<PayloadProtocolIdentifier>,
         default_payload_type: PayloadProtocolIdentifier,
     ) -> Option<Stream<'_>> {
-        let s = StreamState::new(
-            self.side,
-            stream_identifier,
-            self.max_payload_size,
-            default_payload_type,
-        );
+        let mut s = StreamState::new(self.side, stream_identifier, default_payload_type);
+        s.reassembly_queue.max_bytes = self.max_receive_buffer_size as usize;
 
-        if accept {
+        if let Some(ppi) = accept {
             self.stream_queue.push_back(stream_identifier);
+            self.events
+                .push_back(Event::Stream(StreamEvent::OpenedWithPpi {
+                    id: stream_identifier,
+                    ppi,
+                }));
+            #[allow(deprecated)]
             self.events.push_back(Event::Stream(StreamEvent::Opened));
         }
 
@@ -1959,8 +3825,13 @@ impl Association {
         })
     }
 
-    /// get_or_create_stream gets or creates a stream. The caller should hold the lock.
-    fn get_or_create_stream(&mut self, stream_identifier: StreamId) -> Option<Stream<'_>> {
+    /// get_or_create_stream gets or creates a stream for a DATA chunk carrying `ppi`. The
+    /// caller should hold the lock.
+    fn get_or_create_stream(
+        &mut self,
+        stream_identifier: StreamId,
+        ppi: PayloadProtocolIdentifier,
+    ) -> Option<Stream<'_>> {
         if self.streams.contains_key(&stream_identifier) {
             Some(Stream {
                 stream_identifier,
@@ -1969,23 +3840,59 @@ impl Association {
         } else {
             self.create_stream(
                 stream_identifier,
-                true,
+                Some(ppi),
                 PayloadProtocolIdentifier::default(),
             )
         }
     }
 
     pub(crate) fn get_my_receiver_window_credit(&self) -> u32 {
+        if self.payload_queue.is_full() {
+            return 0;
+        }
+
         let mut bytes_queued = 0;
         for s in self.streams.values() {
             bytes_queued += s.get_num_bytes_in_reassembly_queue() as u32;
         }
 
-        if bytes_queued >= self.max_receive_buffer_size {
-            0
-        } else {
-            self.max_receive_buffer_size - bytes_queued
+        self.max_receive_buffer_size.saturating_sub(bytes_queued)
+    }
+
+    /// Drains the control queue, merging consecutive packets whose chunks may
+    /// share a single packet.
+    fn bundle_control_packets(&mut self) -> Vec<Packet> {
+        let mut packets: Vec<Packet> = vec![];
+        for p in self.control_queue.drain(..) {
+            let p = match packets.last_mut() {
+                Some(last) => last.try_bundle(p, self.mtu as usize),
+                None => Some(p),
+            };
+            if let Some(p) = p {
+                packets.push(p);
+            }
+        }
+        packets
+    }
+
+    /// gather_outbound_batch returns up to `max` outgoing packets, gathering new
+    /// ones only once those left over from a previous call were handed out.
+    fn gather_outbound_batch(&mut self, now: Instant, max: usize) -> Vec<Bytes> {
+        if self.gathered_packets.is_empty() && self.needs_gather {
+            self.needs_gather = false;
+            let (raw_packets, ok) = self.gather_outbound(now);
+            // More may be sent once these are out of the way, so only a gather
+            // coming up empty lets the next polls skip it
+            self.needs_gather |= !raw_packets.is_empty();
+            self.gathered_packets.extend(raw_packets);
+            if !ok {
+                // SHUTDOWN COMPLETE is among the packets gathered
+                self.complete_shutdown();
+            }
         }
+
+        let n = std::cmp::min(max, self.gathered_packets.len());
+        self.gathered_packets.drain(..n).collect()
     }
 
     /// gather_outbound gathers outgoing packets. The returned bool value set to
@@ -1994,8 +3901,8 @@ impl Association {
         let mut raw_packets = vec![];
 
         if !self.control_queue.is_empty() {
-            for p in self.control_queue.drain(..) {
-                if let Ok(raw) = p.marshal() {
+            for p in self.bundle_control_packets() {
+                if let Ok(raw) = self.marshal_packet(&p) {
                     raw_packets.push(raw);
                 } else {
                     warn!("[{}] failed to serialize a control packet", self.side);
@@ -2007,6 +3914,8 @@ impl Association {
         let state = self.state();
         match state {
             AssociationState::Established => {
+                self.start_heartbeat_timer_if_stopped(now);
+                self.start_pmtu_probing_if_stopped(now);
                 raw_packets = self.gather_data_packets_to_retransmit(raw_packets, now);
                 raw_packets = self.gather_outbound_data_and_reconfig_packets(raw_packets, now);
                 raw_packets = self.gather_outbound_fast_retransmission_packets(raw_packets, now);
@@ -2014,9 +3923,16 @@ impl Association {
                 raw_packets = self.gather_outbound_forward_tsn_packets(raw_packets);
                 (raw_packets, true)
             }
-            AssociationState::ShutdownPending
-            | AssociationState::ShutdownSent
-            | AssociationState::ShutdownReceived => {
+            AssociationState::ShutdownPending => {
+                // Messages queued before the shutdown are still sent
+                raw_packets = self.gather_data_packets_to_retransmit(raw_packets, now);
+                raw_packets = self.gather_outbound_data_and_reconfig_packets(raw_packets, now);
+                raw_packets = self.gather_outbound_fast_retransmission_packets(raw_packets, now);
+                raw_packets = self.gather_outbound_sack_packets(raw_packets);
+                raw_packets = self.gather_outbound_forward_tsn_packets(raw_packets);
+                (raw_packets, true)
+            }
+            AssociationState::ShutdownSent | AssociationState::ShutdownReceived => {
                 raw_packets = self.gather_data_packets_to_retransmit(raw_packets, now);
                 raw_packets = self.gather_outbound_fast_retransmission_packets(raw_packets, now);
                 raw_packets = self.gather_outbound_sack_packets(raw_packets);
@@ -2025,6 +3941,10 @@ impl Association {
             AssociationState::ShutdownAckSent => {
                 self.gather_outbound_shutdown_packets(raw_packets, now)
             }
+            AssociationState::Closed => {
+                self.start_close_timer_if_stopped(now);
+                (raw_packets, true)
+            }
             _ => (raw_packets, true),
         }
     }
@@ -2034,8 +3954,8 @@ impl Association {
         mut raw_packets: Vec<Bytes>,
         now: Instant,
     ) -> Vec<Bytes> {
-        for p in &self.get_data_packets_to_retransmit(now) {
-            if let Ok(raw) = p.marshal() {
+        for p in self.get_data_packets_to_retransmit(now) {
+            if let Ok(raw) = self.marshal_packet(&p) {
                 raw_packets.push(raw);
             } else {
                 warn!(
@@ -2056,20 +3976,16 @@ impl Association {
         // Pop unsent data chunks from the pending queue to send as much as
         // cwnd and rwnd allow.
         let (chunks, sis_to_reset) = self.pop_pending_data_chunks_to_send(now);
+        let mut packets = vec![];
         if !chunks.is_empty() {
             // Start timer. (noop if already started)
             trace!("[{}] T3-rtx timer start (pt1)", self.side);
             self.timers
                 .restart_if_stale(Timer::T3RTX, now, self.rto_mgr.get_rto());
 
-            for p in &self.bundle_data_chunks_into_packets(chunks) {
-                if let Ok(raw) = p.marshal() {
-                    raw_packets.push(raw);
-                } else {
-                    warn!("[{}] failed to serialize a DATA packet", self.side);
-                }
-            }
+            packets = self.bundle_data_chunks_into_packets(chunks);
         }
+        let num_data_packets = packets.len();
 
         if !sis_to_reset.is_empty() || self.will_retransmit_reconfig {
             if self.will_retransmit_reconfig {
@@ -2079,16 +3995,9 @@ impl Association {
                     self.side,
                     self.reconfigs.len()
                 );
-                for c in self.reconfigs.values() {
-                    let p = self.create_packet(vec![Box::new(c.clone())]);
-                    if let Ok(raw) = p.marshal() {
-                        raw_packets.push(raw);
-                    } else {
-                        warn!(
-                            "[{}] failed to serialize a RECONFIG packet to be retransmitted",
-                            self.side,
-                        );
-                    }
+                let reconfigs: Vec<ChunkReconfig> = self.reconfigs.values().cloned().collect();
+                for c in reconfigs {
+                    packets.push(self.create_packet(vec![Box::new(c)]));
                 }
             }
 
@@ -2114,14 +4023,19 @@ impl Association {
                 };
                 self.reconfigs.insert(rsn, c.clone()); // store in the map for retransmission
 
+                // Append the request to the last DATA packet when it fits. It follows
+                // the chunk carrying sender_last_tsn, so the peer can reset the
+                // streams as soon as the packet arrives. Retransmitted RECONFIGs keep
+                // their own packets, so only a trailing DATA packet is considered.
                 let p = self.create_packet(vec![Box::new(c)]);
-                if let Ok(raw) = p.marshal() {
-                    raw_packets.push(raw);
-                } else {
-                    warn!(
-                        "[{}] failed to serialize a RECONFIG packet to be transmitted",
-                        self.side
-                    );
+                let can_bundle =
+                    self.reconfig_bundling_enabled && packets.len() == num_data_packets;
+                let unbundled = match packets.last_mut() {
+                    Some(last) if can_bundle => last.try_bundle(p, self.mtu as usize),
+                    _ => Some(p),
+                };
+                if let Some(p) = unbundled {
+                    packets.push(p);
                 }
             }
 
@@ -2131,6 +4045,17 @@ impl Association {
             }
         }
 
+        for p in packets {
+            if let Ok(raw) = self.marshal_packet(&p) {
+                raw_packets.push(raw);
+            } else {
+                warn!(
+                    "[{}] failed to serialize a DATA or RECONFIG packet",
+                    self.side
+                );
+            }
+        }
+
         raw_packets
     }
 
@@ -2139,6 +4064,14 @@ impl Association {
         mut raw_packets: Vec<Bytes>,
         now: Instant,
     ) -> Vec<Bytes> {
+        // Once SHUTDOWN went either way, outstanding data is only recovered by
+        // T3-rtx
+        let state = self.state();
+        if state != AssociationState::Established && state != AssociationState::ShutdownPending {
+            self.will_retransmit_fast = false;
+            return raw_packets;
+        }
+
         if self.will_retransmit_fast {
             self.will_retransmit_fast = false;
 
@@ -2147,13 +4080,27 @@ impl Association {
 
             let mut i = 0;
             loop {
-                let tsn = self.cumulative_tsn_ack_point + i + 1;
-                if let Some(c) = self.inflight_queue.get_mut(tsn) {
+                let tsn = self.cumulative_tsn_ack_point.wrapping_add(i + 1);
+                if let Some(mut c) = self.inflight_queue.get_mut(tsn) {
                     if c.acked || c.abandoned() || c.nsent > 1 || c.miss_indicator < 3 {
                         i += 1;
                         continue;
                     }
 
+                    if Association::check_partial_reliability_status(
+                        &mut c,
+                        now,
+                        self.negotiated_extensions.has(SctpExtension::ForwardTsn),
+                        self.side,
+                        &mut self.streams,
+                    ) {
+                        self.will_send_forward_tsn = true;
+                    }
+                    if c.abandoned() {
+                        i += 1;
+                        continue;
+                    }
+
                     // RFC 4960 Sec 7.2.4 Fast Retransmit on Gap Reports
                     //  3)  Determine how many of the earliest (i.e., lowest TSN) DATA chunks
                     //      marked for retransmission will fit into a single packet, subject
@@ -2164,40 +4111,40 @@ impl Association {
                     //      of cwnd and SHOULD NOT delay retransmission for this single
                     //		packet.
 
-                    let data_chunk_size = DATA_CHUNK_HEADER_SIZE + c.user_data.len() as u32;
-                    if self.mtu < fast_retrans_size + data_chunk_size {
+                    let chunk_size = data_chunk_size(&c);
+                    if !to_fast_retrans.is_empty() && self.mtu < fast_retrans_size + chunk_size {
                         break;
                     }
 
-                    fast_retrans_size += data_chunk_size;
+                    fast_retrans_size += chunk_size;
                     self.stats.inc_fast_retrans();
+                    if let Some(s) = self.streams.get_mut(&c.stream_identifier) {
+                        s.stats.add_bytes_retransmitted(c.user_data.len());
+                    }
+                    // Consume the T3-rtx mark as well so that the chunk is not
+                    // sent a second time by get_data_packets_to_retransmit.
+                    c.retransmit = false;
+                    c.last_sent = Some(now);
                     c.nsent += 1;
                 } else {
                     break; // end of pending data
                 }
 
-                if let Some(c) = self.inflight_queue.get_mut(tsn) {
-                    Association::check_partial_reliability_status(
-                        c,
-                        now,
-                        self.use_forward_tsn,
-                        self.side,
-                        &self.streams,
-                    );
+                if let Some(c) = self.inflight_queue.get(tsn) {
                     to_fast_retrans.push(Box::new(c.clone()));
                     trace!(
                         "[{}] fast-retransmit: tsn={} sent={} htna={}",
                         self.side,
                         c.tsn,
                         c.nsent,
-                        self.fast_recover_exit_point
+                        self.congestion.fast_recover_exit_point
                     );
                 }
                 i += 1;
             }
 
             if !to_fast_retrans.is_empty() {
-                if let Ok(raw) = self.create_packet(to_fast_retrans).marshal() {
+                if let Ok(raw) = self.marshal_packet(&self.create_packet(to_fast_retrans)) {
                     raw_packets.push(raw);
                 } else {
                     warn!(
@@ -2216,7 +4163,7 @@ impl Association {
             self.ack_state = AckState::Idle;
             let sack = self.create_selective_ack_chunk();
             trace!("[{}] sending SACK: {}", self.side, sack);
-            if let Ok(raw) = self.create_packet(vec![Box::new(sack)]).marshal() {
+            if let Ok(raw) = self.marshal_packet(&self.create_packet(vec![Box::new(sack)])) {
                 raw_packets.push(raw);
             } else {
                 warn!("[{}] failed to serialize a SACK packet", self.side);
@@ -2234,12 +4181,30 @@ impl Association {
         );*/
         if self.will_send_forward_tsn {
             self.will_send_forward_tsn = false;
+
+            // Chunks may have been abandoned outside of SACK or T3-rtx
+            // processing, so catch up on RFC 3758 Sec 3.5 C1 and C2 here.
+            if sna32lt(
+                self.advanced_peer_tsn_ack_point,
+                self.cumulative_tsn_ack_point,
+            ) {
+                self.advanced_peer_tsn_ack_point = self.cumulative_tsn_ack_point
+            }
+            let mut i = self.advanced_peer_tsn_ack_point + 1;
+            while let Some(c) = self.inflight_queue.get(i) {
+                if !c.abandoned() {
+                    break;
+                }
+                self.advanced_peer_tsn_ack_point = i;
+                i += 1;
+            }
+
             if sna32gt(
                 self.advanced_peer_tsn_ack_point,
                 self.cumulative_tsn_ack_point,
             ) {
                 let fwd_tsn = self.create_forward_tsn();
-                if let Ok(raw) = self.create_packet(vec![Box::new(fwd_tsn)]).marshal() {
+                if let Ok(raw) = self.marshal_packet(&self.create_packet(vec![Box::new(fwd_tsn)])) {
                     raw_packets.push(raw);
                 } else {
                     warn!("[{}] failed to serialize a Forward TSN packet", self.side);
@@ -2264,7 +4229,7 @@ impl Association {
                 cumulative_tsn_ack: self.cumulative_tsn_ack_point,
             };
 
-            if let Ok(raw) = self.create_packet(vec![Box::new(shutdown)]).marshal() {
+            if let Ok(raw) = self.marshal_packet(&self.create_packet(vec![Box::new(shutdown)])) {
                 self.timers
                     .start(Timer::T2Shutdown, now, self.rto_mgr.get_rto());
                 raw_packets.push(raw);
@@ -2276,7 +4241,8 @@ impl Association {
 
             let shutdown_ack = ChunkShutdownAck {};
 
-            if let Ok(raw) = self.create_packet(vec![Box::new(shutdown_ack)]).marshal() {
+            if let Ok(raw) = self.marshal_packet(&self.create_packet(vec![Box::new(shutdown_ack)]))
+            {
                 self.timers
                     .start(Timer::T2Shutdown, now, self.rto_mgr.get_rto());
                 raw_packets.push(raw);
@@ -2286,11 +4252,10 @@ impl Association {
         } else if self.will_send_shutdown_complete {
             self.will_send_shutdown_complete = false;
 
-            let shutdown_complete = ChunkShutdownComplete {};
+            let shutdown_complete = ChunkShutdownComplete::default();
 
-            if let Ok(raw) = self
-                .create_packet(vec![Box::new(shutdown_complete)])
-                .marshal()
+            if let Ok(raw) =
+                self.marshal_packet(&self.create_packet(vec![Box::new(shutdown_complete)]))
             {
                 raw_packets.push(raw);
                 ok = false;
@@ -2308,20 +4273,46 @@ impl Association {
     /// get_data_packets_to_retransmit is called when T3-rtx is timed out and retransmit outstanding data chunks
     /// that are not acked or abandoned yet.
     fn get_data_packets_to_retransmit(&mut self, now: Instant) -> Vec<Packet> {
-        let awnd = std::cmp::min(self.cwnd, self.rwnd);
+        let awnd = std::cmp::min(self.congestion.cwnd, self.congestion.rwnd);
+        let rto = Duration::from_millis(self.rto_mgr.get_rto());
         let mut chunks = vec![];
         let mut bytes_to_send = 0;
         let mut done = false;
         let mut i = 0;
         while !done {
-            let tsn = self.cumulative_tsn_ack_point + i + 1;
-            if let Some(c) = self.inflight_queue.get_mut(tsn) {
+            let tsn = self.cumulative_tsn_ack_point.wrapping_add(i + 1);
+            if let Some(mut c) = self.inflight_queue.get_mut(tsn) {
                 if !c.retransmit {
                     i += 1;
                     continue;
                 }
 
-                if i == 0 && self.rwnd < c.user_data.len() as u32 {
+                if Association::check_partial_reliability_status(
+                    &mut c,
+                    now,
+                    self.negotiated_extensions.has(SctpExtension::ForwardTsn),
+                    self.side,
+                    &mut self.streams,
+                ) {
+                    self.will_send_forward_tsn = true;
+                }
+                // Abandoned data is skipped by a FORWARD TSN instead
+                if c.abandoned() {
+                    c.retransmit = false;
+                    i += 1;
+                    continue;
+                }
+
+                // A chunk retransmitted within the last RTO (i.e. by fast
+                // retransmit) is still considered in flight; drop the mark
+                // instead of sending it twice for the same loss.
+                if c.nsent > 1 && c.last_sent.is_some_and(|t| now < t + rto) {
+                    c.retransmit = false;
+                    i += 1;
+                    continue;
+                }
+
+                if i == 0 && self.congestion.rwnd < c.user_data.len() as u32 {
                     // Send it as a zero window probe
                     done = true;
                 } else if bytes_to_send + c.user_data.len() > awnd as usize {
@@ -2332,21 +4323,17 @@ impl Association {
                 // t3-rtx timer fires
                 c.retransmit = false;
                 bytes_to_send += c.user_data.len();
+                if let Some(s) = self.streams.get_mut(&c.stream_identifier) {
+                    s.stats.add_bytes_retransmitted(c.user_data.len());
+                }
 
+                c.last_sent = Some(now);
                 c.nsent += 1;
             } else {
                 break; // end of pending data
             }
 
-            if let Some(c) = self.inflight_queue.get_mut(tsn) {
-                Association::check_partial_reliability_status(
-                    c,
-                    now,
-                    self.use_forward_tsn,
-                    self.side,
-                    &self.streams,
-                );
-
+            if let Some(c) = self.inflight_queue.get(tsn) {
                 trace!(
                     "[{}] retransmitting tsn={} ssn={} sent={}",
                     self.side,
@@ -2372,6 +4359,8 @@ impl Association {
         let mut chunks = vec![];
         let mut sis_to_reset = vec![]; // stream identifiers to reset
         if !self.pending_queue.is_empty() {
+            self.restart_cwnd_if_idle(now);
+
             // RFC 4960 sec 6.1.  Transmission of DATA Chunks
             //   A) At any given time, the data sender MUST NOT transmit new data to
             //      any destination transport address if its peer's rwnd indicates
@@ -2384,7 +4373,7 @@ impl Association {
                 let (beginning_fragment, unordered, data_len, stream_identifier) = (
                     c.beginning_fragment,
                     c.unordered,
-                    c.user_data.len(),
+                    self.fragment_len(c),
                     c.stream_identifier,
                 );
 
@@ -2392,7 +4381,7 @@ impl Association {
                     sis_to_reset.push(stream_identifier);
                     if self
                         .pending_queue
-                        .pop(beginning_fragment, unordered)
+                        .pop(beginning_fragment, unordered, 0)
                         .is_none()
                     {
                         error!("[{}] failed to pop from pending queue", self.side);
@@ -2400,15 +4389,19 @@ impl Association {
                     continue;
                 }
 
-                if self.inflight_queue.get_num_bytes() + data_len > self.cwnd as usize {
+                if self.inflight_queue.get_num_bytes_in_flight() + data_len
+                    > self.congestion.cwnd as usize
+                {
+                    self.send_blocked = true;
                     break; // would exceeds cwnd
                 }
 
-                if data_len > self.rwnd as usize {
+                if data_len > self.congestion.rwnd as usize {
+                    self.send_blocked = true;
                     break; // no more rwnd
                 }
 
-                self.rwnd -= data_len as u32;
+                self.congestion.rwnd -= data_len as u32;
 
                 if let Some(chunk) = self.move_pending_data_chunk_to_inflight_queue(
                     beginning_fragment,
@@ -2436,9 +4429,38 @@ impl Association {
             }
         }
 
+        if !chunks.is_empty() {
+            self.last_data_sent_at = Some(now);
+            self.notify_write_blocked_streams();
+        }
+
         (chunks, sis_to_reset)
     }
 
+    /// Falls back to the initial cwnd when nothing is in flight and no DATA
+    /// was sent for longer than one RTO
+    fn restart_cwnd_if_idle(&mut self, now: Instant) {
+        if !self.cwnd_idle_restart || !self.inflight_queue.is_empty() {
+            return;
+        }
+
+        let rto = Duration::from_millis(self.rto_mgr.get_rto());
+        let idle = self
+            .last_data_sent_at
+            .is_some_and(|sent_at| now.saturating_duration_since(sent_at) > rto);
+        if !idle {
+            return;
+        }
+
+        let cwnd = self.congestion.cwnd;
+        if self.congestion.restart_after_idle(self.mtu) {
+            debug!(
+                "[{}] restarting cwnd after idle period: {} -> {}",
+                self.side, cwnd, self.congestion.cwnd
+            );
+        }
+    }
+
     /// bundle_data_chunks_into_packets packs DATA chunks into packets. It tries to bundle
     /// DATA chunks into a packet so long as the resulting packet size does not exceed
     /// the path MTU.
@@ -2453,13 +4475,14 @@ impl Association {
             //   single packet.  Furthermore, DATA chunks being retransmitted MAY be
             //   bundled with new DATA chunks, as long as the resulting packet size
             //   does not exceed the path MTU.
-            if bytes_in_packet + c.user_data.len() as u32 > self.mtu {
+            let chunk_size = data_chunk_size(&c);
+            if !chunks_to_send.is_empty() && bytes_in_packet + chunk_size > self.mtu {
                 packets.push(self.create_packet(chunks_to_send));
                 chunks_to_send = vec![];
                 bytes_in_packet = COMMON_HEADER_SIZE;
             }
 
-            bytes_in_packet += DATA_CHUNK_HEADER_SIZE + c.user_data.len() as u32;
+            bytes_in_packet += chunk_size;
             chunks_to_send.push(Box::new(c));
         }
 
@@ -2467,6 +4490,11 @@ impl Association {
             packets.push(self.create_packet(chunks_to_send));
         }
 
+        // Only a lone chunk larger than the MTU may produce an oversized packet
+        debug_assert!(packets
+            .iter()
+            .all(|p| p.chunks.len() == 1 || p.marshal_size() <= self.mtu as usize));
+
         packets
     }
 
@@ -2484,15 +4512,17 @@ impl Association {
         rsn
     }
 
+    /// Abandons the chunk if its PR-SCTP policy says so. Returns true if the
+    /// chunk was abandoned by this call, in which case a FORWARD TSN is due.
     fn check_partial_reliability_status(
         c: &mut ChunkPayloadData,
         now: Instant,
         use_forward_tsn: bool,
         side: Side,
-        streams: &FxHashMap<u16, StreamState>,
-    ) {
-        if !use_forward_tsn {
-            return;
+        streams: &mut FxHashMap<u16, StreamState>,
+    ) -> bool {
+        if !use_forward_tsn || c.abandoned {
+            return false;
         }
 
         // draft-ietf-rtcweb-data-protocol-09.txt section 6
@@ -2501,54 +4531,92 @@ impl Association {
         //		ordered delivery and reliable transmission.
         //
         if c.payload_type == PayloadProtocolIdentifier::Dcep {
-            return;
+            return false;
+        }
+
+        if let Some(deadline) = c.deadline {
+            if now >= deadline {
+                c.set_abandoned(true);
+                if c.beginning_fragment {
+                    if let Some(s) = streams.get_mut(&c.stream_identifier) {
+                        s.stats.inc_messages_abandoned();
+                    }
+                }
+                trace!(
+                    "[{}] marked as abandoned: tsn={} ppi={} (deadline)",
+                    side,
+                    c.tsn,
+                    c.payload_type
+                );
+                return true;
+            }
         }
 
         // PR-SCTP
-        if let Some(s) = streams.get(&c.stream_identifier) {
+        if let Some(s) = streams.get_mut(&c.stream_identifier) {
             let reliability_type: ReliabilityType = s.reliability_type;
             let reliability_value = s.reliability_value;
 
-            if reliability_type == ReliabilityType::Rexmit {
-                if c.nsent >= reliability_value {
-                    c.set_abandoned(true);
-                    trace!(
-                        "[{}] marked as abandoned: tsn={} ppi={} (remix: {})",
-                        side,
-                        c.tsn,
-                        c.payload_type,
-                        c.nsent
-                    );
-                }
-            } else if reliability_type == ReliabilityType::Timed {
-                if let Some(since) = &c.since {
-                    let elapsed = now.duration_since(*since);
-                    if elapsed.as_millis() as u32 >= reliability_value {
+            match reliability_type {
+                ReliabilityType::Reliable => {}
+                ReliabilityType::Rexmit => {
+                    if c.nsent >= reliability_value {
                         c.set_abandoned(true);
+                        if c.beginning_fragment {
+                            s.stats.inc_messages_abandoned();
+                        }
                         trace!(
-                            "[{}] marked as abandoned: tsn={} ppi={} (timed: {:?})",
+                            "[{}] marked as abandoned: tsn={} ppi={} (remix: {})",
                             side,
                             c.tsn,
                             c.payload_type,
-                            elapsed
+                            c.nsent
                         );
                     }
-                } else {
-                    error!("[{}] invalid c.since", side);
+                }
+                ReliabilityType::Timed => {
+                    if let Some(since) = &c.since {
+                        let elapsed = now.duration_since(*since);
+                        if elapsed.as_millis() as u32 >= reliability_value {
+                            c.set_abandoned(true);
+                            if c.beginning_fragment {
+                                s.stats.inc_messages_abandoned();
+                            }
+                            trace!(
+                                "[{}] marked as abandoned: tsn={} ppi={} (timed: {:?})",
+                                side,
+                                c.tsn,
+                                c.payload_type,
+                                elapsed
+                            );
+                        }
+                    } else {
+                        error!("[{}] invalid c.since", side);
+                    }
                 }
             }
         } else {
-            error!("[{}] stream {} not found)", side, c.stream_identifier);
+            // The stream has been reset while the chunk was in flight; nobody
+            // is left to deliver it to.
+            c.set_abandoned(true);
+            warn!(
+                "[{}] marked as abandoned: tsn={} ppi={} (stream {} not found)",
+                side, c.tsn, c.payload_type, c.stream_identifier
+            );
         }
+
+        c.abandoned
     }
 
     fn create_selective_ack_chunk(&mut self) -> ChunkSelectiveAck {
-        ChunkSelectiveAck {
+        let mut sack = ChunkSelectiveAck {
             cumulative_tsn_ack: self.peer_last_tsn,
             advertised_receiver_window_credit: self.get_my_receiver_window_credit(),
             gap_ack_blocks: self.payload_queue.get_gap_ack_blocks(self.peer_last_tsn),
             duplicate_tsn: self.payload_queue.pop_duplicates(),
-        }
+        };
+        sack.sort_and_merge_gap_blocks();
+        sack
     }
 
     /// create_forward_tsn generates ForwardTSN chunk.
@@ -2598,14 +4666,24 @@ impl Association {
         fwd_tsn
     }
 
-    /// Move the chunk peeked with self.pending_queue.peek() to the inflight_queue.
+    /// Size of the DATA chunk the pending message `c` is sent in next
+    fn fragment_len(&self, c: &ChunkPayloadData) -> usize {
+        std::cmp::min(c.user_data.len(), self.max_payload_size as usize)
+    }
+
+    /// Move the next fragment of the message peeked with self.pending_queue.peek()
+    /// to the inflight_queue.
     fn move_pending_data_chunk_to_inflight_queue(
         &mut self,
         beginning_fragment: bool,
         unordered: bool,
         now: Instant,
     ) -> Option<ChunkPayloadData> {
-        if let Some(mut c) = self.pending_queue.pop(beginning_fragment, unordered) {
+        if let Some(mut c) = self.pending_queue.pop(
+            beginning_fragment,
+            unordered,
+            self.max_payload_size as usize,
+        ) {
             // Mark all fragements are in-flight now
             if c.ending_fragment {
                 c.set_all_inflight();
@@ -2615,15 +4693,18 @@ impl Association {
             c.tsn = self.generate_next_tsn();
 
             c.since = Some(now); // use to calculate RTT and also for maxPacketLifeTime
+            c.last_sent = Some(now);
             c.nsent = 1; // being sent for the first time
 
-            Association::check_partial_reliability_status(
+            if Association::check_partial_reliability_status(
                 &mut c,
                 now,
-                self.use_forward_tsn,
+                self.negotiated_extensions.has(SctpExtension::ForwardTsn),
                 self.side,
-                &self.streams,
-            );
+                &mut self.streams,
+            ) {
+                self.will_send_forward_tsn = true;
+            }
 
             trace!(
                 "[{}] sending ppi={} tsn={} ssn={} sent={} len={} ({},{})",
@@ -2668,17 +4749,14 @@ impl Association {
         Ok(())
     }
 
-    /// send_payload_data sends the data chunks.
-    pub(crate) fn send_payload_data(&mut self, chunks: Vec<ChunkPayloadData>) -> Result<()> {
+    /// send_payload_data queues a user message for sending.
+    pub(crate) fn send_payload_data(&mut self, chunk: ChunkPayloadData) -> Result<()> {
         let state = self.state();
         if state != AssociationState::Established {
             return Err(Error::ErrPayloadDataStateNotExist);
         }
 
-        // Push the chunks into the pending queue first.
-        for c in chunks {
-            self.pending_queue.push(c);
-        }
+        self.pending_queue.push(chunk);
 
         self.awake_write_loop();
         Ok(())
@@ -2687,11 +4765,126 @@ impl Association {
     /// buffered_amount returns total amount (in bytes) of currently buffered user data.
     /// This is used only by testing.
     pub(crate) fn buffered_amount(&self) -> usize {
-        self.pending_queue.get_num_bytes() + self.inflight_queue.get_num_bytes()
+        self.total_queued_bytes()
+    }
+
+    /// Makes the next `poll_transmit` gather outbound packets again
+    fn awake_write_loop(&mut self) {
+        self.needs_gather = true;
+    }
+
+    /// HB.interval plus the current RTO in msec, as in RFC 4960 sec 8.3
+    fn heartbeat_interval(&self) -> u64 {
+        self.peer_addr_params
+            .and_then(|p| p.heartbeat_interval)
+            .map_or(0, |d| d.as_millis() as u64)
+            + self.rto_mgr.get_rto()
+    }
+
+    fn pmtu_timer_interval(&self) -> u64 {
+        match self.pmtu_prober.as_ref().map(|p| p.state) {
+            Some(PmtuSearchState::SearchComplete) => PMTU_RAISE_INTERVAL,
+            _ => self.rto_mgr.get_rto(),
+        }
+    }
+
+    fn start_pmtu_probing_if_stopped(&mut self, now: Instant) {
+        if self.timers.get(Timer::Pmtu).is_some() {
+            return;
+        }
+        let Some(prober) = self.pmtu_prober.as_mut() else {
+            return;
+        };
+        if let Some(size) = prober.next_probe() {
+            self.send_pmtu_probe(size);
+        }
+        self.timers
+            .start(Timer::Pmtu, now, self.pmtu_timer_interval());
+    }
+
+    /// Queues a HEARTBEAT chunk padded so that its packet is `size` bytes long
+    /// (RFC 8899 Sec 6.2.1.2)
+    fn send_pmtu_probe(&mut self, size: u32) {
+        let Some(prober) = self.pmtu_prober.as_ref() else {
+            return;
+        };
+        let overhead = COMMON_HEADER_SIZE as usize
+            + CHUNK_HEADER_SIZE
+            + 2 * PARAM_HEADER_LENGTH
+            + PMTU_PROBE_INFO_LENGTH;
+        trace!("[{}] sending PMTU probe of {} bytes", self.side, size);
+        let heartbeat = ChunkHeartbeat {
+            params: vec![
+                Box::new(ParamHeartbeatInfo {
+                    heartbeat_information: prober.probe_info(size),
+                }),
+                Box::new(ParamPadding::new((size as usize).saturating_sub(overhead))),
+            ],
+        };
+        let p = self.create_packet(vec![Box::new(heartbeat)]);
+        self.control_queue.push_back(p);
+        self.awake_write_loop();
+    }
+
+    fn on_pmtu_probe_acked(&mut self, info: &Bytes, now: Instant) {
+        let Some(prober) = self.pmtu_prober.as_mut() else {
+            return;
+        };
+        let Some(size) = prober.on_probe_acked(info) else {
+            return;
+        };
+        debug!("[{}] PMTU probe of {} bytes acked", self.side, size);
+        if size > self.mtu {
+            self.apply_mtu(size);
+        }
+
+        // Carry on with the search right away
+        self.timers.stop(Timer::Pmtu);
+        self.start_pmtu_probing_if_stopped(now);
+    }
+
+    /// Falls back to the base PMTU when full-sized packets keep getting lost
+    /// (RFC 8899 Sec 4.3). The search restarts with the next outbound packet.
+    fn on_pmtu_black_hole(&mut self) {
+        let Some(prober) = self.pmtu_prober.as_mut() else {
+            return;
+        };
+        if self.mtu <= prober.base_size() {
+            return;
+        }
+        let size = prober.on_black_hole();
+        warn!(
+            "[{}] packets of {} bytes are lost, falling back to a PMTU of {}",
+            self.side, self.mtu, size
+        );
+        self.apply_mtu(size);
+        self.timers.stop(Timer::Pmtu);
+    }
+
+    fn start_heartbeat_timer_if_stopped(&mut self, now: Instant) {
+        let enabled = self
+            .peer_addr_params
+            .is_some_and(|p| p.heartbeat_interval.is_some());
+        if enabled && self.timers.get(Timer::Heartbeat).is_none() {
+            self.timers
+                .start(Timer::Heartbeat, now, self.heartbeat_interval());
+        }
+    }
+
+    /// Lingers for two RTOs after closing, so that the endpoint keeps routing
+    /// late packets of the peer here rather than reusing the verification tag
+    fn start_close_timer_if_stopped(&mut self, now: Instant) {
+        if self.lingering && self.timers.get(Timer::Close).is_none() {
+            self.timers
+                .start(Timer::Close, now, 2 * self.rto_mgr.get_rto());
+        }
     }
 
-    fn awake_write_loop(&self) {
-        // No Op on Purpose
+    fn on_close_timeout(&mut self) {
+        debug!("[{}] association drained", self.side);
+        self.lingering = false;
+        self.drained = true;
+        self.endpoint_events.push_back(EndpointEventInner::Drained);
     }
 
     fn close_all_timers(&mut self) {
@@ -2713,6 +4906,26 @@ impl Association {
     }
 
     fn on_retransmission_timeout(&mut self, timer_id: Timer, n_rtos: usize) {
+        // RFC 4960 sec 8.1
+        //   An endpoint should keep a counter on the total number of consecutive
+        //   retransmissions to its peer (this includes retransmissions to all the
+        //   destination transport addresses of the peer if it is multi-homed).
+        if matches!(
+            timer_id,
+            Timer::T1Init | Timer::T1Cookie | Timer::T2Shutdown | Timer::T3RTX
+        ) {
+            self.consecutive_retransmissions += 1;
+            if self.consecutive_retransmissions >= self.max_assoc_retransmissions {
+                error!(
+                    "[{}] {} consecutive retransmissions, peer unreachable",
+                    self.side, self.consecutive_retransmissions
+                );
+                self.error = Some(AssociationError::TimedOut);
+                let _ = self.close();
+                return;
+            }
+        }
+
         match timer_id {
             Timer::T1Init => {
                 if let Err(err) = self.send_init() {
@@ -2754,39 +4967,38 @@ impl Association {
             Timer::T3RTX => {
                 self.stats.inc_t3timeouts();
 
+                if n_rtos >= PMTU_BLACK_HOLE_RTOS {
+                    self.on_pmtu_black_hole();
+                }
+
                 // RFC 4960 sec 6.3.3
                 //  E1)  For the destination address for which the timer expires, adjust
                 //       its ssthresh with rules defined in Section 7.2.3 and set the
                 //       cwnd <- MTU.
-                // RFC 4960 sec 7.2.3
-                //   When the T3-rtx timer expires on an address, SCTP should perform slow
-                //   start by:
-                //      ssthresh = max(cwnd/2, 4*MTU)
-                //      cwnd = 1*MTU
-
-                self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * self.mtu);
-                self.cwnd = self.mtu;
+                self.congestion
+                    .on_retransmit_timeout(self.my_next_tsn.wrapping_sub(1), self.mtu);
+                self.t3_rtx_tsn = self.cumulative_tsn_ack_point.wrapping_add(1);
                 trace!(
                     "[{}] updated cwnd={} ssthresh={} inflight={} (RTO)",
                     self.side,
-                    self.cwnd,
-                    self.ssthresh,
-                    self.inflight_queue.get_num_bytes()
+                    self.congestion.cwnd,
+                    self.congestion.ssthresh,
+                    self.inflight_queue.get_num_bytes_in_flight()
                 );
 
                 // RFC 3758 sec 3.5
                 //  A5) Any time the T3-rtx timer expires, on any destination, the sender
                 //  SHOULD try to advance the "Advanced.Peer.Ack.Point" by following
                 //  the procedures outlined in C2 - C5.
-                if self.use_forward_tsn {
+                if self.use_forward_tsn() {
                     // RFC 3758 Sec 3.5 C2
-                    let mut i = self.advanced_peer_tsn_ack_point + 1;
+                    let mut i = self.advanced_peer_tsn_ack_point.wrapping_add(1);
                     while let Some(c) = self.inflight_queue.get(i) {
                         if !c.abandoned() {
                             break;
                         }
                         self.advanced_peer_tsn_ack_point = i;
-                        i += 1;
+                        i = i.wrapping_add(1);
                     }
 
                     // RFC 3758 Sec 3.5 C3
@@ -2807,7 +5019,7 @@ impl Association {
 
                 debug!(
                     "[{}] T3-rtx timed out: n_rtos={} cwnd={} ssthresh={}",
-                    self.side, n_rtos, self.cwnd, self.ssthresh
+                    self.side, n_rtos, self.congestion.cwnd, self.congestion.ssthresh
                 );
 
                 self.inflight_queue.mark_all_to_retrasmit();
@@ -2819,28 +5031,51 @@ impl Association {
                 self.awake_write_loop();
             }
 
+            Timer::Pmtu => {
+                if let Some(size) = self.pmtu_prober.as_mut().and_then(|p| p.on_probe_timeout()) {
+                    self.send_pmtu_probe(size);
+                }
+            }
+
             _ => {}
         }
     }
 
+    /// Sends a HEARTBEAT whose Heartbeat Information identifies it, so that its
+    /// ACK yields an RTT sample
+    fn send_heartbeat(&mut self, n_rtos: usize, now: Instant) {
+        trace!("[{}] sending HEARTBEAT (n_rtos={})", self.side, n_rtos);
+        let nonce = self.rng.gen::<u64>();
+        let heartbeat = ChunkHeartbeat {
+            params: vec![Box::new(ParamHeartbeatInfo {
+                heartbeat_information: Bytes::copy_from_slice(&nonce.to_be_bytes()),
+            })],
+        };
+        self.heartbeat_sent = Some((nonce, now));
+        let p = self.create_packet(vec![Box::new(heartbeat)]);
+        self.control_queue.push_back(p);
+        self.awake_write_loop();
+    }
+
     fn on_retransmission_failure(&mut self, id: Timer) {
         match id {
             Timer::T1Init => {
                 error!("[{}] retransmission failure: T1-init", self.side);
-                self.error = Some(AssociationError::HandshakeFailed(
-                    Error::ErrHandshakeInitAck,
-                ));
+                // The expiration that failed did not retransmit
+                let attempts = self.timers.retransmissions(id) - 1;
+                self.fail_handshake(HandshakePhase::InitTimeout, attempts);
             }
 
             Timer::T1Cookie => {
                 error!("[{}] retransmission failure: T1-cookie", self.side);
-                self.error = Some(AssociationError::HandshakeFailed(
-                    Error::ErrHandshakeCookieEcho,
-                ));
+                let attempts = self.timers.retransmissions(id) - 1;
+                self.fail_handshake(HandshakePhase::CookieEchoTimeout, attempts);
             }
 
             Timer::T2Shutdown => {
                 error!("[{}] retransmission failure: T2-shutdown", self.side);
+                self.error = Some(AssociationError::TimedOut);
+                let _ = self.close();
             }
 
             Timer::T3RTX => {
@@ -2851,10 +5086,35 @@ impl Association {
                 error!("[{}] retransmission failure: T3-rtx (DATA)", self.side);
             }
 
+            Timer::Heartbeat => {
+                error!("[{}] retransmission failure: HEARTBEAT", self.side);
+                self.error = Some(AssociationError::TimedOut);
+                let _ = self.close();
+            }
+
             _ => {}
         }
     }
 
+    /// The timer retransmitting the handshake chunk we wait an answer for
+    fn handshake_timer(&self) -> Option<Timer> {
+        match self.state() {
+            AssociationState::CookieWait => Some(Timer::T1Init),
+            AssociationState::CookieEchoed => Some(Timer::T1Cookie),
+            _ => None,
+        }
+    }
+
+    /// Closes the association, reporting the handshake failed in `phase` after
+    /// `attempts` retransmissions
+    fn fail_handshake(&mut self, phase: HandshakePhase, attempts: usize) {
+        self.error = Some(AssociationError::HandshakeFailed {
+            phase,
+            attempts: attempts as u32,
+        });
+        let _ = self.close();
+    }
+
     /// Whether no timers are running
     #[cfg(test)]
     pub(crate) fn is_idle(&self) -> bool {
@@ -0,0 +1,163 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+
+/// Number of times a probe size is tried before it is considered too large
+/// (MAX_PROBES, RFC 8899 Sec 5.1.2)
+pub(crate) const PMTU_MAX_PROBES: u32 = 3;
+/// Time after which a completed search is restarted to detect a larger PMTU
+/// (PMTU_RAISE_TIMER, RFC 8899 Sec 5.1.1), in msec
+pub(crate) const PMTU_RAISE_INTERVAL: u64 = 600_000;
+/// Consecutive T3-rtx timeouts after which the current PMTU is suspected to
+/// have become a black hole
+pub(crate) const PMTU_BLACK_HOLE_RTOS: usize = 2;
+/// Length of the Heartbeat Information carried by a probe: a nonce followed
+/// by the probed size
+pub(crate) const PMTU_PROBE_INFO_LENGTH: usize = 12;
+/// Probe sizes are kept 4-byte aligned so that a Padding parameter can fill
+/// the HEARTBEAT chunk up to the exact packet size.
+const PMTU_PROBE_STEP: u32 = 4;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum PmtuSearchState {
+    /// Probing for a larger PMTU
+    Search,
+    /// The largest size the path carries has been found
+    SearchComplete,
+}
+
+/// PmtuProber implements the search algorithm of Datagram Packetization Layer
+/// Path MTU Discovery (RFC 8899). It only decides which sizes to probe; the
+/// association sends the probes as padded HEARTBEAT chunks and reports back
+/// the acknowledgements and timeouts.
+#[derive(Debug, Clone)]
+pub(crate) struct PmtuProber {
+    pub(crate) state: PmtuSearchState,
+    /// Size the search falls back to when the path turns into a black hole
+    base_size: u32,
+    max_size: u32,
+    /// Largest size confirmed to get through
+    low: u32,
+    /// Smallest size known not to get through
+    high: u32,
+    probe_size: Option<u32>,
+    probe_count: u32,
+    nonce: u64,
+}
+
+impl PmtuProber {
+    pub(crate) fn new(current_mtu: u32, base_size: u32, max_size: u32, nonce: u64) -> Self {
+        let max_size = std::cmp::max(align_down(max_size), current_mtu);
+        PmtuProber {
+            state: PmtuSearchState::Search,
+            base_size: std::cmp::min(base_size, current_mtu),
+            max_size,
+            low: current_mtu,
+            high: max_size + PMTU_PROBE_STEP,
+            probe_size: None,
+            probe_count: 0,
+            nonce,
+        }
+    }
+
+    /// The size of the outstanding probe, if any
+    pub(crate) fn probe_size(&self) -> Option<u32> {
+        self.probe_size
+    }
+
+    /// Picks the size of the next probe, halving the unexplored range. Returns
+    /// `None` once the search has converged.
+    pub(crate) fn next_probe(&mut self) -> Option<u32> {
+        let mid = align_down(self.low + (self.high - self.low) / 2);
+        let size = std::cmp::max(mid, self.low + PMTU_PROBE_STEP);
+        if size >= self.high {
+            self.state = PmtuSearchState::SearchComplete;
+            self.probe_size = None;
+            return None;
+        }
+
+        self.state = PmtuSearchState::Search;
+        self.probe_size = Some(size);
+        self.probe_count = 0;
+        Some(size)
+    }
+
+    /// Called when the probe timer fires. Returns the size to probe next, if
+    /// any. An unanswered probe is retried up to `PMTU_MAX_PROBES` times before
+    /// its size is considered too large.
+    pub(crate) fn on_probe_timeout(&mut self) -> Option<u32> {
+        match self.state {
+            PmtuSearchState::Search => {
+                if let Some(size) = self.probe_size {
+                    self.probe_count += 1;
+                    if self.probe_count < PMTU_MAX_PROBES {
+                        return Some(size);
+                    }
+                    self.high = size;
+                }
+            }
+            PmtuSearchState::SearchComplete => {
+                // Look for a larger PMTU again
+                self.high = self.max_size + PMTU_PROBE_STEP;
+            }
+        }
+        self.next_probe()
+    }
+
+    /// Validates the Heartbeat Information echoed in a HEARTBEAT ACK. Returns
+    /// the newly confirmed PMTU if it acknowledges the outstanding probe.
+    pub(crate) fn on_probe_acked(&mut self, info: &Bytes) -> Option<u32> {
+        if info.len() != PMTU_PROBE_INFO_LENGTH {
+            return None;
+        }
+        let reader = &mut info.clone();
+        let nonce = reader.get_u64();
+        let size = reader.get_u32();
+        if nonce != self.nonce || self.probe_size != Some(size) {
+            return None;
+        }
+
+        self.low = size;
+        self.probe_size = None;
+        Some(size)
+    }
+
+    /// Restarts the search from the base size after packets of the current
+    /// PMTU went missing (RFC 8899 Sec 4.3). Returns the size to fall back to.
+    pub(crate) fn on_black_hole(&mut self) -> u32 {
+        self.high = self.low;
+        self.low = self.base_size;
+        self.probe_size = None;
+        self.state = PmtuSearchState::Search;
+        self.low
+    }
+
+    /// Narrows the search to `size` after the network reported that larger
+    /// packets do not get through (RFC 8899 Sec 4.6)
+    pub(crate) fn on_packet_too_big(&mut self, size: u32) {
+        if self.probe_size.is_some_and(|probe| probe > size) {
+            self.probe_size = None;
+        }
+        self.low = std::cmp::min(self.low, size);
+        self.high = std::cmp::min(self.high, size + PMTU_PROBE_STEP);
+    }
+
+    /// The largest size confirmed so far
+    pub(crate) fn confirmed_size(&self) -> u32 {
+        self.low
+    }
+
+    pub(crate) fn base_size(&self) -> u32 {
+        self.base_size
+    }
+
+    /// The Heartbeat Information identifying a probe of `size` bytes
+    pub(crate) fn probe_info(&self, size: u32) -> Bytes {
+        let mut buf = BytesMut::with_capacity(PMTU_PROBE_INFO_LENGTH);
+        buf.put_u64(self.nonce);
+        buf.put_u32(size);
+        buf.freeze()
+    }
+}
+
+fn align_down(size: u32) -> u32 {
+    size - size % PMTU_PROBE_STEP
+}
@@ -45,25 +45,59 @@ impl fmt::Display for AssociationState {
     }
 }
 
-impl AssociationState {
-    pub(crate) fn is_drained(&self) -> bool {
-        matches!(
-            *self,
-            AssociationState::ShutdownSent
-                | AssociationState::ShutdownAckSent
-                | AssociationState::ShutdownPending
-                | AssociationState::ShutdownReceived
-        )
+/// Coarse state of an association, as reported by `Association::status`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AssociationStatus {
+    /// The handshake is in progress
+    Connecting,
+    /// Data can be exchanged with the peer
+    Established,
+    /// A graceful shutdown was started by either endpoint
+    ShuttingDown,
+    /// The association is closed, or was never opened
+    Closed,
+}
+
+impl From<AssociationState> for AssociationStatus {
+    fn from(state: AssociationState) -> Self {
+        match state {
+            AssociationState::CookieWait | AssociationState::CookieEchoed => {
+                AssociationStatus::Connecting
+            }
+            AssociationState::Established => AssociationStatus::Established,
+            AssociationState::ShutdownPending
+            | AssociationState::ShutdownSent
+            | AssociationState::ShutdownReceived
+            | AssociationState::ShutdownAckSent => AssociationStatus::ShuttingDown,
+            AssociationState::Closed => AssociationStatus::Closed,
+        }
     }
 }
 
-/// ack mode (for testing)
-#[derive(Debug, Copy, Clone, PartialEq, Default)]
-pub(crate) enum AckMode {
+/// The handshake message an association is waiting to be answered, as
+/// reported by `Association::handshake_step`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HandshakeStep {
+    /// INIT was sent, waiting for INIT ACK
+    InitSent,
+    /// COOKIE ECHO was sent, waiting for COOKIE ACK
+    CookieEchoed,
+}
+
+/// Controls when the association acknowledges received DATA chunks
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum AckMode {
+    /// Delayed SACKs as described in RFC 4960 sec 6.2: acknowledge at least
+    /// every second packet, and immediately on loss or duplicates
     #[default]
     Normal,
+    /// Acknowledge every packet immediately
     NoDelay,
+    /// Always wait for the delayed ACK timer
     AlwaysDelay,
+    /// Acknowledge immediately only when gap ack blocks are present or a
+    /// duplicate is received, otherwise wait for the delayed ACK timer
+    ImmediateOnLoss,
 }
 
 impl fmt::Display for AckMode {
@@ -72,6 +106,7 @@ impl fmt::Display for AckMode {
             AckMode::Normal => "Normal",
             AckMode::NoDelay => "NoDelay",
             AckMode::AlwaysDelay => "AlwaysDelay",
+            AckMode::ImmediateOnLoss => "ImmediateOnLoss",
         };
         write!(f, "{}", s)
     }
@@ -1,3 +1,5 @@
+use std::time::{Duration, Instant};
+
 /// Association statistics
 #[derive(Default, Debug, Copy, Clone)]
 pub struct AssociationStats {
@@ -6,6 +8,11 @@ pub struct AssociationStats {
     n_t3timeouts: u64,
     n_ack_timeouts: u64,
     n_fast_retrans: u64,
+    n_unknown_tsn_acks: u64,
+    n_reneged_tsns: u64,
+    n_reassembly_drops: u64,
+    n_discarded_packets: u64,
+    n_spurious_timeouts: u64,
 }
 
 impl AssociationStats {
@@ -49,11 +56,158 @@ impl AssociationStats {
         self.n_fast_retrans
     }
 
+    pub fn inc_unknown_tsn_acks(&mut self) {
+        self.n_unknown_tsn_acks += 1;
+    }
+
+    pub fn get_num_unknown_tsn_acks(&mut self) -> u64 {
+        self.n_unknown_tsn_acks
+    }
+
+    pub fn inc_reneged_tsns(&mut self) {
+        self.n_reneged_tsns += 1;
+    }
+
+    pub fn get_num_reneged_tsns(&mut self) -> u64 {
+        self.n_reneged_tsns
+    }
+
+    pub fn inc_reassembly_drops(&mut self) {
+        self.n_reassembly_drops += 1;
+    }
+
+    pub fn get_num_reassembly_drops(&mut self) -> u64 {
+        self.n_reassembly_drops
+    }
+
+    pub fn inc_discarded_packets(&mut self) {
+        self.n_discarded_packets += 1;
+    }
+
+    pub fn get_num_discarded_packets(&mut self) -> u64 {
+        self.n_discarded_packets
+    }
+
+    pub fn inc_spurious_timeouts(&mut self) {
+        self.n_spurious_timeouts += 1;
+    }
+
+    /// Number of T3-rtx expiries later found to be spurious
+    pub fn get_num_spurious_timeouts(&mut self) -> u64 {
+        self.n_spurious_timeouts
+    }
+
     pub fn reset(&mut self) {
         self.n_datas = 0;
         self.n_sacks = 0;
         self.n_t3timeouts = 0;
         self.n_ack_timeouts = 0;
         self.n_fast_retrans = 0;
+        self.n_unknown_tsn_acks = 0;
+        self.n_reneged_tsns = 0;
+        self.n_reassembly_drops = 0;
+        self.n_discarded_packets = 0;
+        self.n_spurious_timeouts = 0;
     }
 }
+
+/// Stream statistics
+#[derive(Default, Debug, Copy, Clone)]
+pub struct StreamStats {
+    n_messages_sent: u64,
+    n_bytes_sent: u64,
+    n_messages_received: u64,
+    n_bytes_received: u64,
+    n_bytes_retransmitted: u64,
+    n_messages_abandoned: u64,
+    buffered_amount: usize,
+    reassembly_queue_bytes: usize,
+}
+
+impl StreamStats {
+    pub(crate) fn inc_messages_sent(&mut self, n_bytes: usize) {
+        self.n_messages_sent += 1;
+        self.n_bytes_sent += n_bytes as u64;
+    }
+
+    pub(crate) fn inc_messages_received(&mut self) {
+        self.n_messages_received += 1;
+    }
+
+    pub(crate) fn add_bytes_received(&mut self, n_bytes: usize) {
+        self.n_bytes_received += n_bytes as u64;
+    }
+
+    pub(crate) fn add_bytes_retransmitted(&mut self, n_bytes: usize) {
+        self.n_bytes_retransmitted += n_bytes as u64;
+    }
+
+    pub(crate) fn inc_messages_abandoned(&mut self) {
+        self.n_messages_abandoned += 1;
+    }
+
+    pub(crate) fn set_queue_depths(
+        &mut self,
+        buffered_amount: usize,
+        reassembly_queue_bytes: usize,
+    ) {
+        self.buffered_amount = buffered_amount;
+        self.reassembly_queue_bytes = reassembly_queue_bytes;
+    }
+
+    /// Messages accepted for sending
+    pub fn get_num_messages_sent(&self) -> u64 {
+        self.n_messages_sent
+    }
+
+    /// User data bytes accepted for sending
+    pub fn get_num_bytes_sent(&self) -> u64 {
+        self.n_bytes_sent
+    }
+
+    /// Messages completely reassembled from the peer's DATA chunks
+    pub fn get_num_messages_received(&self) -> u64 {
+        self.n_messages_received
+    }
+
+    /// User data bytes received, excluding duplicates
+    pub fn get_num_bytes_received(&self) -> u64 {
+        self.n_bytes_received
+    }
+
+    /// User data bytes sent again by fast retransmit or after a T3-rtx timeout
+    pub fn get_num_bytes_retransmitted(&self) -> u64 {
+        self.n_bytes_retransmitted
+    }
+
+    /// Messages given up on by PR-SCTP before being acknowledged
+    pub fn get_num_messages_abandoned(&self) -> u64 {
+        self.n_messages_abandoned
+    }
+
+    /// Bytes queued or in flight at the time the stats were taken
+    pub fn get_buffered_amount(&self) -> usize {
+        self.buffered_amount
+    }
+
+    /// Bytes waiting in the reassembly queue at the time the stats were taken
+    pub fn get_reassembly_queue_bytes(&self) -> usize {
+        self.reassembly_queue_bytes
+    }
+}
+
+/// Round-trip time estimates of the path to the peer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PathRtt {
+    /// Smoothed round-trip time (SRTT)
+    pub srtt: Duration,
+    /// Round-trip time variation (RTTVAR)
+    pub rttvar: Duration,
+    /// Current retransmission timeout
+    pub rto: Duration,
+    /// When the latest RTT measurement was taken
+    pub last_measured: Option<Instant>,
+    /// Number of RTT measurements taken. While it is zero, `srtt` and `rttvar`
+    /// are not estimates yet and `rto` is the initial RTO.
+    pub samples_taken: u64,
+}
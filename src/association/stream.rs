@@ -1,5 +1,4 @@
-use crate::association::state::AssociationState;
-use crate::association::Association;
+use crate::association::{stats::StreamStats, Association};
 use crate::chunk::chunk_payload_data::{ChunkPayloadData, PayloadProtocolIdentifier};
 use crate::error::{Error, Result};
 use crate::queue::reassembly_queue::{Chunks, ReassemblyQueue};
@@ -9,16 +8,41 @@ use crate::util::{ByteSlice, BytesArray, BytesSource};
 use bytes::Bytes;
 use log::{debug, error, trace};
 use std::fmt;
+use std::time::Instant;
 
 /// Identifier for a stream within a particular association
 pub type StreamId = u16;
 
+/// A complete message received on any stream, as returned by
+/// `Association::read_message`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncomingMessage {
+    /// Stream the message was received on
+    pub stream_id: StreamId,
+    /// Payload protocol identifier of the message
+    pub ppi: PayloadProtocolIdentifier,
+    /// Whether the message was sent unordered
+    pub unordered: bool,
+    /// Contents of the message
+    pub data: Bytes,
+}
+
 /// Application events about streams
 #[derive(Debug, PartialEq, Eq)]
 pub enum StreamEvent {
     /// One or more new streams has been opened
+    #[deprecated(note = "use `StreamEvent::OpenedWithPpi`, which is emitted alongside")]
     Opened,
+    /// The peer opened a new stream, which can be taken with `Association::accept_stream`
+    OpenedWithPpi {
+        /// Which stream has been opened
+        id: StreamId,
+        /// Payload protocol identifier of the first DATA chunk received on the stream
+        ppi: PayloadProtocolIdentifier,
+    },
     /// A currently open stream has data or errors waiting to be read
+    ///
+    /// Not repeated for data arriving before this event has been polled.
     Readable {
         /// Which stream is now readable
         id: StreamId,
@@ -49,12 +73,29 @@ pub enum StreamEvent {
         /// Which stream is now readable
         id: StreamId,
     },
+    /// The peer accepted to add outgoing streams
+    OutgoingStreamsAdded {
+        /// Number of outgoing streams now available
+        new_max: u16,
+    },
+    /// An incomplete incoming message was discarded to stay within the reassembly limits
+    MessageDropped {
+        /// Which stream the message was received on
+        id: StreamId,
+        /// Number of bytes discarded
+        bytes: usize,
+    },
 }
 
 /// Reliability type for stream
+///
+/// Streams are fully reliable by default. The partially reliable types only take
+/// effect once the FORWARD TSN extension has been negotiated with the peer;
+/// until then their data is retransmitted like that of a reliable stream.
 #[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub enum ReliabilityType {
-    /// ReliabilityTypeReliable is used for reliable transmission
+    /// ReliabilityTypeReliable is used for reliable transmission. Data is
+    /// retransmitted until acknowledged and never abandoned.
     #[default]
     Reliable = 0,
     /// ReliabilityTypeRexmit is used for partial reliability by retransmission count
@@ -84,6 +125,50 @@ impl From<u8> for ReliabilityType {
     }
 }
 
+/// Per-message options for [`Association::send_with_options`]
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub struct SendOptions {
+    /// Deliver the message without regard to the stream sequence number
+    pub unordered: bool,
+    /// Payload Protocol Identifier carried in the DATA chunks
+    pub ppi: PayloadProtocolIdentifier,
+    /// Abandon the message if it has not been acknowledged by this time.
+    /// Only takes effect when the peer supports partial reliability.
+    pub deadline: Option<Instant>,
+}
+
+/// StreamHandle identifies a stream of an association without borrowing it, so it
+/// can be kept around while the association is used for other things. Operations
+/// take the association as an argument.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct StreamHandle {
+    pub(crate) stream_identifier: StreamId,
+}
+
+impl StreamHandle {
+    /// The stream identifier
+    pub fn stream_identifier(&self) -> StreamId {
+        self.stream_identifier
+    }
+
+    /// Borrows the stream from `association` for the operations of `Stream`
+    pub fn stream<'a>(&self, association: &'a mut Association) -> Result<Stream<'a>> {
+        association.stream(self.stream_identifier)
+    }
+
+    /// Sends `data` as one message with the default Payload Protocol Identifier.
+    /// See `Stream::write_chunk`.
+    pub fn write(&self, association: &mut Association, data: Bytes) -> Result<usize> {
+        self.stream(association)?.write_chunk(&data)
+    }
+
+    /// Returns the next complete message, or None if there is none yet or the
+    /// stream is closed. See `Stream::read_message`.
+    pub fn read(&self, association: &mut Association) -> Option<Bytes> {
+        self.stream(association).ok()?.read_message().ok().flatten()
+    }
+}
+
 /// Stream represents an SCTP stream
 pub struct Stream<'a> {
     pub(crate) stream_identifier: StreamId,
@@ -112,6 +197,40 @@ impl<'a> Stream<'a> {
         Err(Error::ErrStreamClosed)
     }
 
+    /// read_message returns the next complete message as a single buffer of exactly
+    /// its size, or None if no complete message is available yet.
+    pub fn read_message(&mut self) -> Result<Option<Bytes>> {
+        Ok(self.read_sctp()?.map(|chunks| chunks.to_bytes()))
+    }
+
+    /// drain_messages appends complete messages to `messages` for as long as their
+    /// total size stays within `max_bytes`, and returns the number of bytes appended.
+    /// A message larger than the remaining budget stays queued.
+    pub fn drain_messages(&mut self, messages: &mut Vec<Bytes>, max_bytes: usize) -> Result<usize> {
+        let s = match self.association.streams.get_mut(&self.stream_identifier) {
+            Some(s)
+                if s.state == RecvSendState::ReadWritable || s.state == RecvSendState::Readable =>
+            {
+                s
+            }
+            _ => return Err(Error::ErrStreamClosed),
+        };
+
+        let mut n = 0;
+        while let Some(len) = s.reassembly_queue.peek().map(|c| c.len()) {
+            if n + len > max_bytes {
+                break;
+            }
+            match s.reassembly_queue.read() {
+                Some(chunks) => messages.push(chunks.to_bytes()),
+                None => break,
+            }
+            n += len;
+        }
+
+        Ok(n)
+    }
+
     /// write_sctp writes len(p) bytes from p to the DTLS connection
     pub fn write_sctp(&mut self, p: &Bytes, ppi: PayloadProtocolIdentifier) -> Result<usize> {
         self.write_source(&mut ByteSlice::from_slice(p), ppi)
@@ -126,6 +245,25 @@ impl<'a> Stream<'a> {
         self.write_with_ppi(data, self.get_default_payload_type()?)
     }
 
+    /// Send data on the given stream with the U flag set, so the peer may deliver it
+    /// before messages sent earlier (RFC 4960 Sec 6.6). Does not consume a stream
+    /// sequence number.
+    ///
+    /// Returns the number of bytes successfully written.
+    pub fn write_unordered(&mut self, data: &[u8]) -> Result<usize> {
+        let ppi = self.get_default_payload_type()?;
+        self.write_with_options(&mut ByteSlice::from_slice(data), ppi, true)
+    }
+
+    /// Send data on the given stream for in-order delivery, even if the stream
+    /// is configured as unordered.
+    ///
+    /// Returns the number of bytes successfully written.
+    pub fn write_ordered(&mut self, data: &[u8]) -> Result<usize> {
+        let ppi = self.get_default_payload_type()?;
+        self.write_with_options(&mut ByteSlice::from_slice(data), ppi, false)
+    }
+
     /// Send data on the given stream, with a specific payload protocol.
     ///
     /// Returns the number of bytes successfully written.
@@ -154,11 +292,26 @@ impl<'a> Stream<'a> {
         )
     }
 
-    /// write_source writes BytesSource to the DTLS connection
+    /// write_source writes BytesSource to the DTLS connection, ordered as
+    /// configured for the stream
     fn write_source<B: BytesSource>(
         &mut self,
         source: &mut B,
         ppi: PayloadProtocolIdentifier,
+    ) -> Result<usize> {
+        let unordered = match self.association.streams.get(&self.stream_identifier) {
+            Some(s) => s.unordered,
+            None => return Err(Error::ErrStreamClosed),
+        };
+
+        self.write_with_options(source, ppi, unordered)
+    }
+
+    fn write_with_options<B: BytesSource>(
+        &mut self,
+        source: &mut B,
+        ppi: PayloadProtocolIdentifier,
+        unordered: bool,
     ) -> Result<usize> {
         if !self.is_writable() {
             return Err(Error::ErrStreamClosed);
@@ -168,25 +321,20 @@ impl<'a> Stream<'a> {
             return Err(Error::ErrOutboundPacketTooLarge);
         }
 
-        let state: AssociationState = self.association.state();
-        match state {
-            AssociationState::ShutdownSent
-            | AssociationState::ShutdownAckSent
-            | AssociationState::ShutdownPending
-            | AssociationState::ShutdownReceived => return Err(Error::ErrStreamClosed),
-            _ => {}
-        };
-
         let (p, _) = source.pop_chunk(self.association.max_message_size() as usize);
+        let n = p.len();
 
-        if let Some(s) = self.association.streams.get_mut(&self.stream_identifier) {
-            let chunks = s.packetize(&p, ppi);
-            self.association.send_payload_data(chunks)?;
+        self.association.send_with_options(
+            self.stream_identifier,
+            p,
+            SendOptions {
+                unordered,
+                ppi,
+                deadline: None,
+            },
+        )?;
 
-            Ok(p.len())
-        } else {
-            Err(Error::ErrStreamClosed)
-        }
+        Ok(n)
     }
 
     pub fn is_readable(&self) -> bool {
@@ -205,8 +353,21 @@ impl<'a> Stream<'a> {
         }
     }
 
+    /// Which directions of the stream are still open. A stream reset by the
+    /// peer no longer exists and reports `StreamStatus::Closed`.
+    pub fn state(&self) -> StreamStatus {
+        self.association
+            .streams
+            .get(&self.stream_identifier)
+            .map_or(StreamStatus::Closed, |s| s.state.into())
+    }
+
     /// stop closes the read-direction of the stream.
     /// Future calls to read are not permitted after calling stop.
+    ///
+    /// If the stream was readable, its outgoing direction is reset as well
+    /// (RFC 6525), after which writes fail with `Error::ErrStreamClosed`. Data
+    /// written before is still sent.
     pub fn stop(&mut self) -> Result<()> {
         let mut reset = false;
         if let Some(s) = self.association.streams.get_mut(&self.stream_identifier) {
@@ -221,6 +382,9 @@ impl<'a> Stream<'a> {
             // https://tools.ietf.org/html/rfc6525
             self.association
                 .send_reset_request(self.stream_identifier)?;
+            if let Some(s) = self.association.streams.get_mut(&self.stream_identifier) {
+                s.state = RecvSendState::Closed;
+            }
         }
 
         Ok(())
@@ -263,12 +427,38 @@ impl<'a> Stream<'a> {
     }
 
     /// set_reliability_params sets reliability parameters for this stream.
+    ///
+    /// `rel_val` is the number of transmissions for `ReliabilityType::Rexmit` and
+    /// the lifetime in milliseconds for `ReliabilityType::Timed`, and must not be
+    /// 0 for either. It is ignored for `ReliabilityType::Reliable`. Partial
+    /// reliability requires the FORWARD TSN extension to have been negotiated.
+    ///
+    /// `unordered` only applies to subsequent writes. The reliability policy is
+    /// evaluated whenever a chunk is (re)transmitted, so it also applies to data
+    /// already queued or in flight. To keep a message under a single policy,
+    /// changes are rejected while part of a message of this stream was sent and
+    /// the rest is still queued.
     pub fn set_reliability_params(
         &mut self,
         unordered: bool,
         rel_type: ReliabilityType,
         rel_val: u32,
     ) -> Result<()> {
+        match rel_type {
+            ReliabilityType::Reliable => {}
+            ReliabilityType::Rexmit | ReliabilityType::Timed => {
+                if rel_val == 0 {
+                    return Err(Error::ErrZeroReliabilityValue);
+                }
+                if !self.association.use_forward_tsn() {
+                    return Err(Error::ErrPartialReliabilityUnsupported);
+                }
+            }
+        }
+        if self.association.pending_queue.partially_sent_stream() == Some(self.stream_identifier) {
+            return Err(Error::ErrReliabilityChangeMidMessage);
+        }
+
         if let Some(s) = self.association.streams.get_mut(&self.stream_identifier) {
             debug!(
                 "[{}] reliability params: ordered={} type={} value={}",
@@ -283,6 +473,17 @@ impl<'a> Stream<'a> {
         }
     }
 
+    /// set_unordered selects whether `write` and friends send unordered data,
+    /// keeping the reliability parameters of the stream.
+    pub fn set_unordered(&mut self, unordered: bool) -> Result<()> {
+        if let Some(s) = self.association.streams.get_mut(&self.stream_identifier) {
+            s.unordered = unordered;
+            Ok(())
+        } else {
+            Err(Error::ErrStreamClosed)
+        }
+    }
+
     /// buffered_amount returns the number of bytes of data currently queued to be sent over this stream.
     pub fn buffered_amount(&self) -> Result<usize> {
         if let Some(s) = self.association.streams.get(&self.stream_identifier) {
@@ -292,6 +493,17 @@ impl<'a> Stream<'a> {
         }
     }
 
+    /// stats returns the statistics of this stream, including its current queue depths.
+    pub fn stats(&self) -> Result<StreamStats> {
+        if let Some(s) = self.association.streams.get(&self.stream_identifier) {
+            let mut stats = s.stats;
+            stats.set_queue_depths(s.buffered_amount, s.reassembly_queue.get_num_bytes());
+            Ok(stats)
+        } else {
+            Err(Error::ErrStreamClosed)
+        }
+    }
+
     /// buffered_amount_low_threshold returns the number of bytes of buffered outgoing data that is
     /// considered "low." Defaults to 0.
     pub fn buffered_amount_low_threshold(&self) -> Result<usize> {
@@ -334,11 +546,35 @@ impl From<u8> for RecvSendState {
     }
 }
 
+/// Which directions of a stream are open, as reported by `Stream::state`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StreamStatus {
+    /// Data can be both read and written
+    Open,
+    /// `Stream::finish` closed the write direction
+    ReadOnly,
+    /// Only the write direction is open
+    WriteOnly,
+    /// Both directions are closed, e.g. by `Stream::stop`, or the stream was
+    /// reset by the peer
+    Closed,
+}
+
+impl From<RecvSendState> for StreamStatus {
+    fn from(state: RecvSendState) -> Self {
+        match state {
+            RecvSendState::ReadWritable => StreamStatus::Open,
+            RecvSendState::Readable => StreamStatus::ReadOnly,
+            RecvSendState::Writable => StreamStatus::WriteOnly,
+            RecvSendState::Closed => StreamStatus::Closed,
+        }
+    }
+}
+
 /// StreamState represents the state of an SCTP stream
 #[derive(Default, Debug)]
 pub struct StreamState {
     pub(crate) side: Side,
-    pub(crate) max_payload_size: u32,
     pub(crate) stream_identifier: StreamId,
     pub(crate) default_payload_type: PayloadProtocolIdentifier,
     pub(crate) reassembly_queue: ReassemblyQueue,
@@ -349,18 +585,19 @@ pub struct StreamState {
     pub(crate) reliability_value: u32,
     pub(crate) buffered_amount: usize,
     pub(crate) buffered_amount_low: usize,
+    pub(crate) stats: StreamStats,
+    /// A `StreamEvent::Readable` for this stream waits to be polled
+    pub(crate) readable_event_queued: bool,
 }
 impl StreamState {
     pub(crate) fn new(
         side: Side,
         stream_identifier: StreamId,
-        max_payload_size: u32,
         default_payload_type: PayloadProtocolIdentifier,
     ) -> Self {
         StreamState {
             side,
             stream_identifier,
-            max_payload_size,
             default_payload_type,
             reassembly_queue: ReassemblyQueue::new(stream_identifier),
             sequence_number: 0,
@@ -370,73 +607,63 @@ impl StreamState {
             reliability_value: 0,
             buffered_amount: 0,
             buffered_amount_low: 0,
+            stats: StreamStats::default(),
+            readable_event_queued: false,
         }
     }
 
     pub(crate) fn handle_data(&mut self, pd: &ChunkPayloadData) {
-        self.reassembly_queue.push(pd.clone());
+        let n_bytes = self.reassembly_queue.get_num_bytes();
+        if self.reassembly_queue.push(pd.clone()) {
+            self.stats.inc_messages_received();
+        }
+        self.stats
+            .add_bytes_received(self.reassembly_queue.get_num_bytes() - n_bytes);
     }
 
     pub(crate) fn handle_forward_tsn_for_ordered(&mut self, ssn: u16) {
-        if self.unordered {
-            return; // unordered chunks are handled by handleForwardUnordered method
-        }
-
+        // The peer decides per message whether it is ordered, so incomplete ordered
+        // messages are reclaimed regardless of this stream's own send setting.
         // Remove all chunks older than or equal to the new TSN from
         // the reassembly_queue.
         self.reassembly_queue.forward_tsn_for_ordered(ssn);
     }
 
     pub(crate) fn handle_forward_tsn_for_unordered(&mut self, new_cumulative_tsn: u32) {
-        if !self.unordered {
-            return; // ordered chunks are handled by handleForwardTSNOrdered method
-        }
-
         // Remove all chunks older than or equal to the new TSN from
         // the reassembly_queue.
         self.reassembly_queue
             .forward_tsn_for_unordered(new_cumulative_tsn);
     }
 
-    fn packetize(&mut self, raw: &Bytes, ppi: PayloadProtocolIdentifier) -> Vec<ChunkPayloadData> {
-        let mut i = 0;
-        let mut remaining = raw.len();
+    /// Wraps a message into a single DATA chunk for the pending queue, which
+    /// fragments it as it is sent
+    pub(crate) fn packetize(&mut self, raw: &Bytes, opts: &SendOptions) -> ChunkPayloadData {
+        let ppi = opts.ppi;
 
         // From draft-ietf-rtcweb-data-protocol-09, section 6:
         //   All Data Channel Establishment Protocol messages MUST be sent using
         //   ordered delivery and reliable transmission.
-        let unordered = ppi != PayloadProtocolIdentifier::Dcep && self.unordered;
-
-        let mut chunks = vec![];
-
-        let head_abandoned = false;
-        let head_all_inflight = false;
-        while remaining != 0 {
-            let fragment_size = std::cmp::min(self.max_payload_size as usize, remaining); //self.association.max_payload_size
-
-            // Copy the userdata since we'll have to store it until acked
-            // and the caller may re-use the buffer in the mean time
-            let user_data = raw.slice(i..i + fragment_size);
+        let unordered = ppi != PayloadProtocolIdentifier::Dcep && opts.unordered;
+        let deadline = if ppi != PayloadProtocolIdentifier::Dcep {
+            opts.deadline
+        } else {
+            None
+        };
 
-            let chunk = ChunkPayloadData {
-                stream_identifier: self.stream_identifier,
-                user_data,
-                unordered,
-                beginning_fragment: i == 0,
-                ending_fragment: remaining - fragment_size == 0,
-                immediate_sack: false,
-                payload_type: ppi,
-                stream_sequence_number: self.sequence_number,
-                abandoned: head_abandoned, // all fragmented chunks use the same abandoned
-                all_inflight: head_all_inflight, // all fragmented chunks use the same all_inflight
-                ..Default::default()
-            };
-
-            chunks.push(chunk);
-
-            remaining -= fragment_size;
-            i += fragment_size;
-        }
+        let chunk = ChunkPayloadData {
+            stream_identifier: self.stream_identifier,
+            // Keep a handle on the data since we'll have to store it until acked
+            user_data: raw.clone(),
+            unordered,
+            beginning_fragment: true,
+            ending_fragment: true,
+            immediate_sack: false,
+            payload_type: ppi,
+            stream_sequence_number: self.sequence_number,
+            deadline,
+            ..Default::default()
+        };
 
         // RFC 4960 Sec 6.6
         // Note: When transmitting ordered and unordered data, an endpoint does
@@ -450,7 +677,7 @@ impl StreamState {
         self.buffered_amount += raw.len();
         //trace!("[{}] bufferedAmount = {}", self.side, old_value + raw.len());
 
-        chunks
+        chunk
     }
 
     /// This method is called by association's read_loop (go-)routine to notify this stream
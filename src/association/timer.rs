@@ -2,18 +2,31 @@ use std::time::{Duration, Instant};
 
 pub(crate) const ACK_INTERVAL: u64 = 200;
 const MAX_INIT_RETRANS: usize = 8;
-const PATH_MAX_RETRANS: usize = 5;
+pub(crate) const PATH_MAX_RETRANS: usize = 5;
 const NO_MAX_RETRANS: usize = usize::MAX;
-const TIMER_COUNT: usize = 6;
+const TIMER_COUNT: usize = 9;
 
-#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
-pub(crate) enum Timer {
+/// Kinds of timers run by an association
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub enum Timer {
+    /// Retransmits the INIT chunk
     T1Init = 0,
+    /// Retransmits the COOKIE ECHO chunk
     T1Cookie = 1,
+    /// Retransmits the SHUTDOWN or SHUTDOWN ACK chunk
     T2Shutdown = 2,
+    /// Retransmits DATA chunks
     T3RTX = 3,
+    /// Retransmits RECONFIG chunks
     Reconfig = 4,
+    /// Sends a delayed SACK
     Ack = 5,
+    /// Probes an idle path with a HEARTBEAT chunk
+    Heartbeat = 6,
+    /// Sends the next PMTU probe, or gives up on the outstanding one
+    Pmtu = 7,
+    /// Ends the period a closed association lingers for to absorb late packets
+    Close = 8,
 }
 
 impl Timer {
@@ -24,15 +37,31 @@ impl Timer {
         Timer::T3RTX,
         Timer::Reconfig,
         Timer::Ack,
+        Timer::Heartbeat,
+        Timer::Pmtu,
+        Timer::Close,
     ];
 }
 
 /// A table of data associated with each distinct kind of `Timer`
-#[derive(Debug, Copy, Clone, Default)]
+#[derive(Debug, Copy, Clone)]
 pub(crate) struct TimerTable {
     data: [Option<Instant>; TIMER_COUNT],
     retrans: [usize; TIMER_COUNT],
     max_retrans: [usize; TIMER_COUNT],
+    /// upper bound of the exponential backoff (RTO.Max) in msec
+    rto_max: u64,
+}
+
+impl Default for TimerTable {
+    fn default() -> Self {
+        TimerTable {
+            data: [None; TIMER_COUNT],
+            retrans: [0; TIMER_COUNT],
+            max_retrans: [0; TIMER_COUNT],
+            rto_max: RTO_MAX,
+        }
+    }
 }
 
 impl TimerTable {
@@ -45,6 +74,9 @@ impl TimerTable {
                 NO_MAX_RETRANS,   //T3RTX
                 NO_MAX_RETRANS,   //Reconfig
                 NO_MAX_RETRANS,   //Ack
+                PATH_MAX_RETRANS, //Heartbeat
+                NO_MAX_RETRANS,   //Pmtu
+                NO_MAX_RETRANS,   //Close
             ],
             ..Default::default()
         }
@@ -54,19 +86,44 @@ impl TimerTable {
         self.data[timer as usize] = time;
     }
 
+    pub fn set_max_retrans(&mut self, timer: Timer, max_retrans: usize) {
+        self.max_retrans[timer as usize] = max_retrans;
+    }
+
+    pub fn set_rto_max(&mut self, rto_max: u64) {
+        self.rto_max = rto_max;
+    }
+
     pub fn get(&self, timer: Timer) -> Option<Instant> {
         self.data[timer as usize]
     }
 
+    /// Running timers along with the instant each of them fires
+    pub fn active_timers(&self) -> Vec<(Timer, Instant)> {
+        Timer::VALUES
+            .iter()
+            .filter_map(|&timer| self.get(timer).map(|time| (timer, time)))
+            .collect()
+    }
+
+    /// Time left until the timer fires, or `None` if it is not running
+    pub fn timer_remaining(&self, timer: Timer, now: Instant) -> Option<Duration> {
+        self.get(timer)
+            .map(|time| time.saturating_duration_since(now))
+    }
+
     pub fn next_timeout(&self) -> Option<Instant> {
         self.data.iter().filter_map(|&x| x).min()
     }
 
     pub fn start(&mut self, timer: Timer, now: Instant, interval: u64) {
-        let interval = if timer == Timer::Ack {
+        let interval = if matches!(
+            timer,
+            Timer::Ack | Timer::Heartbeat | Timer::Pmtu | Timer::Close
+        ) {
             interval
         } else {
-            calculate_next_timeout(interval, self.retrans[timer as usize])
+            calculate_next_timeout(interval, self.retrans[timer as usize], self.rto_max)
         };
 
         let time = now + Duration::from_millis(interval);
@@ -89,8 +146,13 @@ impl TimerTable {
         self.retrans[timer as usize] = 0;
     }
 
+    /// Number of times the timer expired since it was last stopped
+    pub fn retransmissions(&self, timer: Timer) -> usize {
+        self.retrans[timer as usize]
+    }
+
     pub fn is_expired(&mut self, timer: Timer, after: Instant) -> (bool, bool, usize) {
-        let expired = self.data[timer as usize].map_or(false, |x| x <= after);
+        let expired = self.data[timer as usize].is_some_and(|x| x <= after);
         let mut failure = false;
         if expired {
             self.retrans[timer as usize] += 1;
@@ -103,21 +165,46 @@ impl TimerTable {
     }
 }
 
-const RTO_INITIAL: u64 = 3000; // msec
-const RTO_MIN: u64 = 1000; // msec
-const RTO_MAX: u64 = 60000; // msec
+pub(crate) const RTO_INITIAL: u64 = 3000; // msec
+pub(crate) const RTO_MIN: u64 = 1000; // msec
+pub(crate) const RTO_MAX: u64 = 60000; // msec
 const RTO_ALPHA: u64 = 1;
 const RTO_BETA: u64 = 2;
 const RTO_BASE: u64 = 8;
 
 /// rtoManager manages Rtx timeout values.
 /// This is an implementation of RFC 4960 sec 6.3.1.
-#[derive(Default, Debug)]
+#[derive(Debug)]
 pub(crate) struct RtoManager {
     pub(crate) srtt: u64,
     pub(crate) rttvar: f64,
     pub(crate) rto: u64,
+    pub(crate) rto_initial: u64,
+    pub(crate) rto_min: u64,
+    pub(crate) rto_max: u64,
     pub(crate) no_update: bool,
+    /// Number of RTT measurements taken since the last reset
+    pub(crate) samples_taken: u64,
+    pub(crate) last_rtt_measurement: Option<Instant>,
+    /// Smallest RTT measured since the last reset, in msec
+    pub(crate) min_rtt: u64,
+}
+
+impl Default for RtoManager {
+    fn default() -> Self {
+        RtoManager {
+            srtt: 0,
+            rttvar: 0.0,
+            rto: 0,
+            rto_initial: RTO_INITIAL,
+            rto_min: RTO_MIN,
+            rto_max: RTO_MAX,
+            no_update: false,
+            samples_taken: 0,
+            last_rtt_measurement: None,
+            min_rtt: 0,
+        }
+    }
 }
 
 impl RtoManager {
@@ -129,12 +216,35 @@ impl RtoManager {
         }
     }
 
+    /// set_bounds overrides RTO.Initial, RTO.Min and RTO.Max (in msec).
+    /// The current RTO is reset to the new initial value if no RTT has been measured yet.
+    pub(crate) fn set_bounds(&mut self, rto_initial: u64, rto_min: u64, rto_max: u64) {
+        self.rto_initial = rto_initial;
+        self.rto_min = rto_min;
+        self.rto_max = rto_max;
+
+        if self.no_update {
+            return;
+        }
+        if self.srtt == 0 {
+            self.rto = rto_initial;
+        } else {
+            self.rto = self.rto.clamp(rto_min, rto_max);
+        }
+    }
+
     /// set_new_rtt takes a newly measured RTT then adjust the RTO in msec.
-    pub(crate) fn set_new_rtt(&mut self, rtt: u64) -> u64 {
+    pub(crate) fn set_new_rtt(&mut self, rtt: u64, now: Instant) -> u64 {
         if self.no_update {
             return self.srtt;
         }
 
+        self.samples_taken += 1;
+        self.last_rtt_measurement = Some(now);
+        if self.min_rtt == 0 || rtt < self.min_rtt {
+            self.min_rtt = rtt;
+        }
+
         if self.srtt == 0 {
             // First measurement
             self.srtt = rtt;
@@ -147,7 +257,7 @@ impl RtoManager {
             self.srtt = ((RTO_BASE - RTO_ALPHA) * self.srtt + RTO_ALPHA * rtt) / RTO_BASE;
         }
 
-        self.rto = (self.srtt + (4.0 * self.rttvar) as u64).clamp(RTO_MIN, RTO_MAX);
+        self.rto = (self.srtt + (4.0 * self.rttvar) as u64).clamp(self.rto_min, self.rto_max);
 
         self.srtt
     }
@@ -157,6 +267,21 @@ impl RtoManager {
         self.rto
     }
 
+    /// get_srtt returns the smoothed RTT in msec, or 0 before the first measurement.
+    pub(crate) fn get_srtt(&self) -> u64 {
+        self.srtt
+    }
+
+    /// get_min_rtt returns the smallest measured RTT in msec, or 0 before the first measurement.
+    pub(crate) fn get_min_rtt(&self) -> u64 {
+        self.min_rtt
+    }
+
+    /// get_rttvar returns the RTT variation in msec.
+    pub(crate) fn get_rttvar(&self) -> f64 {
+        self.rttvar
+    }
+
     /// reset resets the RTO variables to the initial values.
     pub(crate) fn reset(&mut self) {
         if self.no_update {
@@ -165,7 +290,10 @@ impl RtoManager {
 
         self.srtt = 0;
         self.rttvar = 0.0;
-        self.rto = RTO_INITIAL;
+        self.rto = self.rto_initial;
+        self.samples_taken = 0;
+        self.last_rtt_measurement = None;
+        self.min_rtt = 0;
     }
 
     /// set RTO value for testing
@@ -175,15 +303,15 @@ impl RtoManager {
     }
 }
 
-fn calculate_next_timeout(rto: u64, n_rtos: usize) -> u64 {
+fn calculate_next_timeout(rto: u64, n_rtos: usize, rto_max: u64) -> u64 {
     // RFC 4096 sec 6.3.3.  Handle T3-rtx Expiration
     //   E2)  For the destination address for which the timer expires, set RTO
     //        <- RTO * 2 ("back off the timer").  The maximum value discussed
     //        in rule C7 above (RTO.max) may be used to provide an upper bound
     //        to this doubling operation.
     if n_rtos < 31 {
-        std::cmp::min(rto << n_rtos, RTO_MAX)
+        std::cmp::min(rto << n_rtos, rto_max)
     } else {
-        RTO_MAX
+        rto_max
     }
 }
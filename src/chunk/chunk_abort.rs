@@ -1,4 +1,6 @@
-use super::{chunk_header::*, chunk_type::*, *};
+use super::{chunk_header::*, chunk_type::*, error_cause::TypedErrorCause, *};
+
+pub(crate) const ABORT_T_BITMASK: u8 = 1;
 
 ///Abort represents an SCTP Chunk of type ABORT
 ///
@@ -19,8 +21,12 @@ use super::{chunk_header::*, chunk_type::*, *};
 ///|                   zero or more Error Causes                   |
 ///|                                                               |
 ///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+///The T bit is set when the sender had no TCB and reflected the Verification
+///Tag of the packet it responds to.
 #[derive(Default, Debug, Clone)]
 pub(crate) struct ChunkAbort {
+    pub(crate) verification_tag_reflected: bool,
     pub(crate) error_causes: Vec<ErrorCause>,
 }
 
@@ -40,12 +46,20 @@ impl fmt::Display for ChunkAbort {
 impl Chunk for ChunkAbort {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_ABORT,
-            flags: 0,
+            typ: self.chunk_type(),
+            flags: if self.verification_tag_reflected {
+                ABORT_T_BITMASK
+            } else {
+                0
+            },
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_ABORT
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -55,7 +69,7 @@ impl Chunk for ChunkAbort {
 
         let mut error_causes = vec![];
         let mut offset = CHUNK_HEADER_SIZE;
-        while offset + 4 <= raw.len() {
+        while offset + 4 <= CHUNK_HEADER_SIZE + header.value_length() {
             let e = ErrorCause::unmarshal(
                 &raw.slice(offset..CHUNK_HEADER_SIZE + header.value_length()),
             )?;
@@ -63,7 +77,10 @@ impl Chunk for ChunkAbort {
             error_causes.push(e);
         }
 
-        Ok(ChunkAbort { error_causes })
+        Ok(ChunkAbort {
+            verification_tag_reflected: (header.flags & ABORT_T_BITMASK) != 0,
+            error_causes,
+        })
     }
 
     fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
@@ -88,3 +105,13 @@ impl Chunk for ChunkAbort {
         self
     }
 }
+
+impl ChunkAbort {
+    /// Decoded form of the error causes
+    pub(crate) fn typed_error_causes(&self) -> Vec<TypedErrorCause> {
+        self.error_causes
+            .iter()
+            .map(TypedErrorCause::from)
+            .collect()
+    }
+}
@@ -20,12 +20,16 @@ impl fmt::Display for ChunkCookieAck {
 impl Chunk for ChunkCookieAck {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_COOKIE_ACK,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_COOKIE_ACK
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -25,12 +25,16 @@ impl fmt::Display for ChunkCookieEcho {
 impl Chunk for ChunkCookieEcho {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_COOKIE_ECHO,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_COOKIE_ECHO
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
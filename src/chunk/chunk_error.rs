@@ -1,4 +1,4 @@
-use super::{chunk_header::*, chunk_type::*, *};
+use super::{chunk_header::*, chunk_type::*, error_cause::TypedErrorCause, *};
 
 ///Operation Error (ERROR) (9)
 ///
@@ -42,12 +42,16 @@ impl fmt::Display for ChunkError {
 impl Chunk for ChunkError {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_ERROR,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_ERROR
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -57,7 +61,7 @@ impl Chunk for ChunkError {
 
         let mut error_causes = vec![];
         let mut offset = CHUNK_HEADER_SIZE;
-        while offset + 4 <= raw.len() {
+        while offset + 4 <= CHUNK_HEADER_SIZE + header.value_length() {
             let e = ErrorCause::unmarshal(
                 &raw.slice(offset..CHUNK_HEADER_SIZE + header.value_length()),
             )?;
@@ -90,3 +94,13 @@ impl Chunk for ChunkError {
         self
     }
 }
+
+impl ChunkError {
+    /// Decoded form of the error causes
+    pub(crate) fn typed_error_causes(&self) -> Vec<TypedErrorCause> {
+        self.error_causes
+            .iter()
+            .map(TypedErrorCause::from)
+            .collect()
+    }
+}
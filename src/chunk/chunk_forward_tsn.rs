@@ -47,12 +47,16 @@ impl fmt::Display for ChunkForwardTsn {
 impl Chunk for ChunkForwardTsn {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_FORWARD_TSN,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_FORWARD_TSN
+    }
+
     fn unmarshal(buf: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(buf)?;
 
@@ -61,7 +65,7 @@ impl Chunk for ChunkForwardTsn {
         }
 
         let mut offset = CHUNK_HEADER_SIZE + NEW_CUMULATIVE_TSN_LENGTH;
-        if buf.len() < offset {
+        if header.value_length() < NEW_CUMULATIVE_TSN_LENGTH {
             return Err(Error::ErrChunkTooShort);
         }
 
@@ -69,7 +73,7 @@ impl Chunk for ChunkForwardTsn {
         let new_cumulative_tsn = reader.get_u32();
 
         let mut streams = vec![];
-        let mut remaining = buf.len() - offset;
+        let mut remaining = CHUNK_HEADER_SIZE + header.value_length() - offset;
         while remaining > 0 {
             let s = ChunkForwardTsnStream::unmarshal(
                 &buf.slice(offset..CHUNK_HEADER_SIZE + header.value_length()),
@@ -137,12 +141,16 @@ impl fmt::Display for ChunkForwardTsnStream {
 impl Chunk for ChunkForwardTsnStream {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: ChunkType(0),
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        ChunkType(0)
+    }
+
     fn unmarshal(buf: &Bytes) -> Result<Self> {
         if buf.len() < FORWARD_TSN_STREAM_LENGTH {
             return Err(Error::ErrChunkTooShort);
@@ -36,6 +36,10 @@ impl Chunk for ChunkHeader {
         self.clone()
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        self.typ
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         if raw.len() < CHUNK_HEADER_SIZE {
             return Err(Error::ErrChunkHeaderTooSmall);
@@ -42,12 +42,16 @@ impl fmt::Display for ChunkHeartbeat {
 impl Chunk for ChunkHeartbeat {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_HEARTBEAT,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_HEARTBEAT
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
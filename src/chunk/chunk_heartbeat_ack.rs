@@ -44,12 +44,16 @@ impl fmt::Display for ChunkHeartbeatAck {
 impl Chunk for ChunkHeartbeatAck {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_HEARTBEAT_ACK,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_HEARTBEAT_ACK
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
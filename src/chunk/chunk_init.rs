@@ -1,5 +1,6 @@
 use super::{chunk_header::*, chunk_type::*, *};
 use crate::param::param_supported_extensions::ParamSupportedExtensions;
+use crate::param::param_uknown::ParamUnknown;
 use crate::param::{param_header::*, *};
 use crate::util::get_padding_size;
 
@@ -104,12 +105,20 @@ impl fmt::Display for ChunkInit {
 impl Chunk for ChunkInit {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: if self.is_ack { CT_INIT_ACK } else { CT_INIT },
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        if self.is_ack {
+            CT_INIT_ACK
+        } else {
+            CT_INIT
+        }
+    }
+
     ///https://tools.ietf.org/html/rfc4960#section-3.2.1
     ///
     ///Chunk values of SCTP control chunks consist of a chunk-type-specific
@@ -131,7 +140,7 @@ impl Chunk for ChunkInit {
 
         if !(header.typ == CT_INIT || header.typ == CT_INIT_ACK) {
             return Err(Error::ErrChunkTypeNotTypeInit);
-        } else if raw.len() < CHUNK_HEADER_SIZE + INIT_CHUNK_MIN_LENGTH {
+        } else if header.value_length() < INIT_CHUNK_MIN_LENGTH {
             return Err(Error::ErrChunkValueNotLongEnough);
         }
 
@@ -152,12 +161,19 @@ impl Chunk for ChunkInit {
 
         let mut params = vec![];
         let mut offset = CHUNK_HEADER_SIZE + INIT_CHUNK_MIN_LENGTH;
-        let mut remaining = raw.len() as isize - offset as isize;
-        while remaining > INIT_OPTIONAL_VAR_HEADER_LENGTH as isize {
+        let mut remaining = (CHUNK_HEADER_SIZE + header.value_length()) as isize - offset as isize;
+        while remaining >= INIT_OPTIONAL_VAR_HEADER_LENGTH as isize {
             let p = build_param(&raw.slice(offset..CHUNK_HEADER_SIZE + header.value_length()))?;
             let p_len = PARAM_HEADER_LENGTH + p.value_length();
             let len_plus_padding = p_len + get_padding_size(p_len);
+            let stop_processing = p
+                .as_any()
+                .downcast_ref::<ParamUnknown>()
+                .is_some_and(|u| u.should_stop_processing());
             params.push(p);
+            if stop_processing {
+                break;
+            }
             offset += len_plus_padding;
             remaining -= len_plus_padding as isize;
         }
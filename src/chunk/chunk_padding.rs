@@ -0,0 +1,80 @@
+use super::{chunk_header::*, chunk_type::*, *};
+
+///ChunkPadding represents an SCTP Chunk of type PAD (RFC 4820 Sec 3)
+///
+///The PAD chunk pads an SCTP packet to a given size, e.g. for path MTU
+///discovery. It is bundled with other chunks and its content is ignored by
+///the receiver.
+///
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///| Type = 0x84   |   Flags=0     |             Length            |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                                                               |
+///|                         Padding Data                          |
+///|                                                               |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone)]
+pub(crate) struct ChunkPadding {
+    pub(crate) padding_data: Bytes,
+}
+
+/// makes ChunkPadding printable
+impl fmt::Display for ChunkPadding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} bytes", self.header(), self.padding_data.len())
+    }
+}
+
+impl ChunkPadding {
+    /// A PAD chunk of `len` zero bytes, excluding the chunk header
+    pub(crate) fn new(len: usize) -> Self {
+        ChunkPadding {
+            padding_data: Bytes::from(vec![0u8; len]),
+        }
+    }
+}
+
+impl Chunk for ChunkPadding {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: self.chunk_type(),
+            flags: 0,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn chunk_type(&self) -> ChunkType {
+        CT_PAD
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(raw)?;
+
+        if header.typ != CT_PAD {
+            return Err(Error::ErrChunkTypeNotPadding);
+        }
+
+        let padding_data = raw.slice(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + header.value_length());
+        Ok(ChunkPadding { padding_data })
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        buf.extend(self.padding_data.clone());
+        Ok(buf.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        self.padding_data.len()
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
@@ -11,7 +11,7 @@ pub(crate) const PAYLOAD_DATA_HEADER_SIZE: usize = 12;
 /// PayloadProtocolIdentifier is an enum for DataChannel payload types
 // PayloadProtocolIdentifier enums
 // <https://www.iana.org/assignments/sctp-parameters/sctp-parameters.xhtml#sctp-parameters-25>
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(C)]
 #[derive(Default)]
 pub enum PayloadProtocolIdentifier {
@@ -104,16 +104,21 @@ pub struct ChunkPayloadData {
 
     /// Partial-reliability parameters used only by sender
     pub(crate) since: Option<Instant>,
+    /// Time after which the chunk is abandoned, regardless of the stream's reliability type
+    pub(crate) deadline: Option<Instant>,
     /// number of transmission made for this chunk
     pub(crate) nsent: u32,
+    /// Time of the most recent transmission of this chunk
+    pub(crate) last_sent: Option<Instant>,
 
     /// valid only with the first fragment
     pub(crate) abandoned: bool,
     /// valid only with the first fragment
     pub(crate) all_inflight: bool,
 
-    /// Retransmission flag set when T1-RTX timeout occurred and this
-    /// chunk is still in the inflight queue
+    /// Retransmission flag set when T3-rtx timeout occurred and this
+    /// chunk is still in the inflight queue. Cleared by whichever
+    /// retransmission (fast or timer based) sends the chunk next.
     pub(crate) retransmit: bool,
 }
 
@@ -132,7 +137,9 @@ impl Default for ChunkPayloadData {
             acked: false,
             miss_indicator: 0,
             since: None,
+            deadline: None,
             nsent: 0,
+            last_sent: None,
             abandoned: false,
             all_inflight: false,
             retransmit: false,
@@ -164,12 +171,16 @@ impl Chunk for ChunkPayloadData {
         }
 
         ChunkHeader {
-            typ: CT_PAYLOAD_DATA,
+            typ: self.chunk_type(),
             flags,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_PAYLOAD_DATA
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -182,7 +193,7 @@ impl Chunk for ChunkPayloadData {
         let beginning_fragment = (header.flags & PAYLOAD_DATA_BEGINING_FRAGMENT_BITMASK) != 0;
         let ending_fragment = (header.flags & PAYLOAD_DATA_ENDING_FRAGMENT_BITMASK) != 0;
 
-        if raw.len() < PAYLOAD_DATA_HEADER_SIZE {
+        if header.value_length() < PAYLOAD_DATA_HEADER_SIZE {
             return Err(Error::ErrChunkPayloadSmall);
         }
 
@@ -210,7 +221,9 @@ impl Chunk for ChunkPayloadData {
             acked: false,
             miss_indicator: 0,
             since: None,
+            deadline: None,
             nsent: 0,
+            last_sent: None,
             abandoned: false,
             all_inflight: false,
             retransmit: false,
@@ -50,12 +50,16 @@ impl fmt::Display for ChunkReconfig {
 impl Chunk for ChunkReconfig {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_RECONFIG,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_RECONFIG
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -71,15 +71,63 @@ impl fmt::Display for ChunkSelectiveAck {
 
 pub(crate) const SELECTIVE_ACK_HEADER_SIZE: usize = 12;
 
+impl ChunkSelectiveAck {
+    /// validate_gap_blocks checks the Gap Ack Blocks against RFC 4960 sec 3.3.4:
+    /// every block must satisfy 0 < start <= end, and the blocks must be in
+    /// ascending order without overlapping. The offsets are 16-bit on the wire,
+    /// so they cannot exceed 65535.
+    pub(crate) fn validate_gap_blocks(&self) -> Result<()> {
+        let mut prev_end: Option<u16> = None;
+        for g in &self.gap_ack_blocks {
+            if g.start == 0 || g.start > g.end {
+                return Err(Error::ErrSackGapAckBlockInvalid);
+            }
+            if prev_end.is_some_and(|end| g.start <= end) {
+                return Err(Error::ErrSackGapAckBlocksUnordered);
+            }
+            prev_end = Some(g.end);
+        }
+
+        Ok(())
+    }
+
+    /// sort_and_merge_gap_blocks sorts the Gap Ack Blocks by start offset and
+    /// merges blocks that overlap or are adjacent. Blocks with start > end are
+    /// swapped into order first.
+    pub(crate) fn sort_and_merge_gap_blocks(&mut self) {
+        for g in &mut self.gap_ack_blocks {
+            if g.start > g.end {
+                std::mem::swap(&mut g.start, &mut g.end);
+            }
+        }
+        self.gap_ack_blocks.sort_by_key(|g| g.start);
+
+        let mut merged: Vec<GapAckBlock> = Vec::with_capacity(self.gap_ack_blocks.len());
+        for g in self.gap_ack_blocks.drain(..) {
+            match merged.last_mut() {
+                Some(last) if g.start <= last.end.saturating_add(1) => {
+                    last.end = last.end.max(g.end);
+                }
+                _ => merged.push(g),
+            }
+        }
+        self.gap_ack_blocks = merged;
+    }
+}
+
 impl Chunk for ChunkSelectiveAck {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_SACK,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_SACK
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -87,7 +135,7 @@ impl Chunk for ChunkSelectiveAck {
             return Err(Error::ErrChunkTypeNotSack);
         }
 
-        if raw.len() < CHUNK_HEADER_SIZE + SELECTIVE_ACK_HEADER_SIZE {
+        if header.value_length() < SELECTIVE_ACK_HEADER_SIZE {
             return Err(Error::ErrSackSizeNotLargeEnoughInfo);
         }
 
@@ -101,10 +149,8 @@ impl Chunk for ChunkSelectiveAck {
         // Here we must account for case where the buffer contains another chunk
         // right after this one. Testing for equality would incorrectly fail the
         // parsing of this chunk and incorrectly close the transport.
-        if raw.len()
-            < CHUNK_HEADER_SIZE
-                + SELECTIVE_ACK_HEADER_SIZE
-                + (4 * gap_ack_blocks_len + 4 * duplicate_tsn_len)
+        if header.value_length()
+            < SELECTIVE_ACK_HEADER_SIZE + (4 * gap_ack_blocks_len + 4 * duplicate_tsn_len)
         {
             return Err(Error::ErrSackSizeNotLargeEnoughInfo);
         }
@@ -26,12 +26,16 @@ impl fmt::Display for ChunkShutdown {
 impl Chunk for ChunkShutdown {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_SHUTDOWN,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_SHUTDOWN
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -39,7 +43,7 @@ impl Chunk for ChunkShutdown {
             return Err(Error::ErrChunkTypeNotShutdown);
         }
 
-        if raw.len() != CHUNK_HEADER_SIZE + CUMULATIVE_TSN_ACK_LENGTH {
+        if header.value_length() != CUMULATIVE_TSN_ACK_LENGTH {
             return Err(Error::ErrInvalidChunkSize);
         }
 
@@ -20,12 +20,16 @@ impl fmt::Display for ChunkShutdownAck {
 impl Chunk for ChunkShutdownAck {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_SHUTDOWN_ACK,
+            typ: self.chunk_type(),
             flags: 0,
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_SHUTDOWN_ACK
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
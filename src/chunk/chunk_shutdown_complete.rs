@@ -1,5 +1,7 @@
 use super::{chunk_header::*, chunk_type::*, *};
 
+pub(crate) const SHUTDOWN_COMPLETE_T_BITMASK: u8 = 1;
+
 ///chunkShutdownComplete represents an SCTP Chunk of type chunkShutdownComplete
 ///
 ///0                   1                   2                   3
@@ -7,8 +9,13 @@ use super::{chunk_header::*, chunk_type::*, *};
 ///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 ///|   Type = 14   |Reserved     |T|      Length = 4               |
 ///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+///The T bit is set when the sender had no TCB and reflected the Verification
+///Tag of the packet it responds to.
 #[derive(Default, Debug, Clone)]
-pub(crate) struct ChunkShutdownComplete;
+pub(crate) struct ChunkShutdownComplete {
+    pub(crate) verification_tag_reflected: bool,
+}
 
 /// makes chunkShutdownComplete printable
 impl fmt::Display for ChunkShutdownComplete {
@@ -20,12 +27,20 @@ impl fmt::Display for ChunkShutdownComplete {
 impl Chunk for ChunkShutdownComplete {
     fn header(&self) -> ChunkHeader {
         ChunkHeader {
-            typ: CT_SHUTDOWN_COMPLETE,
-            flags: 0,
+            typ: self.chunk_type(),
+            flags: if self.verification_tag_reflected {
+                SHUTDOWN_COMPLETE_T_BITMASK
+            } else {
+                0
+            },
             value_length: self.value_length() as u16,
         }
     }
 
+    fn chunk_type(&self) -> ChunkType {
+        CT_SHUTDOWN_COMPLETE
+    }
+
     fn unmarshal(raw: &Bytes) -> Result<Self> {
         let header = ChunkHeader::unmarshal(raw)?;
 
@@ -33,7 +48,9 @@ impl Chunk for ChunkShutdownComplete {
             return Err(Error::ErrChunkTypeNotShutdownComplete);
         }
 
-        Ok(ChunkShutdownComplete {})
+        Ok(ChunkShutdownComplete {
+            verification_tag_reflected: (header.flags & SHUTDOWN_COMPLETE_T_BITMASK) != 0,
+        })
     }
 
     fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
@@ -48,6 +48,7 @@ use super::chunk_abort::*;
 #[test]
 fn test_abort_chunk_one_error_cause() -> Result<()> {
     let abort1 = ChunkAbort {
+        verification_tag_reflected: false,
         error_causes: vec![ErrorCause {
             code: PROTOCOL_VIOLATION,
             ..Default::default()
@@ -67,9 +68,26 @@ fn test_abort_chunk_one_error_cause() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_abort_chunk_t_bit() -> Result<()> {
+    let tests = vec![
+        (Bytes::from_static(&[0x06, 0x00, 0x00, 0x04]), false),
+        (Bytes::from_static(&[0x06, 0x01, 0x00, 0x04]), true),
+    ];
+
+    for (binary, verification_tag_reflected) in tests {
+        let abort = ChunkAbort::unmarshal(&binary)?;
+        assert_eq!(verification_tag_reflected, abort.verification_tag_reflected);
+        assert_eq!(binary, abort.marshal()?);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn test_abort_chunk_many_error_causes() -> Result<()> {
     let abort1 = ChunkAbort {
+        verification_tag_reflected: false,
         error_causes: vec![
             ErrorCause {
                 code: INVALID_MANDATORY_PARAMETER,
@@ -177,6 +195,110 @@ fn test_chunk_error_unrecognized_chunk_type_marshal_with_cause_value_being_nil()
     Ok(())
 }
 
+#[test]
+fn test_chunk_error_typed_unrecognized_chunk_type() -> Result<()> {
+    let c = ChunkError::unmarshal(&RAW_IN)?;
+    assert_eq!(
+        vec![TypedErrorCause::UnrecognizedChunkType {
+            chunk: ORG_UNRECOGNIZED_CHUNK.clone()
+        }],
+        c.typed_error_causes()
+    );
+
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////
+//error_cause_test
+///////////////////////////////////////////////////////////////////
+use super::error_cause::*;
+
+#[test]
+fn test_typed_error_cause_round_trip() -> Result<()> {
+    let causes = vec![
+        TypedErrorCause::InvalidStreamIdentifier {
+            stream_identifier: 7,
+        },
+        TypedErrorCause::MissingMandatoryParameter {
+            param_types: vec![7, 9],
+        },
+        TypedErrorCause::StaleCookie { staleness_us: 1000 },
+        TypedErrorCause::OutOfResource,
+        TypedErrorCause::UnresolvableAddress {
+            address: Bytes::from_static(&[0x0, 0x5, 0x0, 0x8, 0x1, 0x2, 0x3, 0x4]),
+        },
+        TypedErrorCause::UnrecognizedChunkType {
+            chunk: ORG_UNRECOGNIZED_CHUNK.clone(),
+        },
+        TypedErrorCause::InvalidMandatoryParameter,
+        TypedErrorCause::UnrecognizedParameters {
+            params: Bytes::from_static(&[0xc0, 0x0f, 0x0, 0x4]),
+        },
+        TypedErrorCause::NoUserData { tsn: 0x01020304 },
+        TypedErrorCause::CookieReceivedWhileShuttingDown,
+        TypedErrorCause::RestartOfAnAssociationWithNewAddresses {
+            addresses: Bytes::from_static(&[0x0, 0x5, 0x0, 0x8, 0x1, 0x2, 0x3, 0x4]),
+        },
+        TypedErrorCause::UserInitiatedAbort {
+            reason: Bytes::from_static(b"bye"),
+        },
+        TypedErrorCause::ProtocolViolation {
+            information: "bad chunk".to_owned(),
+        },
+        TypedErrorCause::Unknown {
+            code: ErrorCauseCode(0x1234),
+            value: Bytes::from_static(&[0x1]),
+        },
+    ];
+
+    let abort1 = ChunkAbort {
+        verification_tag_reflected: false,
+        error_causes: causes.iter().map(ErrorCause::from).collect(),
+    };
+    let b = abort1.marshal()?;
+    let abort2 = ChunkAbort::unmarshal(&b)?;
+    assert_eq!(causes, abort2.typed_error_causes());
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_error_cause_wire_format() -> Result<()> {
+    let ec = ErrorCause::from(TypedErrorCause::InvalidStreamIdentifier {
+        stream_identifier: 0x0102,
+    });
+    assert_eq!(
+        Bytes::from_static(&[0x0, 0x1, 0x0, 0x8, 0x1, 0x2, 0x0, 0x0]),
+        ec.marshal()
+    );
+
+    let ec = ErrorCause::from(TypedErrorCause::StaleCookie { staleness_us: 5 });
+    assert_eq!(
+        Bytes::from_static(&[0x0, 0x3, 0x0, 0x8, 0x0, 0x0, 0x0, 0x5]),
+        ec.marshal()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_error_cause_malformed_value() -> Result<()> {
+    // NO_USER_DATA needs a 4 byte TSN
+    let ec = ErrorCause {
+        code: NO_USER_DATA,
+        raw: Bytes::from_static(&[0x1, 0x2]),
+    };
+    assert_eq!(
+        TypedErrorCause::Unknown {
+            code: NO_USER_DATA,
+            value: Bytes::from_static(&[0x1, 0x2]),
+        },
+        TypedErrorCause::from(&ec)
+    );
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //chunk_forward_tsn_test
 ///////////////////////////////////////////////////////////////////
@@ -424,10 +546,17 @@ use super::chunk_shutdown_complete::*;
 
 #[test]
 fn test_chunk_shutdown_complete_success() -> Result<()> {
-    let tests = vec![Bytes::from_static(&[0x0e, 0x00, 0x00, 0x04])];
+    let tests = vec![
+        (Bytes::from_static(&[0x0e, 0x00, 0x00, 0x04]), false),
+        (Bytes::from_static(&[0x0e, 0x01, 0x00, 0x04]), true),
+    ];
 
-    for binary in tests {
+    for (binary, verification_tag_reflected) in tests {
         let actual = ChunkShutdownComplete::unmarshal(&binary)?;
+        assert_eq!(
+            verification_tag_reflected,
+            actual.verification_tag_reflected
+        );
         let b = actual.marshal()?;
         assert_eq!(binary, b, "test not equal");
     }
@@ -462,8 +591,10 @@ fn test_chunk_shutdown_complete_failure() -> Result<()> {
 ///////////////////////////////////////////////////////////////////
 use crate::chunk::chunk_init::*;
 use crate::chunk::chunk_payload_data::*;
-use crate::chunk::chunk_selective_ack::ChunkSelectiveAck;
+use crate::chunk::chunk_selective_ack::{ChunkSelectiveAck, GapAckBlock};
 use crate::packet::*;
+use crate::param::build_param;
+use crate::param::param_forward_tsn_supported::ParamForwardTsnSupported;
 use crate::param::param_outgoing_reset_request::ParamOutgoingResetRequest;
 use crate::param::param_state_cookie::*;
 
@@ -632,6 +763,32 @@ fn test_init_marshal_unmarshal() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_init_skips_known_params_without_parser() -> Result<()> {
+    // Supported Address Types (IPv4), as sent first by Linux, then Forward-TSN-Supported
+    let init = ChunkInit {
+        initiate_tag: 123,
+        advertised_receiver_window_credit: 1500,
+        num_outbound_streams: 1,
+        num_inbound_streams: 1,
+        initial_tsn: 123,
+        params: vec![
+            build_param(&Bytes::from_static(&[0x00, 0x0C, 0x00, 0x06, 0x00, 0x05]))?,
+            Box::new(ParamForwardTsnSupported {}),
+        ],
+        ..Default::default()
+    };
+
+    let init = ChunkInit::unmarshal(&init.marshal()?)?;
+    assert_eq!(2, init.params.len());
+    assert!(init.params[1]
+        .as_any()
+        .downcast_ref::<ParamForwardTsnSupported>()
+        .is_some());
+
+    Ok(())
+}
+
 #[test]
 fn test_payload_data_marshal_unmarshal() -> Result<()> {
     let raw_pkt = Bytes::from_static(&[
@@ -671,6 +828,89 @@ fn test_select_ack_chunk() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_select_ack_chunk_truncated_gap_blocks() -> Result<()> {
+    // Claims 1000 gap blocks and 0 duplicate TSNs but carries a single block
+    let raw = Bytes::from_static(&[
+        0x03, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x09, 0x00, 0x01, 0x00, 0x00, 0x03, 0xe8, 0x00,
+        0x00, 0x00, 0x02, 0x00, 0x02,
+    ]);
+    assert_eq!(
+        Some(Error::ErrSackSizeNotLargeEnoughInfo),
+        ChunkSelectiveAck::unmarshal(&raw).err()
+    );
+
+    // Claims duplicate TSNs that are not there
+    let raw = Bytes::from_static(&[
+        0x03, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x09, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00,
+        0x01, 0x00, 0x02, 0x00, 0x02,
+    ]);
+    assert_eq!(
+        Some(Error::ErrSackSizeNotLargeEnoughInfo),
+        ChunkSelectiveAck::unmarshal(&raw).err()
+    );
+
+    Ok(())
+}
+
+fn gap_blocks(blocks: &[(u16, u16)]) -> Vec<GapAckBlock> {
+    blocks
+        .iter()
+        .map(|&(start, end)| GapAckBlock { start, end })
+        .collect()
+}
+
+#[test]
+fn test_select_ack_validate_gap_blocks() -> Result<()> {
+    let tests = vec![
+        (vec![], Ok(())),
+        (vec![(1, 1), (3, 5), (7, 65535)], Ok(())),
+        (vec![(0, 2)], Err(Error::ErrSackGapAckBlockInvalid)),
+        (vec![(5, 3)], Err(Error::ErrSackGapAckBlockInvalid)),
+        (
+            vec![(4, 5), (1, 2)],
+            Err(Error::ErrSackGapAckBlocksUnordered),
+        ),
+        (
+            vec![(1, 4), (4, 6)],
+            Err(Error::ErrSackGapAckBlocksUnordered),
+        ),
+        (
+            vec![(2, 3), (2, 3)],
+            Err(Error::ErrSackGapAckBlocksUnordered),
+        ),
+    ];
+
+    for (blocks, expected) in tests {
+        let sack = ChunkSelectiveAck {
+            gap_ack_blocks: gap_blocks(&blocks),
+            ..Default::default()
+        };
+        assert_eq!(expected, sack.validate_gap_blocks(), "blocks {:?}", blocks);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_select_ack_sort_and_merge_gap_blocks() -> Result<()> {
+    let mut sack = ChunkSelectiveAck {
+        gap_ack_blocks: gap_blocks(&[(9, 10), (2, 3), (4, 4), (2, 3), (12, 14), (13, 20)]),
+        ..Default::default()
+    };
+    sack.sort_and_merge_gap_blocks();
+
+    let merged: Vec<(u16, u16)> = sack
+        .gap_ack_blocks
+        .iter()
+        .map(|g| (g.start, g.end))
+        .collect();
+    assert_eq!(vec![(2, 4), (9, 10), (12, 20)], merged);
+    sack.validate_gap_blocks()?;
+
+    Ok(())
+}
+
 #[test]
 fn test_reconfig_chunk() -> Result<()> {
     let raw_pkt = Bytes::from_static(&[
@@ -751,3 +991,60 @@ fn test_select_ack_chunk_followed_by_a_payload_data_chunk() -> Result<()> {
     );
     Ok(())
 }
+
+#[test]
+fn test_chunk_type_accessor() -> Result<()> {
+    use super::chunk_cookie_ack::ChunkCookieAck;
+    use super::chunk_cookie_echo::ChunkCookieEcho;
+    use super::chunk_heartbeat::ChunkHeartbeat;
+    use super::chunk_heartbeat_ack::ChunkHeartbeatAck;
+    use super::chunk_padding::ChunkPadding;
+    use super::chunk_unknown::ChunkUnknown;
+
+    let tests: Vec<(Box<dyn Chunk>, ChunkType)> = vec![
+        (Box::<ChunkPayloadData>::default(), CT_PAYLOAD_DATA),
+        (Box::<ChunkInit>::default(), CT_INIT),
+        (
+            Box::new(ChunkInit {
+                is_ack: true,
+                ..Default::default()
+            }),
+            CT_INIT_ACK,
+        ),
+        (Box::<ChunkSelectiveAck>::default(), CT_SACK),
+        (Box::<ChunkHeartbeat>::default(), CT_HEARTBEAT),
+        (Box::<ChunkHeartbeatAck>::default(), CT_HEARTBEAT_ACK),
+        (Box::<ChunkAbort>::default(), CT_ABORT),
+        (Box::<ChunkShutdown>::default(), CT_SHUTDOWN),
+        (Box::<ChunkShutdownAck>::default(), CT_SHUTDOWN_ACK),
+        (Box::<ChunkError>::default(), CT_ERROR),
+        (Box::<ChunkCookieEcho>::default(), CT_COOKIE_ECHO),
+        (Box::new(ChunkCookieAck), CT_COOKIE_ACK),
+        (
+            Box::<ChunkShutdownComplete>::default(),
+            CT_SHUTDOWN_COMPLETE,
+        ),
+        (Box::<ChunkReconfig>::default(), CT_RECONFIG),
+        (Box::<ChunkForwardTsn>::default(), CT_FORWARD_TSN),
+        (Box::<ChunkPadding>::default(), CT_PAD),
+        (
+            Box::new(ChunkUnknown {
+                typ: ChunkType(0xc1),
+                ..Default::default()
+            }),
+            ChunkType(0xc1),
+        ),
+    ];
+
+    for (chunk, expected) in tests {
+        assert_eq!(expected, chunk.chunk_type(), "{:?}", chunk);
+        assert_eq!(expected, chunk.header().typ, "{:?}", chunk);
+    }
+
+    // Parsed chunks report the type found on the wire
+    let raw = ChunkShutdownAck {}.marshal()?;
+    let header = ChunkHeader::unmarshal(&raw)?;
+    assert_eq!(CT_SHUTDOWN_ACK, header.chunk_type());
+
+    Ok(())
+}
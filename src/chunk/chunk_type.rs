@@ -1,10 +1,10 @@
 use std::fmt;
 
-// chunkType is an enum for SCTP Chunk Type field
-// This field identifies the type of information contained in the
-// Chunk Value field.
+/// ChunkType is the SCTP Chunk Type field (RFC 4960 Sec 3.2)
+/// This field identifies the type of information contained in the
+/// Chunk Value field.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
-pub(crate) struct ChunkType(pub(crate) u8);
+pub struct ChunkType(pub u8);
 
 pub(crate) const CT_PAYLOAD_DATA: ChunkType = ChunkType(0);
 pub(crate) const CT_INIT: ChunkType = ChunkType(1);
@@ -21,6 +21,7 @@ pub(crate) const CT_COOKIE_ACK: ChunkType = ChunkType(11);
 pub(crate) const CT_CWR: ChunkType = ChunkType(13);
 pub(crate) const CT_SHUTDOWN_COMPLETE: ChunkType = ChunkType(14);
 pub(crate) const CT_RECONFIG: ChunkType = ChunkType(130);
+pub(crate) const CT_PAD: ChunkType = ChunkType(132);
 pub(crate) const CT_FORWARD_TSN: ChunkType = ChunkType(192);
 
 impl fmt::Display for ChunkType {
@@ -42,6 +43,7 @@ impl fmt::Display for ChunkType {
             CT_CWR => "ECNE", // Explicit Congestion Notification Echo
             CT_SHUTDOWN_COMPLETE => "SHUTDOWN-COMPLETE",
             CT_RECONFIG => "RECONFIG", // Re-configuration
+            CT_PAD => "PAD",
             CT_FORWARD_TSN => "FORWARD-TSN",
             _ => others.as_str(),
         };
@@ -71,6 +73,7 @@ mod test {
             (CT_CWR, "ECNE"),
             (CT_SHUTDOWN_COMPLETE, "SHUTDOWN-COMPLETE"),
             (CT_RECONFIG, "RECONFIG"),
+            (CT_PAD, "PAD"),
             (CT_FORWARD_TSN, "FORWARD-TSN"),
             (ChunkType(255), "Unknown ChunkType: 255"),
         ];
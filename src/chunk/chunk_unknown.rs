@@ -0,0 +1,80 @@
+use super::{chunk_header::*, chunk_type::*, *};
+
+/// ChunkUnknown holds a chunk of a type this implementation does not understand
+///
+/// The two highest bits of the chunk type tell the receiver what to do with it
+/// (RFC 4960 sec 3.2):
+///
+/// 00 - Stop processing this SCTP packet and discard it.
+/// 01 - Stop processing this SCTP packet, discard it, and report the chunk
+///      in an ERROR chunk using the Unrecognized Chunk Type cause.
+/// 10 - Skip this chunk and continue processing.
+/// 11 - Skip this chunk and continue processing, but report it in an ERROR
+///      chunk using the Unrecognized Chunk Type cause.
+#[derive(Default, Debug, Clone)]
+pub(crate) struct ChunkUnknown {
+    pub(crate) typ: ChunkType,
+    pub(crate) flags: u8,
+    pub(crate) value: Bytes,
+}
+
+/// makes ChunkUnknown printable
+impl fmt::Display for ChunkUnknown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header())
+    }
+}
+
+impl Chunk for ChunkUnknown {
+    fn header(&self) -> ChunkHeader {
+        ChunkHeader {
+            typ: self.typ,
+            flags: self.flags,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn chunk_type(&self) -> ChunkType {
+        self.typ
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ChunkHeader::unmarshal(raw)?;
+        let value = raw.slice(CHUNK_HEADER_SIZE..CHUNK_HEADER_SIZE + header.value_length());
+        Ok(ChunkUnknown {
+            typ: header.typ,
+            flags: header.flags,
+            value,
+        })
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        buf.extend(self.value.clone());
+        Ok(buf.len())
+    }
+
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn value_length(&self) -> usize {
+        self.value.len()
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
+
+impl ChunkUnknown {
+    /// Whether the rest of the packet must be discarded
+    pub(crate) fn should_stop_processing(&self) -> bool {
+        self.typ.0 & 0x80 == 0
+    }
+
+    /// Whether the chunk must be reported back in an ERROR chunk
+    pub(crate) fn should_report(&self) -> bool {
+        self.typ.0 & 0x40 != 0
+    }
+}
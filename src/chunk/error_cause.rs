@@ -0,0 +1,196 @@
+use super::*;
+
+/// TypedErrorCause is the decoded form of an error cause carried in an ERROR or
+/// ABORT chunk (RFC 4960 sec 3.3.10)
+///
+/// Values that reference other protocol elements, such as addresses or the
+/// offending chunk, are kept in their wire format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypedErrorCause {
+    /// DATA was received on a stream that does not exist
+    InvalidStreamIdentifier { stream_identifier: u16 },
+    /// Mandatory parameters of an INIT or INIT ACK were missing
+    MissingMandatoryParameter { param_types: Vec<u16> },
+    /// A COOKIE ECHO was received after its cookie expired
+    StaleCookie {
+        /// Difference between the expiration and the receive time in microseconds
+        staleness_us: u32,
+    },
+    /// The sender is out of resources
+    OutOfResource,
+    /// The sender cannot resolve an address parameter
+    UnresolvableAddress { address: Bytes },
+    /// The sender did not understand a chunk; holds the chunk as received
+    UnrecognizedChunkType { chunk: Bytes },
+    /// A mandatory parameter of an INIT or INIT ACK was set to an invalid value
+    InvalidMandatoryParameter,
+    /// The sender did not understand some parameters; holds them as received
+    UnrecognizedParameters { params: Bytes },
+    /// A DATA chunk without user data was received
+    NoUserData { tsn: u32 },
+    /// A COOKIE ECHO was received while shutting down
+    CookieReceivedWhileShuttingDown,
+    /// A restart added new addresses to the association
+    RestartOfAnAssociationWithNewAddresses { addresses: Bytes },
+    /// The upper layer aborted the association
+    UserInitiatedAbort { reason: Bytes },
+    /// The peer detected a protocol violation
+    ProtocolViolation { information: String },
+    /// A cause this implementation does not know, or one with a malformed value
+    Unknown { code: ErrorCauseCode, value: Bytes },
+}
+
+impl TypedErrorCause {
+    /// The cause code used on the wire
+    pub fn code(&self) -> ErrorCauseCode {
+        match self {
+            TypedErrorCause::InvalidStreamIdentifier { .. } => INVALID_STREAM_IDENTIFIER,
+            TypedErrorCause::MissingMandatoryParameter { .. } => MISSING_MANDATORY_PARAMETER,
+            TypedErrorCause::StaleCookie { .. } => STALE_COOKIE_ERROR,
+            TypedErrorCause::OutOfResource => OUT_OF_RESOURCE,
+            TypedErrorCause::UnresolvableAddress { .. } => UNRESOLVABLE_ADDRESS,
+            TypedErrorCause::UnrecognizedChunkType { .. } => UNRECOGNIZED_CHUNK_TYPE,
+            TypedErrorCause::InvalidMandatoryParameter => INVALID_MANDATORY_PARAMETER,
+            TypedErrorCause::UnrecognizedParameters { .. } => UNRECOGNIZED_PARAMETERS,
+            TypedErrorCause::NoUserData { .. } => NO_USER_DATA,
+            TypedErrorCause::CookieReceivedWhileShuttingDown => COOKIE_RECEIVED_WHILE_SHUTTING_DOWN,
+            TypedErrorCause::RestartOfAnAssociationWithNewAddresses { .. } => {
+                RESTART_OF_AN_ASSOCIATION_WITH_NEW_ADDRESSES
+            }
+            TypedErrorCause::UserInitiatedAbort { .. } => USER_INITIATED_ABORT,
+            TypedErrorCause::ProtocolViolation { .. } => PROTOCOL_VIOLATION,
+            TypedErrorCause::Unknown { code, .. } => *code,
+        }
+    }
+
+    fn value(&self) -> Bytes {
+        let mut buf = BytesMut::new();
+        match self {
+            TypedErrorCause::InvalidStreamIdentifier { stream_identifier } => {
+                buf.put_u16(*stream_identifier);
+                buf.put_u16(0); // reserved
+            }
+            TypedErrorCause::MissingMandatoryParameter { param_types } => {
+                buf.put_u32(param_types.len() as u32);
+                for t in param_types {
+                    buf.put_u16(*t);
+                }
+            }
+            TypedErrorCause::StaleCookie { staleness_us } => buf.put_u32(*staleness_us),
+            TypedErrorCause::NoUserData { tsn } => buf.put_u32(*tsn),
+            TypedErrorCause::ProtocolViolation { information } => {
+                buf.extend_from_slice(information.as_bytes())
+            }
+            TypedErrorCause::UnresolvableAddress { address: v }
+            | TypedErrorCause::UnrecognizedChunkType { chunk: v }
+            | TypedErrorCause::UnrecognizedParameters { params: v }
+            | TypedErrorCause::RestartOfAnAssociationWithNewAddresses { addresses: v }
+            | TypedErrorCause::UserInitiatedAbort { reason: v }
+            | TypedErrorCause::Unknown { value: v, .. } => return v.clone(),
+            TypedErrorCause::OutOfResource
+            | TypedErrorCause::InvalidMandatoryParameter
+            | TypedErrorCause::CookieReceivedWhileShuttingDown => {}
+        }
+        buf.freeze()
+    }
+}
+
+impl fmt::Display for TypedErrorCause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedErrorCause::InvalidStreamIdentifier { stream_identifier } => {
+                write!(f, "{}: stream={}", self.code(), stream_identifier)
+            }
+            TypedErrorCause::MissingMandatoryParameter { param_types } => {
+                write!(f, "{}: types={:?}", self.code(), param_types)
+            }
+            TypedErrorCause::StaleCookie { staleness_us } => {
+                write!(f, "{}: staleness={}us", self.code(), staleness_us)
+            }
+            TypedErrorCause::NoUserData { tsn } => write!(f, "{}: tsn={}", self.code(), tsn),
+            TypedErrorCause::ProtocolViolation { information } if !information.is_empty() => {
+                write!(f, "{}: {}", self.code(), information)
+            }
+            TypedErrorCause::UserInitiatedAbort { reason } if !reason.is_empty() => {
+                write!(f, "{}: {}", self.code(), String::from_utf8_lossy(reason))
+            }
+            _ => write!(f, "{}", self.code()),
+        }
+    }
+}
+
+impl From<&ErrorCause> for TypedErrorCause {
+    fn from(cause: &ErrorCause) -> Self {
+        let raw = &cause.raw;
+        let reader = &mut raw.clone();
+        let typed = match cause.code {
+            INVALID_STREAM_IDENTIFIER if raw.len() >= 4 => {
+                Some(TypedErrorCause::InvalidStreamIdentifier {
+                    stream_identifier: reader.get_u16(),
+                })
+            }
+            MISSING_MANDATORY_PARAMETER if raw.len() >= 4 => {
+                let n = reader.get_u32() as usize;
+                if reader.remaining() >= n * 2 {
+                    Some(TypedErrorCause::MissingMandatoryParameter {
+                        param_types: (0..n).map(|_| reader.get_u16()).collect(),
+                    })
+                } else {
+                    None
+                }
+            }
+            STALE_COOKIE_ERROR if raw.len() >= 4 => Some(TypedErrorCause::StaleCookie {
+                staleness_us: reader.get_u32(),
+            }),
+            OUT_OF_RESOURCE => Some(TypedErrorCause::OutOfResource),
+            UNRESOLVABLE_ADDRESS => Some(TypedErrorCause::UnresolvableAddress {
+                address: raw.clone(),
+            }),
+            UNRECOGNIZED_CHUNK_TYPE => {
+                Some(TypedErrorCause::UnrecognizedChunkType { chunk: raw.clone() })
+            }
+            INVALID_MANDATORY_PARAMETER => Some(TypedErrorCause::InvalidMandatoryParameter),
+            UNRECOGNIZED_PARAMETERS => Some(TypedErrorCause::UnrecognizedParameters {
+                params: raw.clone(),
+            }),
+            NO_USER_DATA if raw.len() >= 4 => Some(TypedErrorCause::NoUserData {
+                tsn: reader.get_u32(),
+            }),
+            COOKIE_RECEIVED_WHILE_SHUTTING_DOWN => {
+                Some(TypedErrorCause::CookieReceivedWhileShuttingDown)
+            }
+            RESTART_OF_AN_ASSOCIATION_WITH_NEW_ADDRESSES => {
+                Some(TypedErrorCause::RestartOfAnAssociationWithNewAddresses {
+                    addresses: raw.clone(),
+                })
+            }
+            USER_INITIATED_ABORT => Some(TypedErrorCause::UserInitiatedAbort {
+                reason: raw.clone(),
+            }),
+            PROTOCOL_VIOLATION => Some(TypedErrorCause::ProtocolViolation {
+                information: String::from_utf8_lossy(raw).into_owned(),
+            }),
+            _ => None,
+        };
+
+        typed.unwrap_or_else(|| TypedErrorCause::Unknown {
+            code: cause.code,
+            value: raw.clone(),
+        })
+    }
+}
+
+impl From<&TypedErrorCause> for ErrorCause {
+    fn from(cause: &TypedErrorCause) -> Self {
+        ErrorCause {
+            code: cause.code(),
+            raw: cause.value(),
+        }
+    }
+}
+
+impl From<TypedErrorCause> for ErrorCause {
+    fn from(cause: TypedErrorCause) -> Self {
+        ErrorCause::from(&cause)
+    }
+}
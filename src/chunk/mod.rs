@@ -10,6 +10,7 @@ pub(crate) mod chunk_header;
 pub(crate) mod chunk_heartbeat;
 pub(crate) mod chunk_heartbeat_ack;
 pub(crate) mod chunk_init;
+pub(crate) mod chunk_padding;
 pub mod chunk_payload_data;
 pub(crate) mod chunk_reconfig;
 pub(crate) mod chunk_selective_ack;
@@ -17,15 +18,20 @@ pub(crate) mod chunk_shutdown;
 pub(crate) mod chunk_shutdown_ack;
 pub(crate) mod chunk_shutdown_complete;
 pub(crate) mod chunk_type;
+pub(crate) mod chunk_unknown;
+pub(crate) mod error_cause;
 
 use crate::error::{Error, Result};
 use chunk_header::*;
+use chunk_type::ChunkType;
 
 use bytes::{Buf, BufMut, Bytes, BytesMut};
 use std::{any::Any, fmt};
 
 pub(crate) trait Chunk: fmt::Display + fmt::Debug {
     fn header(&self) -> ChunkHeader;
+    /// The type of the chunk, as found in its header
+    fn chunk_type(&self) -> ChunkType;
     fn unmarshal(raw: &Bytes) -> Result<Self>
     where
         Self: Sized;
@@ -98,12 +104,18 @@ pub(crate) struct ErrorCause {
     pub(crate) raw: Bytes,
 }
 
+/// ErrorCauseInvalidStreamIdentifier represents an SCTP error cause
+pub(crate) type ErrorCauseInvalidStreamIdentifier = ErrorCause;
+
 /// ErrorCauseInvalidMandatoryParameter represents an SCTP error cause
 pub(crate) type ErrorCauseInvalidMandatoryParameter = ErrorCause;
 
 /// ErrorCauseUnrecognizedChunkType represents an SCTP error cause
 pub(crate) type ErrorCauseUnrecognizedChunkType = ErrorCause;
 
+/// ErrorCauseUnrecognizedParameter represents an SCTP error cause
+pub(crate) type ErrorCauseUnrecognizedParameter = ErrorCause;
+
 ///
 /// This error cause MAY be included in ABORT chunks that are sent
 /// because an SCTP endpoint detects a protocol violation of the peer
@@ -147,6 +159,9 @@ impl ErrorCause {
         }
 
         let value_length = len as usize - ERROR_CAUSE_HEADER_LENGTH;
+        if buf.len() < ERROR_CAUSE_HEADER_LENGTH + value_length {
+            return Err(Error::ErrErrorCauseTooSmall);
+        }
         let raw = buf.slice(ERROR_CAUSE_HEADER_LENGTH..ERROR_CAUSE_HEADER_LENGTH + value_length);
 
         Ok(ErrorCause { code, raw })
@@ -1,7 +1,13 @@
+use crate::association::timer::{PATH_MAX_RETRANS, RTO_INITIAL, RTO_MAX, RTO_MIN};
+#[cfg(feature = "hmac-cookie")]
+use crate::param::param_state_cookie::CookieClock;
 use crate::util::{AssociationIdGenerator, RandomAssociationIdGenerator};
 
+use rand::{rngs::StdRng, SeedableRng};
 use std::fmt;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 /// MTU for inbound packet (from DTLS)
 pub(crate) const RECEIVE_MTU: usize = 8192;
@@ -11,6 +17,21 @@ pub(crate) const INITIAL_RECV_BUF_SIZE: u32 = 1024 * 1024;
 pub(crate) const COMMON_HEADER_SIZE: u32 = 12;
 pub(crate) const DATA_CHUNK_HEADER_SIZE: u32 = 16;
 pub(crate) const DEFAULT_MAX_MESSAGE_SIZE: u32 = 65536;
+/// SCTP port used by WebRTC data channels (RFC 8841 Sec 4)
+pub(crate) const DEFAULT_SCTP_PORT: u16 = 5000;
+/// Bytes of queued, unsent user data an association buffers across its streams
+pub(crate) const DEFAULT_MAX_TOTAL_SEND_BUFFER_SIZE: u32 = 128 * 1024;
+/// Association.Max.Retrans (RFC 4960 Sec 15)
+pub(crate) const DEFAULT_MAX_ASSOC_RETRANSMISSIONS: u32 = 10;
+/// Largest packet probed for by PMTU discovery: an IPv6/UDP datagram on a
+/// 1500-byte Ethernet link
+pub(crate) const DEFAULT_MAX_PROBE_SIZE: u32 = 1452;
+/// Gap Ack Blocks of a SACK that are processed, bounding the work a peer can
+/// cause with a single SACK
+pub(crate) const DEFAULT_MAX_GAP_ACK_BLOCKS: usize = 256;
+/// Smallest MTU an association accepts: the datagram size every IPv4 host
+/// must be able to receive (RFC 791)
+pub(crate) const MIN_MTU: u32 = 576;
 
 /// Config collects the arguments to create_association construction into
 /// a single structure
@@ -20,6 +41,22 @@ pub struct TransportConfig {
     max_message_size: u32,
     max_num_outbound_streams: u16,
     max_num_inbound_streams: u16,
+    source_port: u16,
+    destination_port: u16,
+    max_stream_reassembly_size: u32,
+    max_reassembly_size: u32,
+    max_send_buffer_size: u32,
+    max_total_send_buffer_size: u32,
+    cwnd_idle_restart: bool,
+    max_assoc_retransmissions: u32,
+    pmtu_discovery: bool,
+    min_probe_size: u32,
+    max_probe_size: u32,
+    min_pmtu: u32,
+    zero_checksum: bool,
+    reconfig_bundling_enabled: bool,
+    rng_seed: Option<u64>,
+    max_gap_ack_blocks: usize,
 }
 
 impl Default for TransportConfig {
@@ -29,6 +66,22 @@ impl Default for TransportConfig {
             max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
             max_num_outbound_streams: u16::MAX,
             max_num_inbound_streams: u16::MAX,
+            source_port: DEFAULT_SCTP_PORT,
+            destination_port: DEFAULT_SCTP_PORT,
+            max_stream_reassembly_size: INITIAL_RECV_BUF_SIZE,
+            max_reassembly_size: INITIAL_RECV_BUF_SIZE,
+            max_send_buffer_size: u32::MAX,
+            max_total_send_buffer_size: DEFAULT_MAX_TOTAL_SEND_BUFFER_SIZE,
+            cwnd_idle_restart: true,
+            max_assoc_retransmissions: DEFAULT_MAX_ASSOC_RETRANSMISSIONS,
+            pmtu_discovery: false,
+            min_probe_size: INITIAL_MTU,
+            max_probe_size: DEFAULT_MAX_PROBE_SIZE,
+            min_pmtu: MIN_MTU,
+            zero_checksum: false,
+            reconfig_bundling_enabled: true,
+            rng_seed: None,
+            max_gap_ack_blocks: DEFAULT_MAX_GAP_ACK_BLOCKS,
         }
     }
 }
@@ -54,6 +107,124 @@ impl TransportConfig {
         self
     }
 
+    /// Sets our SCTP port, 5000 by default. A server only accepts INITs sent to
+    /// this port, and associations answer packets for any other port with an ABORT.
+    pub fn with_source_port(mut self, value: u16) -> Self {
+        self.source_port = value;
+        self
+    }
+
+    /// Sets the SCTP port a client connects to, 5000 by default. Servers reply
+    /// to the port the INIT came from instead.
+    pub fn with_destination_port(mut self, value: u16) -> Self {
+        self.destination_port = value;
+        self
+    }
+
+    /// Limits the bytes of incomplete incoming messages buffered on a single stream.
+    /// The oldest incomplete message is dropped when exceeded.
+    pub fn with_max_stream_reassembly_size(mut self, value: u32) -> Self {
+        self.max_stream_reassembly_size = value;
+        self
+    }
+
+    /// Limits the bytes of incomplete incoming messages buffered across all streams.
+    /// The oldest incomplete message is dropped when exceeded.
+    pub fn with_max_reassembly_size(mut self, value: u32) -> Self {
+        self.max_reassembly_size = value;
+        self
+    }
+
+    /// Limits the bytes a single stream may have queued or in flight. Writes that would
+    /// exceed it fail with `Error::ErrSendBufferFull`. Unlimited by default.
+    pub fn with_max_send_buffer_size(mut self, value: u32) -> Self {
+        self.max_send_buffer_size = value;
+        self
+    }
+
+    /// Limits the bytes queued but not yet sent across all streams of the association.
+    /// Writes that would exceed it fail with `Error::ErrSendBufferFull`, and the stream
+    /// gets a `StreamEvent::Writable` once queued data has been sent. Defaults to 128 KiB.
+    pub fn with_max_total_send_buffer_size(mut self, value: u32) -> Self {
+        self.max_total_send_buffer_size = value;
+        self
+    }
+
+    /// Whether cwnd falls back to its initial value when no data was sent for
+    /// longer than one RTO (RFC 4960 Sec 7.2.1). Enabled by default.
+    pub fn with_cwnd_idle_restart(mut self, value: bool) -> Self {
+        self.cwnd_idle_restart = value;
+        self
+    }
+
+    /// Number of consecutive retransmission timeouts (T1-init, T1-cookie, T2-shutdown
+    /// and T3-rtx) after which the association is closed with
+    /// `AssociationError::TimedOut` (RFC 4960 Sec 8.1). Defaults to 10.
+    pub fn with_max_assoc_retransmissions(mut self, value: u32) -> Self {
+        self.max_assoc_retransmissions = value;
+        self
+    }
+
+    /// Whether the path MTU is discovered by probing with padded HEARTBEAT
+    /// chunks (RFC 8899). Disabled by default, in which case the MTU stays at
+    /// the one derived from `EndpointConfig::max_payload_size`.
+    pub fn with_pmtu_discovery(mut self, value: bool) -> Self {
+        self.pmtu_discovery = value;
+        self
+    }
+
+    /// Packet size PMTU discovery falls back to when larger packets stop
+    /// getting through. Defaults to 1228 bytes.
+    pub fn with_min_probe_size(mut self, value: u32) -> Self {
+        self.min_probe_size = value;
+        self
+    }
+
+    /// Largest packet size PMTU discovery probes for. Defaults to 1452 bytes.
+    pub fn with_max_probe_size(mut self, value: u32) -> Self {
+        self.max_probe_size = value;
+        self
+    }
+
+    /// Smallest MTU `Association::handle_pmtu_signal` lowers the association to,
+    /// whatever the reported MTU. Values below 576 bytes are raised to 576.
+    pub fn with_min_pmtu(mut self, value: u32) -> Self {
+        self.min_pmtu = std::cmp::max(value, MIN_MTU);
+        self
+    }
+
+    /// Whether packets may be sent and received with a zero checksum once the peer
+    /// agrees (RFC 9653). Only enable it when the association runs over DTLS, which
+    /// already protects packet integrity. Disabled by default.
+    pub fn with_zero_checksum(mut self, value: bool) -> Self {
+        self.zero_checksum = value;
+        self
+    }
+
+    /// Whether a new RE-CONFIG chunk is bundled with the DATA chunks sent along with
+    /// it when both fit in the MTU, instead of going out in a packet of its own.
+    /// Enabled by default.
+    pub fn with_reconfig_bundling_enabled(mut self, value: bool) -> Self {
+        self.reconfig_bundling_enabled = value;
+        self
+    }
+
+    /// Seeds the random number generator each association draws its initial TSN,
+    /// State Cookie and HEARTBEAT nonces from, making its packets reproducible.
+    /// Combined with a deterministic `EndpointConfig::aid_generator`, the whole
+    /// handshake is. Meant for tests: every association shares the same sequence.
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng_seed = Some(seed);
+        self
+    }
+
+    /// Number of Gap Ack Blocks of a received SACK that are processed; further
+    /// blocks are ignored as if the peer had not sent them. Defaults to 256.
+    pub fn with_max_gap_ack_blocks(mut self, value: usize) -> Self {
+        self.max_gap_ack_blocks = value;
+        self
+    }
+
     pub(crate) fn max_receive_buffer_size(&self) -> u32 {
         self.max_receive_buffer_size
     }
@@ -69,6 +240,105 @@ impl TransportConfig {
     pub(crate) fn max_num_inbound_streams(&self) -> u16 {
         self.max_num_inbound_streams
     }
+
+    pub(crate) fn max_stream_reassembly_size(&self) -> u32 {
+        self.max_stream_reassembly_size
+    }
+
+    pub(crate) fn max_reassembly_size(&self) -> u32 {
+        self.max_reassembly_size
+    }
+
+    pub(crate) fn max_send_buffer_size(&self) -> u32 {
+        self.max_send_buffer_size
+    }
+
+    pub(crate) fn max_total_send_buffer_size(&self) -> u32 {
+        self.max_total_send_buffer_size
+    }
+
+    pub(crate) fn cwnd_idle_restart(&self) -> bool {
+        self.cwnd_idle_restart
+    }
+
+    pub(crate) fn max_assoc_retransmissions(&self) -> u32 {
+        self.max_assoc_retransmissions
+    }
+
+    pub(crate) fn pmtu_discovery(&self) -> bool {
+        self.pmtu_discovery
+    }
+
+    pub(crate) fn min_probe_size(&self) -> u32 {
+        self.min_probe_size
+    }
+
+    pub(crate) fn max_probe_size(&self) -> u32 {
+        self.max_probe_size
+    }
+
+    pub(crate) fn min_pmtu(&self) -> u32 {
+        self.min_pmtu
+    }
+
+    pub(crate) fn zero_checksum(&self) -> bool {
+        self.zero_checksum
+    }
+
+    pub(crate) fn reconfig_bundling_enabled(&self) -> bool {
+        self.reconfig_bundling_enabled
+    }
+
+    /// Random number generator seeded as configured by `with_rng_seed`, or
+    /// from entropy when no seed was given.
+    pub(crate) fn rng(&self) -> StdRng {
+        self.rng_seed
+            .map_or_else(StdRng::from_entropy, StdRng::seed_from_u64)
+    }
+
+    pub(crate) fn max_gap_ack_blocks(&self) -> usize {
+        self.max_gap_ack_blocks
+    }
+
+    pub(crate) fn source_port(&self) -> u16 {
+        self.source_port
+    }
+
+    pub(crate) fn destination_port(&self) -> u16 {
+        self.destination_port
+    }
+}
+
+/// Per-destination parameters of an association (RFC 4960 Sec 8.3 and 15)
+///
+/// Applied with `Association::set_peer_addr_params`, overriding the RTO bounds the
+/// association was created with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PeerAddrParams {
+    /// HB.interval added to the current RTO between HEARTBEATs.
+    /// `None` disables heartbeats, which is the default.
+    pub heartbeat_interval: Option<Duration>,
+    /// RTO.Initial, used until the first RTT measurement
+    pub rto_initial: Duration,
+    /// RTO.Min
+    pub rto_min: Duration,
+    /// RTO.Max, also bounding the retransmission timer backoff
+    pub rto_max: Duration,
+    /// Path.Max.Retrans, the number of unacknowledged HEARTBEATs after which the
+    /// association is considered lost
+    pub max_retrans: usize,
+}
+
+impl Default for PeerAddrParams {
+    fn default() -> Self {
+        PeerAddrParams {
+            heartbeat_interval: None,
+            rto_initial: Duration::from_millis(RTO_INITIAL),
+            rto_min: Duration::from_millis(RTO_MIN),
+            rto_max: Duration::from_millis(RTO_MAX),
+            max_retrans: PATH_MAX_RETRANS,
+        }
+    }
 }
 
 /// Global configuration for the endpoint, affecting all associations
@@ -155,29 +425,149 @@ impl fmt::Debug for EndpointConfig {
 /// Parameters governing incoming associations
 ///
 /// Default values should be suitable for most internet applications.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct ServerConfig {
     /// Transport configuration to use for incoming associations
     pub transport: Arc<TransportConfig>,
 
     /// Maximum number of concurrent associations
-    pub(crate) concurrent_associations: u32,
+    pub(crate) max_associations: u32,
+
+    /// Maximum number of incoming associations still in their handshake
+    pub(crate) max_incoming_handshakes: u32,
+
+    /// Whether refused INITs are answered with an ABORT rather than dropped
+    pub(crate) abort_refused: bool,
+
+    /// Whether associations follow the peer to a new address
+    pub(crate) migration: bool,
+
+    /// Decides whether an association from a remote address is accepted
+    pub(crate) accept_filter: Option<Arc<dyn Fn(SocketAddr) -> bool + Send + Sync>>,
+
+    /// Whether INITs are answered without keeping any state
+    #[cfg(feature = "hmac-cookie")]
+    pub(crate) stateless_cookies: bool,
+
+    /// Key of the HMAC signing the State Cookies handed out in INIT ACKs
+    #[cfg(feature = "hmac-cookie")]
+    pub(crate) cookie_secret: [u8; 32],
+
+    /// Wall clock the age of signed State Cookies is measured with
+    #[cfg(feature = "hmac-cookie")]
+    pub(crate) cookie_clock: CookieClock,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         ServerConfig {
             transport: Arc::new(TransportConfig::default()),
-            concurrent_associations: 100_000,
+            max_associations: 100_000,
+            max_incoming_handshakes: 100_000,
+            abort_refused: true,
+            migration: false,
+            accept_filter: None,
+            #[cfg(feature = "hmac-cookie")]
+            stateless_cookies: false,
+            #[cfg(feature = "hmac-cookie")]
+            cookie_secret: rand::random(),
+            #[cfg(feature = "hmac-cookie")]
+            cookie_clock: CookieClock::default(),
         }
     }
 }
 
+impl fmt::Debug for ServerConfig {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt.debug_struct("ServerConfig")
+            .field("transport", &self.transport)
+            .field("max_associations", &self.max_associations)
+            .field("max_incoming_handshakes", &self.max_incoming_handshakes)
+            .field("abort_refused", &self.abort_refused)
+            .field("migration", &self.migration)
+            .field(
+                "accept_filter",
+                &self.accept_filter.as_ref().map(|_| "[ elided ]"),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
 impl ServerConfig {
     /// Create a default config with a particular handshake token key
     pub fn new() -> Self {
         ServerConfig::default()
     }
+
+    /// Maximum number of associations the endpoint holds at once, including the
+    /// ones it initiated. Further incoming associations are refused. Defaults
+    /// to 100000.
+    pub fn with_max_associations(mut self, value: u32) -> Self {
+        self.max_associations = value;
+        self
+    }
+
+    /// Maximum number of incoming associations that have received an INIT but
+    /// no COOKIE ECHO yet. Further INITs are refused. Defaults to 100000.
+    ///
+    /// With stateless cookies no state is kept until the COOKIE ECHO arrives,
+    /// so there are never any such associations.
+    pub fn with_max_incoming_handshakes(mut self, value: u32) -> Self {
+        self.max_incoming_handshakes = value;
+        self
+    }
+
+    /// Whether a refused INIT is answered with an ABORT carrying an Out of
+    /// Resource cause, or silently dropped. Enabled by default.
+    pub fn with_abort_refused(mut self, value: bool) -> Self {
+        self.abort_refused = value;
+        self
+    }
+
+    /// Whether an association sends to a new peer address once traffic arrives
+    /// from there and a HEARTBEAT sent to it is acknowledged, e.g. after the
+    /// peer's NAT rebinds. Disabled by default, in which case packets from any
+    /// address are accepted but replies keep going to the original one.
+    pub fn with_migration(mut self, value: bool) -> Self {
+        self.migration = value;
+        self
+    }
+
+    /// Sets a hook deciding whether an association from a remote address is
+    /// accepted, e.g. to limit the associations per IP. Refused associations
+    /// are handled like those exceeding `with_max_associations`.
+    pub fn with_accept_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(SocketAddr) -> bool + Send + Sync + 'static,
+    {
+        self.accept_filter = Some(Arc::new(filter));
+        self
+    }
+
+    /// Whether an INIT is answered with an INIT ACK whose signed State Cookie
+    /// carries the whole association, which is only set up once the cookie
+    /// is echoed back. An INIT flood then costs no memory. Disabled by default.
+    #[cfg(feature = "hmac-cookie")]
+    pub fn with_stateless_cookies(mut self, value: bool) -> Self {
+        self.stateless_cookies = value;
+        self
+    }
+
+    /// Sets the key used to sign State Cookies. Defaults to a random key;
+    /// endpoints sharing a key accept each other's cookies.
+    #[cfg(feature = "hmac-cookie")]
+    pub fn with_cookie_secret(mut self, secret: [u8; 32]) -> Self {
+        self.cookie_secret = secret;
+        self
+    }
+
+    /// Whether the accept filter lets an association from `remote` in
+    pub(crate) fn accept_filter(&self, remote: SocketAddr) -> bool {
+        match &self.accept_filter {
+            Some(filter) => filter(remote),
+            None => true,
+        }
+    }
 }
 
 /// Configuration for outgoing associations
@@ -1,9 +1,13 @@
 use super::*;
+use crate::association::extensions::SctpExtension;
 use crate::association::Event;
+use crate::config::DEFAULT_SCTP_PORT;
 use crate::error::{Error, Result};
 
-use crate::association::state::{AckMode, AssociationState};
-use crate::association::stream::{ReliabilityType, Stream};
+use crate::association::state::{AckMode, AssociationState, AssociationStatus, HandshakeStep};
+use crate::association::stream::{
+    IncomingMessage, ReliabilityType, SendOptions, Stream, StreamEvent, StreamId, StreamStatus,
+};
 use crate::chunk::chunk_abort::ChunkAbort;
 use crate::chunk::chunk_cookie_echo::ChunkCookieEcho;
 use crate::chunk::chunk_error::ChunkError;
@@ -16,10 +20,17 @@ use crate::chunk::chunk_selective_ack::{ChunkSelectiveAck, GapAckBlock};
 use crate::chunk::chunk_shutdown::ChunkShutdown;
 use crate::chunk::chunk_shutdown_ack::ChunkShutdownAck;
 use crate::chunk::chunk_shutdown_complete::ChunkShutdownComplete;
+use crate::chunk::chunk_type::ChunkType;
+use crate::chunk::chunk_unknown::ChunkUnknown;
+use crate::chunk::error_cause::TypedErrorCause;
+use crate::chunk::Chunk;
 use crate::chunk::{ErrorCauseProtocolViolation, PROTOCOL_VIOLATION};
 use crate::packet::{CommonHeader, Packet};
+use crate::param::build_param;
+use crate::param::param_heartbeat_info::ParamHeartbeatInfo;
 use crate::param::param_outgoing_reset_request::ParamOutgoingResetRequest;
 use crate::param::param_reconfig_response::ParamReconfigResponse;
+use crate::Side;
 use assert_matches::assert_matches;
 use lazy_static::lazy_static;
 use log::{info, trace};
@@ -115,7 +126,7 @@ impl TestEndpoint {
             }
         }
 
-        while self.inbound.front().map_or(false, |x| x.0 <= now) {
+        while self.inbound.front().is_some_and(|x| x.0 <= now) {
             let (recv_time, ecn, packet) = self.inbound.pop_front().unwrap();
             if let Some((ch, event)) = self.endpoint.handle(recv_time, remote, None, ecn, packet) {
                 match event {
@@ -135,27 +146,27 @@ impl TestEndpoint {
         }
 
         let mut endpoint_events: Vec<(AssociationHandle, EndpointEvent)> = vec![];
+        let timed_out = self.timeout.is_some_and(|x| x <= now);
+        let mut timeout = None;
         for (ch, conn) in self.associations.iter_mut() {
-            if self.timeout.map_or(false, |x| x <= now) {
-                self.timeout = None;
+            if timed_out {
                 conn.handle_timeout(now);
             }
 
-            for (_, mut events) in self.conn_events.drain() {
-                for event in events.drain(..) {
-                    conn.handle_event(event);
-                }
-            }
-
-            while let Some(event) = conn.poll_endpoint_event() {
-                endpoint_events.push((*ch, event));
+            for event in self.conn_events.remove(ch).into_iter().flatten() {
+                conn.handle_event(event);
             }
 
             while let Some(x) = conn.poll_transmit(now) {
                 self.outbound.extend(split_transmit(x));
             }
-            self.timeout = conn.poll_timeout();
+
+            while let Some(event) = conn.poll_endpoint_event() {
+                endpoint_events.push((*ch, event));
+            }
+            timeout = min_opt(timeout, conn.poll_timeout());
         }
+        self.timeout = timeout;
 
         for (ch, event) in endpoint_events {
             if let Some(event) = self.handle_event(ch, event) {
@@ -328,12 +339,12 @@ impl Pair {
     fn finish_connect(&mut self, client_ch: AssociationHandle, server_ch: AssociationHandle) {
         assert_matches!(
             self.client_conn_mut(client_ch).poll(),
-            Some(Event::Connected { .. })
+            Some(Event::Connected)
         );
 
         assert_matches!(
             self.server_conn_mut(server_ch).poll(),
-            Some(Event::Connected { .. })
+            Some(Event::Connected)
         );
     }
 
@@ -414,95 +425,1382 @@ fn establish_session_pair(
     let chunks = pair.server_stream(server_ch, si)?.read_sctp()?.unwrap();
     let n = chunks.read(&mut buf)?;
 
-    if n != hello_msg.len() {
-        return Err(Error::Other("received data must by 3 bytes".to_owned()));
-    }
+    if n != hello_msg.len() {
+        return Err(Error::Other("received data must by 3 bytes".to_owned()));
+    }
+
+    if chunks.ppi != PayloadProtocolIdentifier::Dcep {
+        return Err(Error::Other("unexpected ppi".to_owned()));
+    }
+
+    if buf[..n] != hello_msg {
+        return Err(Error::Other("received data mismatch".to_owned()));
+    }
+    pair.drive();
+
+    Ok(())
+}
+
+fn close_association_pair(
+    _pair: &mut Pair,
+    _client_ch: AssociationHandle,
+    _server_ch: AssociationHandle,
+    _si: u16,
+) {
+    /*TODO:
+    // Close client
+    tokio::spawn(async move {
+        client.close().await?;
+        let _ = handshake0ch_tx.send(()).await;
+        let _ = closed_rx0.recv().await;
+
+        Result::<()>::Ok(())
+    });
+
+    // Close server
+    tokio::spawn(async move {
+        server.close().await?;
+        let _ = handshake1ch_tx.send(()).await;
+        let _ = closed_rx1.recv().await;
+
+        Result::<()>::Ok(())
+    });
+    */
+}
+
+/// Hands out the same sequence of association ids in every endpoint
+struct SequentialAssociationIdGenerator(AssociationId);
+
+impl AssociationIdGenerator for SequentialAssociationIdGenerator {
+    fn generate_aid(&mut self) -> AssociationId {
+        self.0 += 1;
+        self.0
+    }
+
+    fn aid_lifetime(&self) -> Option<Duration> {
+        None
+    }
+}
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_assoc_handshake_golden_trace() -> Result<()> {
+    let mut endpoint_config = EndpointConfig::new();
+    endpoint_config.aid_generator(|| Box::new(SequentialAssociationIdGenerator(0x1000)));
+    let transport = Arc::new(TransportConfig::default().with_rng_seed(7));
+    let server_config = ServerConfig {
+        transport: transport.clone(),
+        ..Default::default()
+    };
+    let mut pair = Pair::new(Arc::new(endpoint_config), server_config);
+    pair.begin_connect(ClientConfig { transport });
+
+    // Client and server packets alternate until the handshake is done
+    let mut trace = vec![];
+    for _ in 0..2 {
+        pair.drive_client();
+        trace.extend(pair.server.inbound.iter().map(|(_, _, raw)| raw.to_vec()));
+        pair.drive_server();
+        trace.extend(pair.client.inbound.iter().map(|(_, _, raw)| raw.to_vec()));
+    }
+
+    let expected = [
+        // INIT
+        "1388138800000000bb550d220100001a0000100100100000ffffffff6aa8fbbe8008000682c00000",
+        // INIT ACK
+        "1388138800001001bd4665770200003e0000100100100000ffffffff6aa8fbbe00070024e9e0c207865f7e24e8349d4ecdbc8b0f4632842499a0dfa60568e28ab621629e8008000682c00000",
+        // COOKIE ECHO
+        "1388138800001001e16b1f580a000024e9e0c207865f7e24e8349d4ecdbc8b0f4632842499a0dfa60568e28ab621629e",
+        // COOKIE ACK
+        "1388138800001001d42f7d400b000004",
+    ];
+    assert_eq!(expected.iter().map(|s| hex(s)).collect::<Vec<_>>(), trace);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_verification_tags() -> Result<()> {
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    let client = pair.client_conn_mut(client_ch);
+    let (client_my_tag, client_peer_tag) =
+        (client.my_verification_tag(), client.peer_verification_tag());
+    let server = pair.server_conn_mut(server_ch);
+    let (server_my_tag, server_peer_tag) =
+        (server.my_verification_tag(), server.peer_verification_tag());
+
+    // Each side uses the Initiate Tag the other one sent
+    assert_eq!(client_my_tag, server_peer_tag);
+    assert_eq!(server_my_tag, client_peer_tag);
+    assert_ne!(0, client_peer_tag);
+    assert_ne!(0, server_peer_tag);
+
+    // and our own tag is what the endpoint routes packets by
+    assert_eq!(
+        Some(&client_ch),
+        pair.client.endpoint.association_ids.get(&client_my_tag)
+    );
+    assert_eq!(
+        Some(&server_ch),
+        pair.server.endpoint.association_ids.get(&server_my_tag)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_status() -> Result<()> {
+    let mut pair = Pair::default();
+    let client_ch = pair.begin_connect(client_config());
+
+    let client = pair.client_conn_mut(client_ch);
+    assert_eq!(AssociationStatus::Connecting, client.status());
+    assert_eq!(Some(HandshakeStep::InitSent), client.handshake_step());
+    assert!(!client.is_established());
+
+    // INIT and INIT ACK
+    pair.drive_client();
+    pair.drive_server();
+    pair.drive_client();
+    let client = pair.client_conn_mut(client_ch);
+    assert_eq!(AssociationStatus::Connecting, client.status());
+    assert_eq!(Some(HandshakeStep::CookieEchoed), client.handshake_step());
+
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+    for side in [Side::Client, Side::Server] {
+        let conn = match side {
+            Side::Client => pair.client_conn_mut(client_ch),
+            Side::Server => pair.server_conn_mut(server_ch),
+        };
+        assert_eq!(AssociationStatus::Established, conn.status(), "{}", side);
+        assert_eq!(None, conn.handshake_step(), "{}", side);
+        assert!(conn.is_established(), "{}", side);
+    }
+
+    pair.client_conn_mut(client_ch).shutdown()?;
+    assert_eq!(
+        AssociationStatus::ShuttingDown,
+        pair.client_conn_mut(client_ch).status()
+    );
+
+    pair.drive();
+    for side in [Side::Client, Side::Server] {
+        let conn = match side {
+            Side::Client => pair.client_conn_mut(client_ch),
+            Side::Server => pair.server_conn_mut(server_ch),
+        };
+        assert_eq!(AssociationStatus::Closed, conn.status(), "{}", side);
+        assert!(!conn.is_established(), "{}", side);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_stream_opened_events() -> Result<()> {
+    let msg: Bytes = Bytes::from_static(b"ABC");
+    let ids: [StreamId; 3] = [3, 1, 2];
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    for si in ids {
+        pair.client_conn_mut(client_ch)
+            .open_stream(si, PayloadProtocolIdentifier::Binary)?;
+        pair.client_stream(client_ch, si)?
+            .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    }
+    pair.drive();
+
+    let mut opened = vec![];
+    while let Some(event) = pair.server_conn_mut(server_ch).poll() {
+        if let Event::Stream(StreamEvent::OpenedWithPpi { id, .. }) = event {
+            opened.push(id);
+        }
+    }
+    assert_eq!(ids.to_vec(), opened, "every stream should be announced");
+
+    // Accepted in the order they were opened
+    for si in ids {
+        let s = pair.server_conn_mut(server_ch).accept_stream().unwrap();
+        assert_eq!(si, s.stream_identifier);
+    }
+    assert!(pair.server_conn_mut(server_ch).accept_stream().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_accept_stream_id() -> Result<()> {
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    // Both streams go out in the same packet
+    for si in [7, 8] {
+        pair.client_conn_mut(client_ch)
+            .open_stream(si, PayloadProtocolIdentifier::Binary)?;
+        pair.client_stream(client_ch, si)?
+            .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    }
+    pair.drive();
+
+    let mut opened = vec![];
+    while let Some(event) = pair.server_conn_mut(server_ch).poll() {
+        if let Event::Stream(StreamEvent::OpenedWithPpi { id, .. }) = event {
+            opened.push(id);
+        }
+    }
+    assert_eq!(vec![7, 8], opened, "each stream should report its own id");
+
+    let server = pair.server_conn_mut(server_ch);
+    assert_eq!(Some(7), server.accept_stream_id());
+    assert_eq!(Some(8), server.accept_stream_id());
+    assert_eq!(None, server.accept_stream_id());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_stream_opened_ppi() -> Result<()> {
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    // The default PPID of a stream is not what its first chunk carries
+    for (si, ppi) in [
+        (1, PayloadProtocolIdentifier::Dcep),
+        (2, PayloadProtocolIdentifier::String),
+    ] {
+        pair.client_conn_mut(client_ch)
+            .open_stream(si, PayloadProtocolIdentifier::Binary)?;
+        pair.client_stream(client_ch, si)?.write_sctp(&msg, ppi)?;
+    }
+    pair.drive();
+
+    let mut opened = vec![];
+    let mut n_deprecated = 0;
+    while let Some(event) = pair.server_conn_mut(server_ch).poll() {
+        match event {
+            Event::Stream(StreamEvent::OpenedWithPpi { id, ppi }) => opened.push((id, ppi)),
+            #[allow(deprecated)]
+            Event::Stream(StreamEvent::Opened) => n_deprecated += 1,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        vec![
+            (1, PayloadProtocolIdentifier::Dcep),
+            (2, PayloadProtocolIdentifier::String)
+        ],
+        opened,
+        "each stream should report the PPID of its first chunk"
+    );
+    assert_eq!(
+        2, n_deprecated,
+        "the deprecated event should still be emitted"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_rtt() -> Result<()> {
+    let si: u16 = 1;
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+    pair.latency = Duration::from_millis(100);
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    let n = pair
+        .client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(msg.len(), n);
+    pair.drive();
+
+    let client = pair.client_conn_mut(client_ch);
+    let (rtt, rto) = (client.rtt(), client.rto());
+    assert!(
+        rtt >= Duration::from_millis(200),
+        "rtt should cover the round trip, got {:?}",
+        rtt
+    );
+    assert!(rtt < rto, "rtt {:?} should be below rto {:?}", rtt, rto);
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_path_rtt() -> Result<()> {
+    let si: u16 = 1;
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, _server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+    pair.latency = Duration::from_millis(100);
+
+    let path_rtt = pair.client_conn_mut(client_ch).get_path_rtt();
+    assert_eq!(
+        0, path_rtt.samples_taken,
+        "no RTT measured during handshake"
+    );
+    assert_eq!(None, path_rtt.last_measured);
+    assert_eq!(Duration::ZERO, path_rtt.srtt);
+
+    pair.client_conn_mut(client_ch)
+        .open_stream(si, PayloadProtocolIdentifier::Binary)?;
+    for i in 1..=3u64 {
+        pair.client_stream(client_ch, si)?
+            .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+        pair.drive();
+
+        let client = pair.client_conn_mut(client_ch);
+        let path_rtt = client.get_path_rtt();
+        assert_eq!(i, path_rtt.samples_taken, "one sample per round trip");
+        assert!(path_rtt.last_measured.is_some());
+        assert!(
+            path_rtt.srtt >= Duration::from_millis(200),
+            "srtt should cover the round trip, got {:?}",
+            path_rtt.srtt
+        );
+        assert_eq!(client.rtt(), path_rtt.srtt);
+        assert_eq!(client.rto(), path_rtt.rto);
+    }
+
+    let path_rtt = pair.client_conn_mut(client_ch).get_path_rtt();
+    assert!(path_rtt.rttvar > Duration::ZERO);
+    assert!(path_rtt.srtt < path_rtt.rto);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_cwnd_grows_during_single_message() -> Result<()> {
+    let si: u16 = 1;
+    let msg = Bytes::from(vec![0u8; 60000]);
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    let initial_cwnd = pair.client_conn_mut(client_ch).congestion.cwnd;
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    pair.drive();
+
+    let client = pair.client_conn_mut(client_ch);
+    assert_eq!(0, client.bytes_pending());
+    assert!(
+        client.congestion.cwnd > initial_cwnd,
+        "cwnd should grow while the message is sent: {} -> {}",
+        initial_cwnd,
+        client.congestion.cwnd
+    );
+
+    let chunks = pair.server_stream(server_ch, si)?.read_sctp()?.unwrap();
+    assert_eq!(msg.len(), chunks.len());
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_negotiated_extensions() -> Result<()> {
+    let mut pair = Pair::default();
+    let (client_ch, server_ch) = pair.connect();
+
+    let client = *pair.client_conn_mut(client_ch).negotiated_extensions();
+    let server = *pair.server_conn_mut(server_ch).negotiated_extensions();
+    for extensions in [client, server] {
+        assert!(extensions.has(SctpExtension::ForwardTsn));
+        assert!(extensions.has(SctpExtension::ReConfig));
+        assert!(!extensions.has(SctpExtension::IData));
+        assert!(!extensions.has(SctpExtension::Authentication));
+    }
+
+    Ok(())
+}
+
+fn checksums(outbound: &VecDeque<Transmit>) -> Vec<u32> {
+    outbound
+        .iter()
+        .filter_map(|t| match &t.payload {
+            Payload::RawEncode(contents) => Some(contents),
+            _ => None,
+        })
+        .flatten()
+        .map(|raw| u32::from_le_bytes(raw[8..12].try_into().unwrap()))
+        .collect()
+}
+
+/// Connects a client and a server with the given zero checksum settings, sends a
+/// message from the client and returns the checksums of the DATA packets
+fn zero_checksum_exchange(client_zero: bool, server_zero: bool) -> Result<Vec<u32>> {
+    let si: u16 = 1;
+    let mut pair = Pair::new(
+        Arc::new(EndpointConfig::default()),
+        ServerConfig {
+            transport: Arc::new(TransportConfig::default().with_zero_checksum(server_zero)),
+            ..Default::default()
+        },
+    );
+
+    let client_ch = pair.begin_connect(ClientConfig {
+        transport: Arc::new(TransportConfig::default().with_zero_checksum(client_zero)),
+    });
+    pair.client.drive(pair.time, pair.server.addr);
+    assert!(
+        checksums(&pair.client.outbound).iter().all(|c| *c != 0),
+        "INIT must carry a checksum"
+    );
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+    pair.finish_connect(client_ch, server_ch);
+
+    let negotiated = client_zero && server_zero;
+    assert_eq!(negotiated, pair.client_conn_mut(client_ch).zero_checksum);
+    assert_eq!(negotiated, pair.server_conn_mut(server_ch).zero_checksum);
+
+    pair.client_conn_mut(client_ch)
+        .open_stream(si, PayloadProtocolIdentifier::Binary)?;
+    pair.client_stream(client_ch, si)?.write_sctp(
+        &Bytes::from_static(b"hello"),
+        PayloadProtocolIdentifier::Binary,
+    )?;
+    pair.client.drive(pair.time, pair.server.addr);
+    let data_checksums = checksums(&pair.client.outbound);
+    assert!(!data_checksums.is_empty());
+    pair.drive();
+
+    let message = pair.server_conn_mut(server_ch).read_message();
+    assert_eq!(
+        Some(Bytes::from_static(b"hello")),
+        message.map(|m| m.data),
+        "the message should be delivered"
+    );
+
+    Ok(data_checksums)
+}
+
+#[test]
+fn test_assoc_zero_checksum_both_enabled() -> Result<()> {
+    let data_checksums = zero_checksum_exchange(true, true)?;
+    assert!(data_checksums.iter().all(|c| *c == 0));
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_zero_checksum_one_side_enabled() -> Result<()> {
+    for (client_zero, server_zero) in [(true, false), (false, true)] {
+        let data_checksums = zero_checksum_exchange(client_zero, server_zero)?;
+        assert!(data_checksums.iter().all(|c| *c != 0));
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "hmac-cookie")]
+#[test]
+fn test_endpoint_stateless_init_flood() -> Result<()> {
+    let mut server = Endpoint::new(
+        Default::default(),
+        Some(Arc::new(server_config().with_stateless_cookies(true))),
+    );
+    let remote = SocketAddr::from_str("127.0.0.1:5000").unwrap();
+    let now = Instant::now();
+
+    for tag in 1..=5000u32 {
+        let init = Packet {
+            common_header: CommonHeader {
+                source_port: 5000,
+                destination_port: 5000,
+                verification_tag: 0,
+            },
+            chunks: vec![Box::new(ChunkInit {
+                initiate_tag: tag,
+                initial_tsn: tag,
+                num_outbound_streams: 1,
+                num_inbound_streams: 1,
+                advertised_receiver_window_credit: 1500,
+                ..Default::default()
+            })],
+        };
+        assert!(server
+            .handle(now, remote, None, None, init.marshal()?)
+            .is_none());
+    }
+
+    assert_eq!(0, server.associations.len());
+    assert!(server.association_ids.is_empty());
+    let init_acks: Vec<Transmit> = iter::from_fn(|| server.poll_transmit()).collect();
+    assert_eq!(5000, init_acks.len());
+    for t in &init_acks {
+        if let Payload::RawEncode(raws) = &t.payload {
+            let p = Packet::unmarshal(&raws[0])?;
+            assert!(p.chunks[0].as_any().downcast_ref::<ChunkInit>().is_some());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "hmac-cookie")]
+#[test]
+fn test_endpoint_association_created_on_cookie_echo() -> Result<()> {
+    let mut pair = Pair::new(
+        Default::default(),
+        server_config().with_stateless_cookies(true),
+    );
+    let client_ch = pair.begin_connect(client_config());
+
+    // The INIT is answered without an association
+    pair.drive_client();
+    pair.drive_server();
+    assert_eq!(0, pair.server.endpoint.associations.len());
+    assert!(pair.server.accepted.is_none());
+
+    // The COOKIE ECHO sets it up
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+    pair.finish_connect(client_ch, server_ch);
+    assert_eq!(1, pair.server.endpoint.associations.len());
+    assert_eq!(
+        AssociationState::Established,
+        pair.server_conn_mut(server_ch).state()
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "hmac-cookie")]
+#[test]
+fn test_endpoint_stale_cookie_echo_refused() -> Result<()> {
+    let mut pair = Pair::new(
+        Default::default(),
+        server_config().with_stateless_cookies(true),
+    );
+    pair.begin_connect(client_config());
+    pair.drive_client();
+    pair.drive_server();
+
+    // The cookie lifetime is checked against the time passed in by the caller
+    pair.time += Duration::from_secs(61);
+    pair.drive_client();
+    pair.drive_server();
+    assert_eq!(0, pair.server.endpoint.associations.len());
+    assert!(pair.server.accepted.is_none());
+
+    Ok(())
+}
+
+fn out_of_resource_aborts(outbound: &VecDeque<Transmit>) -> usize {
+    outbound
+        .iter()
+        .filter(|t| match &t.payload {
+            Payload::RawEncode(raws) => {
+                let p = Packet::unmarshal(&raws[0]).unwrap();
+                p.chunks[0]
+                    .as_any()
+                    .downcast_ref::<ChunkAbort>()
+                    .is_some_and(|abort| {
+                        abort.typed_error_causes() == vec![TypedErrorCause::OutOfResource]
+                    })
+            }
+            _ => false,
+        })
+        .count()
+}
+
+#[test]
+fn test_endpoint_max_associations() -> Result<()> {
+    let mut pair = Pair::new(Default::default(), server_config().with_max_associations(1));
+    let (client_ch, server_ch) = pair.connect();
+
+    // The second association is aborted right away
+    let refused_ch = pair.begin_connect(client_config());
+    pair.drive_client();
+    pair.server.drive(pair.time, pair.client.addr);
+    assert_eq!(1, out_of_resource_aborts(&pair.server.outbound));
+    pair.drive();
+    assert!(pair.server.accepted.is_none());
+    assert_eq!(1, pair.server.endpoint.associations.len());
+    assert_eq!(
+        AssociationState::Closed,
+        pair.client_conn_mut(refused_ch).state()
+    );
+
+    // The first one is unaffected
+    establish_session_pair(&mut pair, client_ch, server_ch, 1)?;
+    assert_eq!(
+        AssociationState::Established,
+        pair.server_conn_mut(server_ch).state()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_endpoint_accept_filter_drops_silently() -> Result<()> {
+    let config = server_config()
+        .with_accept_filter(|remote| remote.port() == 0)
+        .with_abort_refused(false);
+    let mut pair = Pair::new(Default::default(), config);
+
+    let client_ch = pair.begin_connect(client_config());
+    pair.drive_client();
+    pair.server.drive(pair.time, pair.client.addr);
+    assert!(pair.server.outbound.is_empty());
+    assert!(pair.server.accepted.is_none());
+    assert_eq!(
+        AssociationState::CookieWait,
+        pair.client_conn_mut(client_ch).state()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_endpoint_max_incoming_handshakes() -> Result<()> {
+    let mut pair = Pair::new(
+        Default::default(),
+        server_config().with_max_incoming_handshakes(1),
+    );
+
+    // Only one INIT is taken while neither handshake has completed
+    let client_chs = [
+        pair.begin_connect(client_config()),
+        pair.begin_connect(client_config()),
+    ];
+    pair.drive_client();
+    pair.server.drive(pair.time, pair.client.addr);
+    assert_eq!(1, pair.server.endpoint.incoming_handshakes);
+    assert_eq!(1, out_of_resource_aborts(&pair.server.outbound));
+    pair.server.assert_accept();
+
+    pair.drive();
+    let mut states: Vec<AssociationState> = client_chs
+        .iter()
+        .map(|ch| pair.client_conn_mut(*ch).state())
+        .collect();
+    states.sort_by_key(|state| *state as u8);
+    assert_eq!(
+        vec![AssociationState::Closed, AssociationState::Established],
+        states
+    );
+
+    // Completing the handshake frees the slot. The refused association lingered
+    // meanwhile, so the clock is brought back to the one `connect` starts the
+    // INIT timer with.
+    assert_eq!(0, pair.server.endpoint.incoming_handshakes);
+    pair.time = Instant::now();
+    let third_ch = pair.begin_connect(client_config());
+    pair.drive();
+    let server_ch = pair.server.assert_accept();
+    pair.finish_connect(third_ch, server_ch);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_non_default_ports() -> Result<()> {
+    let si: u16 = 1;
+
+    let server_config = ServerConfig {
+        transport: Arc::new(TransportConfig::default().with_source_port(5002)),
+        ..Default::default()
+    };
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), server_config);
+    let (client_ch, server_ch) = pair.connect_with(ClientConfig {
+        transport: Arc::new(
+            TransportConfig::default()
+                .with_source_port(5001)
+                .with_destination_port(5002),
+        ),
+    });
+
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    let client = pair.client_conn_mut(client_ch);
+    assert_eq!(5001, client.source_port, "client source port");
+    assert_eq!(5002, client.destination_port, "client destination port");
+    let server = pair.server_conn_mut(server_ch);
+    assert_eq!(5002, server.source_port, "server source port");
+    assert_eq!(5001, server.destination_port, "server destination port");
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_endpoint_aborts_init_for_other_port() -> Result<()> {
+    let mut pair = Pair::new(Default::default(), server_config());
+    let client_ch = pair.begin_connect(ClientConfig {
+        transport: Arc::new(TransportConfig::default().with_destination_port(5002)),
+    });
+    pair.drive_client();
+    pair.server.drive(pair.time, pair.client.addr);
+    assert!(pair.server.accepted.is_none());
+
+    // The ABORT comes from the port the INIT was sent to
+    assert_eq!(1, pair.server.outbound.len());
+    let Payload::RawEncode(raws) = &pair.server.outbound[0].payload else {
+        panic!("expected an encoded packet");
+    };
+    let p = Packet::unmarshal(&raws[0])?;
+    assert_eq!(5002, p.common_header.source_port);
+    assert_eq!(DEFAULT_SCTP_PORT, p.common_header.destination_port);
+    let abort = p.chunks[0].as_any().downcast_ref::<ChunkAbort>();
+    assert!(abort.is_some_and(|abort| !abort.verification_tag_reflected));
+
+    pair.drive();
+    assert_eq!(
+        AssociationState::Closed,
+        pair.client_conn_mut(client_ch).state()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_packet_for_other_port_is_out_of_the_blue() -> Result<()> {
+    let si: u16 = 1;
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    let client = pair.client_conn_mut(client_ch);
+    let chunk = ChunkHeartbeat {
+        params: vec![Box::new(ParamHeartbeatInfo {
+            heartbeat_information: Bytes::from_static(b"x"),
+        })],
+    };
+    let packet = Packet {
+        common_header: CommonHeader {
+            source_port: DEFAULT_SCTP_PORT,
+            destination_port: 5002,
+            verification_tag: client.peer_verification_tag(),
+        },
+        chunks: vec![Box::new(chunk)],
+    };
+    pair.server
+        .inbound
+        .push_back((pair.time, None, packet.marshal()?));
+    pair.server.drive(pair.time, pair.client.addr);
+
+    // The server answers with an ABORT without tearing down its association
+    assert_eq!(1, pair.server.outbound.len());
+    let Payload::RawEncode(raws) = &pair.server.outbound[0].payload else {
+        panic!("expected an encoded packet");
+    };
+    let p = Packet::unmarshal(&raws[0])?;
+    assert_eq!(5002, p.common_header.source_port);
+    let abort = p.chunks[0].as_any().downcast_ref::<ChunkAbort>();
+    assert!(abort.is_some_and(|abort| abort.verification_tag_reflected));
+    assert_eq!(
+        AssociationState::Established,
+        pair.server_conn_mut(server_ch).state()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_endpoint_route_by_verification_tag_after_rebinding() -> Result<()> {
+    let si: u16 = 1;
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    // The client's NAT maps it to a new port
+    let rebound = SocketAddr::new(pair.client.addr.ip(), pair.client.addr.port() + 1000);
+    let msg = Bytes::from_static(b"after rebinding");
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    pair.drive_client();
+    pair.server.drive(pair.time, rebound);
+
+    assert!(pair.server.accepted.is_none(), "no new association");
+    assert_eq!(1, pair.server.endpoint.associations.len());
+    assert_eq!(
+        Some(msg),
+        pair.server_stream(server_ch, si)?.read_message()?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_graceful_shutdown_delivers_queued_data() -> Result<()> {
+    let si: u16 = 1;
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    // More than cwnd lets out at once is still queued when shutting down
+    let msg = Bytes::from(vec![7u8; 20_000]);
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    pair.client_conn_mut(client_ch).shutdown()?;
+    assert_eq!(
+        Err(Error::ErrAssociationShuttingDown),
+        pair.client_stream(client_ch, si)?
+            .write_sctp(&msg, PayloadProtocolIdentifier::Binary)
+            .map(|_| ())
+    );
+    pair.drive_client();
+    assert_eq!(
+        AssociationState::ShutdownPending,
+        pair.client_conn_mut(client_ch).state()
+    );
+    assert_eq!(1, pair.client.endpoint.associations.len());
+
+    // The server reads the message before its streams go away with the association
+    let mut received = None;
+    while received.is_none() && pair.step() {
+        received = pair.server_stream(server_ch, si)?.read_message()?;
+    }
+    assert_eq!(Some(msg), received);
+
+    pair.drive();
+    for side in [Side::Client, Side::Server] {
+        let conn = match side {
+            Side::Client => pair.client_conn_mut(client_ch),
+            Side::Server => pair.server_conn_mut(server_ch),
+        };
+        assert_eq!(AssociationState::Closed, conn.state(), "{side}");
+        let mut events = std::iter::from_fn(|| conn.poll());
+        assert!(
+            events.any(|e| matches!(e, Event::ShutdownComplete)),
+            "{side} reports the shutdown"
+        );
+    }
+    assert!(pair.client.endpoint.associations.is_empty());
+    assert!(pair.server.endpoint.associations.is_empty());
+
+    // Nothing is sent once drained
+    let later = pair.time + Duration::from_secs(60);
+    for side in [Side::Client, Side::Server] {
+        let conn = match side {
+            Side::Client => pair.client_conn_mut(client_ch),
+            Side::Server => pair.server_conn_mut(server_ch),
+        };
+        conn.handle_timeout(later);
+        assert!(conn.poll_transmit(later).is_none());
+        assert!(conn.poll_endpoint_event().is_none());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_peer_address_migration() -> Result<()> {
+    let si: u16 = 1;
+    let mut pair = Pair::new(Default::default(), server_config().with_migration(true));
+    let (client_ch, server_ch) = pair.connect();
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+    let old = pair.client.addr;
+    while pair.server_conn_mut(server_ch).poll().is_some() {}
+    pair.server_conn_mut(server_ch).congestion.cwnd = 65535;
+
+    // The client's NAT maps it to a new port
+    let new = SocketAddr::new(old.ip(), old.port() + 1000);
+    pair.client.addr = new;
+    let msg = Bytes::from_static(b"after rebinding");
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    pair.drive_client();
+    pair.server.drive(pair.time, new);
+
+    // Replies keep going to the old address until the new one is validated
+    let outbound = &pair.server.outbound;
+    assert_eq!(new, outbound[0].remote, "HEARTBEAT to the new address");
+    assert!(outbound.iter().skip(1).all(|t| t.remote == old));
+    assert_eq!(old, pair.server_conn_mut(server_ch).remote_addr());
+
+    pair.drive();
+    let server = pair.server_conn_mut(server_ch);
+    assert_eq!(new, server.remote_addr());
+    let changes: Vec<(SocketAddr, SocketAddr)> = iter::from_fn(|| server.poll())
+        .filter_map(|e| match e {
+            Event::PeerAddressChanged { old, new } => Some((old, new)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(vec![(old, new)], changes);
+    assert!(
+        server.congestion.cwnd < 65535,
+        "cwnd should restart from its initial value"
+    );
+    assert_eq!(
+        Some(msg),
+        pair.server_stream(server_ch, si)?.read_message()?
+    );
+
+    // Traffic flows both ways on the new path
+    let reply = Bytes::from_static(b"reply");
+    pair.server_stream(server_ch, si)?
+        .write_sctp(&reply, PayloadProtocolIdentifier::Binary)?;
+    pair.drive();
+    assert_eq!(
+        Some(reply),
+        pair.client_stream(client_ch, si)?.read_message()?
+    );
+    assert_eq!(0, pair.client_conn_mut(client_ch).bytes_in_flight());
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_max_send_buffer_size() -> Result<()> {
+    let si: u16 = 1;
+    let msg = Bytes::from_static(b"ABCDEF");
+
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), ServerConfig::default());
+    let (client_ch, server_ch) = pair.connect_with(ClientConfig {
+        transport: Arc::new(TransportConfig::default().with_max_send_buffer_size(10)),
+    });
+
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    let mut s = pair.client_stream(client_ch, si)?;
+    s.write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(
+        Err(Error::ErrSendBufferFull),
+        s.write_sctp(&msg, PayloadProtocolIdentifier::Binary)
+    );
+    s.write_sctp(&msg.slice(..4), PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(10, s.buffered_amount()?);
+
+    pair.drive();
+    assert_eq!(0, pair.client_stream(client_ch, si)?.buffered_amount()?);
+    let mut low = false;
+    while let Some(e) = pair.client_conn_mut(client_ch).poll() {
+        if let Event::Stream(StreamEvent::BufferedAmountLow { id }) = e {
+            low |= id == si;
+        }
+    }
+    assert!(
+        low,
+        "BufferedAmountLow should signal that writes can resume"
+    );
+
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_max_total_send_buffer_size() -> Result<()> {
+    let msg = Bytes::from(vec![0u8; 600]);
+
+    let mut pair = Pair::new(Arc::new(EndpointConfig::default()), ServerConfig::default());
+    let (client_ch, server_ch) = pair.connect_with(ClientConfig {
+        transport: Arc::new(TransportConfig::default().with_max_total_send_buffer_size(1000)),
+    });
+
+    establish_session_pair(&mut pair, client_ch, server_ch, 1)?;
+    pair.client_conn_mut(client_ch)
+        .open_stream(2, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(
+        1000,
+        pair.client_conn_mut(client_ch).send_buffer_available()
+    );
+
+    pair.client_stream(client_ch, 1)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(400, pair.client_conn_mut(client_ch).send_buffer_available());
+    for si in [1, 2] {
+        assert_eq!(
+            Err(Error::ErrSendBufferFull),
+            pair.client_stream(client_ch, si)?
+                .write_sctp(&msg, PayloadProtocolIdentifier::Binary),
+            "stream {si} should be blocked by the association send buffer"
+        );
+    }
+
+    pair.drive();
+    assert_eq!(
+        1000,
+        pair.client_conn_mut(client_ch).send_buffer_available()
+    );
+    let mut writable = vec![];
+    while let Some(e) = pair.client_conn_mut(client_ch).poll() {
+        if let Event::Stream(StreamEvent::Writable { id }) = e {
+            writable.push(id);
+        }
+    }
+    assert_eq!(vec![1, 2], writable);
+
+    for si in [1, 2] {
+        pair.client_stream(client_ch, si)?
+            .write_sctp(&msg.slice(..400), PayloadProtocolIdentifier::Binary)?;
+    }
+
+    close_association_pair(&mut pair, client_ch, server_ch, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_reliable_simple() -> Result<()> {
+    //let _guard = subscribe();
+
+    let si: u16 = 1;
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    {
+        let a = pair.client_conn_mut(client_ch);
+        assert_eq!(0, a.buffered_amount(), "incorrect bufferedAmount");
+    }
+
+    let n = pair
+        .client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(msg.len(), n, "unexpected length of received data");
+    {
+        let a = pair.client_conn_mut(client_ch);
+        assert_eq!(msg.len(), a.buffered_amount(), "incorrect bufferedAmount");
+    }
+
+    pair.drive();
+
+    let chunks = pair.server_stream(server_ch, si)?.read_sctp()?.unwrap();
+    let (n, ppi) = (chunks.len(), chunks.ppi);
+    assert_eq!(n, msg.len(), "unexpected length of received data");
+    assert_eq!(ppi, PayloadProtocolIdentifier::Binary, "unexpected ppi");
+
+    {
+        let q = &pair
+            .client_conn_mut(client_ch)
+            .streams
+            .get(&si)
+            .unwrap()
+            .reassembly_queue;
+        assert!(!q.is_readable(), "should no longer be readable");
+    }
+
+    {
+        let a = pair.client_conn_mut(client_ch);
+        assert_eq!(0, a.buffered_amount(), "incorrect bufferedAmount");
+    }
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_bytes_in_flight_and_pending() -> Result<()> {
+    let si: u16 = 1;
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    {
+        let a = pair.client_conn_mut(client_ch);
+        assert_eq!(msg.len(), a.bytes_pending(), "queued but not sent");
+        assert_eq!(0, a.bytes_in_flight());
+        assert_eq!(msg.len(), a.total_queued_bytes());
+    }
+
+    // Sent, but the SACK has not arrived yet
+    pair.drive_client();
+    {
+        let a = pair.client_conn_mut(client_ch);
+        assert_eq!(0, a.bytes_pending());
+        assert_eq!(msg.len(), a.bytes_in_flight(), "sent but not acked");
+        assert_eq!(msg.len(), a.total_queued_bytes());
+    }
+
+    pair.drive();
+    {
+        let a = pair.client_conn_mut(client_ch);
+        assert_eq!(0, a.bytes_pending());
+        assert_eq!(0, a.bytes_in_flight());
+        assert_eq!(0, a.total_queued_bytes());
+    }
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_stream_handle() -> Result<()> {
+    let si: u16 = 1;
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    let handle = pair
+        .client_conn_mut(client_ch)
+        .open_stream(si, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(si, handle.stream_identifier());
+
+    // The handle outlives borrows of the association
+    assert_eq!(
+        5,
+        handle.write(
+            pair.client_conn_mut(client_ch),
+            Bytes::from_static(b"hello")
+        )?
+    );
+    pair.drive();
+    assert_eq!(
+        Some(Bytes::from_static(b"hello")),
+        pair.server_conn_mut(server_ch)
+            .read_message()
+            .map(|m| m.data)
+    );
+
+    pair.server_stream(server_ch, si)?.write_sctp(
+        &Bytes::from_static(b"world"),
+        PayloadProtocolIdentifier::Binary,
+    )?;
+    pair.drive();
+    assert_eq!(
+        Some(Bytes::from_static(b"world")),
+        handle.read(pair.client_conn_mut(client_ch))
+    );
+    assert_eq!(None, handle.read(pair.client_conn_mut(client_ch)));
+    assert!(handle
+        .stream(pair.client_conn_mut(client_ch))?
+        .is_writable());
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_read_message_across_streams() -> Result<()> {
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    establish_session_pair(&mut pair, client_ch, server_ch, 1)?;
+
+    let large = Bytes::from(vec![0xAB; 3000]);
+    for si in [2, 3] {
+        pair.client_conn_mut(client_ch)
+            .open_stream(si, PayloadProtocolIdentifier::Binary)?;
+    }
+    pair.client_stream(client_ch, 1)?.write_sctp(
+        &Bytes::from_static(b"one"),
+        PayloadProtocolIdentifier::String,
+    )?;
+    pair.client_stream(client_ch, 2)?.write_unordered(&large)?;
+    pair.client_stream(client_ch, 3)?.write_sctp(
+        &Bytes::from_static(b"three"),
+        PayloadProtocolIdentifier::Binary,
+    )?;
+    pair.drive();
+
+    let a = pair.server_conn_mut(server_ch);
+    let mut messages = vec![];
+    while let Some(m) = a.read_message() {
+        messages.push(m);
+    }
+    messages.sort_by_key(|m| m.stream_id);
+
+    assert_eq!(
+        vec![
+            IncomingMessage {
+                stream_id: 1,
+                ppi: PayloadProtocolIdentifier::String,
+                unordered: false,
+                data: Bytes::from_static(b"one"),
+            },
+            IncomingMessage {
+                stream_id: 2,
+                ppi: PayloadProtocolIdentifier::Binary,
+                unordered: true,
+                data: large,
+            },
+            IncomingMessage {
+                stream_id: 3,
+                ppi: PayloadProtocolIdentifier::Binary,
+                unordered: false,
+                data: Bytes::from_static(b"three"),
+            },
+        ],
+        messages
+    );
+
+    close_association_pair(&mut pair, client_ch, server_ch, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_read_message_and_drain_messages() -> Result<()> {
+    let si: u16 = 1;
+    let large = Bytes::from(vec![0xAB; 2000]);
+    let msgs = [Bytes::from_static(b"ABC"), large, Bytes::from_static(b"DE")];
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    for msg in &msgs {
+        pair.client_stream(client_ch, si)?
+            .write_sctp(msg, PayloadProtocolIdentifier::Binary)?;
+    }
+    pair.drive();
+
+    let mut s = pair.server_stream(server_ch, si)?;
+    assert_eq!(Some(msgs[0].clone()), s.read_message()?);
+
+    // The fragmented message fits the budget, the one after it does not
+    let mut out = vec![];
+    assert_eq!(2000, s.drain_messages(&mut out, 2001)?);
+    assert_eq!(vec![msgs[1].clone()], out);
+
+    out.clear();
+    assert_eq!(2, s.drain_messages(&mut out, usize::MAX)?);
+    assert_eq!(vec![msgs[2].clone()], out);
+    assert_eq!(None, s.read_message()?);
+
+    let a = pair.server_conn_mut(server_ch);
+    assert_eq!(
+        crate::config::INITIAL_RECV_BUF_SIZE,
+        a.get_my_receiver_window_credit(),
+        "reads should release the receive window"
+    );
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_stream_sequence_numbers() -> Result<()> {
+    let si: u16 = 1;
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
 
-    if chunks.ppi != PayloadProtocolIdentifier::Dcep {
-        return Err(Error::Other("unexpected ppi".to_owned()));
-    }
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
 
-    if buf[..n] != hello_msg {
-        return Err(Error::Other("received data mismatch".to_owned()));
+    let si2 = si + 1;
+    pair.client_conn_mut(client_ch)
+        .open_stream(si2, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(
+        Some(0),
+        pair.client_conn_mut(client_ch)
+            .get_stream_send_sequence_number(si2)
+    );
+
+    for _ in 0..10 {
+        pair.client_stream(client_ch, si2)?
+            .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
     }
     pair.drive();
+    while pair.server_stream(server_ch, si2)?.read_sctp()?.is_some() {}
 
-    Ok(())
-}
+    let client = pair.client_conn_mut(client_ch);
+    assert_eq!(Some(10), client.get_stream_send_sequence_number(si2));
+    assert_eq!(None, client.get_stream_send_sequence_number(si2 + 1));
+    assert_eq!(
+        Some((ReliabilityType::Reliable, 0)),
+        client.get_stream_reliability(si2)
+    );
 
-fn close_association_pair(
-    _pair: &mut Pair,
-    _client_ch: AssociationHandle,
-    _server_ch: AssociationHandle,
-    _si: u16,
-) {
-    /*TODO:
-    // Close client
-    tokio::spawn(async move {
-        client.close().await?;
-        let _ = handshake0ch_tx.send(()).await;
-        let _ = closed_rx0.recv().await;
+    let server = pair.server_conn_mut(server_ch);
+    assert_eq!(Some(10), server.get_stream_recv_sequence_number(si2));
+    assert_eq!(None, server.get_stream_recv_sequence_number(si2 + 1));
 
-        Result::<()>::Ok(())
-    });
+    pair.client_stream(client_ch, si2)?.set_reliability_params(
+        false,
+        ReliabilityType::Rexmit,
+        3,
+    )?;
+    assert_eq!(
+        Some((ReliabilityType::Rexmit, 3)),
+        pair.client_conn_mut(client_ch).get_stream_reliability(si2)
+    );
 
-    // Close server
-    tokio::spawn(async move {
-        server.close().await?;
-        let _ = handshake1ch_tx.send(()).await;
-        let _ = closed_rx1.recv().await;
+    close_association_pair(&mut pair, client_ch, server_ch, si);
 
-        Result::<()>::Ok(())
-    });
-    */
+    Ok(())
 }
 
 #[test]
-fn test_assoc_reliable_simple() -> Result<()> {
-    //let _guard = subscribe();
-
+fn test_assoc_stream_stats() -> Result<()> {
     let si: u16 = 1;
-    let msg: Bytes = Bytes::from_static(b"ABC");
+    let sbuf = Bytes::from(vec![0u8; 1000]);
 
     let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
 
     establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+    let base = pair.client_stream(client_ch, si)?.stats()?;
 
-    {
-        let a = pair.client_conn_mut(client_ch);
-        assert_eq!(0, a.buffered_amount(), "incorrect bufferedAmount");
-    }
-
-    let n = pair
-        .client_stream(client_ch, si)?
-        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
-    assert_eq!(msg.len(), n, "unexpected length of received data");
-    {
-        let a = pair.client_conn_mut(client_ch);
-        assert_eq!(msg.len(), a.buffered_amount(), "incorrect bufferedAmount");
-    }
-
+    // The first message is lost and retransmitted
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&sbuf, PayloadProtocolIdentifier::Binary)?;
+    pair.drive_client();
+    pair.server.inbound.clear();
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&sbuf, PayloadProtocolIdentifier::Binary)?;
+    assert_eq!(
+        2000,
+        pair.client_stream(client_ch, si)?
+            .stats()?
+            .get_buffered_amount()
+    );
     pair.drive();
 
-    let chunks = pair.server_stream(server_ch, si)?.read_sctp()?.unwrap();
-    let (n, ppi) = (chunks.len(), chunks.ppi);
-    assert_eq!(n, msg.len(), "unexpected length of received data");
-    assert_eq!(ppi, PayloadProtocolIdentifier::Binary, "unexpected ppi");
+    let stats = pair.client_stream(client_ch, si)?.stats()?;
+    assert_eq!(
+        base.get_num_messages_sent() + 2,
+        stats.get_num_messages_sent()
+    );
+    assert_eq!(base.get_num_bytes_sent() + 2000, stats.get_num_bytes_sent());
+    assert_eq!(1000, stats.get_num_bytes_retransmitted());
+    assert_eq!(0, stats.get_num_messages_abandoned());
+    assert_eq!(0, stats.get_buffered_amount());
+
+    let stats = pair.server_stream(server_ch, si)?.stats()?;
+    assert_eq!(3, stats.get_num_messages_received());
+    assert_eq!(2005, stats.get_num_bytes_received());
+    assert_eq!(2000, stats.get_reassembly_queue_bytes());
+    while pair.server_stream(server_ch, si)?.read_sctp()?.is_some() {}
+    assert_eq!(
+        0,
+        pair.server_stream(server_ch, si)?
+            .stats()?
+            .get_reassembly_queue_bytes()
+    );
 
-    {
-        let q = &pair
-            .client_conn_mut(client_ch)
-            .streams
-            .get(&si)
-            .unwrap()
-            .reassembly_queue;
-        assert!(!q.is_readable(), "should no longer be readable");
-    }
+    // With zero retransmissions allowed, every message is abandoned once sent
+    pair.client_stream(client_ch, si)?
+        .set_reliability_params(false, ReliabilityType::Rexmit, 1)?;
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&sbuf, PayloadProtocolIdentifier::Binary)?;
+    pair.drive_client();
+    pair.server.inbound.clear();
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&sbuf, PayloadProtocolIdentifier::Binary)?;
+    pair.drive();
 
-    {
-        let a = pair.client_conn_mut(client_ch);
-        assert_eq!(0, a.buffered_amount(), "incorrect bufferedAmount");
-    }
+    let stats = pair.client_stream(client_ch, si)?.stats()?;
+    assert_eq!(2, stats.get_num_messages_abandoned());
+    assert_eq!(1000, stats.get_num_bytes_retransmitted());
 
     close_association_pair(&mut pair, client_ch, server_ch, si);
 
@@ -799,6 +2097,60 @@ fn test_assoc_reliable_unordered_ordered() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_assoc_send_with_options_unordered_on_ordered_stream() -> Result<()> {
+    let si: u16 = 6;
+    let mut sbuf = vec![0u8; 1000];
+    for (i, b) in sbuf.iter_mut().enumerate() {
+        *b = (i & 0xff) as u8;
+    }
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    let opts = SendOptions {
+        unordered: true,
+        ppi: PayloadProtocolIdentifier::Binary,
+        deadline: None,
+    };
+
+    sbuf[0..4].copy_from_slice(&0u32.to_be_bytes());
+    pair.client_conn_mut(client_ch)
+        .send_with_options(si, Bytes::from(sbuf.clone()), opts)?;
+    pair.client.drive(pair.time, pair.server.addr);
+    pair.client.delay_outbound(); // Delay it
+
+    sbuf[0..4].copy_from_slice(&1u32.to_be_bytes());
+    pair.client_conn_mut(client_ch)
+        .send_with_options(si, Bytes::from(sbuf.clone()), opts)?;
+    pair.client.drive(pair.time, pair.server.addr);
+    pair.client.finish_delay(); // Reorder it
+
+    pair.drive();
+
+    let mut buf = vec![0u8; 2000];
+
+    // Unordered messages are delivered in arrival order even though the
+    // stream itself is ordered
+    for expected in [1u32, 0u32] {
+        let chunks = pair.server_stream(server_ch, si)?.read_sctp()?.unwrap();
+        let (n, ppi) = (chunks.len(), chunks.ppi);
+        chunks.read(&mut buf)?;
+        assert_eq!(n, sbuf.len(), "unexpected length of received data");
+        assert_eq!(ppi, PayloadProtocolIdentifier::Binary, "unexpected ppi");
+        assert_eq!(
+            u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]),
+            expected,
+            "unexpected received data"
+        );
+    }
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
 #[test]
 fn test_assoc_reliable_retransmission() -> Result<()> {
     //let _guard = subscribe();
@@ -937,12 +2289,12 @@ fn test_assoc_unreliable_rexmit_ordered_no_fragment() -> Result<()> {
 
     establish_session_pair(&mut pair, client_ch, server_ch, si)?;
 
-    // When we set the reliability value to 0 [times], then it will cause
+    // When we set the reliability value to 1 [times], then it will cause
     // the chunk to be abandoned immediately after the first transmission.
     pair.client_stream(client_ch, si)?
-        .set_reliability_params(false, ReliabilityType::Rexmit, 0)?;
+        .set_reliability_params(false, ReliabilityType::Rexmit, 1)?;
     pair.server_stream(server_ch, si)?
-        .set_reliability_params(false, ReliabilityType::Rexmit, 0)?; // doesn't matter
+        .set_reliability_params(false, ReliabilityType::Rexmit, 1)?; // doesn't matter
 
     //br.drop_next_nwrites(0, 1).await; // drop the first packet (second one should be sacked)
 
@@ -1017,12 +2369,12 @@ fn test_assoc_unreliable_rexmit_ordered_fragment() -> Result<()> {
         let a = pair.client_conn_mut(client_ch);
         a.rto_mgr.set_rto(100, true);
     }
-    // When we set the reliability value to 0 [times], then it will cause
+    // When we set the reliability value to 1 [times], then it will cause
     // the chunk to be abandoned immediately after the first transmission.
     pair.client_stream(client_ch, si)?
-        .set_reliability_params(false, ReliabilityType::Rexmit, 0)?;
+        .set_reliability_params(false, ReliabilityType::Rexmit, 1)?;
     pair.server_stream(server_ch, si)?
-        .set_reliability_params(false, ReliabilityType::Rexmit, 0)?; // doesn't matter
+        .set_reliability_params(false, ReliabilityType::Rexmit, 1)?; // doesn't matter
 
     //br.drop_next_nwrites(0, 1).await; // drop the first packet (second one should be sacked)
 
@@ -1091,12 +2443,12 @@ fn test_assoc_unreliable_rexmit_unordered_no_fragment() -> Result<()> {
 
     establish_session_pair(&mut pair, client_ch, server_ch, si)?;
 
-    // When we set the reliability value to 0 [times], then it will cause
+    // When we set the reliability value to 1 [times], then it will cause
     // the chunk to be abandoned immediately after the first transmission.
     pair.client_stream(client_ch, si)?
-        .set_reliability_params(true, ReliabilityType::Rexmit, 0)?;
+        .set_reliability_params(true, ReliabilityType::Rexmit, 1)?;
     pair.server_stream(server_ch, si)?
-        .set_reliability_params(true, ReliabilityType::Rexmit, 0)?; // doesn't matter
+        .set_reliability_params(true, ReliabilityType::Rexmit, 1)?; // doesn't matter
 
     //br.drop_next_nwrites(0, 1).await; // drop the first packet (second one should be sacked)
 
@@ -1165,12 +2517,12 @@ fn test_assoc_unreliable_rexmit_unordered_fragment() -> Result<()> {
 
     establish_session_pair(&mut pair, client_ch, server_ch, si)?;
 
-    // When we set the reliability value to 0 [times], then it will cause
+    // When we set the reliability value to 1 [times], then it will cause
     // the chunk to be abandoned immediately after the first transmission.
     pair.client_stream(client_ch, si)?
-        .set_reliability_params(true, ReliabilityType::Rexmit, 0)?;
+        .set_reliability_params(true, ReliabilityType::Rexmit, 1)?;
     pair.server_stream(server_ch, si)?
-        .set_reliability_params(true, ReliabilityType::Rexmit, 0)?; // doesn't matter
+        .set_reliability_params(true, ReliabilityType::Rexmit, 1)?; // doesn't matter
 
     sbuf[0..4].copy_from_slice(&0u32.to_be_bytes());
     let n = pair.client_stream(client_ch, si)?.write_sctp(
@@ -1247,12 +2599,12 @@ fn test_assoc_unreliable_rexmit_timed_ordered() -> Result<()> {
 
     establish_session_pair(&mut pair, client_ch, server_ch, si)?;
 
-    // When we set the reliability value to 0 [times], then it will cause
-    // the chunk to be abandoned immediately after the first transmission.
+    // When we set the reliability value to 1 [msec], then the chunk will be
+    // abandoned when it is retransmitted.
     pair.client_stream(client_ch, si)?
-        .set_reliability_params(false, ReliabilityType::Timed, 0)?;
+        .set_reliability_params(false, ReliabilityType::Timed, 1)?;
     pair.server_stream(server_ch, si)?
-        .set_reliability_params(false, ReliabilityType::Timed, 0)?; // doesn't matter
+        .set_reliability_params(false, ReliabilityType::Timed, 1)?; // doesn't matter
 
     //br.drop_next_nwrites(0, 1).await; // drop the first packet (second one should be sacked)
 
@@ -1265,6 +2617,11 @@ fn test_assoc_unreliable_rexmit_timed_ordered() -> Result<()> {
     pair.client.drive(pair.time, pair.server.addr);
     pair.client.outbound.clear();
 
+    // The retransmission is lost as well
+    pair.time = pair.client.next_wakeup().unwrap();
+    pair.client.drive(pair.time, pair.server.addr);
+    pair.client.outbound.clear();
+
     sbuf[0..4].copy_from_slice(&1u32.to_be_bytes());
     let n = pair.client_stream(client_ch, si)?.write_sctp(
         &Bytes::from(sbuf.clone()),
@@ -1321,12 +2678,12 @@ fn test_assoc_unreliable_rexmit_timed_unordered() -> Result<()> {
 
     establish_session_pair(&mut pair, client_ch, server_ch, si)?;
 
-    // When we set the reliability value to 0 [times], then it will cause
-    // the chunk to be abandoned immediately after the first transmission.
+    // When we set the reliability value to 1 [msec], then the chunk will be
+    // abandoned when it is retransmitted.
     pair.client_stream(client_ch, si)?
-        .set_reliability_params(true, ReliabilityType::Timed, 0)?;
+        .set_reliability_params(true, ReliabilityType::Timed, 1)?;
     pair.server_stream(server_ch, si)?
-        .set_reliability_params(true, ReliabilityType::Timed, 0)?; // doesn't matter
+        .set_reliability_params(true, ReliabilityType::Timed, 1)?; // doesn't matter
 
     //br.drop_next_nwrites(0, 1).await; // drop the first packet (second one should be sacked)
 
@@ -1339,6 +2696,11 @@ fn test_assoc_unreliable_rexmit_timed_unordered() -> Result<()> {
     pair.client.drive(pair.time, pair.server.addr);
     pair.client.outbound.clear();
 
+    // The retransmission is lost as well
+    pair.time = pair.client.next_wakeup().unwrap();
+    pair.client.drive(pair.time, pair.server.addr);
+    pair.client.outbound.clear();
+
     sbuf[0..4].copy_from_slice(&1u32.to_be_bytes());
     let n = pair.client_stream(client_ch, si)?.write_sctp(
         &Bytes::from(sbuf.clone()),
@@ -1471,7 +2833,7 @@ fn test_assoc_congestion_control_fast_retransmission() -> Result<()> {
 
     {
         let a = pair.client_conn_mut(client_ch);
-        assert!(!a.in_fast_recovery, "should not be in fast-recovery");
+        assert!(!a.congestion.in_fast_recovery, "should not be in fast-recovery");
         debug!("nSACKs      : {}", a.stats.get_num_sacks());
         debug!("nFastRetrans: {}", a.stats.get_num_fast_retrans());
 
@@ -1492,20 +2854,34 @@ fn test_assoc_congestion_control_fast_retransmission() -> Result<()> {
 fn test_assoc_congestion_control_congestion_avoidance() -> Result<()> {
     //let _guard = subscribe();
 
-    let max_receive_buffer_size: u32 = 64 * 1024;
+    let max_receive_buffer_size: u32 = 256 * 1024;
     let si: u16 = 6;
     let n_packets_to_send: u32 = 2000;
 
-    let mut sbuf = vec![0u8; 1000];
-    for (i, b) in sbuf.iter_mut().enumerate() {
-        *b = (i & 0xff) as u8;
-    }
-
     let (mut pair, client_ch, server_ch) =
         create_association_pair(AckMode::Normal, max_receive_buffer_size)?;
+    // All messages are queued up front
+    pair.client_conn_mut(client_ch).max_total_send_buffer_size = u32::MAX;
 
     establish_session_pair(&mut pair, client_ch, server_ch, si)?;
 
+    // cwnd only grows in congestion avoidance when the flight fills it. Messages
+    // of half an MTU keep cwnd a multiple of their size, as it grows by whole
+    // messages in slow start and by one MTU in congestion avoidance.
+    let msg_len = pair.client_conn_mut(client_ch).mtu / 2;
+    let mut sbuf = vec![0u8; msg_len as usize];
+    for (i, b) in sbuf.iter_mut().enumerate() {
+        *b = (i & 0xff) as u8;
+    }
+    // Below the receive window, so that cwnd outgrows ssthresh before the
+    // window caps the flight
+    let ssthresh: u32 = 16 * 1024;
+    {
+        let a = pair.client_conn_mut(client_ch);
+        a.congestion.cwnd = 8 * msg_len;
+        a.congestion.ssthresh = ssthresh;
+    }
+
     {
         pair.client_conn_mut(client_ch).stats.reset();
         pair.server_conn_mut(server_ch).stats.reset();
@@ -1526,6 +2902,9 @@ fn test_assoc_congestion_control_congestion_avoidance() -> Result<()> {
 
     // Repeat calling br.Tick() until the buffered amount becomes 0
     let mut n_packets_received = 0u32;
+    let mut n_cwnd_increases = 0u32;
+    let mut cwnd = pair.client_conn_mut(client_ch).congestion.cwnd;
+    let mut partial_bytes_acked = 0;
     while pair.client_conn_mut(client_ch).buffered_amount() > 0
         && n_packets_received < n_packets_to_send
     {
@@ -1540,6 +2919,27 @@ fn test_assoc_congestion_control_congestion_avoidance() -> Result<()> {
 
         pair.step();
 
+        // In congestion avoidance, acknowledged bytes accumulate in
+        // partial_bytes_acked, and cwnd grows by one MTU each time they reach it
+        let a = pair.client_conn_mut(client_ch);
+        if cwnd > a.congestion.ssthresh {
+            if a.congestion.cwnd == cwnd {
+                assert!(
+                    a.congestion.partial_bytes_acked >= partial_bytes_acked,
+                    "partial_bytes_acked should accumulate"
+                );
+            } else {
+                assert_eq!(
+                    cwnd + a.mtu,
+                    a.congestion.cwnd,
+                    "cwnd should grow by one MTU"
+                );
+                n_cwnd_increases += 1;
+            }
+        }
+        cwnd = a.congestion.cwnd;
+        partial_bytes_acked = a.congestion.partial_bytes_acked;
+
         while let Some(chunks) = pair.server_stream(server_ch, si)?.read_sctp()? {
             let (n, ppi) = (chunks.len(), chunks.ppi);
             chunks.read(&mut rbuf)?;
@@ -1568,16 +2968,21 @@ fn test_assoc_congestion_control_congestion_avoidance() -> Result<()> {
     {
         let a = pair.client_conn_mut(client_ch);
 
-        assert!(!a.in_fast_recovery, "should not be in fast-recovery");
         assert!(
-            a.cwnd > a.ssthresh,
+            !a.congestion.in_fast_recovery,
+            "should not be in fast-recovery"
+        );
+        assert!(
+            a.congestion.cwnd > a.congestion.ssthresh,
             "should be in congestion avoidance mode"
         );
         assert!(
-            a.ssthresh >= max_receive_buffer_size,
-            "{} should not be less than the initial size of 128KB {}",
-            a.ssthresh,
-            max_receive_buffer_size
+            n_cwnd_increases > 0,
+            "cwnd should have grown in congestion avoidance"
+        );
+        assert_eq!(
+            ssthresh, a.congestion.ssthresh,
+            "ssthresh should be unchanged without losses"
         );
 
         debug!("nSACKs      : {}", a.stats.get_num_sacks());
@@ -1666,7 +3071,7 @@ fn test_assoc_congestion_control_slow_reader() -> Result<()> {
             let rwnd = pair
                 .server_conn_mut(server_ch)
                 .get_my_receiver_window_credit();
-            let cwnd = pair.client_conn_mut(client_ch).cwnd;
+            let cwnd = pair.client_conn_mut(client_ch).congestion.cwnd;
             let cmtu = pair.client_conn_mut(client_ch).mtu;
             if cwnd > cmtu || rwnd > 0 {
                 // Do not read until a1.getMyReceiverWindowCredit() becomes zero
@@ -1874,6 +3279,59 @@ fn test_assoc_reset_close_one_way() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_assoc_stream_status() -> Result<()> {
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+    establish_session_pair(&mut pair, client_ch, server_ch, 1)?;
+    establish_session_pair(&mut pair, client_ch, server_ch, 2)?;
+
+    for side in [Side::Client, Side::Server] {
+        let a = match side {
+            Side::Client => pair.client_conn_mut(client_ch),
+            Side::Server => pair.server_conn_mut(server_ch),
+        };
+        assert_eq!(2, a.stream_count());
+        let mut ids: Vec<StreamId> = a.streams_iter().collect();
+        ids.sort();
+        assert_eq!(vec![1, 2], ids);
+        for si in ids {
+            assert_eq!(StreamStatus::Open, a.stream(si)?.state());
+        }
+    }
+
+    // Half-closing a stream keeps it around
+    pair.client_stream(client_ch, 2)?.finish()?;
+    assert_eq!(
+        StreamStatus::ReadOnly,
+        pair.client_stream(client_ch, 2)?.state()
+    );
+    assert!(pair
+        .client_stream(client_ch, 2)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)
+        .is_err());
+
+    // Stopping it resets the stream at the peer, which forgets it
+    pair.client_stream(client_ch, 1)?.stop()?;
+    assert_eq!(
+        StreamStatus::Closed,
+        pair.client_stream(client_ch, 1)?.state()
+    );
+    pair.drive();
+    let server = pair.server_conn_mut(server_ch);
+    assert_eq!(vec![2], server.streams_iter().collect::<Vec<_>>());
+    assert!(server.stream(1).is_err());
+
+    // Closing the association unregisters all streams
+    pair.client_conn_mut(client_ch).shutdown()?;
+    pair.drive();
+    assert_eq!(0, pair.client_conn_mut(client_ch).stream_count());
+    assert_eq!(0, pair.server_conn_mut(server_ch).stream_count());
+
+    Ok(())
+}
+
 #[test]
 fn test_assoc_reset_close_both_ways() -> Result<()> {
     //let _guard = subscribe();
@@ -1970,6 +3428,7 @@ fn test_assoc_abort() -> Result<()> {
 
     let transmit = {
         let abort = ChunkAbort {
+            verification_tag_reflected: false,
             error_causes: vec![ErrorCauseProtocolViolation {
                 code: PROTOCOL_VIOLATION,
                 ..Default::default()
@@ -2020,6 +3479,129 @@ fn test_assoc_abort() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_assoc_recoverable_inbound_errors() -> Result<()> {
+    //let _guard = subscribe();
+
+    let si: u16 = 1;
+    let msg: Bytes = Bytes::from_static(b"ABC");
+
+    let (mut pair, client_ch, server_ch) = create_association_pair(AckMode::NoDelay, 0)?;
+
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    let unknown_param = Bytes::from_static(&[0x80, 0x0E, 0x00, 0x04]);
+    let packets: Vec<Vec<Box<dyn Chunk + Send + Sync>>> = vec![
+        // Unknown chunk type, silently skipped
+        vec![Box::new(ChunkUnknown {
+            typ: ChunkType(0x3f),
+            flags: 0,
+            value: Bytes::from_static(&[0x1, 0x2, 0x3]),
+        })],
+        // ERROR chunks are informational
+        vec![Box::new(ChunkError {
+            error_causes: vec![TypedErrorCause::OutOfResource.into()],
+        })],
+        // RECONFIG with a parameter we cannot handle, dropped
+        vec![Box::new(ChunkReconfig {
+            param_a: Some(build_param(&unknown_param)?),
+            param_b: None,
+        })],
+    ];
+
+    for chunks in packets {
+        let packet = pair
+            .client_conn_mut(client_ch)
+            .create_packet(chunks)
+            .marshal()?;
+        pair.client.outbound.push_back(Transmit {
+            now: pair.time,
+            remote: pair.server.addr,
+            ecn: None,
+            local_ip: None,
+            payload: Payload::RawEncode(vec![packet]),
+        });
+        pair.drive();
+
+        assert_eq!(
+            AssociationState::Established,
+            pair.server_conn_mut(server_ch).state()
+        );
+    }
+    assert_eq!(
+        1,
+        pair.server_conn_mut(server_ch)
+            .stats()
+            .get_num_discarded_packets()
+    );
+
+    // Data still flows in both directions
+    pair.client_stream(client_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    pair.drive();
+    let chunks = pair.server_stream(server_ch, si)?.read_sctp()?.unwrap();
+    assert_eq!(msg.len(), chunks.len());
+
+    pair.server_stream(server_ch, si)?
+        .write_sctp(&msg, PayloadProtocolIdentifier::Binary)?;
+    pair.drive();
+    let chunks = pair.client_stream(client_ch, si)?.read_sctp()?.unwrap();
+    assert_eq!(msg.len(), chunks.len());
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
+#[test]
+fn test_assoc_add_outgoing_streams() -> Result<()> {
+    //let _guard = subscribe();
+
+    let transport = Arc::new(
+        TransportConfig::default()
+            .with_max_num_outbound_streams(2)
+            .with_max_num_inbound_streams(2),
+    );
+    let mut pair = Pair::new(
+        Arc::new(EndpointConfig::default()),
+        ServerConfig {
+            transport: transport.clone(),
+            ..Default::default()
+        },
+    );
+    let (client_ch, server_ch) = pair.connect_with(ClientConfig { transport });
+    assert_eq!(2, pair.client_conn_mut(client_ch).max_outbound_streams());
+
+    let si: u16 = 11;
+    assert_eq!(
+        Err(Error::ErrStreamIdExceedsLimit),
+        pair.client_conn_mut(client_ch)
+            .open_stream(si, PayloadProtocolIdentifier::Binary)
+            .map(|_| ())
+    );
+
+    pair.client_conn_mut(client_ch)
+        .request_add_outgoing_streams(10)?;
+    pair.drive();
+
+    let mut added = vec![];
+    while let Some(event) = pair.client_conn_mut(client_ch).poll() {
+        if let Event::Stream(StreamEvent::OutgoingStreamsAdded { new_max }) = event {
+            added.push(new_max);
+        }
+    }
+    assert_eq!(vec![12], added);
+    assert_eq!(12, pair.client_conn_mut(client_ch).max_outbound_streams());
+    assert_eq!(12, pair.server_conn_mut(server_ch).max_inbound_streams());
+
+    // The new stream identifiers can be used
+    establish_session_pair(&mut pair, client_ch, server_ch, si)?;
+
+    close_association_pair(&mut pair, client_ch, server_ch, si);
+
+    Ok(())
+}
+
 #[test]
 fn test_association_handle_packet_before_init() -> Result<()> {
     //let _guard = subscribe();
@@ -2175,7 +3757,7 @@ fn test_association_handle_packet_before_init() -> Result<()> {
                     destination_port: 1,
                     verification_tag: 0,
                 },
-                chunks: vec![Box::new(ChunkShutdownComplete)],
+                chunks: vec![Box::<ChunkShutdownComplete>::default()],
             },
         ),
     ];
@@ -11,9 +11,13 @@ use std::{
 };
 
 use crate::association::Association;
-use crate::chunk::chunk_type::CT_INIT;
+use crate::chunk::chunk_abort::ChunkAbort;
+use crate::chunk::error_cause::TypedErrorCause;
+use crate::chunk::ErrorCause;
 use crate::config::{ClientConfig, EndpointConfig, ServerConfig, TransportConfig};
-use crate::packet::PartialDecode;
+use crate::packet::{CommonHeader, Packet, PartialDecode};
+#[cfg(feature = "hmac-cookie")]
+use crate::param::param_state_cookie::{ParamStateCookie, COOKIE_LIFETIME};
 use crate::shared::{
     AssociationEvent, AssociationEventInner, AssociationId, EndpointEvent, EndpointEventInner,
 };
@@ -52,6 +56,8 @@ pub struct Endpoint {
     ///
     /// Equivalent to a `ServerConfig.accept_buffer` of `0`, but can be changed after the endpoint is constructed.
     reject_new_associations: bool,
+    /// Number of incoming associations waiting for their COOKIE ECHO
+    incoming_handshakes: usize,
 }
 
 impl fmt::Debug for Endpoint {
@@ -65,6 +71,7 @@ impl fmt::Debug for Endpoint {
             .field("config", &self.config)
             .field("server_config", &self.server_config)
             .field("reject_new_associations", &self.reject_new_associations)
+            .field("incoming_handshakes", &self.incoming_handshakes)
             .finish()
     }
 }
@@ -82,6 +89,7 @@ impl Endpoint {
             associations: Slab::new(),
             local_cid_generator: (config.aid_generator_factory.as_ref())(),
             reject_new_associations: false,
+            incoming_handshakes: 0,
             config,
             server_config,
         }
@@ -113,12 +121,27 @@ impl Endpoint {
                 for cid in conn.loc_cids.values() {
                     self.association_ids.remove(cid);
                 }
+                if conn.handshaking {
+                    self.incoming_handshakes -= 1;
+                }
+            }
+            EndpointEventInner::HandshakeCompleted => {
+                if let Some(conn) = self.associations.get_mut(ch.0) {
+                    if conn.handshaking {
+                        conn.handshaking = false;
+                        self.incoming_handshakes -= 1;
+                    }
+                }
             }
         }
         None
     }
 
     /// Process an incoming UDP datagram
+    ///
+    /// Datagrams are matched to associations by their verification tag, whatever
+    /// address they come from, so an association keeps receiving after the peer's
+    /// address changed, e.g. when its NAT rebinds.
     pub fn handle(
         &mut self,
         now: Instant,
@@ -127,7 +150,7 @@ impl Endpoint {
         ecn: Option<EcnCodepoint>,
         data: Bytes,
     ) -> Option<(AssociationHandle, DatagramEvent)> {
-        let partial_decode = match PartialDecode::unmarshal(&data) {
+        let partial_decode = match PartialDecode::unmarshal_unverified_zero_checksum(&data) {
             Ok(x) => x,
             Err(err) => {
                 trace!("malformed header: {}", err);
@@ -142,8 +165,7 @@ impl Endpoint {
         let known_ch = if dst_cid > 0 {
             self.association_ids.get(&dst_cid).cloned()
         } else {
-            //TODO: improve INIT handling for DoS attack
-            if partial_decode.first_chunk_type == CT_INIT {
+            if partial_decode.is_init() {
                 if let Some(dst_cid) = partial_decode.initiate_tag {
                     self.association_ids.get(&dst_cid).cloned()
                 } else {
@@ -221,25 +243,83 @@ impl Endpoint {
         ecn: Option<EcnCodepoint>,
         partial_decode: PartialDecode,
     ) -> Option<(AssociationHandle, Association)> {
-        if partial_decode.first_chunk_type != CT_INIT
-            || (partial_decode.first_chunk_type == CT_INIT && partial_decode.initiate_tag.is_none())
+        #[cfg(feature = "hmac-cookie")]
+        if partial_decode.is_cookie_echo()
+            && self
+                .server_config
+                .as_ref()
+                .is_some_and(|c| c.stateless_cookies)
+        {
+            return self.handle_cookie_echo(now, remote, local_ip, ecn, partial_decode);
+        }
+
+        if !partial_decode.is_init()
+            || (partial_decode.is_init() && partial_decode.initiate_tag.is_none())
         {
             debug!("refusing first packet with Non-INIT or emtpy initial_tag INIT");
             return None;
         }
 
-        let server_config = self.server_config.as_ref().unwrap();
+        if !partial_decode.verify_checksum() {
+            debug!("refusing INIT without a valid checksum");
+            return None;
+        }
+
+        let server_config = self.server_config.as_ref().unwrap().clone();
+        let initiate_tag = partial_decode.initiate_tag.unwrap_or_default();
+
+        // RFC 9260 Sec 8.4: an INIT for a port we do not listen on is out of
+        // the blue, and answered with an ABORT
+        let local_port = server_config.transport.source_port();
+        if partial_decode.common_header.destination_port != local_port {
+            debug!(
+                "aborting INIT for port {} (listening on {})",
+                partial_decode.common_header.destination_port, local_port
+            );
+            self.send_abort(
+                now,
+                remote,
+                local_ip,
+                &partial_decode.common_header,
+                initiate_tag,
+                vec![],
+            );
+            return None;
+        }
 
-        if self.associations.len() >= server_config.concurrent_associations as usize
-            || self.reject_new_associations
-            || self.is_full()
+        if !self.can_accept(&server_config, remote)
+            || self.incoming_handshakes >= server_config.max_incoming_handshakes as usize
         {
-            debug!("refusing association");
-            //TODO: self.initial_close();
+            debug!("refusing association from {}", remote);
+            self.refuse(
+                now,
+                remote,
+                local_ip,
+                &partial_decode.common_header,
+                initiate_tag,
+                &server_config,
+            );
             return None;
         }
 
-        let server_config = server_config.clone();
+        #[cfg(feature = "hmac-cookie")]
+        if server_config.stateless_cookies {
+            self.handle_stateless_init(now, remote, local_ip, partial_decode, server_config);
+            return None;
+        }
+
+        self.handle_init(now, remote, local_ip, ecn, partial_decode, server_config)
+    }
+
+    fn handle_init(
+        &mut self,
+        now: Instant,
+        remote: SocketAddr,
+        local_ip: Option<IpAddr>,
+        ecn: Option<EcnCodepoint>,
+        partial_decode: PartialDecode,
+        server_config: Arc<ServerConfig>,
+    ) -> Option<(AssociationHandle, Association)> {
         let transport_config = server_config.transport.clone();
 
         let remote_aid = *partial_decode.initiate_tag.as_ref().unwrap();
@@ -254,6 +334,116 @@ impl Endpoint {
             Some(server_config),
             transport_config,
         );
+        self.associations[ch.0].handshaking = true;
+        self.incoming_handshakes += 1;
+
+        conn.handle_event(AssociationEvent(AssociationEventInner::Datagram(
+            Transmit {
+                now,
+                remote,
+                ecn,
+                payload: Payload::PartialDecode(partial_decode),
+                local_ip,
+            },
+        )));
+
+        Some((ch, conn))
+    }
+
+    /// Answers an INIT with an INIT ACK whose signed cookie carries the state
+    /// of the association, which is only created once the cookie is echoed back.
+    /// An INIT flood thus costs no memory.
+    #[cfg(feature = "hmac-cookie")]
+    fn handle_stateless_init(
+        &mut self,
+        now: Instant,
+        remote: SocketAddr,
+        local_ip: Option<IpAddr>,
+        partial_decode: PartialDecode,
+        server_config: Arc<ServerConfig>,
+    ) {
+        let local_aid = self.new_aid();
+        let init_ack = partial_decode
+            .finish()
+            .and_then(|p| Association::stateless_init_ack(&server_config, &p, local_aid, now))
+            .and_then(|p| p.marshal());
+        match init_ack {
+            Ok(raw) => self.transmits.push_back(Transmit {
+                now,
+                remote,
+                ecn: None,
+                payload: Payload::RawEncode(vec![raw]),
+                local_ip,
+            }),
+            Err(err) => debug!("refusing INIT: {}", err),
+        }
+    }
+
+    /// Sets up the association of a COOKIE ECHO answering an INIT ACK sent by
+    /// `handle_stateless_init`
+    #[cfg(feature = "hmac-cookie")]
+    fn handle_cookie_echo(
+        &mut self,
+        now: Instant,
+        remote: SocketAddr,
+        local_ip: Option<IpAddr>,
+        ecn: Option<EcnCodepoint>,
+        partial_decode: PartialDecode,
+    ) -> Option<(AssociationHandle, Association)> {
+        let server_config = self.server_config.clone()?;
+        if !partial_decode.verify_checksum() {
+            debug!("refusing COOKIE ECHO without a valid checksum");
+            return None;
+        }
+
+        let cookie = partial_decode.cookie.clone()?;
+        let header = &partial_decode.common_header;
+        let Some(params) = ParamStateCookie::decode_signed(
+            &cookie,
+            &server_config.cookie_secret,
+            server_config.cookie_clock.unix_time_ms(now),
+            Some(COOKIE_LIFETIME),
+        )
+        .filter(|params| {
+            header.verification_tag == params.my_verification_tag
+                && header.source_port == params.destination_port
+                && header.destination_port == params.source_port
+        }) else {
+            debug!("refusing COOKIE ECHO with an invalid or stale cookie");
+            return None;
+        };
+
+        if self
+            .association_ids
+            .contains_key(&params.my_verification_tag)
+        {
+            debug!("refusing COOKIE ECHO with a verification tag in use");
+            return None;
+        }
+        if !self.can_accept(&server_config, remote) {
+            debug!("refusing association from {}", remote);
+            self.refuse(
+                now,
+                remote,
+                local_ip,
+                &partial_decode.common_header,
+                params.peer_verification_tag,
+                &server_config,
+            );
+            return None;
+        }
+
+        let transport_config = server_config.transport.clone();
+        let (ch, mut conn) = self.add_association(
+            params.peer_verification_tag,
+            params.my_verification_tag,
+            remote,
+            local_ip,
+            now,
+            Some(server_config),
+            transport_config,
+        );
+        conn.restore_from_cookie(&params, cookie);
 
         conn.handle_event(AssociationEvent(AssociationEventInner::Datagram(
             Transmit {
@@ -294,6 +484,7 @@ impl Endpoint {
             cids_issued: 0,
             loc_cids: iter::once((0, local_aid)).collect(),
             initial_remote: remote_addr,
+            handshaking: false,
         });
 
         let ch = AssociationHandle(id);
@@ -312,6 +503,72 @@ impl Endpoint {
         &self.config
     }
 
+    /// Whether a new incoming association from `remote` can be set up
+    fn can_accept(&self, server_config: &ServerConfig, remote: SocketAddr) -> bool {
+        self.associations.len() < server_config.max_associations as usize
+            && !self.reject_new_associations
+            && !self.is_full()
+            && server_config.accept_filter(remote)
+    }
+
+    /// Answers the first packet of a refused association with an ABORT carrying
+    /// an Out of Resource cause, unless the server config drops it silently
+    fn refuse(
+        &mut self,
+        now: Instant,
+        remote: SocketAddr,
+        local_ip: Option<IpAddr>,
+        header: &CommonHeader,
+        peer_verification_tag: u32,
+        server_config: &ServerConfig,
+    ) {
+        if !server_config.abort_refused {
+            return;
+        }
+
+        self.send_abort(
+            now,
+            remote,
+            local_ip,
+            header,
+            peer_verification_tag,
+            vec![TypedErrorCause::OutOfResource.into()],
+        );
+    }
+
+    /// Answers the packet with `header` with an ABORT
+    fn send_abort(
+        &mut self,
+        now: Instant,
+        remote: SocketAddr,
+        local_ip: Option<IpAddr>,
+        header: &CommonHeader,
+        peer_verification_tag: u32,
+        error_causes: Vec<ErrorCause>,
+    ) {
+        let abort = Packet {
+            common_header: CommonHeader {
+                source_port: header.destination_port,
+                destination_port: header.source_port,
+                verification_tag: peer_verification_tag,
+            },
+            chunks: vec![Box::new(ChunkAbort {
+                error_causes,
+                ..Default::default()
+            })],
+        };
+        match abort.marshal() {
+            Ok(raw) => self.transmits.push_back(Transmit {
+                now,
+                remote,
+                ecn: None,
+                payload: Payload::RawEncode(vec![raw]),
+                local_ip,
+            }),
+            Err(err) => debug!("failed to serialize an ABORT: {}", err),
+        }
+    }
+
     /// Whether we've used up 3/4 of the available AID space
     fn is_full(&self) -> bool {
         (((u32::MAX >> 1) + (u32::MAX >> 2)) as usize) < self.association_ids.len()
@@ -329,6 +586,8 @@ pub(crate) struct AssociationMeta {
     /// Only needed to support associations with zero-length AIDs, which cannot migrate, so we don't
     /// bother keeping it up to date.
     initial_remote: SocketAddr,
+    /// Incoming association that has not received its COOKIE ECHO yet
+    handshaking: bool,
 }
 
 /// Internal identifier for an `Association` currently associated with an endpoint
@@ -1,3 +1,5 @@
+use crate::chunk::error_cause::TypedErrorCause;
+
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -37,6 +39,8 @@ pub enum Error {
     ErrChunkTypeNotHeartbeat,
     #[error("ChunkType is not of type HEARTBEATACK")]
     ErrChunkTypeNotHeartbeatAck,
+    #[error("ChunkType is not of type PAD")]
+    ErrChunkTypeNotPadding,
     #[error("heartbeat is not long enough to contain Heartbeat Info")]
     ErrHeartbeatNotLongEnoughInfo,
     #[error("failed to parse param type")]
@@ -78,6 +82,8 @@ pub enum Error {
     ErrSsnResetRequestParamTooShort,
     #[error("reconfig response parameter too short")]
     ErrReconfigRespParamTooShort,
+    #[error("add outgoing streams request parameter too short")]
+    ErrAddOutgoingStreamsRequestParamTooShort,
     #[error("invalid algorithm type")]
     ErrInvalidAlgorithmType,
 
@@ -127,6 +133,10 @@ pub enum Error {
     ErrChunkTypeNotSack,
     #[error("SACK Chunk size is not large enough to contain header")]
     ErrSackSizeNotLargeEnoughInfo,
+    #[error("SACK gap ack block has invalid offsets")]
+    ErrSackGapAckBlockInvalid,
+    #[error("SACK gap ack blocks are not in ascending order or overlap")]
+    ErrSackGapAckBlocksUnordered,
 
     #[error("invalid chunk size")]
     ErrInvalidChunkSize,
@@ -156,10 +166,12 @@ pub enum Error {
     #[error("try again")]
     ErrTryAgain,
 
-    #[error("abort chunk, with following errors: {0}")]
-    ErrAbortChunk(String),
+    #[error("abort chunk, with following errors: {}", format_error_causes(.0))]
+    ErrAbortChunk(Vec<TypedErrorCause>),
     #[error("shutdown called in non-Established state")]
     ErrShutdownNonEstablished,
+    #[error("association is shutting down")]
+    ErrAssociationShuttingDown,
     #[error("association closed before connecting")]
     ErrAssociationClosedBeforeConn,
     #[error("association init failed")]
@@ -194,12 +206,14 @@ pub enum Error {
     ErrTsnRequestNotExist,
     #[error("sending reset packet in non-Established state")]
     ErrResetPacketInStateNotExist,
+    #[error("adding streams in non-Established state")]
+    ErrAddStreamsNonEstablished,
     #[error("unexpected parameter type")]
     ErrParameterType,
     #[error("sending payload data in non-Established state")]
     ErrPayloadDataStateNotExist,
-    #[error("unhandled chunk type")]
-    ErrChunkTypeUnhandled,
+    #[error("unhandled chunk type {0:#04x}")]
+    ErrChunkTypeUnhandled(u8),
     #[error("handshake failed (INIT ACK)")]
     ErrHandshakeInitAck,
     #[error("handshake failed (COOKIE ECHO)")]
@@ -211,6 +225,16 @@ pub enum Error {
     ErrStreamClosed,
     #[error("Stream not existed")]
     ErrStreamNotExisted,
+    #[error("send buffer of the stream is full")]
+    ErrSendBufferFull,
+    #[error("stream identifier exceeds the negotiated number of streams")]
+    ErrStreamIdExceedsLimit,
+    #[error("partial reliability requires a reliability value greater than 0")]
+    ErrZeroReliabilityValue,
+    #[error("partial reliability requires the peer to support FORWARD TSN")]
+    ErrPartialReliabilityUnsupported,
+    #[error("reliability parameters cannot change while a message is partially sent")]
+    ErrReliabilityChangeMidMessage,
     #[error("Short buffer to be filled")]
     ErrShortBuffer,
     #[error("Io EOF")]
@@ -221,7 +245,23 @@ pub enum Error {
     ErrNetConnRead,
     #[error("Max Data Channel ID")]
     ErrMaxDataChannelID,
+    #[error("address is not a peer address of the association")]
+    ErrPeerAddrUnknown,
+    #[error("invalid peer address parameters")]
+    ErrPeerAddrParamsInvalid,
+    #[error("local ip address family does not match the peer address")]
+    ErrLocalIpFamilyMismatch,
+    #[error("mtu is smaller than 576 bytes")]
+    ErrMtuTooSmall,
 
     #[error("{0}")]
     Other(String),
 }
+
+fn format_error_causes(causes: &[TypedErrorCause]) -> String {
+    causes
+        .iter()
+        .map(|c| format!("({})", c))
+        .collect::<Vec<_>>()
+        .join("")
+}
@@ -0,0 +1,29 @@
+//! Entry points for the fuzz targets in `fuzz/`, only built with the `fuzz` feature
+
+use crate::error::Result;
+use crate::packet::{Packet, PartialDecode, PACKET_HEADER_SIZE};
+use crate::util::generate_packet_checksum;
+
+use bytes::Bytes;
+
+/// Decodes `raw` the way the endpoint does for routing, then decodes the chunks
+pub fn partial_decode(raw: &Bytes) -> Result<()> {
+    PartialDecode::unmarshal(raw)?.finish()?;
+    Ok(())
+}
+
+/// Decodes `raw` into a packet in one go
+pub fn unmarshal_packet(raw: &Bytes) -> Result<()> {
+    Packet::unmarshal(raw)?;
+    Ok(())
+}
+
+/// Writes the CRC32c checksum of `raw` into its common header, so arbitrary
+/// input gets past checksum validation
+pub fn set_packet_checksum(raw: &mut [u8]) {
+    if raw.len() < PACKET_HEADER_SIZE {
+        return;
+    }
+    let checksum = generate_packet_checksum(&Bytes::copy_from_slice(raw));
+    raw[8..12].copy_from_slice(&checksum.to_le_bytes());
+}
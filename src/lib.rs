@@ -26,19 +26,29 @@ use std::{
 
 mod association;
 pub use crate::association::{
-    stats::AssociationStats,
-    stream::{ReliabilityType, Stream, StreamEvent, StreamId, StreamState},
-    Association, AssociationError, Event,
+    extensions::{NegotiatedExtensions, SctpExtension},
+    state::{AckMode, AssociationStatus, HandshakeStep},
+    stats::{AssociationStats, PathRtt, StreamStats},
+    stream::{
+        IncomingMessage, ReliabilityType, SendOptions, Stream, StreamEvent, StreamHandle, StreamId,
+        StreamState, StreamStatus,
+    },
+    timer::Timer,
+    AbortCause, Association, AssociationError, Event, HandshakePhase,
 };
 
 pub(crate) mod chunk;
 pub use crate::chunk::{
     chunk_payload_data::{ChunkPayloadData, PayloadProtocolIdentifier},
+    chunk_type::ChunkType,
+    error_cause::TypedErrorCause,
     ErrorCauseCode,
 };
 
 mod config;
-pub use crate::config::{ClientConfig, EndpointConfig, ServerConfig, TransportConfig};
+pub use crate::config::{
+    ClientConfig, EndpointConfig, PeerAddrParams, ServerConfig, TransportConfig,
+};
 
 mod endpoint;
 pub use crate::endpoint::{AssociationHandle, ConnectError, DatagramEvent, Endpoint};
@@ -46,7 +56,11 @@ pub use crate::endpoint::{AssociationHandle, ConnectError, DatagramEvent, Endpoi
 mod error;
 pub use crate::error::Error;
 
+#[cfg(feature = "fuzz")]
+pub mod fuzzing;
+
 mod packet;
+pub use crate::packet::{CommonHeader, PartialDecode};
 
 mod shared;
 pub use crate::shared::{AssociationEvent, AssociationId, EcnCodepoint, EndpointEvent};
@@ -57,6 +71,7 @@ pub(crate) mod queue;
 pub use crate::queue::reassembly_queue::{Chunk, Chunks};
 
 pub(crate) mod util;
+pub use crate::util::{AssociationIdGenerator, RandomAssociationIdGenerator};
 
 /// Whether an endpoint was the initiator of an association
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
@@ -102,8 +117,6 @@ impl ops::Not for Side {
     }
 }
 
-use crate::packet::PartialDecode;
-
 /// Payload in Incoming/outgoing Transmit
 #[derive(Debug)]
 pub enum Payload {
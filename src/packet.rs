@@ -5,7 +5,9 @@ use crate::chunk::chunk_error::ChunkError;
 use crate::chunk::chunk_forward_tsn::ChunkForwardTsn;
 use crate::chunk::chunk_header::*;
 use crate::chunk::chunk_heartbeat::ChunkHeartbeat;
+use crate::chunk::chunk_heartbeat_ack::ChunkHeartbeatAck;
 use crate::chunk::chunk_init::ChunkInit;
+use crate::chunk::chunk_padding::ChunkPadding;
 use crate::chunk::chunk_payload_data::ChunkPayloadData;
 use crate::chunk::chunk_reconfig::ChunkReconfig;
 use crate::chunk::chunk_selective_ack::ChunkSelectiveAck;
@@ -13,6 +15,7 @@ use crate::chunk::chunk_shutdown::ChunkShutdown;
 use crate::chunk::chunk_shutdown_ack::ChunkShutdownAck;
 use crate::chunk::chunk_shutdown_complete::ChunkShutdownComplete;
 use crate::chunk::chunk_type::*;
+use crate::chunk::chunk_unknown::ChunkUnknown;
 use crate::chunk::Chunk;
 use crate::error::{Error, Result};
 use crate::util::*;
@@ -50,27 +53,51 @@ use std::fmt;
 ///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 ///|                           Checksum                            |
 ///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
-
 pub(crate) const PACKET_HEADER_SIZE: usize = 12;
 
-#[derive(Default, Debug)]
-pub(crate) struct CommonHeader {
-    pub(crate) source_port: u16,
-    pub(crate) destination_port: u16,
-    pub(crate) verification_tag: u32,
+/// CommonHeader is the header shared by all SCTP packets
+#[derive(Default, Debug, PartialEq, Eq)]
+pub struct CommonHeader {
+    /// SCTP port number of the sender
+    pub source_port: u16,
+    /// SCTP port number the packet is destined to
+    pub destination_port: u16,
+    /// Verification Tag identifying the association on the receiving side
+    pub verification_tag: u32,
 }
 
+/// PartialDecode is a packet whose common header and first chunk header were
+/// parsed, which is enough to route it to an association
 #[derive(Default, Debug)]
 pub struct PartialDecode {
-    pub(crate) common_header: CommonHeader,
+    /// Common header of the packet
+    pub common_header: CommonHeader,
     pub(crate) remaining: Bytes,
-    pub(crate) first_chunk_type: ChunkType,
-    pub(crate) initiate_tag: Option<u32>,
-    pub(crate) cookie: Option<Bytes>,
+    /// Type of the first chunk in the packet
+    pub first_chunk_type: ChunkType,
+    /// Initiate Tag of an INIT or INIT ACK chunk
+    pub initiate_tag: Option<u32>,
+    /// State cookie of a COOKIE ECHO chunk
+    pub cookie: Option<Bytes>,
+    /// The packet carried a zero checksum, which was not verified
+    pub(crate) zero_checksum: bool,
 }
 
 impl PartialDecode {
-    pub(crate) fn unmarshal(raw: &Bytes) -> Result<Self> {
+    /// Verifies the checksum of `raw` and parses its common header and the
+    /// header of its first chunk
+    pub fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let p = Self::unmarshal_unverified_zero_checksum(raw)?;
+        if !p.verify_checksum() {
+            return Err(Error::ErrChecksumMismatch);
+        }
+        Ok(p)
+    }
+
+    /// Like `unmarshal`, but leaves a zero checksum unverified: whether it is
+    /// acceptable depends on the association (RFC 9653), which checks it with
+    /// `verify_checksum` if not.
+    pub(crate) fn unmarshal_unverified_zero_checksum(raw: &Bytes) -> Result<Self> {
         if raw.len() < PACKET_HEADER_SIZE {
             return Err(Error::ErrPacketRawTooSmall);
         }
@@ -81,9 +108,8 @@ impl PartialDecode {
         let destination_port = reader.get_u16();
         let verification_tag = reader.get_u32();
         let their_checksum = reader.get_u32_le();
-        let our_checksum = generate_packet_checksum(raw);
-
-        if their_checksum != our_checksum {
+        let zero_checksum = their_checksum == 0;
+        if !zero_checksum && their_checksum != generate_packet_checksum(raw) {
             return Err(Error::ErrChecksumMismatch);
         }
 
@@ -98,6 +124,9 @@ impl PartialDecode {
         let mut cookie = None;
         match header.typ {
             CT_INIT | CT_INIT_ACK => {
+                if header.value_length() < 4 {
+                    return Err(Error::ErrChunkValueNotLongEnough);
+                }
                 initiate_tag = Some(reader.get_u32());
             }
             CT_COOKIE_ECHO => {
@@ -119,67 +148,135 @@ impl PartialDecode {
             first_chunk_type: header.typ,
             initiate_tag,
             cookie,
+            zero_checksum,
         })
     }
 
-    pub(crate) fn finish(self) -> Result<Packet> {
-        let mut chunks = vec![];
-        let mut offset = 0;
-        loop {
-            // Exact match, no more chunks
-            if offset == self.remaining.len() {
-                break;
-            } else if offset + CHUNK_HEADER_SIZE > self.remaining.len() {
-                return Err(Error::ErrParseSctpChunkNotEnoughData);
-            }
+    /// Cheaply tells whether a datagram is an SCTP packet, e.g. to demultiplex
+    /// SCTP from other protocols sharing a UDP flow: it must hold a common header
+    /// and its CRC32c checksum must match. Packets sent with a zero checksum
+    /// (RFC 9653) do not pass.
+    pub fn looks_like_sctp(raw: &Bytes) -> bool {
+        raw.len() >= PACKET_HEADER_SIZE
+            && (&raw[8..PACKET_HEADER_SIZE]).get_u32_le() == generate_packet_checksum(raw)
+    }
 
-            let ct = ChunkType(self.remaining[offset]);
-            let c: Box<dyn Chunk + Send + Sync> = match ct {
-                CT_INIT => Box::new(ChunkInit::unmarshal(&self.remaining.slice(offset..))?),
-                CT_INIT_ACK => Box::new(ChunkInit::unmarshal(&self.remaining.slice(offset..))?),
-                CT_ABORT => Box::new(ChunkAbort::unmarshal(&self.remaining.slice(offset..))?),
-                CT_COOKIE_ECHO => {
-                    Box::new(ChunkCookieEcho::unmarshal(&self.remaining.slice(offset..))?)
-                }
-                CT_COOKIE_ACK => {
-                    Box::new(ChunkCookieAck::unmarshal(&self.remaining.slice(offset..))?)
-                }
-                CT_HEARTBEAT => {
-                    Box::new(ChunkHeartbeat::unmarshal(&self.remaining.slice(offset..))?)
-                }
-                CT_PAYLOAD_DATA => Box::new(ChunkPayloadData::unmarshal(
-                    &self.remaining.slice(offset..),
-                )?),
-                CT_SACK => Box::new(ChunkSelectiveAck::unmarshal(
-                    &self.remaining.slice(offset..),
-                )?),
-                CT_RECONFIG => Box::new(ChunkReconfig::unmarshal(&self.remaining.slice(offset..))?),
-                CT_FORWARD_TSN => {
-                    Box::new(ChunkForwardTsn::unmarshal(&self.remaining.slice(offset..))?)
-                }
-                CT_ERROR => Box::new(ChunkError::unmarshal(&self.remaining.slice(offset..))?),
-                CT_SHUTDOWN => Box::new(ChunkShutdown::unmarshal(&self.remaining.slice(offset..))?),
-                CT_SHUTDOWN_ACK => Box::new(ChunkShutdownAck::unmarshal(
-                    &self.remaining.slice(offset..),
-                )?),
-                CT_SHUTDOWN_COMPLETE => Box::new(ChunkShutdownComplete::unmarshal(
-                    &self.remaining.slice(offset..),
-                )?),
-                _ => return Err(Error::ErrUnmarshalUnknownChunkType),
-            };
-
-            let chunk_value_padding = get_padding_size(c.value_length());
-            offset += CHUNK_HEADER_SIZE + c.value_length() + chunk_value_padding;
-            chunks.push(c);
+    /// Source port of a raw packet, read without parsing or verifying it
+    pub fn source_port(raw: &Bytes) -> Option<u16> {
+        (raw.len() >= PACKET_HEADER_SIZE).then(|| (&raw[0..2]).get_u16())
+    }
+
+    /// Destination port of a raw packet, read without parsing or verifying it
+    pub fn destination_port(raw: &Bytes) -> Option<u16> {
+        (raw.len() >= PACKET_HEADER_SIZE).then(|| (&raw[2..4]).get_u16())
+    }
+
+    /// Whether the packet carries a valid CRC32c checksum
+    pub(crate) fn verify_checksum(&self) -> bool {
+        if !self.zero_checksum {
+            return true;
         }
+        let mut crc = Crc32c::new();
+        crc.update(&self.common_header.source_port.to_be_bytes());
+        crc.update(&self.common_header.destination_port.to_be_bytes());
+        crc.update(&self.common_header.verification_tag.to_be_bytes());
+        crc.update(&FOUR_ZEROES[..]);
+        crc.update(&self.remaining);
+        crc.finalize() == 0
+    }
+
+    /// Whether the packet may be accepted without a checksum by an association
+    /// that agreed to zero checksums. Handshake packets never may.
+    pub(crate) fn is_zero_checksum_acceptable(&self) -> bool {
+        !is_handshake_chunk_type(self.first_chunk_type)
+    }
+
+    /// Whether the first chunk is an INIT, whose Initiate Tag is `initiate_tag`
+    pub fn is_init(&self) -> bool {
+        self.first_chunk_type == CT_INIT
+    }
+
+    /// Whether the first chunk is an INIT ACK, whose Initiate Tag is `initiate_tag`
+    pub fn is_init_ack(&self) -> bool {
+        self.first_chunk_type == CT_INIT_ACK
+    }
+
+    /// Whether the first chunk is a COOKIE ECHO, whose State Cookie is `cookie`
+    pub fn is_cookie_echo(&self) -> bool {
+        self.first_chunk_type == CT_COOKIE_ECHO
+    }
 
+    /// Parses the chunks of a packet whose checksum `unmarshal` already
+    /// verified, so that it is not computed again
+    pub(crate) fn finish(self) -> Result<Packet> {
         Ok(Packet {
+            chunks: unmarshal_chunks(&self.remaining)?,
             common_header: self.common_header,
-            chunks,
         })
     }
 }
 
+/// Parses the chunks following the common header
+fn unmarshal_chunks(raw: &Bytes) -> Result<Vec<Box<dyn Chunk + Send + Sync>>> {
+    let mut chunks = vec![];
+    let mut offset = 0;
+    loop {
+        // Exact match, no more chunks
+        if offset == raw.len() {
+            break;
+        } else if offset + CHUNK_HEADER_SIZE > raw.len() {
+            return Err(Error::ErrParseSctpChunkNotEnoughData);
+        }
+
+        let ct = ChunkType(raw[offset]);
+        let c: Box<dyn Chunk + Send + Sync> = match ct {
+            CT_INIT => Box::new(ChunkInit::unmarshal(&raw.slice(offset..))?),
+            CT_INIT_ACK => Box::new(ChunkInit::unmarshal(&raw.slice(offset..))?),
+            CT_ABORT => Box::new(ChunkAbort::unmarshal(&raw.slice(offset..))?),
+            CT_COOKIE_ECHO => Box::new(ChunkCookieEcho::unmarshal(&raw.slice(offset..))?),
+            CT_COOKIE_ACK => Box::new(ChunkCookieAck::unmarshal(&raw.slice(offset..))?),
+            CT_HEARTBEAT => Box::new(ChunkHeartbeat::unmarshal(&raw.slice(offset..))?),
+            CT_HEARTBEAT_ACK => Box::new(ChunkHeartbeatAck::unmarshal(&raw.slice(offset..))?),
+            CT_PAYLOAD_DATA => Box::new(ChunkPayloadData::unmarshal(&raw.slice(offset..))?),
+            CT_SACK => Box::new(ChunkSelectiveAck::unmarshal(&raw.slice(offset..))?),
+            CT_RECONFIG => Box::new(ChunkReconfig::unmarshal(&raw.slice(offset..))?),
+            CT_FORWARD_TSN => Box::new(ChunkForwardTsn::unmarshal(&raw.slice(offset..))?),
+            CT_ERROR => Box::new(ChunkError::unmarshal(&raw.slice(offset..))?),
+            CT_SHUTDOWN => Box::new(ChunkShutdown::unmarshal(&raw.slice(offset..))?),
+            CT_SHUTDOWN_ACK => Box::new(ChunkShutdownAck::unmarshal(&raw.slice(offset..))?),
+            CT_SHUTDOWN_COMPLETE => {
+                Box::new(ChunkShutdownComplete::unmarshal(&raw.slice(offset..))?)
+            }
+            CT_PAD => Box::new(ChunkPadding::unmarshal(&raw.slice(offset..))?),
+            _ => Box::new(ChunkUnknown::unmarshal(&raw.slice(offset..))?),
+        };
+
+        let chunk_value_padding = get_padding_size(c.value_length());
+        offset += CHUNK_HEADER_SIZE + c.value_length() + chunk_value_padding;
+        let stop = stops_processing(c.as_ref());
+        chunks.push(c);
+        if stop {
+            break;
+        }
+    }
+
+    Ok(chunks)
+}
+
+/// Whether `ct` is a handshake chunk type. Packets with these chunks always
+/// carry a real checksum, as the peer cannot know yet whether zero checksums
+/// were agreed upon (RFC 9653 Sec 5.2)
+fn is_handshake_chunk_type(ct: ChunkType) -> bool {
+    matches!(ct, CT_INIT | CT_INIT_ACK | CT_COOKIE_ECHO | CT_COOKIE_ACK)
+}
+
+/// An unknown chunk whose type says the rest of the packet must be discarded
+fn stops_processing(c: &(dyn Chunk + Send + Sync)) -> bool {
+    c.as_any()
+        .downcast_ref::<ChunkUnknown>()
+        .is_some_and(|c| c.should_stop_processing())
+}
+
 #[derive(Default, Debug)]
 pub(crate) struct Packet {
     pub(crate) common_header: CommonHeader,
@@ -224,41 +321,7 @@ impl Packet {
             return Err(Error::ErrChecksumMismatch);
         }
 
-        let mut chunks = vec![];
-        let mut offset = PACKET_HEADER_SIZE;
-        loop {
-            // Exact match, no more chunks
-            if offset == raw.len() {
-                break;
-            } else if offset + CHUNK_HEADER_SIZE > raw.len() {
-                return Err(Error::ErrParseSctpChunkNotEnoughData);
-            }
-
-            let ct = ChunkType(raw[offset]);
-            let c: Box<dyn Chunk + Send + Sync> = match ct {
-                CT_INIT => Box::new(ChunkInit::unmarshal(&raw.slice(offset..))?),
-                CT_INIT_ACK => Box::new(ChunkInit::unmarshal(&raw.slice(offset..))?),
-                CT_ABORT => Box::new(ChunkAbort::unmarshal(&raw.slice(offset..))?),
-                CT_COOKIE_ECHO => Box::new(ChunkCookieEcho::unmarshal(&raw.slice(offset..))?),
-                CT_COOKIE_ACK => Box::new(ChunkCookieAck::unmarshal(&raw.slice(offset..))?),
-                CT_HEARTBEAT => Box::new(ChunkHeartbeat::unmarshal(&raw.slice(offset..))?),
-                CT_PAYLOAD_DATA => Box::new(ChunkPayloadData::unmarshal(&raw.slice(offset..))?),
-                CT_SACK => Box::new(ChunkSelectiveAck::unmarshal(&raw.slice(offset..))?),
-                CT_RECONFIG => Box::new(ChunkReconfig::unmarshal(&raw.slice(offset..))?),
-                CT_FORWARD_TSN => Box::new(ChunkForwardTsn::unmarshal(&raw.slice(offset..))?),
-                CT_ERROR => Box::new(ChunkError::unmarshal(&raw.slice(offset..))?),
-                CT_SHUTDOWN => Box::new(ChunkShutdown::unmarshal(&raw.slice(offset..))?),
-                CT_SHUTDOWN_ACK => Box::new(ChunkShutdownAck::unmarshal(&raw.slice(offset..))?),
-                CT_SHUTDOWN_COMPLETE => {
-                    Box::new(ChunkShutdownComplete::unmarshal(&raw.slice(offset..))?)
-                }
-                _ => return Err(Error::ErrUnmarshalUnknownChunkType),
-            };
-
-            let chunk_value_padding = get_padding_size(c.value_length());
-            offset += CHUNK_HEADER_SIZE + c.value_length() + chunk_value_padding;
-            chunks.push(c);
-        }
+        let chunks = unmarshal_chunks(&raw.slice(PACKET_HEADER_SIZE..))?;
 
         Ok(Packet {
             common_header: CommonHeader {
@@ -270,7 +333,9 @@ impl Packet {
         })
     }
 
-    pub(crate) fn marshal_to(&self, writer: &mut BytesMut) -> Result<usize> {
+    /// Marshals the packet into `writer`. With `zero_checksum` the checksum is left
+    /// zero unless the packet carries a handshake chunk.
+    pub(crate) fn marshal_to(&self, writer: &mut BytesMut, zero_checksum: bool) -> Result<usize> {
         // Populate static headers
         // 8-12 is Checksum which will be populated when packet is complete
         writer.put_u16(self.common_header.source_port);
@@ -292,9 +357,18 @@ impl Packet {
             }
         }
 
-        let mut digest = ISCSI_CRC.digest();
-        digest.update(writer);
-        let checksum = digest.finalize();
+        if zero_checksum
+            && !self
+                .chunks
+                .iter()
+                .any(|c| is_handshake_chunk_type(c.header().typ))
+        {
+            return Ok(writer.len());
+        }
+
+        let mut crc = Crc32c::new();
+        crc.update(writer);
+        let checksum = crc.finalize();
 
         // Checksum is already in BigEndian
         // Using LittleEndian stops it from being flipped
@@ -305,10 +379,52 @@ impl Packet {
     }
 
     pub(crate) fn marshal(&self) -> Result<Bytes> {
-        let mut buf = BytesMut::with_capacity(PACKET_HEADER_SIZE);
-        self.marshal_to(&mut buf)?;
+        self.marshal_with_zero_checksum(false)
+    }
+
+    pub(crate) fn marshal_with_zero_checksum(&self, zero_checksum: bool) -> Result<Bytes> {
+        let mut buf = BytesMut::with_capacity(self.marshal_size());
+        self.marshal_to(&mut buf, zero_checksum)?;
         Ok(buf.freeze())
     }
+
+    /// Size of the marshalled packet, including chunk padding
+    pub(crate) fn marshal_size(&self) -> usize {
+        self.chunks.iter().fold(PACKET_HEADER_SIZE, |n, c| {
+            let chunk_size = CHUNK_HEADER_SIZE + c.value_length();
+            n + chunk_size + get_padding_size(chunk_size)
+        })
+    }
+
+    /// Appends the chunks of `other` to this packet if both share the common header,
+    /// the result fits in `mtu` and the bundling rules of RFC 4960 Sec 6.10 allow it.
+    /// Returns `other` back otherwise.
+    pub(crate) fn try_bundle(&mut self, other: Packet, mtu: usize) -> Option<Packet> {
+        // INIT, INIT ACK, and SHUTDOWN COMPLETE chunks MUST NOT be bundled with any
+        // other chunk, COOKIE ECHO must be the first chunk, and chunks following an
+        // ABORT are ignored by the receiver.
+        let must_be_alone = |p: &Packet| {
+            p.chunks
+                .iter()
+                .any(|c| matches!(c.header().typ, CT_INIT | CT_INIT_ACK | CT_SHUTDOWN_COMPLETE))
+        };
+        let bundlable = self.common_header == other.common_header
+            && !must_be_alone(self)
+            && !must_be_alone(&other)
+            && !self.chunks.iter().any(|c| c.header().typ == CT_ABORT)
+            && !other
+                .chunks
+                .first()
+                .is_some_and(|c| c.header().typ == CT_COOKIE_ECHO)
+            && self.marshal_size() + other.marshal_size() - PACKET_HEADER_SIZE <= mtu;
+
+        if bundlable {
+            self.chunks.extend(other.chunks);
+            None
+        } else {
+            Some(other)
+        }
+    }
 }
 
 impl Packet {
@@ -417,6 +533,83 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_partial_decode_unknown_chunk_stops_processing() -> Result<()> {
+        let sack = || {
+            Box::new(ChunkSelectiveAck {
+                cumulative_tsn_ack: 1,
+                advertised_receiver_window_credit: 1024,
+                gap_ack_blocks: vec![],
+                duplicate_tsn: vec![],
+            })
+        };
+        let unknown = |typ| {
+            Box::new(ChunkUnknown {
+                typ: ChunkType(typ),
+                flags: 0,
+                value: Bytes::from_static(&[0xff]),
+            })
+        };
+
+        for (typ, n_chunks) in [(0x3f, 2), (0xbf, 3)] {
+            let raw = Packet {
+                common_header: CommonHeader {
+                    source_port: 5000,
+                    destination_port: 5000,
+                    verification_tag: 1,
+                },
+                chunks: vec![sack(), unknown(typ), sack()],
+            }
+            .marshal()?;
+
+            let pkt = PartialDecode::unmarshal(&raw)?.finish()?;
+            assert_eq!(n_chunks, pkt.chunks.len(), "type {:#x}", typ);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packet_unmarshal_truncated_chunk_value() -> Result<()> {
+        // Chunks whose length leaves no room for their fixed fields, followed
+        // by another chunk so that the packet itself is long enough
+        for typ in [
+            CT_INIT,
+            CT_INIT_ACK,
+            CT_PAYLOAD_DATA,
+            CT_SACK,
+            CT_FORWARD_TSN,
+            CT_SHUTDOWN,
+        ] {
+            let mut raw = BytesMut::new();
+            raw.put_u16(5000);
+            raw.put_u16(5000);
+            raw.put_u32(1);
+            raw.put_u32(0);
+            raw.put_u8(typ.0);
+            raw.put_u8(0);
+            raw.put_u16(CHUNK_HEADER_SIZE as u16);
+            raw.put_u8(0xbf);
+            raw.put_u8(0);
+            raw.put_u16(24);
+            raw.extend_from_slice(&[0xff; 20]);
+            let checksum = generate_packet_checksum(&raw.clone().freeze());
+            raw[8..12].copy_from_slice(&checksum.to_le_bytes());
+            let raw = raw.freeze();
+
+            assert!(Packet::unmarshal(&raw).is_err(), "{}", typ);
+            assert!(
+                PartialDecode::unmarshal(&raw)
+                    .and_then(|p| p.finish())
+                    .is_err(),
+                "{}",
+                typ
+            );
+        }
+
+        Ok(())
+    }
+
     /*fn BenchmarkPacketGenerateChecksum(b *testing.B) {
         var data [1024]byte
 
@@ -461,6 +654,11 @@ mod test {
         let pkt = PartialDecode::unmarshal(&raw_pkt)?;
 
         assert_eq!(pkt.first_chunk_type, CT_INIT_ACK);
+        assert!(pkt.is_init_ack());
+        assert!(!pkt.is_init() && !pkt.is_cookie_echo());
+        assert_eq!(5000, pkt.common_header.source_port);
+        assert_eq!(5000, pkt.common_header.destination_port);
+        assert_eq!(0xce1579a2, pkt.common_header.verification_tag);
         if let Some(initiate_tag) = pkt.initiate_tag {
             assert_eq!(
                 initiate_tag, 3951119873u32,
@@ -471,4 +669,241 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_packet_try_bundle() -> Result<()> {
+        let packet = |chunks: Vec<Box<dyn Chunk + Send + Sync>>| Packet {
+            common_header: CommonHeader {
+                source_port: 5000,
+                destination_port: 5000,
+                verification_tag: 1,
+            },
+            chunks,
+        };
+
+        let mut p = packet(vec![Box::new(ChunkCookieAck {})]);
+        assert!(p
+            .try_bundle(packet(vec![Box::new(ChunkShutdownAck {})]), 1200)
+            .is_none());
+        assert_eq!(2, p.chunks.len());
+        assert_eq!(p.marshal()?.len(), p.marshal_size());
+
+        // Size limit
+        let mtu = p.marshal_size() + CHUNK_HEADER_SIZE - 1;
+        assert!(p
+            .try_bundle(packet(vec![Box::new(ChunkCookieAck {})]), mtu)
+            .is_some());
+
+        // INIT must be alone
+        let init = packet(vec![Box::new(ChunkInit::default())]);
+        assert!(p.try_bundle(init, 1200).is_some());
+
+        // Nothing may follow an ABORT
+        let mut abort = packet(vec![Box::new(ChunkAbort::default())]);
+        assert!(abort
+            .try_bundle(packet(vec![Box::new(ChunkCookieAck {})]), 1200)
+            .is_some());
+
+        // Different verification tag
+        let mut other = packet(vec![Box::new(ChunkCookieAck {})]);
+        other.common_header.verification_tag = 2;
+        assert!(p.try_bundle(other, 1200).is_some());
+        assert_eq!(2, p.chunks.len());
+
+        Ok(())
+    }
+
+    /// A PMTU probe laid out the way usrsctp sends it: a HEARTBEAT followed by
+    /// a PAD chunk filling the packet up to the probed size
+    fn pmtu_probe_packet(size: usize) -> Bytes {
+        let mut raw = BytesMut::new();
+        raw.put_u16(5000);
+        raw.put_u16(5000);
+        raw.put_u32(0x2b3c4d5e);
+        raw.put_u32(0);
+        // HEARTBEAT with a 56-byte Heartbeat Info parameter
+        raw.put_u8(CT_HEARTBEAT.0);
+        raw.put_u8(0);
+        raw.put_u16(60);
+        raw.put_u16(1);
+        raw.put_u16(56);
+        raw.put_u32(0x6651_a2f4); // time_value_1
+        raw.put_u32(0x000b_3c1a); // time_value_2
+        raw.put_u32(0x9d21_e8b4); // random_value1
+        raw.put_u32(0x07c5_5f31); // random_value2
+        raw.put_u8(2); // addr_family
+        raw.put_u8(16); // addr_len
+        raw.put_u16(0);
+        raw.put_slice(&[192, 0, 2, 1]);
+        raw.put_slice(&[0; 28]);
+        // PAD
+        let pad_len = size - raw.len();
+        raw.put_u8(CT_PAD.0);
+        raw.put_u8(0);
+        raw.put_u16(pad_len as u16);
+        raw.put_slice(&vec![0; pad_len - CHUNK_HEADER_SIZE]);
+
+        let checksum = generate_packet_checksum(&raw.clone().freeze());
+        raw[8..12].copy_from_slice(&checksum.to_le_bytes());
+        raw.freeze()
+    }
+
+    #[test]
+    fn test_packet_unmarshal_pmtu_probe() -> Result<()> {
+        for size in [1200, 1280, 1452] {
+            let raw = pmtu_probe_packet(size);
+
+            for pkt in [
+                Packet::unmarshal(&raw)?,
+                PartialDecode::unmarshal(&raw)?.finish()?,
+            ] {
+                assert_eq!(2, pkt.chunks.len());
+                assert!(pkt.chunks[0]
+                    .as_any()
+                    .downcast_ref::<ChunkHeartbeat>()
+                    .is_some());
+                let pad = pkt.chunks[1]
+                    .as_any()
+                    .downcast_ref::<ChunkPadding>()
+                    .expect("should be a PAD chunk");
+                assert_eq!(size - 76, pad.padding_data.len());
+                assert_eq!(raw, pkt.marshal()?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packet_unmarshal_pad_bundled_with_sack() -> Result<()> {
+        let raw = Packet {
+            common_header: CommonHeader {
+                source_port: 5000,
+                destination_port: 5000,
+                verification_tag: 1,
+            },
+            chunks: vec![
+                Box::new(ChunkPadding::new(5)),
+                Box::new(ChunkSelectiveAck {
+                    cumulative_tsn_ack: 1,
+                    advertised_receiver_window_credit: 1024,
+                    gap_ack_blocks: vec![],
+                    duplicate_tsn: vec![],
+                }),
+            ],
+        }
+        .marshal()?;
+
+        let pkt = Packet::unmarshal(&raw)?;
+        assert_eq!(2, pkt.chunks.len());
+        assert_eq!(5, pkt.chunks[0].value_length());
+        assert!(pkt.chunks[1]
+            .as_any()
+            .downcast_ref::<ChunkSelectiveAck>()
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packet_zero_checksum() -> Result<()> {
+        let sack = Packet {
+            common_header: CommonHeader {
+                source_port: 5000,
+                destination_port: 5000,
+                verification_tag: 1,
+            },
+            chunks: vec![Box::new(ChunkSelectiveAck {
+                cumulative_tsn_ack: 1,
+                advertised_receiver_window_credit: 1024,
+                gap_ack_blocks: vec![],
+                duplicate_tsn: vec![],
+            })],
+        };
+
+        let raw = sack.marshal_with_zero_checksum(true)?;
+        assert_eq!(&[0, 0, 0, 0], &raw[8..12]);
+        assert_eq!(
+            Err(Error::ErrChecksumMismatch),
+            PartialDecode::unmarshal(&raw).map(|_| ())
+        );
+        let pd = PartialDecode::unmarshal_unverified_zero_checksum(&raw)?;
+        assert!(pd.is_zero_checksum_acceptable());
+        assert!(!pd.verify_checksum(), "zero checksum is not a valid CRC32c");
+
+        // A correctly-checksummed packet is accepted either way
+        let pd = PartialDecode::unmarshal(&sack.marshal()?)?;
+        assert!(pd.verify_checksum());
+
+        let init = Packet {
+            common_header: CommonHeader {
+                source_port: 5000,
+                destination_port: 5000,
+                verification_tag: 0,
+            },
+            chunks: vec![Box::new(ChunkInit {
+                initiate_tag: 1,
+                num_outbound_streams: 1,
+                num_inbound_streams: 1,
+                advertised_receiver_window_credit: 1500,
+                ..Default::default()
+            })],
+        };
+        let raw = init.marshal_with_zero_checksum(true)?;
+        assert_eq!(init.marshal()?, raw, "INIT must carry a real checksum");
+        let pd = PartialDecode::unmarshal(&raw)?;
+        assert!(!pd.is_zero_checksum_acceptable());
+        assert!(pd.verify_checksum());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_decode_looks_like_sctp() -> Result<()> {
+        let packet = Packet {
+            common_header: CommonHeader {
+                source_port: 5000,
+                destination_port: 5001,
+                verification_tag: 1,
+            },
+            chunks: vec![Box::new(ChunkSelectiveAck {
+                cumulative_tsn_ack: 1,
+                advertised_receiver_window_credit: 1024,
+                gap_ack_blocks: vec![],
+                duplicate_tsn: vec![],
+            })],
+        };
+        let raw = packet.marshal()?;
+        assert!(PartialDecode::looks_like_sctp(&raw));
+
+        let parsed = Packet::unmarshal(&raw)?;
+        assert_eq!(
+            Some(parsed.common_header.source_port),
+            PartialDecode::source_port(&raw)
+        );
+        assert_eq!(
+            Some(parsed.common_header.destination_port),
+            PartialDecode::destination_port(&raw)
+        );
+
+        let mut corrupted = BytesMut::from(&raw[..]);
+        corrupted[13] ^= 0xff;
+        let not_sctp = [
+            // Too short for a common header
+            Bytes::from_static(&[0x13, 0x88, 0x13, 0x88]),
+            // Start of a DTLS 1.2 handshake record
+            Bytes::from_static(&[
+                0x16, 0xfe, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x8c,
+            ]),
+            corrupted.freeze(),
+            raw.slice(..raw.len() - 4),
+        ];
+        for raw in &not_sctp {
+            assert!(!PartialDecode::looks_like_sctp(raw), "{:?}", raw);
+        }
+        assert_eq!(None, PartialDecode::source_port(&not_sctp[0]));
+        assert_eq!(None, PartialDecode::destination_port(&not_sctp[0]));
+
+        Ok(())
+    }
 }
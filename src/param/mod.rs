@@ -1,11 +1,13 @@
 #[cfg(test)]
 mod param_test;
 
+pub(crate) mod param_add_outgoing_streams_request;
 pub(crate) mod param_chunk_list;
 pub(crate) mod param_forward_tsn_supported;
 pub(crate) mod param_header;
 pub(crate) mod param_heartbeat_info;
 pub(crate) mod param_outgoing_reset_request;
+pub(crate) mod param_padding;
 pub(crate) mod param_random;
 pub(crate) mod param_reconfig_response;
 pub(crate) mod param_requested_hmac_algorithm;
@@ -13,15 +15,19 @@ pub(crate) mod param_state_cookie;
 pub(crate) mod param_supported_extensions;
 pub(crate) mod param_type;
 pub(crate) mod param_uknown;
+pub(crate) mod param_unrecognized;
+pub(crate) mod param_zero_checksum;
 
 use crate::error::{Error, Result};
 use crate::param::{
+    param_add_outgoing_streams_request::ParamAddOutgoingStreamsRequest,
     param_chunk_list::ParamChunkList, param_forward_tsn_supported::ParamForwardTsnSupported,
     param_heartbeat_info::ParamHeartbeatInfo,
-    param_outgoing_reset_request::ParamOutgoingResetRequest, param_random::ParamRandom,
-    param_reconfig_response::ParamReconfigResponse,
+    param_outgoing_reset_request::ParamOutgoingResetRequest, param_padding::ParamPadding,
+    param_random::ParamRandom, param_reconfig_response::ParamReconfigResponse,
     param_requested_hmac_algorithm::ParamRequestedHmacAlgorithm,
     param_state_cookie::ParamStateCookie, param_supported_extensions::ParamSupportedExtensions,
+    param_unrecognized::ParamUnrecognized, param_zero_checksum::ParamZeroChecksumAcceptable,
 };
 use param_header::*;
 use param_type::*;
@@ -72,16 +78,16 @@ pub(crate) fn build_param(raw_param: &Bytes) -> Result<Box<dyn Param + Send + Sy
         ParamType::HeartbeatInfo => Ok(Box::new(ParamHeartbeatInfo::unmarshal(raw_param)?)),
         ParamType::OutSsnResetReq => Ok(Box::new(ParamOutgoingResetRequest::unmarshal(raw_param)?)),
         ParamType::ReconfigResp => Ok(Box::new(ParamReconfigResponse::unmarshal(raw_param)?)),
-        _ => {
-            // According to RFC https://datatracker.ietf.org/doc/html/rfc4960#section-3.2.1
-            let stop_processing = ((raw_type >> 15) & 0x01) == 0;
-            if stop_processing {
-                Err(Error::ErrParamTypeUnhandled { typ: raw_type })
-            } else {
-                // We still might need to report this param as unrecognized.
-                // This depends on the context though.
-                Ok(Box::new(ParamUnknown::unmarshal(raw_param)?))
-            }
+        ParamType::AddOutStreamsReq => Ok(Box::new(ParamAddOutgoingStreamsRequest::unmarshal(
+            raw_param,
+        )?)),
+        ParamType::Padding => Ok(Box::new(ParamPadding::unmarshal(raw_param)?)),
+        ParamType::ZeroChecksumAcceptable => {
+            Ok(Box::new(ParamZeroChecksumAcceptable::unmarshal(raw_param)?))
         }
+        ParamType::UnrecognizedParam => Ok(Box::new(ParamUnrecognized::unmarshal(raw_param)?)),
+        // The action bits of unknown params (RFC 4960 sec 3.2.1) are applied
+        // by the enclosing chunk, which knows whether and how to report them.
+        _ => Ok(Box::new(ParamUnknown::unmarshal(raw_param)?)),
     }
 }
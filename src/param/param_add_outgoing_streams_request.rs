@@ -0,0 +1,85 @@
+use super::{param_header::*, param_type::*, *};
+
+use bytes::BufMut;
+
+///This parameter is used by the sender to request that additional
+///outgoing streams (i.e., incoming streams for the receiver) be added
+///to the association.
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|     Parameter Type = 17       |      Parameter Length = 12    |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|          Re-configuration Request Sequence Number             |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|      Number of new streams    |         Reserved              |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub(crate) struct ParamAddOutgoingStreamsRequest {
+    /// reconfig_request_sequence_number is used to identify the request.  It is a monotonically
+    /// increasing number that is initialized to the same value as the
+    /// initial TSN.  It is increased by 1 whenever sending a new Re-
+    /// configuration Request Parameter.
+    pub(crate) reconfig_request_sequence_number: u32,
+    /// This value indicates the number of streams the sender of the
+    /// request wants to add.
+    pub(crate) number_of_new_streams: u16,
+}
+
+impl fmt::Display for ParamAddOutgoingStreamsRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}",
+            self.header(),
+            self.reconfig_request_sequence_number,
+            self.number_of_new_streams
+        )
+    }
+}
+
+impl Param for ParamAddOutgoingStreamsRequest {
+    fn header(&self) -> ParamHeader {
+        ParamHeader {
+            typ: ParamType::AddOutStreamsReq,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ParamHeader::unmarshal(raw)?;
+        if header.value_length() < 8 {
+            return Err(Error::ErrAddOutgoingStreamsRequestParamTooShort);
+        }
+
+        let reader =
+            &mut raw.slice(PARAM_HEADER_LENGTH..PARAM_HEADER_LENGTH + header.value_length());
+        let reconfig_request_sequence_number = reader.get_u32();
+        let number_of_new_streams = reader.get_u16();
+
+        Ok(ParamAddOutgoingStreamsRequest {
+            reconfig_request_sequence_number,
+            number_of_new_streams,
+        })
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        buf.put_u32(self.reconfig_request_sequence_number);
+        buf.put_u16(self.number_of_new_streams);
+        buf.put_u16(0); // reserved
+        Ok(buf.len())
+    }
+
+    fn value_length(&self) -> usize {
+        8
+    }
+
+    fn clone_to(&self) -> Box<dyn Param + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
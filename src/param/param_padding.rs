@@ -0,0 +1,66 @@
+use super::{param_header::*, param_type::*, *};
+
+/// The Padding parameter (RFC 4820 Sec 4) pads an INIT or HEARTBEAT chunk to
+/// a given size. The receiver ignores its content.
+///
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|     Parameter Type = 0x8005   |       Parameter Length        |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|                                                               |
+///|                         Padding Data                          |
+///|                                                               |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Default, Debug, Clone, PartialEq)]
+pub(crate) struct ParamPadding {
+    pub(crate) padding_data: Bytes,
+}
+
+impl fmt::Display for ParamPadding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} bytes", self.header(), self.padding_data.len())
+    }
+}
+
+impl ParamPadding {
+    /// A Padding parameter of `len` zero bytes, excluding the parameter header
+    pub(crate) fn new(len: usize) -> Self {
+        ParamPadding {
+            padding_data: Bytes::from(vec![0u8; len]),
+        }
+    }
+}
+
+impl Param for ParamPadding {
+    fn header(&self) -> ParamHeader {
+        ParamHeader {
+            typ: ParamType::Padding,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ParamHeader::unmarshal(raw)?;
+        let padding_data =
+            raw.slice(PARAM_HEADER_LENGTH..PARAM_HEADER_LENGTH + header.value_length());
+        Ok(ParamPadding { padding_data })
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        buf.extend(self.padding_data.clone());
+        Ok(buf.len())
+    }
+
+    fn value_length(&self) -> usize {
+        self.padding_data.len()
+    }
+
+    fn clone_to(&self) -> Box<dyn Param + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
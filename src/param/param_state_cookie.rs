@@ -2,6 +2,81 @@ use super::{param_header::*, param_type::*, *};
 
 use rand::Rng;
 
+#[cfg(feature = "hmac-cookie")]
+use crate::association::extensions::NegotiatedExtensions;
+#[cfg(feature = "hmac-cookie")]
+use bytes::{Buf, BufMut};
+#[cfg(feature = "hmac-cookie")]
+use hmac::{Hmac, Mac};
+#[cfg(feature = "hmac-cookie")]
+use sha2::Sha256;
+#[cfg(feature = "hmac-cookie")]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "hmac-cookie")]
+use subtle::ConstantTimeEq;
+
+/// Length of the HMAC-SHA256 tag ending a signed cookie
+#[cfg(feature = "hmac-cookie")]
+const COOKIE_HMAC_LENGTH: usize = 32;
+/// Length of the signed part of a cookie: peer verification tag, source and
+/// destination ports, creation time in ms since the UNIX epoch, then the
+/// remaining `CookieParams`
+#[cfg(feature = "hmac-cookie")]
+const COOKIE_CONTENT_LENGTH: usize = 4 + 2 + 2 + 8 + 4 + 4 + 4 + 2 + 2 + 1 + 1;
+/// Lifetime of a signed cookie (Valid.Cookie.Life, RFC 4960 Sec 15), in msec
+#[cfg(feature = "hmac-cookie")]
+pub(crate) const COOKIE_LIFETIME: u64 = 60_000;
+
+/// Converts the instants passed in by the caller to the wall clock time
+/// carried by signed cookies, so that endpoints sharing a key agree on their age
+#[cfg(feature = "hmac-cookie")]
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct CookieClock {
+    instant: Instant,
+    system_time: SystemTime,
+}
+
+#[cfg(feature = "hmac-cookie")]
+impl Default for CookieClock {
+    fn default() -> Self {
+        CookieClock {
+            instant: Instant::now(),
+            system_time: SystemTime::now(),
+        }
+    }
+}
+
+#[cfg(feature = "hmac-cookie")]
+impl CookieClock {
+    /// Time of `now` in msec since the UNIX epoch
+    pub(crate) fn unix_time_ms(&self, now: Instant) -> u64 {
+        let time = if now >= self.instant {
+            self.system_time + now.duration_since(self.instant)
+        } else {
+            self.system_time - self.instant.duration_since(now)
+        };
+        time.duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as u64)
+    }
+}
+
+/// Parameters of an association carried by a signed cookie, so that a server
+/// can answer an INIT without keeping any state (RFC 4960 Sec 5.1.3)
+#[cfg(feature = "hmac-cookie")]
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) struct CookieParams {
+    pub(crate) peer_verification_tag: u32,
+    pub(crate) source_port: u16,
+    pub(crate) destination_port: u16,
+    pub(crate) my_verification_tag: u32,
+    pub(crate) my_initial_tsn: u32,
+    pub(crate) peer_initial_tsn: u32,
+    pub(crate) num_outbound_streams: u16,
+    pub(crate) num_inbound_streams: u16,
+    pub(crate) extensions: NegotiatedExtensions,
+    pub(crate) zero_checksum: bool,
+}
+
 #[derive(Default, Debug, Clone, PartialEq)]
 pub(crate) struct ParamStateCookie {
     pub(crate) cookie: Bytes,
@@ -49,12 +124,107 @@ impl Param for ParamStateCookie {
 
 impl ParamStateCookie {
     pub(crate) fn new() -> Self {
+        Self::generate(&mut rand::thread_rng())
+    }
+
+    /// A cookie of random bytes drawn from `rng`
+    pub(crate) fn generate<R: Rng>(rng: &mut R) -> Self {
         let mut cookie = BytesMut::new();
         cookie.resize(32, 0);
-        rand::thread_rng().fill(cookie.as_mut());
+        rng.fill(cookie.as_mut());
 
         ParamStateCookie {
             cookie: cookie.freeze(),
         }
     }
 }
+
+#[cfg(feature = "hmac-cookie")]
+impl ParamStateCookie {
+    /// Creates a cookie carrying `params`, authenticated by an HMAC-SHA256 tag
+    /// keyed with `secret`. `created_at` is in msec since the UNIX epoch.
+    pub(crate) fn new_signed(secret: &[u8; 32], params: &CookieParams, created_at: u64) -> Self {
+        let mut cookie = BytesMut::with_capacity(COOKIE_CONTENT_LENGTH + COOKIE_HMAC_LENGTH);
+        cookie.put_u32(params.peer_verification_tag);
+        cookie.put_u16(params.source_port);
+        cookie.put_u16(params.destination_port);
+        cookie.put_u64(created_at);
+        cookie.put_u32(params.my_verification_tag);
+        cookie.put_u32(params.my_initial_tsn);
+        cookie.put_u32(params.peer_initial_tsn);
+        cookie.put_u16(params.num_outbound_streams);
+        cookie.put_u16(params.num_inbound_streams);
+        cookie.put_u8(params.extensions.bits());
+        cookie.put_u8(params.zero_checksum as u8);
+        let tag = cookie_hmac(secret, &cookie);
+        cookie.extend_from_slice(&tag);
+
+        ParamStateCookie {
+            cookie: cookie.freeze(),
+        }
+    }
+
+    /// Returns the parameters of `cookie` if it carries a valid tag for
+    /// `secret` and, when `max_age` is given, was issued at most `max_age`
+    /// msec before `now`, in msec since the UNIX epoch
+    pub(crate) fn decode_signed(
+        cookie: &[u8],
+        secret: &[u8; 32],
+        now: u64,
+        max_age: Option<u64>,
+    ) -> Option<CookieParams> {
+        if cookie.len() != COOKIE_CONTENT_LENGTH + COOKIE_HMAC_LENGTH {
+            return None;
+        }
+
+        let (content, tag) = cookie.split_at(COOKIE_CONTENT_LENGTH);
+        if !bool::from(cookie_hmac(secret, content)[..].ct_eq(tag)) {
+            return None;
+        }
+
+        let reader = &mut &content[..];
+        let peer_verification_tag = reader.get_u32();
+        let source_port = reader.get_u16();
+        let destination_port = reader.get_u16();
+        let created_at = reader.get_u64();
+        if max_age.is_some_and(|max_age| now.saturating_sub(created_at) > max_age) {
+            return None;
+        }
+
+        Some(CookieParams {
+            peer_verification_tag,
+            source_port,
+            destination_port,
+            my_verification_tag: reader.get_u32(),
+            my_initial_tsn: reader.get_u32(),
+            peer_initial_tsn: reader.get_u32(),
+            num_outbound_streams: reader.get_u16(),
+            num_inbound_streams: reader.get_u16(),
+            extensions: NegotiatedExtensions::from_bits(reader.get_u8()),
+            zero_checksum: reader.get_u8() != 0,
+        })
+    }
+
+    /// Checks that `cookie` carries a valid tag for `secret` and was issued
+    /// for the given association
+    pub(crate) fn verify_signed(
+        cookie: &[u8],
+        secret: &[u8; 32],
+        peer_verification_tag: u32,
+        source_port: u16,
+        destination_port: u16,
+    ) -> bool {
+        Self::decode_signed(cookie, secret, 0, None).is_some_and(|params| {
+            params.peer_verification_tag == peer_verification_tag
+                && params.source_port == source_port
+                && params.destination_port == destination_port
+        })
+    }
+}
+
+#[cfg(feature = "hmac-cookie")]
+fn cookie_hmac(secret: &[u8; 32], content: &[u8]) -> [u8; COOKIE_HMAC_LENGTH] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(content);
+    mac.finalize().into_bytes().into()
+}
@@ -210,6 +210,40 @@ fn test_param_reconfig_response_failure() -> Result<()> {
     Ok(())
 }
 
+///////////////////////////////////////////////////////////////////
+//param_add_outgoing_streams_request_test
+///////////////////////////////////////////////////////////////////
+use super::param_add_outgoing_streams_request::*;
+
+static CHUNK_ADD_OUTGOING_STREAMS_REQUEST: Bytes =
+    Bytes::from_static(&[0x0, 0x11, 0x0, 0xc, 0x0, 0x0, 0x0, 0x2, 0x0, 0xa, 0x0, 0x0]);
+
+#[test]
+fn test_param_add_outgoing_streams_request_success() -> Result<()> {
+    let binary = CHUNK_ADD_OUTGOING_STREAMS_REQUEST.clone();
+    let parsed = ParamAddOutgoingStreamsRequest {
+        reconfig_request_sequence_number: 2,
+        number_of_new_streams: 10,
+    };
+
+    let actual = ParamAddOutgoingStreamsRequest::unmarshal(&binary)?;
+    assert_eq!(parsed, actual);
+    assert_eq!(binary, actual.marshal()?);
+
+    let p = build_param(&binary)?;
+    assert!(p
+        .as_any()
+        .downcast_ref::<ParamAddOutgoingStreamsRequest>()
+        .is_some());
+
+    let result = ParamAddOutgoingStreamsRequest::unmarshal(&Bytes::from_static(&[
+        0x0, 0x11, 0x0, 0x8, 0x0, 0x0, 0x0, 0x2,
+    ]));
+    assert!(result.is_err(), "expected unmarshal to fail");
+
+    Ok(())
+}
+
 #[test]
 fn test_reconfig_result_stringer() -> Result<()> {
     let tests = vec![
@@ -236,6 +270,48 @@ fn test_reconfig_result_stringer() -> Result<()> {
     Ok(())
 }
 
+///////////////////////////////////////////////////////////////////
+//param_padding_test
+///////////////////////////////////////////////////////////////////
+use super::param_padding::*;
+
+#[test]
+fn test_param_padding_success() -> Result<()> {
+    let binary = Bytes::from_static(&[0x80, 0x05, 0x00, 0x0c, 0, 0, 0, 0, 0, 0, 0, 0]);
+
+    let p = build_param(&binary)?;
+    let padding = p
+        .as_any()
+        .downcast_ref::<ParamPadding>()
+        .expect("should be a Padding parameter");
+    assert_eq!(8, padding.padding_data.len());
+    assert_eq!(binary, p.marshal()?);
+    assert_eq!(binary, ParamPadding::new(8).marshal()?);
+
+    Ok(())
+}
+
+///////////////////////////////////////////////////////////////////
+//param_zero_checksum_test
+///////////////////////////////////////////////////////////////////
+use super::param_zero_checksum::*;
+
+#[test]
+fn test_param_zero_checksum_acceptable_success() -> Result<()> {
+    let binary = Bytes::from_static(&[0x80, 0x01, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01]);
+
+    let p = build_param(&binary)?;
+    let zc = p
+        .as_any()
+        .downcast_ref::<ParamZeroChecksumAcceptable>()
+        .expect("should be a Zero Checksum Acceptable parameter");
+    assert_eq!(ZERO_CHECKSUM_EDMID_DTLS, zc.edmid);
+    assert_eq!(binary, p.marshal()?);
+    assert_eq!(binary, ParamZeroChecksumAcceptable::default().marshal()?);
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //param_test
 ///////////////////////////////////////////////////////////////////
@@ -253,6 +329,20 @@ fn test_build_param_success() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_build_param_unrecognized() -> Result<()> {
+    let binary = Bytes::from_static(&[0x0, 0x8, 0x0, 0x8, 0xc0, 0x0f, 0x0, 0x4]);
+    let p = build_param(&binary)?;
+    let unrecognized = p
+        .as_any()
+        .downcast_ref::<super::param_unrecognized::ParamUnrecognized>()
+        .expect("should be an Unrecognized Parameter");
+    assert_eq!(binary.slice(4..), unrecognized.raw_param);
+    assert_eq!(binary, p.marshal()?);
+
+    Ok(())
+}
+
 #[test]
 fn test_build_param_failure() -> Result<()> {
     let tests = vec![
@@ -267,3 +357,69 @@ fn test_build_param_failure() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(feature = "hmac-cookie")]
+#[test]
+fn test_param_state_cookie_signed() -> Result<()> {
+    use super::param_state_cookie::{CookieParams, ParamStateCookie};
+    use crate::association::extensions::{NegotiatedExtensions, SctpExtension};
+
+    let secret = [7u8; 32];
+    let params = CookieParams {
+        peer_verification_tag: 1234,
+        source_port: 5000,
+        destination_port: 5001,
+        my_verification_tag: 4321,
+        my_initial_tsn: u32::MAX,
+        peer_initial_tsn: 1,
+        num_outbound_streams: 10,
+        num_inbound_streams: 20,
+        extensions: [SctpExtension::ReConfig, SctpExtension::ForwardTsn]
+            .into_iter()
+            .collect::<NegotiatedExtensions>(),
+        zero_checksum: true,
+    };
+    let p = ParamStateCookie::new_signed(&secret, &params, 1_000_000);
+    assert!(ParamStateCookie::verify_signed(
+        &p.cookie, &secret, 1234, 5000, 5001
+    ));
+    assert_eq!(
+        Some(params),
+        ParamStateCookie::decode_signed(&p.cookie, &secret, 1_060_000, Some(60_000))
+    );
+    assert_eq!(
+        None,
+        ParamStateCookie::decode_signed(&p.cookie, &secret, 1_060_001, Some(60_000))
+    );
+
+    // Wrong key or association
+    assert!(!ParamStateCookie::verify_signed(
+        &p.cookie, &[8u8; 32], 1234, 5000, 5001
+    ));
+    assert!(!ParamStateCookie::verify_signed(
+        &p.cookie, &secret, 1235, 5000, 5001
+    ));
+    assert!(!ParamStateCookie::verify_signed(
+        &p.cookie, &secret, 1234, 5001, 5000
+    ));
+
+    // Any modified byte invalidates the cookie
+    for i in 0..p.cookie.len() {
+        let mut tampered = p.cookie.to_vec();
+        tampered[i] ^= 0x01;
+        assert!(
+            !ParamStateCookie::verify_signed(&tampered, &secret, 1234, 5000, 5001),
+            "tampered byte {} should be detected",
+            i
+        );
+    }
+    assert!(!ParamStateCookie::verify_signed(
+        &p.cookie[..p.cookie.len() - 1],
+        &secret,
+        1234,
+        5000,
+        5001
+    ));
+
+    Ok(())
+}
@@ -17,6 +17,26 @@ pub struct ParamUnknown {
     value: Bytes,
 }
 
+impl ParamUnknown {
+    /// Whether the type is not defined at all. Parameters defined by the RFCs
+    /// but without meaning here, e.g. Supported Address Types, are skipped.
+    fn is_unrecognized(&self) -> bool {
+        matches!(ParamType::from(self.typ), ParamType::Unknown { .. })
+    }
+
+    /// Whether the highest bit of the type is clear (`00` and `01`): no further
+    /// parameters of the enclosing chunk may be processed (RFC 4960 3.2.1).
+    pub(crate) fn should_stop_processing(&self) -> bool {
+        self.is_unrecognized() && self.typ & 0x8000 == 0
+    }
+
+    /// Whether the second highest bit of the type is set (`01` and `11`): the
+    /// parameter must be reported back in an Unrecognized Parameter.
+    pub(crate) fn should_report(&self) -> bool {
+        self.is_unrecognized() && self.typ & 0x4000 != 0
+    }
+}
+
 impl Display for ParamUnknown {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "ParamUnknown( {} {:?} )", self.header(), self.value)
@@ -0,0 +1,48 @@
+use super::{param_header::*, param_type::*, *};
+
+/// Unrecognized Parameter, sent in an INIT ACK to report a parameter of the INIT
+/// whose type asks to be reported when it is not understood. The value is the
+/// offending parameter copied as-is, including its type and length fields.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub(crate) struct ParamUnrecognized {
+    pub(crate) raw_param: Bytes,
+}
+
+impl fmt::Display for ParamUnrecognized {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:?}", self.header(), self.raw_param)
+    }
+}
+
+impl Param for ParamUnrecognized {
+    fn header(&self) -> ParamHeader {
+        ParamHeader {
+            typ: ParamType::UnrecognizedParam,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ParamHeader::unmarshal(raw)?;
+        let raw_param = raw.slice(PARAM_HEADER_LENGTH..PARAM_HEADER_LENGTH + header.value_length());
+        Ok(ParamUnrecognized { raw_param })
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        buf.extend(self.raw_param.clone());
+        Ok(buf.len())
+    }
+
+    fn value_length(&self) -> usize {
+        self.raw_param.len()
+    }
+
+    fn clone_to(&self) -> Box<dyn Param + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
@@ -0,0 +1,73 @@
+use super::{param_header::*, param_type::*, *};
+
+use bytes::BufMut;
+
+/// Error Detection Method Identifier for SCTP over DTLS (RFC 9653 Sec 7)
+pub(crate) const ZERO_CHECKSUM_EDMID_DTLS: u32 = 1;
+
+/// The Zero Checksum Acceptable parameter (RFC 9653 Sec 5.1) tells the peer
+/// that packets sent to this endpoint may carry a zero checksum, because an
+/// alternate error detection method such as DTLS protects them.
+///
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|     Parameter Type = 0x8001   |      Parameter Length = 8     |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///|           Error Detection Method Identifier (EDMID)           |
+///+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParamZeroChecksumAcceptable {
+    pub(crate) edmid: u32,
+}
+
+impl Default for ParamZeroChecksumAcceptable {
+    fn default() -> Self {
+        ParamZeroChecksumAcceptable {
+            edmid: ZERO_CHECKSUM_EDMID_DTLS,
+        }
+    }
+}
+
+impl fmt::Display for ParamZeroChecksumAcceptable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.header(), self.edmid)
+    }
+}
+
+impl Param for ParamZeroChecksumAcceptable {
+    fn header(&self) -> ParamHeader {
+        ParamHeader {
+            typ: ParamType::ZeroChecksumAcceptable,
+            value_length: self.value_length() as u16,
+        }
+    }
+
+    fn unmarshal(raw: &Bytes) -> Result<Self> {
+        let header = ParamHeader::unmarshal(raw)?;
+        if header.value_length() < 4 {
+            return Err(Error::ErrParamHeaderTooShort);
+        }
+        let reader =
+            &mut raw.slice(PARAM_HEADER_LENGTH..PARAM_HEADER_LENGTH + header.value_length());
+        let edmid = reader.get_u32();
+        Ok(ParamZeroChecksumAcceptable { edmid })
+    }
+
+    fn marshal_to(&self, buf: &mut BytesMut) -> Result<usize> {
+        self.header().marshal_to(buf)?;
+        buf.put_u32(self.edmid);
+        Ok(buf.len())
+    }
+
+    fn value_length(&self) -> usize {
+        4
+    }
+
+    fn clone_to(&self) -> Box<dyn Param + Send + Sync> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &(dyn Any + Send + Sync) {
+        self
+    }
+}
@@ -2,7 +2,16 @@ use crate::chunk::chunk_payload_data::ChunkPayloadData;
 use crate::chunk::chunk_selective_ack::GapAckBlock;
 use crate::util::*;
 
+use log::warn;
+
 use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+
+/// Whether the chunk counts towards the flight size: sent, and neither acked,
+/// abandoned nor waiting to be retransmitted.
+fn is_in_flight(c: &ChunkPayloadData) -> bool {
+    !c.acked && !c.retransmit && !c.abandoned()
+}
 
 #[derive(Default, Debug)]
 pub(crate) struct PayloadQueue {
@@ -10,7 +19,43 @@ pub(crate) struct PayloadQueue {
     chunk_map: HashMap<u32, ChunkPayloadData>,
     pub(crate) sorted: Vec<u32>,
     dup_tsn: Vec<u32>,
+    // bytes not acked yet, outstanding towards the peer's rwnd
     n_bytes: usize,
+    // bytes in flight towards cwnd
+    n_bytes_in_flight: usize,
+    max_chunks: Option<usize>,
+}
+
+/// Mutable reference to a queued chunk that updates the flight size of the
+/// queue when it is released, should the chunk have been resent or abandoned.
+pub(crate) struct ChunkMut<'a> {
+    chunk: &'a mut ChunkPayloadData,
+    n_bytes_in_flight: &'a mut usize,
+    was_in_flight: bool,
+}
+
+impl Deref for ChunkMut<'_> {
+    type Target = ChunkPayloadData;
+
+    fn deref(&self) -> &Self::Target {
+        self.chunk
+    }
+}
+
+impl DerefMut for ChunkMut<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.chunk
+    }
+}
+
+impl Drop for ChunkMut<'_> {
+    fn drop(&mut self) {
+        match (self.was_in_flight, is_in_flight(self.chunk)) {
+            (false, true) => *self.n_bytes_in_flight += self.chunk.user_data.len(),
+            (true, false) => *self.n_bytes_in_flight -= self.chunk.user_data.len(),
+            _ => {}
+        }
+    }
 }
 
 impl PayloadQueue {
@@ -18,6 +63,21 @@ impl PayloadQueue {
         PayloadQueue::default()
     }
 
+    /// Creates a queue that holds at most `max` chunks, apart from the one
+    /// that advances the cumulative TSN.
+    pub(crate) fn new_with_capacity(max: usize) -> Self {
+        PayloadQueue {
+            max_chunks: Some(max),
+            ..Default::default()
+        }
+    }
+
+    /// Changes the number of chunks the queue holds. Chunks already queued
+    /// beyond the new capacity are kept.
+    pub(crate) fn set_capacity(&mut self, max: usize) {
+        self.max_chunks = Some(max);
+    }
+
     pub(crate) fn update_sorted_keys(&mut self) {
         self.sorted.sort_by(|a, b| {
             if sna32lt(*a, *b) {
@@ -34,6 +94,9 @@ impl PayloadQueue {
 
     pub(crate) fn push_no_check(&mut self, p: ChunkPayloadData) {
         self.n_bytes += p.user_data.len();
+        if is_in_flight(&p) {
+            self.n_bytes_in_flight += p.user_data.len();
+        }
         self.sorted.push(p.tsn);
         self.chunk_map.insert(p.tsn, p);
         //self.length += 1;
@@ -51,7 +114,16 @@ impl PayloadQueue {
             return false;
         }
 
+        // The next expected TSN is popped right away, so it is always accepted.
+        // Otherwise a full queue could never drain.
+        if self.is_full() && p.tsn != cumulative_tsn.wrapping_add(1) {
+            return false;
+        }
+
         self.n_bytes += p.user_data.len();
+        if is_in_flight(&p) {
+            self.n_bytes_in_flight += p.user_data.len();
+        }
         self.sorted.push(p.tsn);
         self.chunk_map.insert(p.tsn, p);
         //self.length += 1;
@@ -60,13 +132,33 @@ impl PayloadQueue {
         true
     }
 
+    pub(crate) fn is_full(&self) -> bool {
+        self.max_chunks
+            .is_some_and(|max| self.chunk_map.len() >= max)
+    }
+
+    /// memory_pressure returns the fill level of the queue relative to its
+    /// capacity, or 0.0 if it is unbounded.
+    pub(crate) fn memory_pressure(&self) -> f32 {
+        match self.max_chunks {
+            Some(max) if max > 0 => self.chunk_map.len() as f32 / max as f32,
+            Some(_) => 1.0,
+            None => 0.0,
+        }
+    }
+
     /// pop pops only if the oldest chunk's TSN matches the given TSN.
     pub(crate) fn pop(&mut self, tsn: u32) -> Option<ChunkPayloadData> {
         if !self.sorted.is_empty() && tsn == self.sorted[0] {
             self.sorted.remove(0);
             if let Some(c) = self.chunk_map.remove(&tsn) {
                 //self.length -= 1;
-                self.n_bytes -= c.user_data.len();
+                if !c.acked {
+                    self.n_bytes -= c.user_data.len();
+                }
+                if is_in_flight(&c) {
+                    self.n_bytes_in_flight -= c.user_data.len();
+                }
                 return Some(c);
             }
         }
@@ -78,8 +170,13 @@ impl PayloadQueue {
     pub(crate) fn get(&self, tsn: u32) -> Option<&ChunkPayloadData> {
         self.chunk_map.get(&tsn)
     }
-    pub(crate) fn get_mut(&mut self, tsn: u32) -> Option<&mut ChunkPayloadData> {
-        self.chunk_map.get_mut(&tsn)
+    pub(crate) fn get_mut(&mut self, tsn: u32) -> Option<ChunkMut<'_>> {
+        let n_bytes_in_flight = &mut self.n_bytes_in_flight;
+        self.chunk_map.get_mut(&tsn).map(|chunk| ChunkMut {
+            was_in_flight: is_in_flight(chunk),
+            chunk,
+            n_bytes_in_flight,
+        })
     }
 
     /// popDuplicates returns an array of TSN values that were found duplicate.
@@ -88,34 +185,33 @@ impl PayloadQueue {
     }
 
     pub(crate) fn get_gap_ack_blocks(&self, cumulative_tsn: u32) -> Vec<GapAckBlock> {
-        if self.chunk_map.is_empty() {
-            return vec![];
-        }
+        let mut gap_ack_blocks: Vec<GapAckBlock> = vec![];
+        for tsn in &self.sorted {
+            if sna32lte(*tsn, cumulative_tsn) {
+                continue;
+            }
 
-        let mut b = GapAckBlock::default();
-        let mut gap_ack_blocks = vec![];
-        for (i, tsn) in self.sorted.iter().enumerate() {
-            let diff = if *tsn >= cumulative_tsn {
-                (*tsn - cumulative_tsn) as u16
-            } else {
-                0
-            };
-
-            if i == 0 {
-                b.start = diff;
-                b.end = b.start;
-            } else if b.end + 1 == diff {
-                b.end += 1;
-            } else {
-                gap_ack_blocks.push(b);
+            // Gap ack blocks carry 16-bit offsets from the cumulative TSN. As the
+            // TSNs are sorted, none of the remaining ones can be reported either.
+            let offset = tsn.wrapping_sub(cumulative_tsn);
+            if offset > u16::MAX as u32 {
+                warn!(
+                    "TSN {} is too far from cumTSN {} to be reported in a gap ack block",
+                    tsn, cumulative_tsn
+                );
+                break;
+            }
+            let offset = offset as u16;
 
-                b.start = diff;
-                b.end = diff;
+            match gap_ack_blocks.last_mut() {
+                Some(b) if b.end + 1 == offset => b.end = offset,
+                _ => gap_ack_blocks.push(GapAckBlock {
+                    start: offset,
+                    end: offset,
+                }),
             }
         }
 
-        gap_ack_blocks.push(b);
-
         gap_ack_blocks
     }
 
@@ -127,13 +223,20 @@ impl PayloadQueue {
         s
     }
 
+    /// mark_as_acked marks a chunk as gap-acked. The user data is kept until the
+    /// chunk is cumulatively acked, as the peer is allowed to renege on it.
     pub(crate) fn mark_as_acked(&mut self, tsn: u32) -> usize {
         let n_bytes_acked = if let Some(c) = self.chunk_map.get_mut(&tsn) {
+            if c.acked {
+                return 0;
+            }
+            if is_in_flight(c) {
+                self.n_bytes_in_flight -= c.user_data.len();
+            }
             c.acked = true;
             c.retransmit = false;
             let n = c.user_data.len();
             self.n_bytes -= n;
-            c.user_data.clear();
             n
         } else {
             0
@@ -142,6 +245,25 @@ impl PayloadQueue {
         n_bytes_acked
     }
 
+    /// mark_as_reneged reverts a previous mark_as_acked, making the chunk outstanding
+    /// again. Returns the number of bytes put back in flight.
+    pub(crate) fn mark_as_reneged(&mut self, tsn: u32) -> usize {
+        if let Some(c) = self.chunk_map.get_mut(&tsn) {
+            if c.acked {
+                c.acked = false;
+                c.miss_indicator = 0;
+                let n = c.user_data.len();
+                self.n_bytes += n;
+                if is_in_flight(c) {
+                    self.n_bytes_in_flight += n;
+                }
+                return n;
+            }
+        }
+
+        0
+    }
+
     pub(crate) fn get_last_tsn_received(&self) -> Option<&u32> {
         self.sorted.last()
     }
@@ -151,14 +273,36 @@ impl PayloadQueue {
             if c.acked || c.abandoned() {
                 continue;
             }
+            if is_in_flight(c) {
+                self.n_bytes_in_flight -= c.user_data.len();
+            }
             c.retransmit = true;
         }
     }
 
+    /// Clears the retransmission marks of chunks that have not been resent yet
+    pub(crate) fn unmark_all_to_retransmit(&mut self) {
+        for c in self.chunk_map.values_mut() {
+            if c.retransmit {
+                c.retransmit = false;
+                if is_in_flight(c) {
+                    self.n_bytes_in_flight += c.user_data.len();
+                }
+            }
+        }
+    }
+
+    /// Bytes that are not acked yet, including those waiting for retransmission
     pub(crate) fn get_num_bytes(&self) -> usize {
         self.n_bytes
     }
 
+    /// Bytes of chunks that were sent and are neither acked, abandoned nor
+    /// marked for retransmission (the flight size of RFC 4960 Sec 6.1)
+    pub(crate) fn get_num_bytes_in_flight(&self) -> usize {
+        self.n_bytes_in_flight
+    }
+
     pub(crate) fn len(&self) -> usize {
         //assert_eq!(self.chunk_map.len(), self.length);
         self.chunk_map.len()
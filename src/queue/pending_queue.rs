@@ -1,3 +1,4 @@
+use crate::association::stream::StreamId;
 use crate::chunk::chunk_payload_data::ChunkPayloadData;
 
 use std::collections::VecDeque;
@@ -49,54 +50,94 @@ impl PendingQueue {
         self.ordered_queue.front()
     }
 
+    /// Pops the next DATA chunk to send, carrying at most `max_fragment_size` bytes
+    /// of user data. Messages are queued whole and fragmented here, so a large
+    /// message only becomes separate chunks as it is actually sent.
     pub(crate) fn pop(
         &mut self,
         beginning_fragment: bool,
         unordered: bool,
+        max_fragment_size: usize,
     ) -> Option<ChunkPayloadData> {
-        let popped = if self.selected {
-            let popped = if self.unordered_is_selected {
-                self.unordered_queue.pop_front()
-            } else {
-                self.ordered_queue.pop_front()
-            };
-            if let Some(p) = &popped {
-                if p.ending_fragment {
-                    self.selected = false;
-                }
-            }
-            popped
+        if !self.selected && !beginning_fragment {
+            return None;
+        }
+        let unordered = if self.selected {
+            self.unordered_is_selected
+        } else {
+            unordered
+        };
+        let queue = if unordered {
+            &mut self.unordered_queue
         } else {
-            if !beginning_fragment {
-                return None;
+            &mut self.ordered_queue
+        };
+
+        let max_fragment_size = std::cmp::max(max_fragment_size, 1);
+        let popped = match queue.front_mut() {
+            Some(c) if c.user_data.len() > max_fragment_size => {
+                // Carve the next fragment off the front of the message
+                let fragment = ChunkPayloadData {
+                    user_data: c.user_data.split_to(max_fragment_size),
+                    ending_fragment: false,
+                    ..c.clone()
+                };
+                c.beginning_fragment = false;
+                fragment
             }
-            if unordered {
-                let popped = { self.unordered_queue.pop_front() };
-                if let Some(p) = &popped {
-                    if !p.ending_fragment {
-                        self.selected = true;
-                        self.unordered_is_selected = true;
-                    }
-                }
-                popped
-            } else {
-                let popped = { self.ordered_queue.pop_front() };
-                if let Some(p) = &popped {
-                    if !p.ending_fragment {
-                        self.selected = true;
-                        self.unordered_is_selected = false;
-                    }
-                }
-                popped
+            Some(_) => {
+                self.queue_len -= 1;
+                queue.pop_front()?
             }
+            None => return None,
         };
 
-        if let Some(p) = &popped {
-            self.n_bytes -= p.user_data.len();
-            self.queue_len -= 1;
+        self.selected = !popped.ending_fragment;
+        self.unordered_is_selected = unordered;
+        self.n_bytes -= popped.user_data.len();
+
+        Some(popped)
+    }
+
+    /// The stream of the message whose first fragments were popped while the
+    /// rest of it is still queued, if any
+    pub(crate) fn partially_sent_stream(&self) -> Option<StreamId> {
+        if !self.selected {
+            return None;
+        }
+        let front = if self.unordered_is_selected {
+            self.unordered_queue.front()
+        } else {
+            self.ordered_queue.front()
+        };
+        front.map(|c| c.stream_identifier)
+    }
+
+    /// Removes every queued chunk of the given stream and returns the number of
+    /// user data bytes removed.
+    pub(crate) fn drain_stream(&mut self, stream_identifier: StreamId) -> usize {
+        // A partially sent message of this stream must not keep the queue selected
+        if self.partially_sent_stream() == Some(stream_identifier) {
+            self.selected = false;
+        }
+
+        let mut n_bytes = 0;
+        let mut n_chunks = 0;
+        for q in [&mut self.unordered_queue, &mut self.ordered_queue] {
+            q.retain(|c| {
+                if c.stream_identifier == stream_identifier {
+                    n_bytes += c.user_data.len();
+                    n_chunks += 1;
+                    false
+                } else {
+                    true
+                }
+            });
         }
 
-        popped
+        self.n_bytes -= n_bytes;
+        self.queue_len -= n_chunks;
+        n_bytes
     }
 
     pub(crate) fn get_num_bytes(&self) -> usize {
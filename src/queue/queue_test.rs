@@ -21,6 +21,32 @@ fn make_payload(tsn: u32, n_bytes: usize) -> ChunkPayloadData {
     }
 }
 
+#[test]
+fn test_payload_queue_capacity() -> Result<()> {
+    let mut pq = PayloadQueue::new_with_capacity(4);
+    assert_eq!(0.0, pq.memory_pressure());
+
+    for tsn in 2..4 {
+        assert!(pq.push(make_payload(tsn, 10), 0), "should be accepted");
+    }
+    assert_eq!(0.5, pq.memory_pressure());
+
+    for tsn in 4..8 {
+        pq.push(make_payload(tsn, 10), 0);
+    }
+    assert!(pq.is_full(), "should be full");
+    assert_eq!(4, pq.len(), "item count mismatch");
+    assert_eq!(1.0, pq.memory_pressure());
+    assert!(pq.pop_duplicates().is_empty(), "not duplicates");
+
+    // The chunk next to the cumulative TSN still fits
+    assert!(pq.push(make_payload(1, 10), 0), "should be accepted");
+    assert_eq!(5, pq.len(), "item count mismatch");
+
+    assert_eq!(0.0, PayloadQueue::new().memory_pressure());
+    Ok(())
+}
+
 #[test]
 fn test_payload_queue_push_no_check() -> Result<()> {
     let mut pq = PayloadQueue::new();
@@ -106,6 +132,45 @@ fn test_payload_queue_get_gap_ack_block() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_payload_queue_get_gap_ack_block_wrapped() -> Result<()> {
+    let cumulative_tsn = u32::MAX - 5;
+    let mut pq = PayloadQueue::new();
+    for tsn in [u32::MAX - 3, u32::MAX - 2, 3, 4] {
+        assert!(pq.push(make_payload(tsn, 0), cumulative_tsn));
+    }
+
+    let gab = pq.get_gap_ack_blocks(cumulative_tsn);
+    assert_eq!(2, gab.len());
+    assert_eq!((2, 3), (gab[0].start, gab[0].end));
+    assert_eq!((9, 10), (gab[1].start, gab[1].end));
+
+    Ok(())
+}
+
+#[test]
+fn test_payload_queue_get_gap_ack_block_offset_limit() -> Result<()> {
+    let cumulative_tsn = u32::MAX - 10;
+    let mut pq = PayloadQueue::new();
+    let last_reportable = cumulative_tsn.wrapping_add(u16::MAX as u32);
+    for tsn in [
+        cumulative_tsn.wrapping_add(2),
+        last_reportable,
+        last_reportable.wrapping_add(1),
+        last_reportable.wrapping_add(2),
+    ] {
+        assert!(pq.push(make_payload(tsn, 0), cumulative_tsn));
+    }
+
+    // TSNs beyond the 16-bit offset range are left out
+    let gab = pq.get_gap_ack_blocks(cumulative_tsn);
+    assert_eq!(2, gab.len());
+    assert_eq!((2, 2), (gab[0].start, gab[0].end));
+    assert_eq!((u16::MAX, u16::MAX), (gab[1].start, gab[1].end));
+
+    Ok(())
+}
+
 #[test]
 fn test_payload_queue_get_last_tsn_received() -> Result<()> {
     let mut pq = PayloadQueue::new();
@@ -160,6 +225,41 @@ fn test_payload_queue_mark_all_to_retrasmit() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_payload_queue_bytes_in_flight() -> Result<()> {
+    let mut pq = PayloadQueue::new();
+
+    for i in 0..3 {
+        pq.push(make_payload(i + 1, 10), 0);
+    }
+    assert_eq!(30, pq.get_num_bytes_in_flight());
+
+    pq.mark_as_acked(2);
+    assert_eq!(20, pq.get_num_bytes());
+    assert_eq!(20, pq.get_num_bytes_in_flight());
+
+    // Chunks marked for retransmission leave the flight but stay outstanding
+    pq.mark_all_to_retrasmit();
+    assert_eq!(20, pq.get_num_bytes());
+    assert_eq!(0, pq.get_num_bytes_in_flight());
+
+    // Resending a chunk puts it back in flight
+    pq.get_mut(1).unwrap().retransmit = false;
+    assert_eq!(10, pq.get_num_bytes_in_flight());
+
+    pq.unmark_all_to_retransmit();
+    assert_eq!(20, pq.get_num_bytes_in_flight());
+
+    pq.mark_as_reneged(2);
+    assert_eq!(30, pq.get_num_bytes_in_flight());
+
+    pq.pop(1);
+    assert_eq!(20, pq.get_num_bytes());
+    assert_eq!(20, pq.get_num_bytes_in_flight());
+
+    Ok(())
+}
+
 #[test]
 fn test_payload_queue_reset_retransmit_flag_on_ack() -> Result<()> {
     let mut pq = PayloadQueue::new();
@@ -188,6 +288,36 @@ fn test_payload_queue_reset_retransmit_flag_on_ack() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_payload_queue_mark_as_reneged() -> Result<()> {
+    let mut pq = PayloadQueue::new();
+
+    for i in 0..3 {
+        pq.push(make_payload(i + 1, 10), 0);
+    }
+
+    assert_eq!(10, pq.mark_as_acked(2), "should ack 10 bytes");
+    assert_eq!(0, pq.mark_as_acked(2), "should not ack twice");
+    assert_eq!(20, pq.get_num_bytes(), "total bytes mismatch");
+    assert_eq!(
+        10,
+        pq.get(2).unwrap().user_data.len(),
+        "gap-acked data should be kept"
+    );
+
+    assert_eq!(10, pq.mark_as_reneged(2), "should put 10 bytes back");
+    assert_eq!(0, pq.mark_as_reneged(2), "should not renege twice");
+    assert_eq!(30, pq.get_num_bytes(), "total bytes mismatch");
+    assert!(!pq.get(2).unwrap().acked, "should no longer be acked");
+
+    pq.mark_as_acked(2);
+    pq.pop(1);
+    pq.pop(2);
+    assert_eq!(10, pq.get_num_bytes(), "total bytes mismatch");
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //pending_queue_test
 ///////////////////////////////////////////////////////////////////
@@ -289,7 +419,7 @@ fn test_pending_queue_push_and_pop() -> Result<()> {
         assert_eq!(i, c.tsn, "TSN should match");
         let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
 
-        let result = pq.pop(beginning_fragment, unordered);
+        let result = pq.pop(beginning_fragment, unordered, usize::MAX);
         assert!(result.is_some(), "should not error: {}", i);
     }
 
@@ -307,7 +437,7 @@ fn test_pending_queue_push_and_pop() -> Result<()> {
         assert_eq!(i, c.tsn, "TSN should match");
         let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
 
-        let result = pq.pop(beginning_fragment, unordered);
+        let result = pq.pop(beginning_fragment, unordered, usize::MAX);
         assert!(result.is_some(), "should not error: {}", i);
     }
 
@@ -334,7 +464,7 @@ fn test_pending_queue_unordered_wins() -> Result<()> {
     let c = c.unwrap();
     assert_eq!(1, c.tsn, "TSN should match");
     let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
-    let result = pq.pop(beginning_fragment, unordered);
+    let result = pq.pop(beginning_fragment, unordered, usize::MAX);
     assert!(result.is_some(), "should not error");
 
     let c = pq.peek();
@@ -342,7 +472,7 @@ fn test_pending_queue_unordered_wins() -> Result<()> {
     let c = c.unwrap();
     assert_eq!(3, c.tsn, "TSN should match");
     let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
-    let result = pq.pop(beginning_fragment, unordered);
+    let result = pq.pop(beginning_fragment, unordered, usize::MAX);
     assert!(result.is_some(), "should not error");
 
     let c = pq.peek();
@@ -350,7 +480,7 @@ fn test_pending_queue_unordered_wins() -> Result<()> {
     let c = c.unwrap();
     assert_eq!(0, c.tsn, "TSN should match");
     let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
-    let result = pq.pop(beginning_fragment, unordered);
+    let result = pq.pop(beginning_fragment, unordered, usize::MAX);
     assert!(result.is_some(), "should not error");
 
     let c = pq.peek();
@@ -358,7 +488,7 @@ fn test_pending_queue_unordered_wins() -> Result<()> {
     let c = c.unwrap();
     assert_eq!(2, c.tsn, "TSN should match");
     let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
-    let result = pq.pop(beginning_fragment, unordered);
+    let result = pq.pop(beginning_fragment, unordered, usize::MAX);
     assert!(result.is_some(), "should not error");
 
     assert_eq!(0, pq.get_num_bytes(), "total bytes mismatch");
@@ -384,13 +514,104 @@ fn test_pending_queue_fragments() -> Result<()> {
         let c = c.unwrap();
         assert_eq!(exp, c.tsn, "TSN should match");
         let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
-        let result = pq.pop(beginning_fragment, unordered);
+        let result = pq.pop(beginning_fragment, unordered, usize::MAX);
         assert!(result.is_some(), "should not error: {}", exp);
     }
 
     Ok(())
 }
 
+#[test]
+fn test_pending_queue_drain_stream() -> Result<()> {
+    let mut pq = PendingQueue::new();
+    for (tsn, si, unordered) in [(0, 1, false), (1, 2, false), (2, 1, true), (3, 2, true)] {
+        let mut c = make_data_chunk(tsn, unordered, NO_FRAGMENT);
+        c.stream_identifier = si;
+        pq.push(c);
+    }
+
+    // The partially popped message of stream 1 must not keep the queue selected
+    let mut c = make_data_chunk(4, false, FRAG_BEGIN);
+    c.stream_identifier = 1;
+    let mut tail = make_data_chunk(5, false, FRAG_END);
+    tail.stream_identifier = 1;
+    let mut pq2 = PendingQueue::new();
+    pq2.push(c);
+    pq2.push(tail);
+    assert!(pq2.pop(true, false, usize::MAX).is_some());
+    assert_eq!(10, pq2.drain_stream(1));
+    assert!(pq2.is_empty());
+    let mut c = make_data_chunk(6, false, NO_FRAGMENT);
+    c.stream_identifier = 3;
+    pq2.push(c);
+    assert_eq!(6, pq2.pop(true, false, usize::MAX).unwrap().tsn);
+
+    assert_eq!(
+        20,
+        pq.drain_stream(1),
+        "should drain both chunks of stream 1"
+    );
+    assert_eq!(0, pq.drain_stream(1), "nothing left to drain");
+    assert_eq!(2, pq.len());
+    assert_eq!(20, pq.get_num_bytes());
+
+    for exp in [3, 1] {
+        let c = pq.peek().unwrap();
+        assert_eq!(exp, c.tsn, "TSN should match");
+        assert_eq!(2, c.stream_identifier);
+        let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
+        assert!(pq.pop(beginning_fragment, unordered, usize::MAX).is_some());
+    }
+    assert!(pq.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn test_pending_queue_fragment_on_pop() -> Result<()> {
+    let mut pq = PendingQueue::new();
+    pq.push(make_data_chunk(0, false, FRAG_BEGIN));
+    pq.push(make_data_chunk(0, false, FRAG_END));
+    pq.push(make_data_chunk(0, true, NO_FRAGMENT));
+    assert_eq!(3, pq.len());
+    assert_eq!(30, pq.get_num_bytes());
+
+    // (size, beginning, ending, unordered)
+    let expects = [
+        (4, true, false, true),
+        (4, false, false, true),
+        (2, false, true, true),
+        (4, true, false, false),
+        (4, false, false, false),
+        (2, false, false, false),
+        (4, false, false, false),
+        (4, false, false, false),
+        (2, false, true, false),
+    ];
+    for (i, (size, b, e, u)) in expects.into_iter().enumerate() {
+        if i == 1 {
+            assert_eq!(3, pq.len(), "a partially sent message stays queued");
+            assert_eq!(26, pq.get_num_bytes());
+        }
+        let c = pq.peek().unwrap();
+        let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
+        let c = pq.pop(beginning_fragment, unordered, 4).unwrap();
+        assert_eq!(
+            (size, b, e, u),
+            (
+                c.user_data.len(),
+                c.beginning_fragment,
+                c.ending_fragment,
+                c.unordered
+            )
+        );
+    }
+    assert!(pq.is_empty());
+    assert_eq!(0, pq.get_num_bytes());
+
+    Ok(())
+}
+
 // Once decided ordered or unordered, the decision should persist until
 // it pops a chunk with ending_fragment flags set to true.
 #[test]
@@ -403,7 +624,7 @@ fn test_pending_queue_selection_persistence() -> Result<()> {
     let c = c.unwrap();
     assert_eq!(0, c.tsn, "TSN should match");
     let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
-    let result = pq.pop(beginning_fragment, unordered);
+    let result = pq.pop(beginning_fragment, unordered, usize::MAX);
     assert!(result.is_some(), "should not error: {}", 0);
 
     pq.push(make_data_chunk(1, true, NO_FRAGMENT));
@@ -418,7 +639,7 @@ fn test_pending_queue_selection_persistence() -> Result<()> {
         let c = c.unwrap();
         assert_eq!(exp, c.tsn, "TSN should match");
         let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
-        let result = pq.pop(beginning_fragment, unordered);
+        let result = pq.pop(beginning_fragment, unordered, usize::MAX);
         assert!(result.is_some(), "should not error: {}", exp);
     }
 
@@ -695,6 +916,27 @@ fn test_reassembly_queue_ignores_chunk_with_wrong_si() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_reassembly_queue_accepts_chunk_when_full() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+    rq.max_bytes = 4;
+
+    for tsn in 0..4 {
+        rq.push(ChunkPayloadData {
+            payload_type: PayloadProtocolIdentifier::Binary,
+            beginning_fragment: tsn == 0,
+            tsn,
+            user_data: Bytes::from_static(b"ABC"),
+            ..Default::default()
+        });
+    }
+
+    // The limit is enforced by the association, the queue takes every chunk
+    assert!(rq.is_full());
+    assert_eq!(12, rq.get_num_bytes(), "num bytes mismatch");
+    Ok(())
+}
+
 #[test]
 fn test_reassembly_queue_ignores_chunk_with_stale_ssn() -> Result<()> {
     let mut rq = ReassemblyQueue::new(0);
@@ -928,6 +1170,137 @@ fn test_reassembly_queue_forward_tsn_for_unordered_framents() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_reassembly_queue_drop_oldest_incomplete_ordered() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    // SSN 0: incomplete, SSN 1: complete, SSN 2: incomplete
+    rq.push(ChunkPayloadData {
+        beginning_fragment: true,
+        tsn: 10,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"AB"),
+        ..Default::default()
+    });
+    rq.push(ChunkPayloadData {
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn: 12,
+        stream_sequence_number: 1,
+        user_data: Bytes::from_static(b"CDE"),
+        ..Default::default()
+    });
+    rq.push(ChunkPayloadData {
+        beginning_fragment: true,
+        tsn: 13,
+        stream_sequence_number: 2,
+        user_data: Bytes::from_static(b"FGHI"),
+        ..Default::default()
+    });
+    rq.push(ChunkPayloadData {
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn: 15,
+        stream_sequence_number: 3,
+        user_data: Bytes::from_static(b"J"),
+        ..Default::default()
+    });
+    assert_eq!(10, rq.get_num_bytes(), "num bytes mismatch");
+    assert_eq!(
+        6,
+        rq.get_num_incomplete_bytes(),
+        "incomplete bytes mismatch"
+    );
+    assert!(!rq.is_readable(), "SSN 0 should block delivery");
+    assert_eq!(Some(10), rq.oldest_incomplete_tsn());
+
+    // Dropping SSN 0 unblocks SSN 1
+    assert_eq!(2, rq.drop_oldest_incomplete());
+    assert_eq!(8, rq.get_num_bytes(), "num bytes mismatch");
+    assert_eq!(
+        4,
+        rq.get_num_incomplete_bytes(),
+        "incomplete bytes mismatch"
+    );
+    assert!(rq.is_readable(), "SSN 1 should be readable");
+    let chunks = rq.read().unwrap();
+    assert_eq!(1, chunks.ssn);
+
+    // SSN 2 is dropped before it becomes the next expected SSN
+    assert_eq!(4, rq.drop_oldest_incomplete());
+    assert_eq!(
+        0,
+        rq.get_num_incomplete_bytes(),
+        "incomplete bytes mismatch"
+    );
+    assert_eq!(0, rq.drop_oldest_incomplete(), "nothing left to drop");
+
+    // Late fragments of a dropped message are discarded
+    let complete = rq.push(ChunkPayloadData {
+        ending_fragment: true,
+        tsn: 14,
+        stream_sequence_number: 2,
+        user_data: Bytes::from_static(b"K"),
+        ..Default::default()
+    });
+    assert!(!complete, "late fragment should be discarded");
+    assert_eq!(1, rq.get_num_bytes(), "num bytes mismatch");
+
+    // SSN 3 is delivered, skipping over SSN 2
+    let chunks = rq.read().unwrap();
+    assert_eq!(3, chunks.ssn);
+    assert_eq!(4, rq.next_ssn);
+    assert_eq!(0, rq.get_num_bytes(), "num bytes mismatch");
+
+    Ok(())
+}
+
+#[test]
+fn test_reassembly_queue_drop_oldest_incomplete_unordered() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    for (tsn, beginning_fragment, data) in [
+        (20, true, &b"AB"[..]),
+        (21, false, &b"CD"[..]),
+        (23, true, &b"EFG"[..]),
+    ] {
+        rq.push(ChunkPayloadData {
+            unordered: true,
+            beginning_fragment,
+            tsn,
+            user_data: Bytes::copy_from_slice(data),
+            ..Default::default()
+        });
+    }
+    // An older incomplete ordered message
+    rq.push(ChunkPayloadData {
+        beginning_fragment: true,
+        tsn: 22,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"H"),
+        ..Default::default()
+    });
+    assert_eq!(
+        8,
+        rq.get_num_incomplete_bytes(),
+        "incomplete bytes mismatch"
+    );
+
+    // Fragments of the oldest message (tsn 20-21) are dropped together
+    assert_eq!(4, rq.drop_oldest_incomplete());
+    assert_eq!(1, rq.unordered_chunks.len());
+    assert_eq!(1, rq.drop_oldest_incomplete(), "ordered tsn 22 is next");
+    assert_eq!(3, rq.drop_oldest_incomplete());
+    assert_eq!(0, rq.get_num_bytes(), "num bytes mismatch");
+    assert_eq!(
+        0,
+        rq.get_num_incomplete_bytes(),
+        "incomplete bytes mismatch"
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_chunk_set_empty_chunk_set() -> Result<()> {
     let cset = Chunks::new(0, PayloadProtocolIdentifier::default(), vec![]);
@@ -1036,3 +1409,55 @@ fn test_reassembly_queue_ssn_overflow_in_forward_tsn_for_ordered() -> Result<()>
 
     Ok(())
 }
+
+#[test]
+fn test_reassembly_queue_many_fragments() -> Result<()> {
+    use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+    const N: u32 = 10_000;
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for unordered in [false, true] {
+        for order in ["in-order", "reversed", "shuffled"] {
+            let mut rq = ReassemblyQueue::new(0);
+
+            let mut offsets: Vec<u32> = (0..N).collect();
+            match order {
+                "reversed" => offsets.reverse(),
+                "shuffled" => offsets.shuffle(&mut rng),
+                _ => {}
+            }
+
+            for (i, offset) in offsets.iter().enumerate() {
+                let complete = rq.push(ChunkPayloadData {
+                    unordered,
+                    beginning_fragment: *offset == 0,
+                    ending_fragment: *offset == N - 1,
+                    // Start close to the TSN wrap-around point
+                    tsn: (u32::MAX - 10).wrapping_add(*offset),
+                    user_data: Bytes::copy_from_slice(&offset.to_be_bytes()),
+                    ..Default::default()
+                });
+                assert_eq!(
+                    i as u32 == N - 1,
+                    complete,
+                    "{} unordered={}: set should complete with the last fragment",
+                    order,
+                    unordered
+                );
+            }
+
+            assert!(rq.is_readable(), "{}: should be readable", order);
+            assert_eq!(4 * N as usize, rq.get_num_bytes(), "num bytes mismatch");
+
+            let chunks = rq.read().unwrap();
+            assert_eq!(N as usize, chunks.chunks.len(), "{}: fragment count", order);
+            for (i, c) in chunks.chunks.iter().enumerate() {
+                assert_eq!(&(i as u32).to_be_bytes()[..], &c.user_data[..]);
+            }
+            assert_eq!(0, rq.get_num_bytes(), "num bytes mismatch");
+        }
+    }
+
+    Ok(())
+}
@@ -5,25 +5,26 @@ use crate::StreamId;
 
 use bytes::{Bytes, BytesMut};
 use std::cmp::Ordering;
-
-fn sort_chunks_by_tsn(c: &mut [ChunkPayloadData]) {
-    c.sort_by(|a, b| {
-        if sna32lt(a.tsn, b.tsn) {
-            Ordering::Less
-        } else {
-            Ordering::Greater
-        }
-    });
+use std::collections::VecDeque;
+
+fn cmp_tsn(a: u32, b: u32) -> Ordering {
+    if a == b {
+        Ordering::Equal
+    } else if sna32lt(a, b) {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
 }
 
-fn sort_chunks_by_ssn(c: &mut [Chunks]) {
-    c.sort_by(|a, b| {
-        if sna16lt(a.ssn, b.ssn) {
-            Ordering::Less
-        } else {
-            Ordering::Greater
-        }
-    });
+fn cmp_ssn(a: u16, b: u16) -> Ordering {
+    if a == b {
+        Ordering::Equal
+    } else if sna16lt(a, b) {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
 }
 
 /// A chunk of data from the stream
@@ -100,6 +101,19 @@ impl Chunks {
         })
     }
 
+    /// Concatenates the user data of all chunks into a single buffer
+    pub(crate) fn to_bytes(&self) -> Bytes {
+        if let [c] = self.chunks.as_slice() {
+            return c.user_data.clone();
+        }
+
+        let mut buf = BytesMut::with_capacity(self.len());
+        for c in &self.chunks {
+            buf.extend_from_slice(&c.user_data);
+        }
+        buf.freeze()
+    }
+
     pub(crate) fn new(
         ssn: u16,
         ppi: PayloadProtocolIdentifier,
@@ -115,17 +129,13 @@ impl Chunks {
     }
 
     pub(crate) fn push(&mut self, chunk: ChunkPayloadData) -> bool {
-        // check if dup
-        for c in &self.chunks {
-            if c.tsn == chunk.tsn {
-                return false;
-            }
+        // Chunks are kept sorted by TSN. Fragments usually arrive in order, in
+        // which case the binary search lands on the end and the insert is an append.
+        match self.chunks.binary_search_by(|c| cmp_tsn(c.tsn, chunk.tsn)) {
+            Ok(_) => return false, // dup
+            Err(pos) => self.chunks.insert(pos, chunk),
         }
 
-        // append and sort
-        self.chunks.push(chunk);
-        sort_chunks_by_tsn(&mut self.chunks);
-
         // Check if we now have a complete set
         self.is_complete()
     }
@@ -138,53 +148,53 @@ impl Chunks {
         //   3. TSN monotinically increase by 1 from beginning to end
 
         // 0.
-        let n_chunks = self.chunks.len();
-        if n_chunks == 0 {
-            return false;
-        }
+        let (first, last) = match (self.chunks.first(), self.chunks.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return false,
+        };
 
         // 1.
-        if !self.chunks[0].beginning_fragment {
+        if !first.beginning_fragment {
             return false;
         }
 
         // 2.
-        if !self.chunks[n_chunks - 1].ending_fragment {
+        if !last.ending_fragment {
             return false;
         }
 
         // 3.
-        let mut last_tsn = 0u32;
-        for (i, c) in self.chunks.iter().enumerate() {
-            if i > 0 {
-                // Fragments must have contiguous TSN
-                // From RFC 4960 Section 3.3.1:
-                //   When a user message is fragmented into multiple chunks, the TSNs are
-                //   used by the receiver to reassemble the message.  This means that the
-                //   TSNs for each fragment of a fragmented user message MUST be strictly
-                //   sequential.
-                if c.tsn != last_tsn + 1 {
-                    // mid or end fragment is missing
-                    return false;
-                }
-            }
-
-            last_tsn = c.tsn;
-        }
-
-        true
+        // Fragments must have contiguous TSN
+        // From RFC 4960 Section 3.3.1:
+        //   When a user message is fragmented into multiple chunks, the TSNs are
+        //   used by the receiver to reassemble the message.  This means that the
+        //   TSNs for each fragment of a fragmented user message MUST be strictly
+        //   sequential.
+        // The chunks are sorted and free of duplicates, so they are contiguous
+        // exactly when the TSN span matches the number of chunks.
+        last.tsn.wrapping_sub(first.tsn) as usize + 1 == self.chunks.len()
     }
 }
 
 #[derive(Default, Debug)]
 pub(crate) struct ReassemblyQueue {
     pub(crate) si: StreamId,
-    pub(crate) next_ssn: u16,
     /// expected SSN for next ordered chunk
-    pub(crate) ordered: Vec<Chunks>,
-    pub(crate) unordered: Vec<Chunks>,
-    pub(crate) unordered_chunks: Vec<ChunkPayloadData>,
+    pub(crate) next_ssn: u16,
+    /// Ordered chunk sets, sorted by SSN
+    pub(crate) ordered: VecDeque<Chunks>,
+    /// Complete unordered chunk sets, in the order they were completed
+    pub(crate) unordered: VecDeque<Chunks>,
+    /// Unordered chunks not yet part of a complete set, sorted by TSN
+    pub(crate) unordered_chunks: VecDeque<ChunkPayloadData>,
+    /// SSNs of ordered messages that were dropped before completion
+    pub(crate) dropped_ssns: Vec<u16>,
     pub(crate) n_bytes: usize,
+    /// Bytes held by messages that are not complete yet
+    pub(crate) n_incomplete_bytes: usize,
+    /// Once n_bytes reaches this, the association drops further chunks unless
+    /// the stream waits for them. Like the receive window, it may be overshot.
+    pub(crate) max_bytes: usize,
 }
 
 impl ReassemblyQueue {
@@ -197,10 +207,13 @@ impl ReassemblyQueue {
         ReassemblyQueue {
             si,
             next_ssn: 0, // From RFC 4960 Sec 6.5:
-            ordered: vec![],
-            unordered: vec![],
-            unordered_chunks: vec![],
+            ordered: VecDeque::new(),
+            unordered: VecDeque::new(),
+            unordered_chunks: VecDeque::new(),
+            dropped_ssns: vec![],
             n_bytes: 0,
+            n_incomplete_bytes: 0,
+            max_bytes: usize::MAX,
         }
     }
 
@@ -211,122 +224,135 @@ impl ReassemblyQueue {
 
         if chunk.unordered {
             // First, insert into unordered_chunks array
-            //atomic.AddUint64(&r.n_bytes, uint64(len(chunk.userData)))
+            let pos = match self
+                .unordered_chunks
+                .binary_search_by(|c| cmp_tsn(c.tsn, chunk.tsn))
+            {
+                Ok(_) => return false, // dup
+                Err(pos) => pos,
+            };
             self.n_bytes += chunk.user_data.len();
-            self.unordered_chunks.push(chunk);
-            sort_chunks_by_tsn(&mut self.unordered_chunks);
+            self.n_incomplete_bytes += chunk.user_data.len();
+            self.unordered_chunks.insert(pos, chunk);
 
-            // Scan unordered_chunks that are contiguous (in TSN)
+            // Only a set containing the new chunk can have become complete.
             // If found, append the complete set to the unordered array
-            if let Some(cset) = self.find_complete_unordered_chunk_set() {
-                self.unordered.push(cset);
+            if let Some(cset) = self.find_complete_unordered_chunk_set(pos) {
+                self.n_incomplete_bytes -= cset.len();
+                self.unordered.push_back(cset);
                 return true;
             }
 
             false
         } else {
             // This is an ordered chunk
-            if sna16lt(chunk.stream_sequence_number, self.next_ssn) {
+            if sna16lt(chunk.stream_sequence_number, self.next_ssn)
+                || self.dropped_ssns.contains(&chunk.stream_sequence_number)
+            {
                 return false;
             }
 
-            self.n_bytes += chunk.user_data.len();
+            let ssn = chunk.stream_sequence_number;
+            let n_bytes = chunk.user_data.len();
 
             // Check if a chunkSet with the SSN already exists
-            for s in &mut self.ordered {
-                if s.ssn == chunk.stream_sequence_number {
-                    return s.push(chunk);
-                }
-            }
-
             // If not found, create a new chunkSet
-            let mut cset = Chunks::new(chunk.stream_sequence_number, chunk.payload_type, vec![]);
-            let unordered = chunk.unordered;
-            let ok = cset.push(chunk);
-            self.ordered.push(cset);
-            if !unordered {
-                sort_chunks_by_ssn(&mut self.ordered);
+            match self.ordered.binary_search_by(|s| cmp_ssn(s.ssn, ssn)) {
+                Ok(i) => {
+                    let cset = &mut self.ordered[i];
+                    if cset.is_complete() {
+                        return false;
+                    }
+                    let n_chunks = cset.chunks.len();
+                    let ok = cset.push(chunk);
+                    if n_chunks != cset.chunks.len() {
+                        self.n_bytes += n_bytes;
+                        self.n_incomplete_bytes += n_bytes;
+                    }
+                    if ok {
+                        self.n_incomplete_bytes -= cset.len();
+                    }
+                    ok
+                }
+                Err(i) => {
+                    let mut cset = Chunks::new(ssn, chunk.payload_type, vec![]);
+                    let ok = cset.push(chunk);
+                    self.ordered.insert(i, cset);
+                    self.n_bytes += n_bytes;
+                    if !ok {
+                        self.n_incomplete_bytes += n_bytes;
+                    }
+                    ok
+                }
             }
-
-            ok
         }
     }
 
-    pub(crate) fn find_complete_unordered_chunk_set(&mut self) -> Option<Chunks> {
-        let mut start_idx = -1isize;
-        let mut n_chunks = 0usize;
-        let mut last_tsn = 0u32;
-        let mut found = false;
-
-        for (i, c) in self.unordered_chunks.iter().enumerate() {
-            // seek beginning
-            if c.beginning_fragment {
-                start_idx = i as isize;
-                n_chunks = 1;
-                last_tsn = c.tsn;
-
-                if c.ending_fragment {
-                    found = true;
-                    break;
+    /// Looks for a complete set around the chunk at `pos` in unordered_chunks and,
+    /// if found, removes it from unordered_chunks.
+    pub(crate) fn find_complete_unordered_chunk_set(&mut self, pos: usize) -> Option<Chunks> {
+        let chunks = &self.unordered_chunks;
+        let (mut start, mut end) = (pos, pos);
+        let mut found_beginning = chunks[pos].beginning_fragment;
+        let mut found_ending = chunks[pos].ending_fragment;
+
+        // Extend the contiguous run in both directions in lockstep, so that the
+        // cost is bounded by the side that gives up first. Fragments arriving in
+        // order (or in reverse order) fail on the first step.
+        while !(found_beginning && found_ending) {
+            if !found_beginning {
+                if start == 0
+                    || chunks[start - 1].ending_fragment
+                    || chunks[start - 1].tsn.wrapping_add(1) != chunks[start].tsn
+                {
+                    return None;
                 }
-                continue;
-            }
-
-            if start_idx < 0 {
-                continue;
-            }
-
-            // Check if contiguous in TSN
-            if c.tsn != last_tsn + 1 {
-                start_idx = -1;
-                continue;
+                start -= 1;
+                found_beginning = chunks[start].beginning_fragment;
             }
 
-            last_tsn = c.tsn;
-            n_chunks += 1;
-
-            if c.ending_fragment {
-                found = true;
-                break;
+            if !found_ending {
+                if end + 1 == chunks.len()
+                    || chunks[end + 1].beginning_fragment
+                    || chunks[end].tsn.wrapping_add(1) != chunks[end + 1].tsn
+                {
+                    return None;
+                }
+                end += 1;
+                found_ending = chunks[end].ending_fragment;
             }
         }
 
-        if !found {
-            return None;
-        }
-
         // Extract the range of chunks
-        let chunks: Vec<ChunkPayloadData> = self
-            .unordered_chunks
-            .drain(start_idx as usize..(start_idx as usize) + n_chunks)
-            .collect();
+        let chunks: Vec<ChunkPayloadData> = self.unordered_chunks.drain(start..=end).collect();
         Some(Chunks::new(0, chunks[0].payload_type, chunks))
     }
 
-    pub(crate) fn is_readable(&self) -> bool {
-        // Check unordered first
-        if !self.unordered.is_empty() {
-            // The chunk sets in r.unordered should all be complete.
-            return true;
+    /// Returns the chunk set the next `read` would return, without removing it
+    pub(crate) fn peek(&self) -> Option<&Chunks> {
+        // The chunk sets in unordered are all complete
+        if let Some(chunks) = self.unordered.front() {
+            return Some(chunks);
         }
 
-        // Check ordered sets
-        if !self.ordered.is_empty() {
-            let cset = &self.ordered[0];
-            if cset.is_complete() && sna16lte(cset.ssn, self.next_ssn) {
-                return true;
-            }
+        let chunks = self.ordered.front()?;
+        if chunks.is_complete() && sna16lte(chunks.ssn, self.next_ssn) {
+            Some(chunks)
+        } else {
+            None
         }
-        false
+    }
+
+    pub(crate) fn is_readable(&self) -> bool {
+        self.peek().is_some()
     }
 
     pub(crate) fn read(&mut self) -> Option<Chunks> {
         // Check unordered first
-        let chunks = if !self.unordered.is_empty() {
-            self.unordered.remove(0)
-        } else if !self.ordered.is_empty() {
+        let chunks = if let Some(chunks) = self.unordered.pop_front() {
+            chunks
+        } else if let Some(chunks) = self.ordered.front() {
             // Now, check ordered
-            let chunks = &self.ordered[0];
             if !chunks.is_complete() {
                 return None;
             }
@@ -335,8 +361,9 @@ impl ReassemblyQueue {
             }
             if chunks.ssn == self.next_ssn {
                 self.next_ssn = self.next_ssn.wrapping_add(1);
+                self.skip_dropped_ssns();
             }
-            self.ordered.remove(0)
+            self.ordered.pop_front()?
         } else {
             return None;
         };
@@ -353,17 +380,18 @@ impl ReassemblyQueue {
             .ordered
             .iter()
             .filter(|s| sna16lte(s.ssn, last_ssn) && !s.is_complete())
-            .fold(0, |n, s| {
-                n + s.chunks.iter().fold(0, |acc, c| acc + c.user_data.len())
-            });
+            .fold(0, |n, s| n + s.len());
         self.subtract_num_bytes(num_bytes);
+        self.subtract_num_incomplete_bytes(num_bytes);
 
         self.ordered
             .retain(|s| !sna16lte(s.ssn, last_ssn) || s.is_complete());
+        self.dropped_ssns.retain(|ssn| !sna16lte(*ssn, last_ssn));
 
         // Finally, forward next_ssn
         if sna16lte(self.next_ssn, last_ssn) {
             self.next_ssn = last_ssn.wrapping_add(1);
+            self.skip_dropped_ssns();
         }
     }
 
@@ -373,21 +401,92 @@ impl ReassemblyQueue {
     /// Just remove chunks that are equal to or older than new_cumulative_tsn
     /// from the unordered_chunks
     pub(crate) fn forward_tsn_for_unordered(&mut self, new_cumulative_tsn: u32) {
-        let mut last_idx: isize = -1;
-        for (i, c) in self.unordered_chunks.iter().enumerate() {
-            if sna32gt(c.tsn, new_cumulative_tsn) {
-                break;
-            }
-            last_idx = i as isize;
+        let n = self
+            .unordered_chunks
+            .partition_point(|c| sna32lte(c.tsn, new_cumulative_tsn));
+        let num_bytes = self
+            .unordered_chunks
+            .drain(..n)
+            .fold(0, |acc, c| acc + c.user_data.len());
+        self.subtract_num_bytes(num_bytes);
+        self.subtract_num_incomplete_bytes(num_bytes);
+    }
+
+    /// Returns the TSN of the first fragment of the oldest incomplete message.
+    pub(crate) fn oldest_incomplete_tsn(&self) -> Option<u32> {
+        let ordered = self
+            .ordered
+            .iter()
+            .find(|s| !s.is_complete())
+            .and_then(|s| s.chunks.first())
+            .map(|c| c.tsn);
+        let unordered = self.unordered_chunks.front().map(|c| c.tsn);
+
+        match (ordered, unordered) {
+            (Some(a), Some(b)) => Some(if sna32lt(a, b) { a } else { b }),
+            (a, b) => a.or(b),
         }
-        if last_idx >= 0 {
-            for i in 0..(last_idx + 1) as usize {
-                self.subtract_num_bytes(self.unordered_chunks[i].user_data.len());
+    }
+
+    /// Discards the oldest incomplete message and returns the number of bytes freed.
+    /// An ordered message that is dropped is skipped over, so it does not block
+    /// delivery of the messages after it.
+    pub(crate) fn drop_oldest_incomplete(&mut self) -> usize {
+        let oldest_tsn = match self.oldest_incomplete_tsn() {
+            Some(tsn) => tsn,
+            None => return 0,
+        };
+
+        let num_bytes = if self
+            .unordered_chunks
+            .front()
+            .is_some_and(|c| c.tsn == oldest_tsn)
+        {
+            // Drop the leading run of fragments that belong to the same message
+            let mut n = 1;
+            while n < self.unordered_chunks.len()
+                && !self.unordered_chunks[n].beginning_fragment
+                && self.unordered_chunks[n - 1].tsn.wrapping_add(1) == self.unordered_chunks[n].tsn
+            {
+                n += 1;
             }
-            self.unordered_chunks.drain(..(last_idx + 1) as usize);
+            self.unordered_chunks
+                .drain(..n)
+                .fold(0, |acc, c| acc + c.user_data.len())
+        } else if let Some(i) = self.ordered.iter().position(|s| !s.is_complete()) {
+            let cset = self.ordered.remove(i).unwrap_or_default();
+            if cset.ssn == self.next_ssn {
+                self.next_ssn = self.next_ssn.wrapping_add(1);
+                self.skip_dropped_ssns();
+            } else {
+                self.dropped_ssns.push(cset.ssn);
+            }
+            cset.len()
+        } else {
+            0
+        };
+
+        self.subtract_num_bytes(num_bytes);
+        self.subtract_num_incomplete_bytes(num_bytes);
+
+        num_bytes
+    }
+
+    fn skip_dropped_ssns(&mut self) {
+        while let Some(i) = self
+            .dropped_ssns
+            .iter()
+            .position(|ssn| *ssn == self.next_ssn)
+        {
+            self.dropped_ssns.swap_remove(i);
+            self.next_ssn = self.next_ssn.wrapping_add(1);
         }
     }
 
+    fn subtract_num_incomplete_bytes(&mut self, n_bytes: usize) {
+        self.n_incomplete_bytes = self.n_incomplete_bytes.saturating_sub(n_bytes);
+    }
+
     pub(crate) fn subtract_num_bytes(&mut self, n_bytes: usize) {
         if self.n_bytes >= n_bytes {
             self.n_bytes -= n_bytes;
@@ -396,7 +495,20 @@ impl ReassemblyQueue {
         }
     }
 
+    /// Whether the chunk belongs to the ordered message to be delivered next
+    pub(crate) fn is_next_ssn(&self, chunk: &ChunkPayloadData) -> bool {
+        !chunk.unordered && chunk.stream_sequence_number == self.next_ssn
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.n_bytes >= self.max_bytes
+    }
+
     pub(crate) fn get_num_bytes(&self) -> usize {
         self.n_bytes
     }
+
+    pub(crate) fn get_num_incomplete_bytes(&self) -> usize {
+        self.n_incomplete_bytes
+    }
 }
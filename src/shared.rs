@@ -37,6 +37,8 @@ impl EndpointEvent {
 pub(crate) enum EndpointEventInner {
     /// The association has been drained
     Drained,
+    /// The association received the COOKIE ECHO completing its handshake
+    HandshakeCompleted,
     /*// The association needs association identifiers
     NeedIdentifiers(Instant, u64),
     /// Stop routing Association ID for this sequence number to the Association
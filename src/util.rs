@@ -1,7 +1,8 @@
 use crate::shared::AssociationId;
 
 use bytes::Bytes;
-use crc::{Crc, Table, CRC_32_ISCSI};
+#[cfg(not(feature = "hw-crc32c"))]
+use crc::{Crc, Digest, Table, CRC_32_ISCSI};
 use std::time::Duration;
 
 /// This function is non-inline to prevent the optimizer from looking inside it.
@@ -81,15 +82,52 @@ pub(crate) fn get_padding_size(len: usize) -> usize {
 /// Allocate and zero this data once.
 /// We need to use it for the checksum and don't want to allocate/clear each time.
 pub(crate) static FOUR_ZEROES: Bytes = Bytes::from_static(&[0, 0, 0, 0]);
-pub(crate) const ISCSI_CRC: Crc<u32, Table<16>> = Crc::<u32, Table<16>>::new(&CRC_32_ISCSI);
+#[cfg(not(feature = "hw-crc32c"))]
+const ISCSI_CRC: Crc<u32, Table<16>> = Crc::<u32, Table<16>>::new(&CRC_32_ISCSI);
+
+/// Incremental CRC32c computation. Uses the CPU's CRC32c instructions with the
+/// `hw-crc32c` feature, a slicing-by-16 table otherwise.
+pub(crate) struct Crc32c {
+    #[cfg(feature = "hw-crc32c")]
+    crc: u32,
+    #[cfg(not(feature = "hw-crc32c"))]
+    digest: Digest<'static, u32, Table<16>>,
+}
+
+impl Crc32c {
+    pub(crate) fn new() -> Self {
+        Crc32c {
+            #[cfg(feature = "hw-crc32c")]
+            crc: 0,
+            #[cfg(not(feature = "hw-crc32c"))]
+            digest: ISCSI_CRC.digest(),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        #[cfg(feature = "hw-crc32c")]
+        {
+            self.crc = crc32c::crc32c_append(self.crc, data);
+        }
+        #[cfg(not(feature = "hw-crc32c"))]
+        self.digest.update(data);
+    }
+
+    pub(crate) fn finalize(self) -> u32 {
+        #[cfg(feature = "hw-crc32c")]
+        return self.crc;
+        #[cfg(not(feature = "hw-crc32c"))]
+        self.digest.finalize()
+    }
+}
 
 /// Fastest way to do a crc32 without allocating.
 pub(crate) fn generate_packet_checksum(raw: &Bytes) -> u32 {
-    let mut digest = ISCSI_CRC.digest();
-    digest.update(&raw[0..8]);
-    digest.update(&FOUR_ZEROES[..]);
-    digest.update(&raw[12..]);
-    digest.finalize()
+    let mut crc = Crc32c::new();
+    crc.update(&raw[0..8]);
+    crc.update(&FOUR_ZEROES[..]);
+    crc.update(&raw[12..]);
+    crc.finalize()
 }
 
 /// A [`BytesSource`] implementation for `&'a mut [Bytes]`
@@ -530,4 +568,42 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_crc32c_vectors() {
+        // RFC 3720 Appendix B.4
+        let incrementing: Vec<u8> = (0..32).collect();
+        let decrementing: Vec<u8> = (0..32).rev().collect();
+        let tests: Vec<(&[u8], u32)> = vec![
+            (b"123456789", 0xe306_9283),
+            (&[0u8; 32], 0x8a91_36aa),
+            (&[0xffu8; 32], 0x62a8_ab43),
+            (&incrementing, 0x46dd_794e),
+            (&decrementing, 0x113f_db5c),
+        ];
+
+        for (data, expected) in tests {
+            let mut crc = Crc32c::new();
+            crc.update(data);
+            assert_eq!(expected, crc.finalize(), "{:?}", data);
+
+            // Incremental updates yield the same checksum
+            let mut crc = Crc32c::new();
+            for part in data.chunks(5) {
+                crc.update(part);
+            }
+            assert_eq!(expected, crc.finalize(), "{:?}", data);
+        }
+    }
+
+    #[test]
+    fn test_generate_packet_checksum() {
+        let raw = Bytes::from_static(&[
+            0x13, 0x88, 0x13, 0x88, 0x00, 0x00, 0x00, 0x00, 0x06, 0xa9, 0x00, 0xe1,
+        ]);
+        assert_eq!(
+            u32::from_le_bytes([0x06, 0xa9, 0x00, 0xe1]),
+            generate_packet_checksum(&raw)
+        );
+    }
 }